@@ -1,12 +1,16 @@
 use std::any::{Any, TypeId};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::error::Error;
+use std::rc::Rc;
 use async_trait::async_trait;
+use futures_util::future::join_all;
 use thiserror::Error;
 use crate::path::{AssetPath, InvalidCharacters};
 use crate::source::AssetSource;
 
 pub mod path;
+pub mod registry;
 pub mod source;
 
 #[async_trait(? Send)]
@@ -14,8 +18,20 @@ pub trait AssetPipeline {
     async fn load_asset(&self, path: AssetPath, source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError>;
 }
 
+/// Loads assets with the pipeline registered for their type.
+///
+/// Assets loaded with [AssetPipelines::load_asset] are cached by path and type, so loading them
+/// again doesn't repeat the I/O. The cache doesn't know which files an asset was built from, so
+/// when an asset changes, e.g. as reported by an [AssetWatcher](source::AssetWatcher),
+/// [AssetPipelines::invalidate] its path along with the paths of the assets built from it, such as
+/// those an [AssetRegistry](registry::AssetRegistry) marks dirty, before loading them again.
+///
+/// Neither is the source part of the cache key, so the cache is tied to a single source. Loading a
+/// cached path from another source returns the asset loaded from the first one, so
+/// [AssetPipelines::clear_cache] when switching sources, or keep an [AssetPipelines] per source.
 pub struct AssetPipelines {
     pipelines: HashMap<TypeId, Box<dyn AssetPipeline>>,
+    cache: RefCell<HashMap<(AssetPath, TypeId), Rc<dyn Any>>>,
 }
 
 #[derive(Debug, Error)]
@@ -28,6 +44,8 @@ pub enum LoadAssetError {
     InvalidPath(InvalidCharacters),
     #[error("unknown error loading asset: {:?}", .0)]
     UnknownError(AssetPath),
+    #[error("asset source can't list directory: {:?}", .0)]
+    ListingUnsupported(AssetPath),
     #[error("pipeline error: {}", .0)]
     Other(Box<dyn Error>),
 }
@@ -40,7 +58,7 @@ impl LoadAssetError {
 
 impl AssetPipelines {
     pub fn new(pipelines: HashMap<TypeId, Box<dyn AssetPipeline>>) -> Self {
-        AssetPipelines { pipelines }
+        AssetPipelines { pipelines, cache: RefCell::new(HashMap::new()) }
     }
 
     pub async fn load_asset_of_type(&self, path: AssetPath, typ: TypeId, source: &impl AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
@@ -49,10 +67,207 @@ impl AssetPipelines {
         pipeline.load_asset(path, source).await
     }
 
-    pub async fn load_asset<T: 'static>(&self, path: AssetPath, source: &impl AssetSource) -> Result<T, LoadAssetError> {
-        let boxed = self.load_asset_of_type(path.clone(), TypeId::of::<T>(), source).await?;
+    /// Loads the asset, or returns the one loaded from the same path before, whichever source it
+    /// was loaded from. Failed loads aren't cached.
+    pub async fn load_asset<T: 'static>(&self, path: AssetPath, source: &impl AssetSource) -> Result<Rc<T>, LoadAssetError> {
+        let key = (path, TypeId::of::<T>());
+        let cached = self.cache.borrow().get(&key).cloned();
+        let asset = match cached {
+            Some(asset) => asset,
+            None => {
+                let asset: Rc<dyn Any> = self.load_asset_of_type(key.0.clone(), key.1, source).await?.into();
+                self.cache.borrow_mut().insert(key, asset.clone());
+                asset
+            }
+        };
+        Ok(asset.downcast::<T>().unwrap())
+    }
+
+    /// Loads the asset with its pipeline, bypassing the cache, e.g. to get an instance that can
+    /// be modified.
+    pub async fn load_asset_uncached<T: 'static>(&self, path: AssetPath, source: &impl AssetSource) -> Result<T, LoadAssetError> {
+        let boxed = self.load_asset_of_type(path, TypeId::of::<T>(), source).await?;
         Ok(*boxed.downcast::<T>().unwrap())
     }
+
+    /// Drops the cached assets of every type loaded from `path`, so they are loaded again on the
+    /// next [AssetPipelines::load_asset].
+    pub fn invalidate(&self, path: &AssetPath) {
+        self.cache.borrow_mut().retain(|(cached, _), _| cached != path);
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    /// Loads each of the given assets in order. `progress` is invoked as each asset completes,
+    /// successfully or not, with the number of completed assets and the total number of assets.
+    /// Bypasses the cache like [AssetPipelines::load_asset_uncached].
+    pub async fn load_assets<F>(&self, assets: Vec<(AssetPath, TypeId)>, source: &impl AssetSource, mut progress: F) -> Vec<Result<Box<dyn Any>, LoadAssetError>>
+        where F: FnMut(usize, usize) {
+        let total = assets.len();
+        let mut results = Vec::with_capacity(total);
+        for (path, typ) in assets {
+            results.push(self.load_asset_of_type(path, typ, source).await);
+            progress(results.len(), total);
+        }
+        results
+    }
+
+    /// Loads all of the given assets concurrently, interleaving their pipelines on the current
+    /// thread while they wait on the source. Results are in the order of `requests`, each asset
+    /// failing on its own. Bypasses the cache like [AssetPipelines::load_asset_uncached], so
+    /// every asset is loaded from the source and neither read from nor added to the cache.
+    pub async fn load_many(&self, requests: &[(AssetPath, TypeId)], source: &impl AssetSource) -> Vec<Result<Box<dyn Any>, LoadAssetError>> {
+        join_all(requests.iter().map(|(path, typ)| self.load_asset_of_type(path.clone(), *typ, source))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::{Any, TypeId};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::task::{Context, Poll};
+
+    use async_trait::async_trait;
+
+    use crate::{AssetPipeline, AssetPipelines, LoadAssetError};
+    use crate::path::AssetPath;
+    use crate::source::{AssetReader, AssetSource};
+
+    struct EmptySource;
+
+    #[async_trait(? Send)]
+    impl AssetSource for EmptySource {
+        async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+            Err(LoadAssetError::NotFound(path.clone()))
+        }
+    }
+
+    /// Loads the path of the asset as a string.
+    struct PathPipeline;
+
+    #[async_trait(? Send)]
+    impl AssetPipeline for PathPipeline {
+        async fn load_asset(&self, path: AssetPath, _source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
+            Ok(Box::new(path.path_string().to_owned()))
+        }
+    }
+
+    /// Counts how often assets are loaded.
+    struct CountingPipeline(Rc<Cell<u32>>);
+
+    #[async_trait(? Send)]
+    impl AssetPipeline for CountingPipeline {
+        async fn load_asset(&self, path: AssetPath, _source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
+            self.0.set(self.0.get() + 1);
+            Ok(Box::new(path.path_string().to_owned()))
+        }
+    }
+
+    #[test]
+    fn cached_assets() {
+        let loads = Rc::new(Cell::new(0));
+        let pipelines = AssetPipelines::new(HashMap::from([
+            (TypeId::of::<String>(), Box::new(CountingPipeline(loads.clone())) as Box<dyn AssetPipeline>),
+        ]));
+        let path = |path: &str| AssetPath::new(path).unwrap();
+        let load = |path| futures::executor::block_on(pipelines.load_asset::<String>(path, &EmptySource)).unwrap();
+
+        let first = load(path("/a"));
+        let second = load(path("/a"));
+        assert_eq!(*first, "/a");
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(loads.get(), 1);
+        assert_eq!(*load(path("/b")), "/b");
+        assert_eq!(loads.get(), 2);
+
+        let uncached = futures::executor::block_on(pipelines.load_asset_uncached::<String>(path("/a"), &EmptySource)).unwrap();
+        assert_eq!(uncached, "/a");
+        assert_eq!(loads.get(), 3);
+
+        pipelines.invalidate(&path("/a"));
+        assert!(!Rc::ptr_eq(&first, &load(path("/a"))));
+        load(path("/b"));
+        assert_eq!(loads.get(), 4);
+
+        assert!(futures::executor::block_on(pipelines.load_asset::<u32>(path("/a"), &EmptySource)).is_err());
+    }
+
+    /// Returns pending once, like a source waiting for a file.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// Logs when loading each asset starts and ends.
+    struct LoggingPipeline(Rc<RefCell<Vec<String>>>);
+
+    #[async_trait(? Send)]
+    impl AssetPipeline for LoggingPipeline {
+        async fn load_asset(&self, path: AssetPath, _source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
+            self.0.borrow_mut().push(format!("start {}", path.path_string()));
+            YieldOnce(false).await;
+            self.0.borrow_mut().push(format!("end {}", path.path_string()));
+            Ok(Box::new(path.path_string().to_owned()))
+        }
+    }
+
+    #[test]
+    fn load_many_concurrently() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let pipelines = AssetPipelines::new(HashMap::from([
+            (TypeId::of::<String>(), Box::new(LoggingPipeline(log.clone())) as Box<dyn AssetPipeline>),
+        ]));
+        let requests = [
+            (AssetPath::new("/a").unwrap(), TypeId::of::<String>()),
+            (AssetPath::new("/b").unwrap(), TypeId::of::<u32>()),
+            (AssetPath::new("/c").unwrap(), TypeId::of::<String>()),
+        ];
+
+        let results = futures::executor::block_on(pipelines.load_many(&requests, &EmptySource));
+
+        assert_eq!(*log.borrow(), vec!["start /a", "start /c", "end /a", "end /c"]);
+        assert_eq!(results[0].as_ref().unwrap().downcast_ref::<String>().unwrap(), "/a");
+        assert!(matches!(results[1], Err(LoadAssetError::UnknownType(_))));
+        assert_eq!(results[2].as_ref().unwrap().downcast_ref::<String>().unwrap(), "/c");
+    }
+
+    #[test]
+    fn load_assets_progress() {
+        let pipelines = AssetPipelines::new(HashMap::from([
+            (TypeId::of::<String>(), Box::new(PathPipeline) as Box<dyn AssetPipeline>),
+        ]));
+        let assets = vec![
+            (AssetPath::new("/a").unwrap(), TypeId::of::<String>()),
+            (AssetPath::new("/b").unwrap(), TypeId::of::<u32>()),
+            (AssetPath::new("/c").unwrap(), TypeId::of::<String>()),
+        ];
+
+        let mut calls = vec![];
+        let results = futures::executor::block_on(
+            pipelines.load_assets(assets, &EmptySource, |loaded, total| calls.push((loaded, total))),
+        );
+
+        assert_eq!(calls, vec![(1, 3), (2, 3), (3, 3)]);
+        assert_eq!(results[0].as_ref().unwrap().downcast_ref::<String>().unwrap(), "/a");
+        assert!(matches!(results[1], Err(LoadAssetError::UnknownType(_))));
+        assert_eq!(results[2].as_ref().unwrap().downcast_ref::<String>().unwrap(), "/c");
+    }
 }
 
 
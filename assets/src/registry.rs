@@ -0,0 +1,238 @@
+use std::any::{Any, type_name};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+use crate::path::AssetPath;
+
+/// Reference to an asset of type `T` in an [AssetRegistry].
+pub struct AssetHandle<T> {
+    index: usize,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> AssetHandle<T> {
+    fn new(index: usize) -> Self {
+        AssetHandle { index, phantom: PhantomData }
+    }
+
+    pub fn untyped(self) -> UntypedAssetHandle {
+        UntypedAssetHandle(self.index)
+    }
+}
+
+impl<T> Clone for AssetHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for AssetHandle<T> {}
+
+impl<T> PartialEq for AssetHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+
+impl<T> Eq for AssetHandle<T> {}
+
+impl<T> Hash for AssetHandle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+
+impl<T> Debug for AssetHandle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "AssetHandle<{}>({})", type_name::<T>(), self.index)
+    }
+}
+
+/// [AssetHandle] with its asset type erased, e.g. to list assets of different types.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UntypedAssetHandle(usize);
+
+impl<T> From<AssetHandle<T>> for UntypedAssetHandle {
+    fn from(value: AssetHandle<T>) -> Self {
+        value.untyped()
+    }
+}
+
+struct Entry {
+    path: AssetPath,
+    asset: Box<dyn Any>,
+    /// Assets built from this one, which have to be rebuilt when it changes.
+    dependents: Vec<usize>,
+    dirty: bool,
+}
+
+/// Keeps loaded assets by path, together with which assets were built from which, e.g. a material
+/// from its shader modules. When an asset is reloaded, everything depending on it, directly or
+/// through other assets, is marked dirty until taken with [AssetRegistry::take_dirty] to be
+/// rebuilt.
+#[derive(Default)]
+pub struct AssetRegistry {
+    entries: Vec<Entry>,
+    paths: HashMap<AssetPath, usize>,
+}
+
+impl AssetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the asset loaded from `path`. An asset already registered for the path is replaced
+    /// like with [AssetRegistry::replace], keeping its handle and dependents. Panics if that asset
+    /// is of another type, as its existing handles would no longer match it.
+    pub fn insert<T: 'static>(&mut self, path: AssetPath, asset: T) -> AssetHandle<T> {
+        if let Some(&index) = self.paths.get(&path) {
+            assert!(
+                self.entries[index].asset.is::<T>(),
+                "asset {:?} is already registered with another type than {}", path, type_name::<T>(),
+            );
+            let handle = AssetHandle::new(index);
+            self.replace(handle, asset);
+            return handle;
+        }
+        let index = self.entries.len();
+        self.entries.push(Entry {
+            path: path.clone(),
+            asset: Box::new(asset),
+            dependents: vec![],
+            dirty: false,
+        });
+        self.paths.insert(path, index);
+        AssetHandle::new(index)
+    }
+
+    /// Handle of the asset registered for `path`, if there is one of type `T`.
+    pub fn handle<T: 'static>(&self, path: &AssetPath) -> Option<AssetHandle<T>> {
+        self.paths.get(path)
+            .filter(|index| self.entries[**index].asset.is::<T>())
+            .map(|index| AssetHandle::new(*index))
+    }
+
+    pub fn get<T: 'static>(&self, handle: AssetHandle<T>) -> &T {
+        self.entries[handle.index].asset.downcast_ref().expect("asset of the handle type")
+    }
+
+    pub fn path(&self, handle: impl Into<UntypedAssetHandle>) -> &AssetPath {
+        &self.entries[handle.into().0].path
+    }
+
+    /// Records that `dependent` was built from `dependency`.
+    pub fn add_dependency(&mut self, dependent: impl Into<UntypedAssetHandle>, dependency: impl Into<UntypedAssetHandle>) {
+        let dependent = dependent.into().0;
+        let dependents = &mut self.entries[dependency.into().0].dependents;
+        if !dependents.contains(&dependent) {
+            dependents.push(dependent);
+        }
+    }
+
+    /// Replaces the asset of `handle`, e.g. after reloading it from its path, and marks everything
+    /// depending on it dirty.
+    pub fn replace<T: 'static>(&mut self, handle: AssetHandle<T>, asset: T) {
+        let entry = &mut self.entries[handle.index];
+        entry.asset = Box::new(asset);
+        entry.dirty = false;
+        self.invalidate_dependents(handle.index);
+    }
+
+    /// Marks everything depending on the asset registered for `path` dirty, without replacing the
+    /// asset itself, e.g. for paths reported by an [AssetWatcher](crate::source::AssetWatcher).
+    /// Returns whether an asset is registered for the path.
+    pub fn invalidate(&mut self, path: &AssetPath) -> bool {
+        match self.paths.get(path) {
+            Some(&index) => {
+                self.invalidate_dependents(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn invalidate_dependents(&mut self, index: usize) {
+        let mut pending = self.entries[index].dependents.clone();
+        while let Some(dependent) = pending.pop() {
+            let entry = &mut self.entries[dependent];
+            // already dirty assets had their dependents marked too, which also stops cycles
+            if !entry.dirty {
+                entry.dirty = true;
+                pending.extend(&entry.dependents);
+            }
+        }
+    }
+
+    pub fn is_dirty(&self, handle: impl Into<UntypedAssetHandle>) -> bool {
+        self.entries[handle.into().0].dirty
+    }
+
+    /// Returns the dirty assets in the order they were registered, so assets loaded before the
+    /// assets depending on them are rebuilt first, and marks them clean.
+    pub fn take_dirty(&mut self) -> Vec<UntypedAssetHandle> {
+        self.entries.iter_mut()
+            .enumerate()
+            .filter(|(_, entry)| entry.dirty)
+            .map(|(index, entry)| {
+                entry.dirty = false;
+                UntypedAssetHandle(index)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::AssetPath;
+    use crate::registry::AssetRegistry;
+
+    struct ShaderModule(&'static str);
+
+    struct Material {
+        modules: Vec<&'static str>,
+    }
+
+    #[test]
+    fn reload_marks_dependents() {
+        let mut registry = AssetRegistry::new();
+        let path = |path: &str| AssetPath::new(path).unwrap();
+        let vertex = registry.insert(path("/vertex.wgsl"), ShaderModule("vertex"));
+        let fragment = registry.insert(path("/fragment.wgsl"), ShaderModule("fragment"));
+        let other = registry.insert(path("/other.wgsl"), ShaderModule("other"));
+        let material = registry.insert(path("/ship.material"), Material { modules: vec!["vertex", "fragment"] });
+        let outline = registry.insert(path("/outline.material"), Material { modules: vec![] });
+        registry.add_dependency(material, vertex);
+        registry.add_dependency(material, fragment);
+        registry.add_dependency(outline, material);
+        assert_eq!(registry.get(material).modules, vec!["vertex", "fragment"]);
+        assert_eq!(registry.handle::<ShaderModule>(&path("/fragment.wgsl")), Some(fragment));
+        assert_eq!(registry.handle::<Material>(&path("/fragment.wgsl")), None);
+
+        registry.insert(path("/fragment.wgsl"), ShaderModule("fragment v2"));
+        assert_eq!(registry.get(fragment).0, "fragment v2");
+        assert!(!registry.is_dirty(fragment));
+        assert!(!registry.is_dirty(vertex));
+        assert!(!registry.is_dirty(other));
+        assert!(registry.is_dirty(material));
+        assert!(registry.is_dirty(outline));
+
+        assert_eq!(registry.take_dirty(), vec![material.untyped(), outline.untyped()]);
+        assert!(!registry.is_dirty(material));
+        assert!(registry.take_dirty().is_empty());
+
+        assert!(registry.invalidate(&path("/vertex.wgsl")));
+        assert!(!registry.invalidate(&path("/missing.wgsl")));
+        assert_eq!(registry.take_dirty(), vec![material.untyped(), outline.untyped()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already registered with another type")]
+    fn insert_other_type() {
+        let mut registry = AssetRegistry::new();
+        let path = AssetPath::new("/ship.material").unwrap();
+        registry.insert(path.clone(), ShaderModule("vertex"));
+        registry.insert(path, Material { modules: vec![] });
+    }
+}
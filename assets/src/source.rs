@@ -1,4 +1,7 @@
 #[cfg(not(target_family = "wasm"))]
+pub mod archive;
+pub mod chained;
+#[cfg(not(target_family = "wasm"))]
 pub mod desktop_fs;
 #[cfg(target_family = "wasm")]
 pub mod web_request;
@@ -11,6 +14,36 @@ use crate::path::AssetPath;
 #[async_trait(? Send)]
 pub trait AssetSource: Sync {
     async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError>;
+
+    /// Paths of the asset files in the directory at `path`, sorted, e.g. to discover every level
+    /// under `/levels`. Files whose names aren't valid in an [AssetPath] are skipped.
+    async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+        Err(LoadAssetError::ListingUnsupported(path.clone()))
+    }
+}
+
+/// Path of the file `name` in the directory at `directory`, or `None` with a warning if the name
+/// isn't valid in an [AssetPath]. Names that would leave the directory, such as those of a
+/// manifest containing a separator or `..`, are skipped too.
+pub(crate) fn directory_entry(directory: &AssetPath, name: &str) -> Option<AssetPath> {
+    if name.contains(AssetPath::SEPARATOR) || name == "." || name == ".." {
+        log::warn!(target: "assets", "Skipping directory entry outside of {:?}: {}", directory, name);
+        return None;
+    }
+    let path = format!("{}/{}", directory.path_string().trim_end_matches(AssetPath::SEPARATOR), name);
+    match AssetPath::new(&path) {
+        Ok(path) => Some(path),
+        Err(err) => {
+            log::warn!(target: "assets", "Skipping directory entry: {}", err);
+            None
+        }
+    }
+}
+
+/// Keeps track of assets to report which of them changed, so they can be reloaded.
+pub trait AssetWatcher {
+    /// Paths of the watched assets that were modified since the previous poll.
+    fn poll_changes(&mut self) -> Vec<AssetPath>;
 }
 
 #[async_trait(? Send)]
@@ -36,3 +69,19 @@ impl<R: Read + Send> AssetReader for ReadAssetReader<R> {
         vec
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::path::AssetPath;
+    use crate::source::directory_entry;
+
+    #[test]
+    fn directory_entries() {
+        let directory = AssetPath::new("/levels/").unwrap();
+        assert_eq!(directory_entry(&directory, "1.pipeline"), Some(AssetPath::new("/levels/1.pipeline").unwrap()));
+        assert_eq!(directory_entry(&directory, "invalid name"), None);
+        for name in ["..", ".", "../game.wgsl", "nested/1.pipeline"] {
+            assert_eq!(directory_entry(&directory, name), None, "{name}");
+        }
+    }
+}
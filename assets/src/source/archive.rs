@@ -0,0 +1,118 @@
+use std::fs::File;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use zip::result::{ZipError, ZipResult};
+use zip::ZipArchive;
+
+use crate::LoadAssetError;
+use crate::path::AssetPath;
+use crate::source::{AssetReader, AssetSource, directory_entry, ReadAssetReader};
+
+/// Serves assets from the entries of a zip archive, e.g. to distribute them as a single file
+/// instead of a directory. Asset paths resolve to entries relative to the root of the archive.
+pub struct ZipAssetSource<R = File> {
+    archive: Mutex<ZipArchive<R>>,
+}
+
+impl ZipAssetSource<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> ZipResult<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> ZipAssetSource<R> {
+    pub fn new(reader: R) -> ZipResult<Self> {
+        Ok(ZipAssetSource { archive: Mutex::new(ZipArchive::new(reader)?) })
+    }
+}
+
+fn entry_name(path: &AssetPath) -> &str {
+    path.path_string().trim_start_matches(AssetPath::SEPARATOR)
+}
+
+#[async_trait(? Send)]
+impl<R: Read + Seek + Send> AssetSource for ZipAssetSource<R> {
+    async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+        let mut archive = self.archive.lock().unwrap();
+        let mut entry = match archive.by_name(entry_name(path)) {
+            Ok(entry) if entry.is_file() => entry,
+            Ok(_) | Err(ZipError::FileNotFound) => return Err(LoadAssetError::NotFound(path.clone())),
+            Err(err) => return Err(LoadAssetError::other(err)),
+        };
+        // entries can't outlive the lock of the archive. Their size is read from the archive, which
+        // might be corrupt or malicious, so it isn't trusted to preallocate.
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(LoadAssetError::other)?;
+        Ok(Box::new(ReadAssetReader::new(Cursor::new(contents))))
+    }
+
+    async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+        let prefix = match entry_name(path).trim_end_matches(AssetPath::SEPARATOR) {
+            "" => String::new(),
+            directory => format!("{}/", directory),
+        };
+        let archive = self.archive.lock().unwrap();
+        if !prefix.is_empty() && !archive.file_names().any(|name| name.starts_with(&prefix)) {
+            return Err(LoadAssetError::NotFound(path.clone()));
+        }
+        let mut paths: Vec<_> = archive.file_names()
+            .filter_map(|name| name.strip_prefix(&prefix))
+            .filter(|name| !name.is_empty() && !name.contains('/'))
+            .filter_map(|name| directory_entry(path, name))
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    use crate::LoadAssetError;
+    use crate::path::AssetPath;
+    use crate::source::AssetSource;
+    use crate::source::archive::ZipAssetSource;
+
+    fn archive(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer.add_directory("levels/", FileOptions::default()).unwrap();
+        for (name, contents) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn read_entries() {
+        let source = ZipAssetSource::new(Cursor::new(archive(&[
+            ("game.wgsl", "shader"),
+            ("levels/1.pipeline", "first"),
+            ("levels/2.pipeline", "second"),
+            ("levels/extra/3.pipeline", "third"),
+        ]))).unwrap();
+        let read = |path| futures::executor::block_on(async {
+            let mut reader = source.open_asset_file(&AssetPath::new(path).unwrap()).await?;
+            Ok::<_, LoadAssetError>(String::from_utf8(reader.read_fully().await).unwrap())
+        });
+
+        assert_eq!(read("/game.wgsl").unwrap(), "shader");
+        assert_eq!(read("/levels/2.pipeline").unwrap(), "second");
+        assert!(matches!(read("/missing.wgsl"), Err(LoadAssetError::NotFound(_))));
+        assert!(matches!(read("/levels/"), Err(LoadAssetError::NotFound(_))));
+
+        let list = |path| futures::executor::block_on(source.list_dir(&AssetPath::new(path).unwrap())).unwrap();
+        let paths: Vec<_> = list("/levels").iter().map(|path| path.path_string().to_owned()).collect();
+        assert_eq!(paths, vec!["/levels/1.pipeline", "/levels/2.pipeline"]);
+        assert_eq!(list("/"), vec![AssetPath::new("/game.wgsl").unwrap()]);
+        let missing = futures::executor::block_on(source.list_dir(&AssetPath::new("/missing").unwrap()));
+        assert!(matches!(missing, Err(LoadAssetError::NotFound(_))));
+    }
+}
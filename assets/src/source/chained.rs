@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use crate::LoadAssetError;
+use crate::path::AssetPath;
+use crate::source::{AssetReader, AssetSource};
+
+/// Looks up assets in each of its sources in order, so earlier sources override assets of later
+/// ones. Useful for checking a user directory before falling back to the bundled assets.
+pub struct ChainedAssetSource {
+    sources: Vec<Box<dyn AssetSource>>,
+}
+
+impl ChainedAssetSource {
+    pub fn new(sources: Vec<Box<dyn AssetSource>>) -> Self {
+        ChainedAssetSource { sources }
+    }
+
+    /// Appends a source with lower priority than all current sources.
+    pub fn with_source<S: AssetSource + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+}
+
+#[async_trait(? Send)]
+impl AssetSource for ChainedAssetSource {
+    async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+        for source in &self.sources {
+            if let Ok(reader) = source.open_asset_file(path).await {
+                return Ok(reader);
+            }
+        }
+        Err(LoadAssetError::NotFound(path.clone()))
+    }
+
+    /// Merges the listings of every source that can list the directory.
+    async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+        let mut listed = None;
+        for source in &self.sources {
+            if let Ok(paths) = source.list_dir(path).await {
+                listed.get_or_insert_with(Vec::new).extend(paths);
+            }
+        }
+        let mut paths = listed.ok_or_else(|| LoadAssetError::NotFound(path.clone()))?;
+        paths.sort();
+        paths.dedup();
+        Ok(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use async_trait::async_trait;
+
+    use crate::LoadAssetError;
+    use crate::path::AssetPath;
+    use crate::source::{AssetReader, AssetSource, ReadAssetReader};
+    use crate::source::chained::ChainedAssetSource;
+
+    /// Serves assets from memory, keyed by path.
+    struct MemorySource(HashMap<&'static str, &'static str>);
+
+    #[async_trait(? Send)]
+    impl AssetSource for MemorySource {
+        async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+            match self.0.get(path.path_string()) {
+                Some(contents) => Ok(Box::new(ReadAssetReader::new(Cursor::new(contents.as_bytes())))),
+                None => Err(LoadAssetError::NotFound(path.clone())),
+            }
+        }
+
+        async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+            let prefix = format!("{}/", path.path_string());
+            let mut paths: Vec<_> = self.0.keys()
+                .filter(|file| file.strip_prefix(&prefix).is_some_and(|name| !name.contains('/')))
+                .map(|file| AssetPath::new(file).unwrap())
+                .collect();
+            paths.sort();
+            Ok(paths)
+        }
+    }
+
+    /// Can't list directories.
+    struct OpaqueSource;
+
+    #[async_trait(? Send)]
+    impl AssetSource for OpaqueSource {
+        async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+            Err(LoadAssetError::NotFound(path.clone()))
+        }
+    }
+
+    fn read(source: &impl AssetSource, path: &str) -> Result<String, LoadAssetError> {
+        futures::executor::block_on(async {
+            let mut reader = source.open_asset_file(&AssetPath::new(path).unwrap()).await?;
+            Ok(String::from_utf8(reader.read_fully().await).unwrap())
+        })
+    }
+
+    #[test]
+    fn override_and_fallback() {
+        let source = ChainedAssetSource::new(vec![])
+            .with_source(MemorySource(HashMap::from([("/shared", "override")])))
+            .with_source(MemorySource(HashMap::from([("/shared", "base"), ("/base-only", "base")])));
+
+        assert_eq!(read(&source, "/shared").unwrap(), "override");
+        assert_eq!(read(&source, "/base-only").unwrap(), "base");
+        assert!(matches!(read(&source, "/missing"), Err(LoadAssetError::NotFound(_))));
+    }
+
+    #[test]
+    fn merged_listing() {
+        let source = ChainedAssetSource::new(vec![])
+            .with_source(OpaqueSource)
+            .with_source(MemorySource(HashMap::from([("/levels/b", ""), ("/levels/a", "")])))
+            .with_source(MemorySource(HashMap::from([("/levels/a", ""), ("/levels/c/d", ""), ("/other", "")])));
+        let list = |source: &ChainedAssetSource| futures::executor::block_on(source.list_dir(&AssetPath::new("/levels").unwrap()));
+
+        let paths: Vec<_> = list(&source).unwrap().iter().map(|path| path.path_string().to_owned()).collect();
+        assert_eq!(paths, vec!["/levels/a", "/levels/b"]);
+        assert!(matches!(list(&ChainedAssetSource::new(vec![]).with_source(OpaqueSource)), Err(LoadAssetError::NotFound(_))));
+    }
+}
@@ -1,9 +1,12 @@
+use std::collections::HashMap;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use async_trait::async_trait;
 use crate::LoadAssetError;
 use crate::path::AssetPath;
-use crate::source::{AssetReader, AssetSource, ReadAssetReader};
+use crate::source::{AssetReader, AssetSource, AssetWatcher, directory_entry, ReadAssetReader};
 
 pub struct DirectoryAssetSource {
     directory: PathBuf,
@@ -14,19 +17,269 @@ impl DirectoryAssetSource {
         // TODO: Validate
         DirectoryAssetSource { directory: path.into() }
     }
+
+    /// Creates a watcher for assets in the same directory as this source.
+    pub fn watcher(&self) -> DirectoryWatcher {
+        DirectoryWatcher {
+            directory: self.directory.clone(),
+            watched: HashMap::new(),
+        }
+    }
+
+    /// Creates a watcher for assets in the same directory as this source, notified by the file
+    /// system instead of polling the modification time of every watched file.
+    #[cfg(feature = "watch")]
+    pub fn notify_watcher(&self) -> notify::Result<notify_watcher::NotifyWatcher> {
+        notify_watcher::NotifyWatcher::new(&self.directory)
+    }
+}
+
+fn file_path(directory: &Path, path: &AssetPath) -> PathBuf {
+    path.path_string()
+        .trim_start_matches("/")
+        .split("/")
+        .fold(directory.to_path_buf(), |path, segment| path.join(segment))
 }
 
 #[async_trait(?Send)]
 impl AssetSource for DirectoryAssetSource {
     async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
-        let file_path = path.path_string()
-            .trim_start_matches("/")
-            .split("/")
-            .fold(self.directory.clone(), |path, segment| path.join(segment));
-
-        match File::open(file_path) {
+        match File::open(file_path(&self.directory, path)) {
             Err(_) => Err(LoadAssetError::NotFound(path.clone())),
             Ok(file) => Ok(Box::new(ReadAssetReader::new(file))),
         }
     }
+
+    async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+        let entries = std::fs::read_dir(file_path(&self.directory, path))
+            .map_err(|err| match err.kind() {
+                ErrorKind::NotFound => LoadAssetError::NotFound(path.clone()),
+                _ => LoadAssetError::other(err),
+            })?;
+        let mut paths = vec![];
+        for entry in entries {
+            let entry = entry.map_err(LoadAssetError::other)?;
+            if !entry.file_type().map_err(LoadAssetError::other)?.is_file() {
+                continue;
+            }
+            let name = entry.file_name();
+            let Some(name) = name.to_str() else {
+                log::warn!(target: "assets", "Skipping directory entry with invalid name: {:?}", name);
+                continue;
+            };
+            paths.extend(directory_entry(path, name));
+        }
+        paths.sort();
+        Ok(paths)
+    }
+}
+
+/// Watches assets of a [DirectoryAssetSource] by comparing the modification times of their files
+/// on every poll.
+pub struct DirectoryWatcher {
+    directory: PathBuf,
+    watched: HashMap<AssetPath, Option<SystemTime>>,
+}
+
+impl DirectoryWatcher {
+    fn modified(&self, path: &AssetPath) -> Option<SystemTime> {
+        file_path(&self.directory, path).metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
+    /// Starts watching an asset. Only changes after this call are reported.
+    pub fn watch(&mut self, path: AssetPath) {
+        let modified = self.modified(&path);
+        self.watched.insert(path, modified);
+    }
+
+    pub fn unwatch(&mut self, path: &AssetPath) {
+        self.watched.remove(path);
+    }
+}
+
+impl AssetWatcher for DirectoryWatcher {
+    /// Also reports assets whose files were created or removed since the previous poll.
+    fn poll_changes(&mut self) -> Vec<AssetPath> {
+        let mut changed = vec![];
+        for (path, previous) in &self.watched {
+            if self.modified(path) != *previous {
+                changed.push(path.clone());
+            }
+        }
+        for path in &changed {
+            let modified = self.modified(path);
+            self.watched.insert(path.clone(), modified);
+        }
+        changed.sort();
+        changed
+    }
+}
+
+#[cfg(feature = "watch")]
+pub mod notify_watcher {
+    use std::collections::HashSet;
+    use std::path::{Component, Path, PathBuf};
+    use std::sync::mpsc::{channel, Receiver};
+
+    use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+    use crate::path::AssetPath;
+    use crate::source::AssetWatcher;
+
+    /// Watches assets of a [DirectoryAssetSource](super::DirectoryAssetSource) with the file
+    /// system notifications of the platform, see [DirectoryAssetSource::notify_watcher](super::DirectoryAssetSource::notify_watcher).
+    pub struct NotifyWatcher {
+        directory: PathBuf,
+        watched: HashSet<AssetPath>,
+        events: Receiver<notify::Result<Event>>,
+        // stops watching when dropped
+        _watcher: RecommendedWatcher,
+    }
+
+    impl NotifyWatcher {
+        pub(super) fn new(directory: &Path) -> notify::Result<Self> {
+            // notifications report canonical paths, e.g. through symbolic links
+            let directory = directory.canonicalize()?;
+            let (sender, events) = channel();
+            let mut watcher = notify::recommended_watcher(sender)?;
+            watcher.watch(&directory, RecursiveMode::Recursive)?;
+            Ok(NotifyWatcher { directory, watched: HashSet::new(), events, _watcher: watcher })
+        }
+
+        /// Starts watching an asset. Only changes after this call are reported.
+        pub fn watch(&mut self, path: AssetPath) {
+            self.watched.insert(path);
+        }
+
+        pub fn unwatch(&mut self, path: &AssetPath) {
+            self.watched.remove(path);
+        }
+
+        fn asset_path(&self, path: &Path) -> Option<AssetPath> {
+            let relative = path.strip_prefix(&self.directory).ok()?;
+            let mut path_string = String::new();
+            for component in relative.components() {
+                match component {
+                    Component::Normal(segment) => {
+                        path_string.push('/');
+                        path_string.push_str(segment.to_str()?);
+                    }
+                    _ => return None,
+                }
+            }
+            AssetPath::new(&path_string).ok()
+        }
+    }
+
+    impl AssetWatcher for NotifyWatcher {
+        /// Also reports assets whose files were created or removed since the previous poll.
+        fn poll_changes(&mut self) -> Vec<AssetPath> {
+            let mut changed = vec![];
+            for event in self.events.try_iter() {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        log::warn!(target: "assets", "Error watching asset directory: {}", err);
+                        continue;
+                    }
+                };
+                if matches!(event.kind, EventKind::Access(_)) {
+                    continue;
+                }
+                for path in &event.paths {
+                    if let Some(path) = self.asset_path(path) {
+                        if self.watched.contains(&path) && !changed.contains(&path) {
+                            changed.push(path);
+                        }
+                    }
+                }
+            }
+            changed.sort();
+            changed
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    use crate::LoadAssetError;
+    use crate::path::AssetPath;
+    use crate::source::{AssetSource, AssetWatcher};
+    use crate::source::desktop_fs::DirectoryAssetSource;
+
+    #[test]
+    fn watch_modified_file() {
+        let directory = std::env::temp_dir().join(format!("krill-assets-watch-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("shaders")).unwrap();
+        let file_path = directory.join("shaders").join("main.wgsl");
+        std::fs::write(&file_path, "before").unwrap();
+
+        let mut watcher = DirectoryAssetSource::new(&directory).watcher();
+        let path = AssetPath::new("/shaders/main.wgsl").unwrap();
+        watcher.watch(path.clone());
+        watcher.watch(AssetPath::new("/missing.wgsl").unwrap());
+        assert!(watcher.poll_changes().is_empty());
+
+        // set explicitly, as the clock may be coarser than the time between both writes
+        File::options().write(true).open(&file_path).unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert_eq!(watcher.poll_changes(), vec![path]);
+        assert!(watcher.poll_changes().is_empty());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn list_directory() {
+        let directory = std::env::temp_dir().join(format!("krill-assets-list-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("levels").join("nested")).unwrap();
+        for name in ["b.pipeline", "a.pipeline", "invalid name.pipeline"] {
+            std::fs::write(directory.join("levels").join(name), "").unwrap();
+        }
+
+        let source = DirectoryAssetSource::new(&directory);
+        let list = |path| futures::executor::block_on(source.list_dir(&AssetPath::new(path).unwrap()));
+        let expected = vec![AssetPath::new("/levels/a.pipeline").unwrap(), AssetPath::new("/levels/b.pipeline").unwrap()];
+        assert_eq!(list("/levels").unwrap(), expected);
+        assert_eq!(list("/levels/").unwrap(), expected);
+        assert!(list("/levels/nested").unwrap().is_empty());
+        assert!(matches!(list("/missing"), Err(LoadAssetError::NotFound(_))));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn notify_modified_file() {
+        use std::time::Instant;
+
+        let directory = std::env::temp_dir().join(format!("krill-assets-notify-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("shaders")).unwrap();
+        let file_path = directory.join("shaders").join("main.wgsl");
+        std::fs::write(&file_path, "before").unwrap();
+        std::fs::write(directory.join("other.wgsl"), "before").unwrap();
+
+        let mut watcher = DirectoryAssetSource::new(&directory).notify_watcher().unwrap();
+        let path = AssetPath::new("/shaders/main.wgsl").unwrap();
+        watcher.watch(path.clone());
+        assert!(watcher.poll_changes().is_empty());
+
+        std::fs::write(directory.join("other.wgsl"), "after").unwrap();
+        std::fs::write(&file_path, "after").unwrap();
+        // notifications arrive asynchronously
+        let started = Instant::now();
+        let mut changed = vec![];
+        while changed.is_empty() && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+            changed = watcher.poll_changes();
+        }
+        assert_eq!(changed, vec![path]);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
 }
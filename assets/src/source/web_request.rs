@@ -1,21 +1,41 @@
 use std::mem::swap;
 use async_trait::async_trait;
+use futures_util::StreamExt;
 use reqwest::StatusCode;
 use crate::LoadAssetError;
 use crate::path::AssetPath;
-use crate::source::{AssetReader, AssetSource};
+use crate::source::{AssetReader, AssetSource, directory_entry};
 pub use reqwest::{Url, IntoUrl};
 
+/// Invoked as the body of an asset is received, with the path of the asset, the number of bytes
+/// received so far and the total size of the body, if known.
+pub type DownloadProgress = dyn Fn(&AssetPath, u64, Option<u64>) + Sync;
+
+/// Name of the file listing the files of a directory for [AssetSource::list_dir], as a JSON array
+/// of file names, since directories can't be listed over HTTP.
+pub const DIRECTORY_MANIFEST: &str = "index.json";
+
 pub struct WebRequestAssetSource {
     base_url: Url,
+    progress: Option<Box<DownloadProgress>>,
 }
 
 impl WebRequestAssetSource {
     pub fn new<U: IntoUrl>(base_url: U) -> Result<Self, reqwest::Error> {
         Ok(WebRequestAssetSource {
-            base_url: base_url.into_url()?
+            base_url: base_url.into_url()?,
+            progress: None,
         })
     }
+
+    /// Reports byte-level progress while asset bodies are streamed from the response.
+    pub fn with_progress<F>(self, progress: F) -> Self
+        where F: Fn(&AssetPath, u64, Option<u64>) + Sync + 'static {
+        WebRequestAssetSource {
+            progress: Some(Box::new(progress)),
+            ..self
+        }
+    }
 }
 
 #[async_trait(? Send)]
@@ -28,7 +48,16 @@ impl AssetSource for WebRequestAssetSource {
         match reqwest::get(url).await {
             Ok(response) => match response.status() {
                 StatusCode::OK => {
-                    let response = response.bytes().await.map_err(|_| LoadAssetError::UnknownError(path.clone()))?.to_vec();
+                    let total = response.content_length();
+                    let mut body = response.bytes_stream();
+                    let mut response = Vec::with_capacity(total.unwrap_or_default() as _);
+                    while let Some(chunk) = body.next().await {
+                        let chunk = chunk.map_err(|_| LoadAssetError::UnknownError(path.clone()))?;
+                        response.extend_from_slice(&chunk);
+                        if let Some(progress) = &self.progress {
+                            progress(path, response.len() as _, total);
+                        }
+                    }
                     Ok(Box::new(WebRequestAssetReader { response }) as _)
                 }
                 StatusCode::NOT_FOUND => Err(LoadAssetError::NotFound(path.clone())),
@@ -37,6 +66,19 @@ impl AssetSource for WebRequestAssetSource {
             Err(err) => Err(LoadAssetError::other(err)),
         }
     }
+
+    /// Lists the files named in the [DIRECTORY_MANIFEST] of the directory.
+    async fn list_dir(&self, path: &AssetPath) -> Result<Vec<AssetPath>, LoadAssetError> {
+        let manifest = directory_entry(path, DIRECTORY_MANIFEST)
+            .ok_or_else(|| LoadAssetError::NotFound(path.clone()))?;
+        let contents = self.open_asset_file(&manifest).await?.read_fully().await;
+        let names: Vec<String> = serde_json::from_slice(&contents).map_err(LoadAssetError::other)?;
+        let mut paths: Vec<_> = names.iter()
+            .filter_map(|name| directory_entry(path, name))
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
 }
 
 struct WebRequestAssetReader {
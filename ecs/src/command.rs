@@ -0,0 +1,87 @@
+use crate::world::{ComponentBundle, EntityId, World};
+
+type Command = Box<dyn FnOnce(&mut World)>;
+
+/// Records structural changes to a [World] to apply them later with [World::apply], e.g. while
+/// iterating a view that borrows the world.
+#[derive(Default)]
+pub struct CommandBuffer {
+    commands: Vec<Command>,
+}
+
+impl CommandBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues spawning an entity with the components of the hlist `components`, see
+    /// [World::spawn].
+    pub fn spawn(&mut self, components: impl ComponentBundle + 'static) {
+        self.commands.push(Box::new(move |world| {
+            world.spawn(components);
+        }));
+    }
+
+    /// Queues dropping `entity`, see [World::drop_entity].
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.commands.push(Box::new(move |world| world.drop_entity(entity)));
+    }
+
+    /// Queues putting `component` onto `entity`. Skipped if the entity was dropped by the time
+    /// the buffer is applied.
+    pub fn put<C: 'static>(&mut self, entity: EntityId, component: C) {
+        self.commands.push(Box::new(move |world| {
+            if world.is_alive(entity) {
+                world.components_mut::<C>().put(entity, component);
+            }
+        }));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Runs the queued commands in the order they were recorded.
+    pub(crate) fn run(self, world: &mut World) {
+        for command in self.commands {
+            command(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::hlist;
+
+    use crate::command::CommandBuffer;
+    use crate::world::{View, World};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn deferred_changes() {
+        let mut world = World::default().with_component::<Health>();
+        let wounded = world.spawn(hlist!(Health(1)));
+        let healthy = world.spawn(hlist!(Health(3)));
+
+        let mut commands = CommandBuffer::new();
+        for (entity, health) in View::builder().required::<Health>().build(&world).iter_flat() {
+            if health.0 == 1 {
+                commands.despawn(entity);
+                commands.put(entity, Health(2));
+            } else {
+                commands.put(entity, Health(health.0 - 1));
+                commands.spawn(hlist!(Health(health.0)));
+            }
+        }
+        assert!(world.is_alive(wounded));
+        world.apply(commands);
+
+        assert!(world.is_dead(wounded));
+        assert_eq!(world.components::<Health>().get(healthy), Some(&Health(2)));
+        let mut healths: Vec<_> = world.components::<Health>().iter().map(|(_, health)| health.0).collect();
+        healths.sort();
+        assert_eq!(healths, vec![2, 3]);
+    }
+}
@@ -1,3 +1,6 @@
-mod sparse_vec;
+mod packed_vec;
+pub mod command;
+pub mod schedule;
+pub mod snapshot;
 pub mod world;
 pub mod store;
@@ -0,0 +1,118 @@
+/// Marks a slot without a value in [PackedVec::indices].
+const EMPTY: u32 = u32::MAX;
+
+/// Values of sparse slot indices, packed contiguously so iteration only visits present values and
+/// their memory scales with their count rather than the highest slot index. Only the index from
+/// slots into the packed values grows with the highest slot, by a `u32` per slot.
+pub struct PackedVec<T> {
+    /// Position of the value of each slot in `values`, or [EMPTY].
+    indices: Vec<u32>,
+    values: Vec<T>,
+    /// Slot of each value in `values`.
+    slots: Vec<usize>,
+}
+
+impl<T> Default for PackedVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> PackedVec<T> {
+    pub fn new() -> Self {
+        PackedVec { indices: Vec::new(), values: Vec::new(), slots: Vec::new() }
+    }
+
+    fn position(&self, index: usize) -> Option<usize> {
+        match self.indices.get(index) {
+            Some(&position) if position != EMPTY => Some(position as usize),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.position(index).map(|position| &self.values[position])
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        self.position(index).map(|position| &mut self.values[position])
+    }
+
+    /// Raw pointer to the value of slot `index`, which unlike [PackedVec::get_mut] doesn't borrow
+    /// the values, so pointers to values of different slots can be used at the same time.
+    pub fn get_ptr(&mut self, index: usize) -> Option<*mut T> {
+        let position = self.position(index)?;
+        // SAFETY: positions in the indices are always within the values
+        Some(unsafe { self.values.as_mut_ptr().add(position) })
+    }
+
+    pub fn set(&mut self, index: usize, value: T) -> Option<T> {
+        if let Some(position) = self.position(index) {
+            return Some(std::mem::replace(&mut self.values[position], value));
+        }
+
+        if index >= self.indices.len() {
+            self.indices.resize(index + 1, EMPTY);
+        }
+        self.indices[index] = self.values.len() as u32;
+        self.values.push(value);
+        self.slots.push(index);
+        None
+    }
+
+    /// Removes the value of slot `index` if it matches `predicate`. The last value is moved into
+    /// its place, so removal doesn't leave holes.
+    pub fn remove_if<F: FnOnce(&T) -> bool>(&mut self, index: usize, predicate: F) -> Option<T> {
+        let position = self.position(index)?;
+        if !predicate(&self.values[position]) {
+            return None;
+        }
+
+        self.indices[index] = EMPTY;
+        self.slots.swap_remove(position);
+        let value = self.values.swap_remove(position);
+        if let Some(&moved) = self.slots.get(position) {
+            self.indices[moved] = position as u32;
+        }
+        Some(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Iterates over the present values with their slot indices, in packed order.
+    pub fn iter(&self) -> impl Iterator<Item=(usize, &T)> {
+        self.slots.iter().copied().zip(&self.values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packed_vec::PackedVec;
+
+    #[test]
+    fn packed() {
+        let mut values = PackedVec::new();
+        assert_eq!(values.set(3, "a"), None);
+        assert_eq!(values.set(10_000, "b"), None);
+        assert_eq!(values.set(7, "c"), None);
+        assert_eq!(values.set(3, "d"), Some("a"));
+        // memory for values scales with their count, not the highest slot
+        assert_eq!(values.values.len(), 3);
+        assert!(values.values.capacity() < 10);
+
+        // removing moves the last value into the hole
+        assert_eq!(values.remove_if(3, |_| false), None);
+        assert_eq!(values.remove_if(3, |_| true), Some("d"));
+        assert_eq!(values.get(3), None);
+        assert_eq!(values.get(7), Some(&"c"));
+        assert_eq!(values.get(10_000), Some(&"b"));
+        assert_eq!(values.iter().collect::<Vec<_>>(), vec![(7, &"c"), (10_000, &"b")]);
+
+        *values.get_mut(7).unwrap() = "e";
+        assert_eq!(values.remove_if(10_000, |_| true), Some("b"));
+        assert_eq!(values.iter().collect::<Vec<_>>(), vec![(7, &"e")]);
+        assert_eq!(values.remove_if(10_000, |_| true), None);
+    }
+}
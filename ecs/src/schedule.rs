@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::world::World;
+
+type System = Box<dyn FnMut(&mut World)>;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ScheduleError {
+    #[error("system {0} is added more than once")]
+    DuplicateSystem(String),
+    #[error("system {system} is ordered relative to unknown system {other}")]
+    UnknownSystem { system: String, other: String },
+    /// Systems forming a cycle of ordering constraints, each constrained to run before the next
+    /// and the last before the first.
+    #[error("ordering constraints form a cycle: {}", cycle_description(.0))]
+    Cycle(Vec<String>),
+}
+
+fn cycle_description(cycle: &[String]) -> String {
+    let mut description = cycle.join(" -> ");
+    if let Some(first) = cycle.first() {
+        description.push_str(" -> ");
+        description.push_str(first);
+    }
+    description
+}
+
+struct SystemEntry {
+    name: String,
+    system: System,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Collects named systems and the order they have to run in, see [Schedule::builder].
+#[derive(Default)]
+pub struct ScheduleBuilder {
+    systems: Vec<SystemEntry>,
+}
+
+/// Ordering constraints of a system added with [ScheduleBuilder::add_system].
+pub struct SystemConstraints<'a> {
+    entry: &'a mut SystemEntry,
+}
+
+impl SystemConstraints<'_> {
+    /// Runs the system before the system named `other`.
+    pub fn before(self, other: &str) -> Self {
+        self.entry.before.push(other.to_owned());
+        self
+    }
+
+    /// Runs the system after the system named `other`.
+    pub fn after(self, other: &str) -> Self {
+        self.entry.after.push(other.to_owned());
+        self
+    }
+}
+
+impl ScheduleBuilder {
+    /// Adds `system` under `name`, which other systems refer to in their constraints.
+    pub fn add_system(&mut self, name: &str, system: impl FnMut(&mut World) + 'static) -> SystemConstraints<'_> {
+        self.systems.push(SystemEntry {
+            name: name.to_owned(),
+            system: Box::new(system),
+            before: vec![],
+            after: vec![],
+        });
+        SystemConstraints { entry: self.systems.last_mut().unwrap() }
+    }
+
+    /// Orders the systems so every system runs after the systems it is constrained to follow.
+    /// Unconstrained systems keep the order they were added in.
+    pub fn build(self) -> Result<Schedule, ScheduleError> {
+        let mut indices = HashMap::new();
+        for (index, entry) in self.systems.iter().enumerate() {
+            if indices.insert(entry.name.as_str(), index).is_some() {
+                return Err(ScheduleError::DuplicateSystem(entry.name.clone()));
+            }
+        }
+        let index_of = |entry: &SystemEntry, other: &String| indices.get(other.as_str())
+            .copied()
+            .ok_or_else(|| ScheduleError::UnknownSystem { system: entry.name.clone(), other: other.clone() });
+
+        // edges from each system to the systems that have to run after it
+        let mut successors = vec![vec![]; self.systems.len()];
+        let mut predecessor_counts = vec![0; self.systems.len()];
+        for (index, entry) in self.systems.iter().enumerate() {
+            for other in &entry.before {
+                let other = index_of(entry, other)?;
+                successors[index].push(other);
+                predecessor_counts[other] += 1;
+            }
+            for other in &entry.after {
+                let other = index_of(entry, other)?;
+                successors[other].push(index);
+                predecessor_counts[index] += 1;
+            }
+        }
+
+        // Kahn's algorithm, always taking the earliest added system that is ready
+        let mut order = Vec::with_capacity(self.systems.len());
+        let mut scheduled = vec![false; self.systems.len()];
+        while let Some(next) = (0..self.systems.len()).find(|index| !scheduled[*index] && predecessor_counts[*index] == 0) {
+            scheduled[next] = true;
+            order.push(next);
+            for successor in &successors[next] {
+                predecessor_counts[*successor] -= 1;
+            }
+        }
+        if order.len() < self.systems.len() {
+            let cycle = find_cycle(&successors, &scheduled);
+            return Err(ScheduleError::Cycle(cycle.into_iter().map(|index| self.systems[index].name.clone()).collect()));
+        }
+
+        let mut systems: Vec<_> = self.systems.into_iter().map(Some).collect();
+        let systems = order.into_iter()
+            .map(|index| systems[index].take().unwrap())
+            .map(|entry| (entry.name, entry.system))
+            .collect();
+        Ok(Schedule { systems })
+    }
+}
+
+/// Finds a cycle among the systems left unscheduled by the topological sort. Each of them has a
+/// predecessor that is unscheduled too, so walking predecessors eventually revisits a system.
+fn find_cycle(successors: &[Vec<usize>], scheduled: &[bool]) -> Vec<usize> {
+    let predecessor = |index: usize| (0..successors.len())
+        .find(|other| !scheduled[*other] && successors[*other].contains(&index))
+        .expect("unscheduled system has an unscheduled predecessor");
+    let mut path = vec![scheduled.iter().position(|scheduled| !scheduled).unwrap()];
+    loop {
+        let next = predecessor(*path.last().unwrap());
+        if let Some(start) = path.iter().position(|index| *index == next) {
+            // the path was walked backwards
+            let mut cycle = path.split_off(start);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(next);
+    }
+}
+
+/// Systems run in an order satisfying their constraints.
+pub struct Schedule {
+    systems: Vec<(String, System)>,
+}
+
+impl Schedule {
+    pub fn builder() -> ScheduleBuilder {
+        ScheduleBuilder::default()
+    }
+
+    /// Names of the systems in the order they run.
+    pub fn order(&self) -> impl Iterator<Item=&str> {
+        self.systems.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn run(&mut self, world: &mut World) {
+        for (_, system) in &mut self.systems {
+            system(world);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::schedule::{Schedule, ScheduleError};
+    use crate::world::World;
+
+    #[test]
+    fn constrained_order() {
+        let log = Rc::new(RefCell::new(vec![]));
+        let system = |name: &'static str| {
+            let log = log.clone();
+            move |_: &mut World| log.borrow_mut().push(name)
+        };
+        let mut builder = Schedule::builder();
+        builder.add_system("render", system("render")).after("collide");
+        builder.add_system("input", system("input"));
+        builder.add_system("collide", system("collide")).after("spawn");
+        builder.add_system("spawn", system("spawn")).before("render").after("input");
+        let mut schedule = builder.build().unwrap();
+        assert_eq!(schedule.order().collect::<Vec<_>>(), vec!["input", "spawn", "collide", "render"]);
+
+        schedule.run(&mut World::default());
+        assert_eq!(*log.borrow(), vec!["input", "spawn", "collide", "render"]);
+    }
+
+    #[test]
+    fn cyclic_constraints() {
+        let mut builder = Schedule::builder();
+        builder.add_system("input", |_| {});
+        builder.add_system("spawn", |_| {}).after("collide");
+        builder.add_system("collide", |_| {}).after("render");
+        builder.add_system("render", |_| {}).after("spawn").after("input");
+        let error = builder.build().err().unwrap();
+        assert_eq!(error, ScheduleError::Cycle(vec!["render".to_owned(), "collide".to_owned(), "spawn".to_owned()]));
+        assert_eq!(error.to_string(), "ordering constraints form a cycle: render -> collide -> spawn -> render");
+
+        let mut builder = Schedule::builder();
+        builder.add_system("spawn", |_| {}).before("render");
+        assert_eq!(builder.build().err().unwrap().to_string(), "system spawn is ordered relative to unknown system render");
+    }
+}
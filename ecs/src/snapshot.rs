@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::world::{Generation, Tick};
+
+/// Entities and serializable components of a [World](crate::world::World), taken by
+/// [World::snapshot](crate::world::World::snapshot). It is itself serializable, e.g. to write a
+/// save file.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldSnapshot {
+    /// Generation of each entity slot, and whether the entity in it is alive.
+    pub(crate) entities: Vec<(Generation, bool)>,
+    pub(crate) spawn_ticks: Vec<Tick>,
+    pub(crate) enabled: Vec<bool>,
+    pub(crate) tick: Tick,
+    /// Components by type name, as a list of entity index, generation and component.
+    pub(crate) components: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("component type {0} is not registered as serializable")]
+    UnknownComponent(String),
+    #[error("failed to convert components of type {name}: {source}")]
+    Component { name: String, source: serde_json::Error },
+    #[error("snapshot is inconsistent: {0}")]
+    Inconsistent(String),
+}
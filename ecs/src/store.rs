@@ -1,35 +1,406 @@
-use crate::sparse_vec::SparseVec;
+use std::collections::HashMap;
+use std::mem::ManuallyDrop;
+use std::ptr::{addr_of_mut, NonNull};
+
+use crate::packed_vec::PackedVec;
 use crate::world::{EntityId, Generation};
 
+/// How a [ComponentStore] keeps its components.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum StorageKind {
+    /// Components packed contiguously for fast iteration, with a small index entry for every
+    /// entity, suited for components most entities have.
+    #[default]
+    Dense,
+    /// Only entities that have the component take up memory, suited for rare components.
+    Sparse,
+}
+
+/// Value of a sparse storage, allocated on its own and owned through a raw pointer. Looking up an
+/// entry in the map references the other entries it probes, which only covers these pointers and
+/// not the values that might be borrowed mutably at the same time, see [Storage::get_ptr].
+struct SparseEntry<T>(NonNull<T>);
+
+impl<T> SparseEntry<T> {
+    fn new(value: T) -> Self {
+        // SAFETY: the pointer of a box is never null
+        SparseEntry(unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(value))) })
+    }
+
+    fn into_inner(self) -> T {
+        let entry = ManuallyDrop::new(self);
+        // SAFETY: the value was allocated by a box, which is no longer freed by the entry
+        *unsafe { Box::from_raw(entry.0.as_ptr()) }
+    }
+
+    fn get(&self) -> &T {
+        // SAFETY: the entry owns the value, borrowed for as long as the entry
+        unsafe { self.0.as_ref() }
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        // SAFETY: the entry owns the value, borrowed exclusively for as long as the entry
+        unsafe { self.0.as_mut() }
+    }
+}
+
+impl<T> Drop for SparseEntry<T> {
+    fn drop(&mut self) {
+        // SAFETY: the value was allocated by a box, and the entry is its only owner
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
+}
+
+enum Storage<T> {
+    Dense(PackedVec<(Generation, T)>),
+    Sparse(HashMap<usize, SparseEntry<(Generation, T)>>),
+}
+
+impl<T> Storage<T> {
+    fn get(&self, index: usize) -> Option<&(Generation, T)> {
+        match self {
+            Storage::Dense(components) => components.get(index),
+            Storage::Sparse(components) => components.get(&index).map(SparseEntry::get),
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut (Generation, T)> {
+        match self {
+            Storage::Dense(components) => components.get_mut(index),
+            Storage::Sparse(components) => components.get_mut(&index).map(SparseEntry::get_mut),
+        }
+    }
+
+    /// Raw pointer to the entry of `index`. Neither storage references the entries while looking
+    /// it up: dense values are offset from the start of their vector, and the map of sparse
+    /// values only holds pointers to them.
+    ///
+    /// # Safety
+    ///
+    /// `storage` must be valid for writes, and no reference into the storage may be alive.
+    /// References to entries other than the one of `index` are fine.
+    unsafe fn get_ptr(storage: *mut Self, index: usize) -> Option<*mut (Generation, T)> {
+        match &mut *storage {
+            Storage::Dense(components) => components.get_ptr(index),
+            Storage::Sparse(components) => components.get(&index).map(|entry| entry.0.as_ptr()),
+        }
+    }
+
+    fn set(&mut self, index: usize, value: (Generation, T)) -> Option<(Generation, T)> {
+        match self {
+            Storage::Dense(components) => components.set(index, value),
+            Storage::Sparse(components) => components.insert(index, SparseEntry::new(value)).map(SparseEntry::into_inner),
+        }
+    }
+
+    fn remove(&mut self, entity: EntityId) -> Option<T> {
+        let removed = match self {
+            Storage::Dense(components) => components
+                .remove_if(entity.index, |(generation, _)| *generation == entity.generation),
+            Storage::Sparse(components) => match components.get(&entity.index).map(SparseEntry::get) {
+                Some((generation, _)) if *generation == entity.generation => components.remove(&entity.index).map(SparseEntry::into_inner),
+                _ => None,
+            },
+        };
+        removed.map(|(_, component)| component)
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Storage::Dense(components) => components.len(),
+            Storage::Sparse(components) => components.len(),
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item=(usize, &(Generation, T))> + '_> {
+        match self {
+            Storage::Dense(components) => Box::new(components.iter()),
+            Storage::Sparse(components) => Box::new(components.iter().map(|(index, entry)| (*index, entry.get()))),
+        }
+    }
+}
+
+/// A component with the [version](ComponentStore::version) of the store it last changed at.
+struct Tracked<T> {
+    changed: u64,
+    component: T,
+}
+
+/// Invoked with each component a [ComponentStore] drops, see [ComponentStore::set_on_remove].
+pub type RemoveHook<T> = Box<dyn FnMut(EntityId, T)>;
+
 pub struct ComponentStore<T> {
-    components: SparseVec<(Generation, T)>,
+    components: Storage<Tracked<T>>,
+    version: u64,
+    /// Version at the last [ComponentStore::drain_changes].
+    drained: u64,
+    on_remove: Option<RemoveHook<T>>,
 }
 
 impl<T> Default for ComponentStore<T> {
     fn default() -> Self {
-        Self { components: SparseVec::new() }
+        Self::new(StorageKind::default())
     }
 }
 
 impl<T> ComponentStore<T> {
+    pub fn new(kind: StorageKind) -> Self {
+        let components = match kind {
+            StorageKind::Dense => Storage::Dense(PackedVec::new()),
+            StorageKind::Sparse => Storage::Sparse(HashMap::new()),
+        };
+        Self { components, version: 0, drained: 0, on_remove: None }
+    }
+
+    pub fn kind(&self) -> StorageKind {
+        match self.components {
+            Storage::Dense(_) => StorageKind::Dense,
+            Storage::Sparse(_) => StorageKind::Sparse,
+        }
+    }
+
     pub fn get(&self, entity: EntityId) -> Option<&T> {
+        self.tracked(entity).map(|tracked| &tracked.component)
+    }
+
+    fn tracked(&self, entity: EntityId) -> Option<&Tracked<T>> {
         match self.components.get(entity.index) {
-            Some((generation, component)) if generation == &entity.generation => Some(component),
+            Some((generation, tracked)) if generation == &entity.generation => Some(tracked),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the component of `entity`. Counts as a mutation of the
+    /// store, whether or not the component is actually changed.
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<&mut T> {
+        match self.components.get_mut(entity.index) {
+            Some((generation, tracked)) if generation == &entity.generation => {
+                self.version += 1;
+                tracked.changed = self.version;
+                Some(&mut tracked.component)
+            }
             _ => None,
         }
     }
 
+    /// Like [ComponentStore::get_mut], but through a raw pointer to the store, so the components
+    /// of different entities can be borrowed mutably at the same time.
+    ///
+    /// # Safety
+    ///
+    /// `store` must be valid for writes for `'a`, and no reference into the store may be alive
+    /// other than to components of other entities.
+    pub(crate) unsafe fn get_mut_ptr<'a>(store: *mut Self, entity: EntityId) -> Option<&'a mut T> {
+        let entry = Storage::get_ptr(addr_of_mut!((*store).components), entity.index)?;
+        if (*entry).0 != entity.generation {
+            return None;
+        }
+        let version = addr_of_mut!((*store).version);
+        *version += 1;
+        let tracked = addr_of_mut!((*entry).1);
+        (*tracked).changed = *version;
+        Some(&mut *addr_of_mut!((*tracked).component))
+    }
+
+    /// Sets the component of `entity`. A component it replaces, whether of the same entity or
+    /// left behind by a dropped entity in the same slot, is passed to the
+    /// [on_remove hook](ComponentStore::set_on_remove).
     pub fn put(&mut self, entity: EntityId, component: T) {
-        self.components.set(entity.index, (entity.generation, component));
+        self.version += 1;
+        let tracked = Tracked { changed: self.version, component };
+        let replaced = self.components.set(entity.index, (entity.generation, tracked));
+        if let Some((generation, tracked)) = replaced {
+            self.removed(EntityId { index: entity.index, generation }, tracked.component);
+        }
     }
 
+    /// Removes and returns the component of `entity`. As the caller takes the component, it is not
+    /// passed to the [on_remove hook](ComponentStore::set_on_remove).
     pub fn remove(&mut self, entity: EntityId) -> Option<T> {
-        self.components
-            .remove_if(entity.index, |(generation, _)| *generation == entity.generation)
-            .map(|(_, component)| component)
+        let removed = self.components.remove(entity);
+        if removed.is_some() {
+            self.version += 1;
+        }
+        removed.map(|tracked| tracked.component)
+    }
+
+    /// Monotonically increasing counter, advanced by every mutation of the store. Comparing it
+    /// against a previously seen version tells whether any component might have changed since.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Whether the component of `entity` was put or mutably accessed after the store was at
+    /// `version`, see [ComponentStore::version]. Always false if the entity has no component.
+    pub fn changed_since(&self, entity: EntityId, version: u64) -> bool {
+        self.tracked(entity).is_some_and(|tracked| tracked.changed > version)
     }
 
     pub fn has(&self, entity: EntityId) -> bool {
         self.get(entity).is_some()
     }
+
+    /// Iterates over all components with the entities they belong to, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item=(EntityId, &T)> {
+        self.components.iter()
+            .map(|(index, (generation, tracked))| (EntityId { index, generation: *generation }, &tracked.component))
+    }
+
+    /// Returns the entities whose component was put or mutably accessed since the previous call,
+    /// or since the store was created, sorted by index. Like [ComponentStore::changed_since], but
+    /// the store keeps track of the version itself, so there can only be a single consumer.
+    pub fn drain_changes(&mut self) -> Vec<EntityId> {
+        let drained = std::mem::replace(&mut self.drained, self.version);
+        let mut changed: Vec<_> = self.components.iter()
+            .filter(|(_, (_, tracked))| tracked.changed > drained)
+            .map(|(index, (generation, _))| EntityId { index, generation: *generation })
+            .collect();
+        changed.sort_unstable_by_key(|entity| entity.index);
+        changed
+    }
+
+    /// Number of components in the store. Components left behind by dropped entities count too.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the entities with a component, in no particular order. Only visits the
+    /// components of the store, unlike scanning every entity of the world.
+    pub fn entities(&self) -> impl Iterator<Item=EntityId> + '_ {
+        self.components.iter()
+            .map(|(index, (generation, _))| EntityId { index, generation: *generation })
+    }
+
+    /// Removes the component of `entity`, passing it to the
+    /// [on_remove hook](ComponentStore::set_on_remove). Returns whether there was a component.
+    pub fn discard(&mut self, entity: EntityId) -> bool {
+        match self.remove(entity) {
+            Some(component) => {
+                self.removed(entity, component);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sets a hook receiving every component the store would otherwise drop, together with the
+    /// entity it belonged to, e.g. to release resources the component refers to. Components are
+    /// dropped when they are replaced by [ComponentStore::put], [discarded](ComponentStore::discard),
+    /// or when their entity is dropped with [World::drop_entity](crate::world::World::drop_entity).
+    pub fn set_on_remove(&mut self, hook: impl FnMut(EntityId, T) + 'static) {
+        self.on_remove = Some(Box::new(hook));
+    }
+
+    fn removed(&mut self, entity: EntityId, component: T) {
+        if let Some(hook) = &mut self.on_remove {
+            hook(entity, component);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::{ComponentStore, StorageKind};
+    use crate::world::World;
+
+    #[test]
+    fn version() {
+        let mut world = World::default();
+        let entity = world.new_entity();
+        let other = world.new_entity();
+        let mut store = ComponentStore::default();
+        assert_eq!(store.version(), 0);
+
+        store.put(entity, 1u32);
+        let version = store.version();
+        assert!(version > 0);
+
+        assert_eq!(store.get(entity), Some(&1));
+        assert!(store.has(entity));
+        assert_eq!(store.version(), version);
+
+        *store.get_mut(entity).unwrap() = 2;
+        assert!(store.version() > version);
+        let version = store.version();
+
+        // nothing to mutate
+        assert_eq!(store.get_mut(other), None);
+        assert_eq!(store.remove(other), None);
+        assert_eq!(store.version(), version);
+
+        assert_eq!(store.remove(entity), Some(2));
+        assert!(store.version() > version);
+    }
+
+    #[test]
+    fn changed_since() {
+        for kind in [StorageKind::Dense, StorageKind::Sparse] {
+            let mut world = World::default();
+            let [moving, still, removed] = [(); 3].map(|_| world.new_entity());
+            let mut store = ComponentStore::new(kind);
+            store.put(moving, 1.0f32);
+            store.put(still, 2.0);
+            store.put(removed, 3.0);
+            let version = store.version();
+            assert!(!store.changed_since(moving, version));
+            assert!(store.changed_since(still, 0));
+
+            *store.get_mut(moving).unwrap() += 1.0;
+            store.remove(removed);
+            assert!(store.changed_since(moving, version));
+            assert!(!store.changed_since(still, version));
+            assert!(!store.changed_since(removed, version));
+
+            store.put(still, 2.0);
+            assert!(store.changed_since(still, version));
+        }
+    }
+
+    #[test]
+    fn stale_generation() {
+        for kind in [StorageKind::Dense, StorageKind::Sparse] {
+            let mut world = World::default();
+            let dropped = world.new_entity();
+            let mut store = ComponentStore::new(kind);
+            assert_eq!(store.kind(), kind);
+            store.put(dropped, "dropped");
+
+            world.drop_entity(dropped);
+            // reuses the slot of the dropped entity
+            let reused = world.new_entity();
+            assert!(!store.has(reused));
+            assert_eq!(store.remove(reused), None);
+
+            store.put(reused, "reused");
+            assert_eq!(store.get(dropped), None);
+            assert_eq!(store.get(reused), Some(&"reused"));
+        }
+    }
+
+    #[test]
+    fn semantics() {
+        for kind in [StorageKind::Dense, StorageKind::Sparse] {
+            let mut world = World::default();
+            let entities: Vec<_> = (0..4).map(|_| world.new_entity()).collect();
+            let mut store = ComponentStore::new(kind);
+
+            for (i, entity) in entities.iter().enumerate() {
+                store.put(*entity, i);
+            }
+            store.put(entities[1], 10);
+            assert_eq!(store.get(entities[1]), Some(&10));
+            assert_eq!(store.remove(entities[0]), Some(0));
+            assert!(!store.has(entities[0]));
+            assert!(store.has(entities[3]));
+            *store.get_mut(entities[3]).unwrap() += 1;
+
+            let mut components: Vec<_> = store.iter().map(|(entity, component)| (entity, *component)).collect();
+            components.sort_by_key(|(entity, _)| entity.index);
+            assert_eq!(components, vec![(entities[1], 10), (entities[2], 2), (entities[3], 4)]);
+        }
+    }
 }
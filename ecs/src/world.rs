@@ -1,21 +1,92 @@
 use std::any::{Any, type_name, TypeId};
-use std::collections::HashMap;
+use std::cell::UnsafeCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
 use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use utils::hlist::{FnMapHList, Mappable, Prepend};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use utils::hlist::{FnMapHList, IntoColumns, IntoTuple, Mappable, Prepend};
 
-use crate::store::ComponentStore;
+use crate::command::CommandBuffer;
+use crate::snapshot::{SnapshotError, WorldSnapshot};
+use crate::store::{ComponentStore, StorageKind};
 
 pub type Generation = u32;
 
-#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+/// Value of the counter advanced by [World::tick].
+pub type Tick = u64;
+
+/// Ids are ordered by the slot of the entity, then by generation.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Debug)]
 pub struct EntityId {
     pub(crate) index: usize,
     pub(crate) generation: Generation,
 }
 
+/// An [EntityId] tagged with a marker type, so ids of different kinds of entities can't be mixed
+/// up. The marker is purely a compile-time tag and adds no runtime cost.
+///
+/// ```compile_fail
+/// use ecs::world::{Entity, World};
+///
+/// #[derive(Default)]
+/// struct Player;
+/// #[derive(Default)]
+/// struct Meteor;
+///
+/// let mut world = World::default().with_component::<Player>();
+/// let mut meteor: Entity<Meteor> = Entity::from_id_unchecked(world.new_entity());
+/// meteor = world.spawn_typed::<Player>();
+/// ```
+pub struct Entity<M> {
+    id: EntityId,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> Entity<M> {
+    /// Tags an id without verifying that the entity has the `M` marker component.
+    pub fn from_id_unchecked(id: EntityId) -> Self {
+        Entity { id, marker: PhantomData }
+    }
+
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+}
+
+impl<M> Clone for Entity<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Entity<M> {}
+
+impl<M> PartialEq for Entity<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<M> Eq for Entity<M> {}
+
+impl<M> Debug for Entity<M> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Entity<{}>({:?})", type_name::<M>(), self.id)
+    }
+}
+
+impl<M> From<Entity<M>> for EntityId {
+    fn from(value: Entity<M>) -> Self {
+        value.id
+    }
+}
+
 enum EntityState {
     Alive(Generation),
     Dead(Generation),
@@ -64,58 +135,428 @@ impl PartialEq<EntityId> for &EntityState {
     }
 }
 
-pub struct GenericComponentStore(Box<dyn Any>);
+/// Marks a component type that can be duplicated by [World::clone_entity]. Such components must
+/// be registered with [World::add_cloneable_component].
+pub trait CloneComponent: Clone + 'static {}
+
+type ComponentCloner = fn(&mut GenericComponentStore, EntityId, EntityId);
+
+/// Marks a component type that is included in [World::debug_entity]. Such components must be
+/// registered with [World::add_debuggable_component].
+pub trait DebuggableComponent: Debug + 'static {}
+
+type ComponentDebugger = fn(&GenericComponentStore, EntityId) -> Option<String>;
+
+/// Marks a component type that is registered on first use by [World::components_mut_or_default]
+/// and [World::put], unless the world is [strict](World::set_strict).
+pub trait DefaultComponent: 'static {
+    /// Storage the component type is registered with.
+    const KIND: StorageKind = StorageKind::Dense;
+}
+
+type ComponentDiscarder = fn(&mut GenericComponentStore, EntityId);
+
+/// Marks a component type that is included in [World::snapshot]. Such components must be
+/// registered with [World::add_serializable_component].
+pub trait SerializableComponent: Serialize + DeserializeOwned + 'static {}
+
+/// Serialized form of the components of a store, see [WorldSnapshot].
+type SerializedComponents<C> = Vec<(usize, Generation, C)>;
+
+#[derive(Copy, Clone)]
+struct ComponentSerde {
+    serialize: fn(&GenericComponentStore) -> serde_json::Result<Value>,
+    /// Deserializes into a boxed [SerializedComponents], so every store of a snapshot can be
+    /// checked before the world is changed.
+    deserialize: fn(Value) -> serde_json::Result<Box<dyn Any>>,
+    /// Ids of the entities in a boxed [SerializedComponents].
+    entities: fn(&dyn Any) -> Vec<EntityId>,
+    restore: fn(&mut GenericComponentStore, Box<dyn Any>),
+}
+
+impl ComponentSerde {
+    fn new<C: SerializableComponent>() -> Self {
+        ComponentSerde {
+            serialize: |store| {
+                let components: SerializedComponents<&C> = store.store_for::<C>().iter()
+                    .map(|(entity, component)| (entity.index, entity.generation, component))
+                    .collect();
+                serde_json::to_value(components)
+            },
+            deserialize: |value| {
+                let components: SerializedComponents<C> = serde_json::from_value(value)?;
+                Ok(Box::new(components))
+            },
+            entities: |components| {
+                components.downcast_ref::<SerializedComponents<C>>()
+                    .expect("component type has already been checked")
+                    .iter()
+                    .map(|(index, generation, _)| EntityId { index: *index, generation: *generation })
+                    .collect()
+            },
+            restore: |store, components| {
+                let components = components.downcast::<SerializedComponents<C>>()
+                    .expect("component type has already been checked");
+                let store = store.store_for_mut::<C>();
+                for (index, generation, component) in *components {
+                    store.put(EntityId { index, generation }, component);
+                }
+            },
+        }
+    }
+}
+
+pub struct GenericComponentStore {
+    store: Box<dyn Any>,
+    type_name: &'static str,
+    cloner: Option<ComponentCloner>,
+    debugger: Option<ComponentDebugger>,
+    serde: Option<ComponentSerde>,
+    discarder: ComponentDiscarder,
+}
 
 impl GenericComponentStore {
-    fn new<C: 'static>() -> GenericComponentStore {
-        let store = ComponentStore::<C>::default();
-        GenericComponentStore(Box::new(store))
+    fn new<C: 'static>(kind: StorageKind) -> GenericComponentStore {
+        let store = ComponentStore::<C>::new(kind);
+        GenericComponentStore {
+            store: Box::new(store),
+            type_name: type_name::<C>(),
+            cloner: None,
+            debugger: None,
+            serde: None,
+            discarder: |store, entity| { store.store_for_mut::<C>().discard(entity); },
+        }
     }
 
     fn store_for<C: 'static>(&self) -> &ComponentStore<C> {
-        self.0.downcast_ref().expect("component type has already been checked")
+        self.store.downcast_ref().expect("component type has already been checked")
     }
 
     fn store_for_mut<C: 'static>(&mut self) -> &mut ComponentStore<C> {
-        self.0.downcast_mut().expect("component type has already been checked")
+        self.store.downcast_mut().expect("component type has already been checked")
+    }
+}
+
+/// An hlist of component types, see [World::with_components].
+pub trait ComponentList {
+    fn register(world: &mut World, kind: StorageKind);
+}
+
+impl ComponentList for () {
+    fn register(_world: &mut World, _kind: StorageKind) {}
+}
+
+impl<H: 'static, T: ComponentList> ComponentList for (H, T) {
+    fn register(world: &mut World, kind: StorageKind) {
+        world.add_component::<H>(kind);
+        T::register(world, kind);
+    }
+}
+
+/// An hlist of components, put together onto a new entity by [World::spawn].
+pub trait ComponentBundle {
+    fn put(self, world: &World, entity: EntityId);
+}
+
+impl ComponentBundle for () {
+    fn put(self, _world: &World, _entity: EntityId) {}
+}
+
+impl<H: 'static, T: ComponentBundle> ComponentBundle for (H, T) {
+    fn put(self, world: &World, entity: EntityId) {
+        let (head, tail) = self;
+        world.components_mut::<H>().put(entity, head);
+        tail.put(world, entity);
     }
 }
 
 #[derive(Default)]
 pub struct World {
     entities: Vec<EntityState>,
+    /// Indices of the dead entity slots, most recently dropped last.
+    free: Vec<usize>,
+    /// Tick each entity was spawned at, by entity index.
+    spawn_ticks: Vec<Tick>,
+    /// Whether each entity is matched by views by default, by entity index.
+    enabled: Vec<bool>,
+    tick: Tick,
+    /// Whether [DefaultComponent]s must be registered explicitly like any other component.
+    strict: bool,
     components: HashMap<TypeId, RwLock<GenericComponentStore>>,
+    /// Shared state that doesn't belong to any entity, by type.
+    resources: HashMap<TypeId, Box<dyn Any>>,
 }
 
 impl World {
+    /// Creates an entity without components. The slot of the most recently dropped entity is
+    /// reused if there is one, with a new generation so ids of the dropped entity stay dead.
     pub fn new_entity(&mut self) -> EntityId {
-        for (index, state) in self.entities.iter_mut().enumerate() {
-            if state.is_dead() {
-                let generation = state.make_alive();
-                return EntityId { index, generation };
-            }
+        if let Some(index) = self.free.pop() {
+            let generation = self.entities[index].make_alive();
+            self.spawn_ticks[index] = self.tick;
+            self.enabled[index] = true;
+            return EntityId { index, generation };
         }
 
         let index = self.entities.len();
         let generation = 0;
 
         self.entities.push(EntityState::Alive(generation));
+        self.spawn_ticks.push(self.tick);
+        self.enabled.push(true);
 
         EntityId { index, generation }
     }
 
-    pub fn add_component<C: 'static>(&mut self) {
+    /// Creates a new entity with every component of the hlist `components`:
+    ///
+    /// ```
+    /// use ecs::world::World;
+    /// use utils::{hlist, HList};
+    ///
+    /// struct Position(f32, f32);
+    /// struct Velocity(f32, f32);
+    ///
+    /// let mut world = World::default().with_components::<HList!(Position, Velocity)>();
+    /// let entity = world.spawn(hlist!(Position(0.0, 0.0), Velocity(1.0, 0.0)));
+    /// ```
+    ///
+    /// Panics if a component type of the bundle isn't registered.
+    pub fn spawn(&mut self, components: impl ComponentBundle) -> EntityId {
+        let entity = self.new_entity();
+        components.put(self, entity);
+        entity
+    }
+
+    /// Advances the tick counter, typically once per frame, returning the new tick.
+    pub fn tick(&mut self) -> Tick {
+        self.tick += 1;
+        self.tick
+    }
+
+    pub fn current_tick(&self) -> Tick {
+        self.tick
+    }
+
+    /// Returns the tick `entity` was spawned at, if it is alive.
+    pub fn spawn_tick(&self, entity: EntityId) -> Option<Tick> {
+        self.is_alive(entity).then(|| self.spawn_ticks[entity.index])
+    }
+
+    /// Registers a component type, kept in the given kind of storage. The storage kind is
+    /// transparent to [World::components] and views.
+    pub fn add_component<C: 'static>(&mut self, kind: StorageKind) {
         self.components.insert(
             TypeId::of::<C>(),
-            RwLock::new(GenericComponentStore::new::<C>()),
+            RwLock::new(GenericComponentStore::new::<C>(kind)),
         );
     }
 
     pub fn with_component<C: 'static>(mut self) -> Self {
-        self.add_component::<C>();
+        self.add_component::<C>(StorageKind::Dense);
         self
     }
 
+    pub fn with_sparse_component<C: 'static>(mut self) -> Self {
+        self.add_component::<C>(StorageKind::Sparse);
+        self
+    }
+
+    /// Registers every component type of the hlist `L`, all kept in the given kind of storage.
+    pub fn add_components<L: ComponentList>(&mut self, kind: StorageKind) {
+        L::register(self, kind);
+    }
+
+    /// Registers every component type of the hlist `L`, as with [World::with_component]:
+    ///
+    /// ```
+    /// use ecs::world::World;
+    /// use utils::HList;
+    ///
+    /// struct Position(f32, f32);
+    /// struct Velocity(f32, f32);
+    ///
+    /// let world = World::default().with_components::<HList!(Position, Velocity)>();
+    /// ```
+    pub fn with_components<L: ComponentList>(mut self) -> Self {
+        self.add_components::<L>(StorageKind::Dense);
+        self
+    }
+
+    /// Registers a component type that is copied along when an entity is duplicated with
+    /// [World::clone_entity]. If the component type is already registered, it is made cloneable
+    /// while keeping its existing components.
+    pub fn add_cloneable_component<C: CloneComponent>(&mut self) {
+        let store = self.components.entry(TypeId::of::<C>())
+            .or_insert_with(|| RwLock::new(GenericComponentStore::new::<C>(StorageKind::Dense)));
+        store.get_mut().unwrap().cloner = Some(|store, source, target| {
+            let store = store.store_for_mut::<C>();
+            if let Some(component) = store.get(source).cloned() {
+                store.put(target, component);
+            }
+        });
+    }
+
+    pub fn with_cloneable_component<C: CloneComponent>(mut self) -> Self {
+        self.add_cloneable_component::<C>();
+        self
+    }
+
+    /// Registers a component type that is included in [World::debug_entity]. If the component type
+    /// is already registered, it is made debuggable while keeping its existing components.
+    pub fn add_debuggable_component<C: DebuggableComponent>(&mut self) {
+        let store = self.components.entry(TypeId::of::<C>())
+            .or_insert_with(|| RwLock::new(GenericComponentStore::new::<C>(StorageKind::Dense)));
+        store.get_mut().unwrap().debugger = Some(|store, entity| {
+            store.store_for::<C>().get(entity)
+                .map(|component| format!("{}: {:?}", type_name::<C>(), component))
+        });
+    }
+
+    pub fn with_debuggable_component<C: DebuggableComponent>(mut self) -> Self {
+        self.add_debuggable_component::<C>();
+        self
+    }
+
+    /// Registers a component type that is included in [World::snapshot]. If the component type is
+    /// already registered, it is made serializable while keeping its existing components.
+    pub fn add_serializable_component<C: SerializableComponent>(&mut self) {
+        let store = self.components.entry(TypeId::of::<C>())
+            .or_insert_with(|| RwLock::new(GenericComponentStore::new::<C>(StorageKind::Dense)));
+        store.get_mut().unwrap().serde = Some(ComponentSerde::new::<C>());
+    }
+
+    pub fn with_serializable_component<C: SerializableComponent>(mut self) -> Self {
+        self.add_serializable_component::<C>();
+        self
+    }
+
+    /// Captures the entities of the world along with their serializable components. Components
+    /// that were not registered with [World::add_serializable_component] are left out.
+    pub fn snapshot(&self) -> Result<WorldSnapshot, SnapshotError> {
+        let mut components = BTreeMap::new();
+        for store in self.components.values() {
+            let store = store.read().unwrap();
+            if let Some(serde) = store.serde {
+                let serialized = (serde.serialize)(&store)
+                    .map_err(|source| SnapshotError::Component { name: store.type_name.to_owned(), source })?;
+                components.insert(store.type_name.to_owned(), serialized);
+            }
+        }
+
+        Ok(WorldSnapshot {
+            entities: self.entities.iter().map(|state| (state.generation(), state.is_alive())).collect(),
+            spawn_ticks: self.spawn_ticks.clone(),
+            enabled: self.enabled.clone(),
+            tick: self.tick,
+            components,
+        })
+    }
+
+    /// Replaces the entities of the world with those of `snapshot`, keeping the indices and
+    /// generations of their ids, so ids taken before the snapshot stay valid. The current entities
+    /// are dropped along with all of their components, including those that aren't serializable.
+    ///
+    /// The world is left unchanged if the snapshot contains a component type that isn't
+    /// registered as serializable, components that fail to deserialize, or components of entities
+    /// that aren't alive in it.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) -> Result<(), SnapshotError> {
+        let entities = snapshot.entities.len();
+        if snapshot.spawn_ticks.len() != entities || snapshot.enabled.len() != entities {
+            return Err(SnapshotError::Inconsistent(format!(
+                "{} entities, but {} spawn ticks and {} enabled flags",
+                entities, snapshot.spawn_ticks.len(), snapshot.enabled.len(),
+            )));
+        }
+
+        let mut restored = Vec::with_capacity(snapshot.components.len());
+        for (name, serialized) in snapshot.components {
+            let (typ, serde) = self.components.iter()
+                .find_map(|(typ, store)| {
+                    let store = store.read().unwrap();
+                    (store.type_name == name).then_some(store.serde).flatten().map(|serde| (*typ, serde))
+                })
+                .ok_or_else(|| SnapshotError::UnknownComponent(name.clone()))?;
+            let components = (serde.deserialize)(serialized)
+                .map_err(|source| SnapshotError::Component { name: name.clone(), source })?;
+            let dead = (serde.entities)(components.as_ref()).into_iter()
+                .find(|entity| snapshot.entities.get(entity.index) != Some(&(entity.generation, true)));
+            if let Some(entity) = dead {
+                return Err(SnapshotError::Inconsistent(format!("component of type {} belongs to {:?}, which isn't alive", name, entity)));
+            }
+            restored.push((typ, serde, components));
+        }
+
+        self.drop_entities(self.entities_snapshot());
+        self.entities = snapshot.entities.into_iter()
+            .map(|(generation, alive)| if alive { EntityState::Alive(generation) } else { EntityState::Dead(generation) })
+            .collect();
+        self.free = (0..self.entities.len()).rev()
+            .filter(|index| self.entities[*index].is_dead())
+            .collect();
+        self.spawn_ticks = snapshot.spawn_ticks;
+        self.enabled = snapshot.enabled;
+        self.tick = snapshot.tick;
+        for (typ, serde, components) in restored {
+            let store = self.components.get_mut(&typ).unwrap().get_mut().unwrap();
+            (serde.restore)(store, components);
+        }
+        Ok(())
+    }
+
+    /// Formats every debuggable component of `entity`, one per line and sorted by type name.
+    /// Components that were not registered with [World::add_debuggable_component] are omitted.
+    pub fn debug_entity(&self, entity: EntityId) -> String {
+        if !self.is_alive(entity) {
+            return format!("{:?} (dead)", entity);
+        }
+
+        let mut components: Vec<_> = self.components.values()
+            .filter_map(|store| {
+                let store = store.read().unwrap();
+                store.debugger.and_then(|debugger| debugger(&store, entity))
+            })
+            .collect();
+        components.sort();
+
+        let mut dump = format!("{:?}", entity);
+        for component in components {
+            dump.push_str("\n  ");
+            dump.push_str(&component);
+        }
+        dump
+    }
+
+    /// Creates a new entity with a copy of every cloneable component of `source`. Components that
+    /// were registered with [World::add_component] are not copied. Returns `None` without creating
+    /// an entity if `source` is not alive.
+    pub fn clone_entity(&mut self, source: EntityId) -> Option<EntityId> {
+        if !self.is_alive(source) {
+            return None;
+        }
+        let entity = self.new_entity();
+        for store in self.components.values_mut() {
+            let store = store.get_mut().unwrap();
+            if let Some(cloner) = store.cloner {
+                cloner(store, source, entity);
+            }
+        }
+        Some(entity)
+    }
+
+    /// Creates a new entity with a default `M` marker component, returning its typed id.
+    pub fn spawn_typed<M: 'static + Default>(&mut self) -> Entity<M> {
+        let entity = self.new_entity();
+        self.components_mut::<M>().put(entity, M::default());
+        Entity::from_id_unchecked(entity)
+    }
+
+    /// Returns the typed id of `entity` if it is alive and has an `M` marker component.
+    pub fn get_typed<M: 'static>(&self, entity: EntityId) -> Option<Entity<M>> {
+        (self.is_alive(entity) && self.components::<M>().has(entity))
+            .then(|| Entity::from_id_unchecked(entity))
+    }
+
     pub fn is_alive(&self, entity: EntityId) -> bool {
         self.entities.get(entity.index).map_or(false, |state| state == entity)
     }
@@ -124,10 +565,73 @@ impl World {
         !self.is_alive(entity)
     }
 
+    /// Disables or re-enables `entity`. Disabled entities keep their components, but are skipped
+    /// by views unless they [include disabled entities](ViewBuilder::include_disabled). Entities
+    /// are enabled when spawned.
+    pub fn set_enabled(&mut self, entity: EntityId, enabled: bool) {
+        if self.is_alive(entity) {
+            self.enabled[entity.index] = enabled;
+        }
+    }
+
+    /// Whether `entity` is alive and enabled, see [World::set_enabled].
+    pub fn is_enabled(&self, entity: EntityId) -> bool {
+        self.is_alive(entity) && self.enabled[entity.index]
+    }
+
+    /// Drops the entity along with its components, which are passed to the
+    /// [on_remove hooks](World::on_remove) of their types.
     pub fn drop_entity(&mut self, entity: EntityId) {
-        if let Some(state) = self.entities.get_mut(entity.index) {
-            if state.is_alive() {
-                state.make_dead();
+        if !self.is_alive(entity) {
+            return;
+        }
+
+        self.entities[entity.index].make_dead();
+        self.free.push(entity.index);
+        for store in self.components.values_mut() {
+            let store = store.get_mut().unwrap();
+            (store.discarder)(store, entity);
+        }
+    }
+
+    /// Drops every entity of `entities`, see [World::drop_entity]. Duplicate and already dropped
+    /// ids are skipped.
+    pub fn drop_entities(&mut self, entities: impl IntoIterator<Item=EntityId>) {
+        for entity in entities {
+            self.drop_entity(entity);
+        }
+    }
+
+    /// Applies the changes recorded in `commands`, in the order they were recorded.
+    pub fn apply(&mut self, commands: CommandBuffer) {
+        commands.run(self);
+    }
+
+    /// Sets the hook receiving every component of type `C` that is dropped, see
+    /// [ComponentStore::set_on_remove].
+    pub fn on_remove<C: 'static>(&mut self, hook: impl FnMut(EntityId, C) + 'static) {
+        self.components_mut::<C>().set_on_remove(hook);
+    }
+
+    /// Panics if `types` contains a component type more than once, or a component type that is
+    /// currently locked by a view or component store lock. Call it with the component types about
+    /// to be accessed mutably, to catch overlapping access that would otherwise deadlock. Only
+    /// checks in debug builds.
+    pub fn assert_disjoint(&self, types: &[TypeId]) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        for (i, typ) in types.iter().enumerate() {
+            let store = self.components.get(typ).expect("unknown component type");
+            // the name is behind the lock, which might be held for writing
+            let type_name = || store.try_read()
+                .map_or_else(|_| format!("{:?}", typ), |store| store.type_name.to_owned());
+            if types[..i].contains(typ) {
+                panic!("component type {} is accessed mutably more than once", type_name());
+            }
+            if store.try_write().is_err() {
+                panic!("component type {} is already locked", type_name());
             }
         }
     }
@@ -138,7 +642,73 @@ impl World {
     }
 
     pub fn components_mut<C: 'static>(&self) -> ComponentStoreWriteLock<'_, C> {
-        ComponentStoreWriteLock::lock(&self.components[&TypeId::of::<C>()])
+        ComponentStoreWriteLock::lock(self.components.get(&TypeId::of::<C>())
+            .unwrap_or_else(|| panic!("unknown component type: {}", type_name::<C>())))
+    }
+
+    /// Returns the alive entities whose `C` component was put or mutably accessed since the
+    /// previous call, see [ComponentStore::drain_changes]. Lets e.g. a renderer update only the
+    /// transforms that moved, complementing the [on_remove hooks](World::on_remove).
+    pub fn changes<C: 'static>(&self) -> impl Iterator<Item=EntityId> + '_ {
+        let changed = self.components_mut::<C>().drain_changes();
+        changed.into_iter().filter(|entity| self.is_alive(*entity))
+    }
+
+    /// Requires every component type to be registered before use, including
+    /// [DefaultComponent]s, so a missing registration panics instead of going unnoticed.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn strict(mut self) -> Self {
+        self.set_strict(true);
+        self
+    }
+
+    /// Like [World::components_mut], but registers the component type first if it isn't yet.
+    /// Panics like [World::components_mut] in a [strict](World::set_strict) world.
+    pub fn components_mut_or_default<C: DefaultComponent>(&mut self) -> ComponentStoreWriteLock<'_, C> {
+        if !self.strict && !self.components.contains_key(&TypeId::of::<C>()) {
+            self.add_component::<C>(C::KIND);
+        }
+        self.components_mut()
+    }
+
+    /// Puts `component` onto `entity`, registering its type first like
+    /// [World::components_mut_or_default].
+    pub fn put<C: DefaultComponent>(&mut self, entity: EntityId, component: C) {
+        self.components_mut_or_default::<C>().put(entity, component);
+    }
+
+    /// Stores `resource` in the world, replacing any previous resource of the same type. Useful
+    /// for shared state that systems iterating the world need, but no entity owns.
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.resources.insert(TypeId::of::<R>(), Box::new(resource));
+    }
+
+    /// Returns the resource of type `R`. Panics if there is none, see [World::insert_resource].
+    pub fn resource<R: 'static>(&self) -> &R {
+        self.resources.get(&TypeId::of::<R>())
+            .and_then(|resource| resource.downcast_ref())
+            .unwrap_or_else(|| panic!("unknown resource type: {}", type_name::<R>()))
+    }
+
+    /// Like [World::resource], but returns a mutable reference.
+    pub fn resource_mut<R: 'static>(&mut self) -> &mut R {
+        self.resources.get_mut(&TypeId::of::<R>())
+            .and_then(|resource| resource.downcast_mut())
+            .unwrap_or_else(|| panic!("unknown resource type: {}", type_name::<R>()))
+    }
+
+    /// Number of alive entities. Visits every entity slot, so it is O(n) in the number of entities
+    /// ever alive at once.
+    pub fn len(&self) -> usize {
+        self.entities.iter().filter(|state| state.is_alive()).count()
+    }
+
+    /// Whether no entity is alive, see [World::len].
+    pub fn is_empty(&self) -> bool {
+        !self.entities.iter().any(EntityState::is_alive)
     }
 
     pub fn entity_iter(&self) -> impl Iterator<Item=EntityId> + '_ {
@@ -147,36 +717,76 @@ impl World {
             .filter_map(|(index, state)| state.alive_generation().map(|gen| (index, gen)))
             .map(|(index, generation)| EntityId { index, generation })
     }
+
+    /// Collects the ids of all entities alive right now. Unlike [World::entity_iter], the snapshot
+    /// doesn't borrow the world, so entities can be spawned and dropped while iterating it.
+    /// Entities spawned after the snapshot are not part of it, while entities dropped after it
+    /// still are, so check [World::is_alive] before using an id. Only the set of ids is captured:
+    /// components of the entities in the snapshot may still change.
+    pub fn entities_snapshot(&self) -> Vec<EntityId> {
+        self.entity_iter().collect()
+    }
 }
 
-pub struct ComponentBinding<T, R> {
+pub struct ComponentBinding<T, R, L> {
     typ: PhantomData<T>,
     requirement: PhantomData<R>,
+    lock: PhantomData<L>,
+    /// Store version that [Changed] components must have changed after.
+    since: u64,
 }
 
-impl<T, R> Default for ComponentBinding<T, R> {
+impl<T, R, L> Default for ComponentBinding<T, R, L> {
     fn default() -> Self {
         ComponentBinding {
             typ: Default::default(),
             requirement: Default::default(),
+            lock: Default::default(),
+            since: 0,
         }
     }
 }
 
-impl<T, R> Clone for ComponentBinding<T, R> {
+impl<T, R, L> Clone for ComponentBinding<T, R, L> {
     fn clone(&self) -> Self {
-        ComponentBinding::default()
+        *self
+    }
+}
+
+impl<T, R, L> Copy for ComponentBinding<T, R, L> {}
+
+/// Hlist of [ComponentBinding]s, listing the component types a [ViewBuilder] binds.
+pub trait Bindings {
+    /// Appends the type id, type name and whether it is bound mutably, for every binding.
+    fn collect_types(types: &mut Vec<(TypeId, &'static str, bool)>);
+}
+
+impl<T: 'static, R, L: LockType, Tail: Bindings> Bindings for (ComponentBinding<T, R, L>, Tail) {
+    fn collect_types(types: &mut Vec<(TypeId, &'static str, bool)>) {
+        types.push((TypeId::of::<T>(), type_name::<T>(), L::EXCLUSIVE));
+        Tail::collect_types(types);
     }
 }
 
-impl<T, R> Copy for ComponentBinding<T, R> {}
+impl Bindings for () {
+    fn collect_types(_types: &mut Vec<(TypeId, &'static str, bool)>) {}
+}
 
 pub trait BindingRequirement {
+    /// Whether only components changed since the version of the binding are matched, see
+    /// [ComponentStore::changed_since].
+    const CHANGED_ONLY: bool = false;
+
     type Resolved<T, C>: Prepend
         where C: Prepend;
 
     fn resolve<T, C>(component: Option<T>, list: C) -> Result<Self::Resolved<T, C>, ()>
         where C: Prepend;
+
+    /// Whether an entity matches the requirement, depending on whether it has the component.
+    fn accepts(present: bool) -> bool {
+        Self::resolve(present.then_some(()), ()).is_ok()
+    }
 }
 
 pub struct Required;
@@ -219,23 +829,57 @@ impl BindingRequirement for Marked {
     }
 }
 
-pub struct Bound<'v, T: 'static, R: BindingRequirement> {
-    store: ComponentStoreLock<'v, T, ReadLockType>,
-    binding: ComponentBinding<T, R>,
+/// Like [Required], but only matches components that changed since a version of their store.
+pub struct Changed;
+
+impl BindingRequirement for Changed {
+    const CHANGED_ONLY: bool = true;
+
+    type Resolved<T, C> = (T, C)
+        where C: Prepend;
+
+    fn resolve<T, C>(component: Option<T>, list: C) -> Result<(T, C), ()>
+        where C: Prepend {
+        Required::resolve(component, list)
+    }
+}
+
+/// Only matches entities without the component, which is left out of the result.
+pub struct Excluded;
+
+impl BindingRequirement for Excluded {
+    type Resolved<T, C> = C
+        where C: Prepend;
+
+    fn resolve<T, C>(component: Option<T>, list: C) -> Result<C, ()>
+        where C: Prepend {
+        match component {
+            Some(_) => Err(()),
+            None => Ok(list),
+        }
+    }
+}
+
+pub struct Bound<'v, T: 'static, R: BindingRequirement, L: LockType> {
+    // components are handed out through a shared reference to the view, mutable ones are only
+    // reachable through the exclusive methods of View
+    store: UnsafeCell<ComponentStoreLock<'v, T, L>>,
+    binding: ComponentBinding<T, R, L>,
 }
 
 pub struct StoreLocker<'a> {
     world: &'a World,
 }
 
-impl<'a, T, R, Tail, RTail> FnMapHList<(ComponentBinding<T, R>, Tail), (Bound<'a, T, R>, RTail)> for StoreLocker<'a>
+impl<'a, T, R, L, Tail, RTail> FnMapHList<(ComponentBinding<T, R, L>, Tail), (Bound<'a, T, R, L>, RTail)> for StoreLocker<'a>
     where T: 'static,
           R: BindingRequirement,
+          L: LockType,
           Self: FnMapHList<Tail, RTail> {
-    fn invoke(self, list: (ComponentBinding<T, R>, Tail)) -> (Bound<'a, T, R>, RTail) {
+    fn invoke(self, list: (ComponentBinding<T, R, L>, Tail)) -> (Bound<'a, T, R, L>, RTail) {
         let (binding, tail) = list;
-        let store = self.world.components();
-        (Bound { store, binding }, self.invoke(tail))
+        let store = ComponentStoreLock::lock(&self.world.components[&TypeId::of::<T>()]);
+        (Bound { store: UnsafeCell::new(store), binding }, self.invoke(tail))
     }
 }
 
@@ -247,44 +891,126 @@ impl<'a> FnMapHList<(), ()> for StoreLocker<'a> {
 
 pub struct ViewBuilder<C> {
     components: C,
+    spawned_since: Option<Tick>,
+    include_disabled: bool,
 }
 
 impl ViewBuilder<()> {
     fn new() -> Self {
-        Self { components: () }
+        Self { components: (), spawned_since: None, include_disabled: false }
     }
 }
 
 impl<C> ViewBuilder<C>
     where C: Prepend {
-    fn with_binding<T: 'static, R>(self, binding: ComponentBinding<T, R>) -> ViewBuilder<(ComponentBinding<T, R>, C)> {
-        ViewBuilder { components: self.components.prepend(binding) }
+    fn with_binding<T: 'static, R, L>(self, binding: ComponentBinding<T, R, L>) -> ViewBuilder<(ComponentBinding<T, R, L>, C)> {
+        ViewBuilder {
+            components: self.components.prepend(binding),
+            spawned_since: self.spawned_since,
+            include_disabled: self.include_disabled,
+        }
+    }
+
+    /// Only matches entities spawned after the given tick.
+    pub fn spawned_since(self, tick: Tick) -> Self {
+        ViewBuilder { spawned_since: Some(tick), ..self }
     }
 
-    pub fn required<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Required>, C)> {
+    /// Skips [disabled](World::set_enabled) entities, which is the default.
+    pub fn enabled_only(self) -> Self {
+        ViewBuilder { include_disabled: false, ..self }
+    }
+
+    /// Also matches [disabled](World::set_enabled) entities.
+    pub fn include_disabled(self) -> Self {
+        ViewBuilder { include_disabled: true, ..self }
+    }
+
+    pub fn required<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Required, ReadLockType>, C)> {
+        self.with_binding(ComponentBinding::default())
+    }
+
+    pub fn optional<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Optional, ReadLockType>, C)> {
+        self.with_binding(ComponentBinding::default())
+    }
+
+    /// Like [ViewBuilder::required], but locks the component store for writing, so the view
+    /// yields `&mut T`. Only [View::iter_mut] and [View::get_mut] are available on a view with
+    /// mutable bindings.
+    pub fn required_mut<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Required, WriteLockType>, C)> {
+        self.with_binding(ComponentBinding::default())
+    }
+
+    /// Like [ViewBuilder::optional], but yields `Option<&mut T>`, see [ViewBuilder::required_mut].
+    pub fn optional_mut<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Optional, WriteLockType>, C)> {
         self.with_binding(ComponentBinding::default())
     }
 
-    pub fn optional<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Optional>, C)> {
+    pub fn marked<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Marked, ReadLockType>, C)> {
         self.with_binding(ComponentBinding::default())
     }
 
-    pub fn marked<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Marked>, C)> {
+    /// Like [ViewBuilder::required], but only matches entities whose `T` component was put or
+    /// mutably accessed after its store was at `version`, e.g. a [ComponentStore::version]
+    /// recorded when the components were last processed.
+    pub fn changed<T: 'static>(self, version: u64) -> ViewBuilder<(ComponentBinding<T, Changed, ReadLockType>, C)> {
+        self.with_binding(ComponentBinding { since: version, ..Default::default() })
+    }
+
+    /// Only matches entities that don't have a `T` component.
+    pub fn without<T: 'static>(self) -> ViewBuilder<(ComponentBinding<T, Excluded, ReadLockType>, C)> {
         self.with_binding(ComponentBinding::default())
     }
 
+    /// Locks the bound component stores of `world`.
+    ///
+    /// Panics if a mutably bound component type is bound more than once, as that would alias
+    /// mutable references.
     pub fn build<'a, R>(self, world: &'a World) -> View<'a, R>
-        where C: Mappable,
+        where C: Mappable + Bindings,
               R: Bounds,
               StoreLocker<'a>: FnMapHList<C, R> {
+        let mut types = Vec::new();
+        C::collect_types(&mut types);
+        let exclusive: Vec<_> = types.iter()
+            .filter(|(_, _, exclusive)| *exclusive)
+            .map(|(typ, name, _)| (*typ, *name))
+            .collect();
+        for (typ, name) in &exclusive {
+            if types.iter().filter(|(other, _, _)| other == typ).count() > 1 {
+                panic!("component type {} is bound mutably more than once in a view", name);
+            }
+        }
+        // catches write locks that would deadlock on a lock held elsewhere
+        world.assert_disjoint(&exclusive.iter().map(|(typ, _)| *typ).collect::<Vec<_>>());
+
         let stores = self.components.map(StoreLocker { world });
-        View { world, bounds: stores }
+        View { world, bounds: stores, spawned_since: self.spawned_since, include_disabled: self.include_disabled }
+    }
+
+    /// Builds a view over the entities of two separate worlds, e.g. game and UI entities, which
+    /// can then be iterated in one pass. See [ViewBuilder::build] for the locking rules, which
+    /// apply to each world separately. A [ViewBuilder::spawned_since] tick is compared against
+    /// each world's own ticks. Panics if both worlds are the same, whose entities would be visited
+    /// twice and whose mutably bound stores would be locked twice.
+    pub fn build_joined<'a, R>(self, primary: &'a World, secondary: &'a World) -> JoinedView<'a, R>
+        where C: Mappable + Bindings + Copy,
+              R: Bounds,
+              StoreLocker<'a>: FnMapHList<C, R> {
+        assert!(!std::ptr::eq(primary, secondary), "a joined view must be built over two separate worlds");
+        let secondary_builder = ViewBuilder { ..self };
+        JoinedView {
+            primary: self.build(primary),
+            secondary: secondary_builder.build(secondary),
+        }
     }
 }
 
 pub struct View<'w, B: Bounds> {
     world: &'w World,
     bounds: B,
+    spawned_since: Option<Tick>,
+    include_disabled: bool,
 }
 
 impl<'w> View<'w, ()> {
@@ -294,56 +1020,280 @@ impl<'w> View<'w, ()> {
 }
 
 impl<'w, B: Bounds> View<'w, B> {
-    pub fn iter<'v>(&'v self) -> EntityIterator<'w, 'v, B, impl 'w + Iterator<Item=EntityId>>
+    /// Alive entities passing the spawn tick and enabled filters, before matching the bounds, in
+    /// the order of their indices. When the view binds a component every match must have, only
+    /// the entities of the smallest such store are visited rather than every entity of the world.
+    fn candidates(&self) -> impl 'w + Iterator<Item=EntityId> {
+        let world = self.world;
+        let spawned_since = self.spawned_since;
+        let include_disabled = self.include_disabled;
+        let (driven, scanned) = match self.bounds.smallest_required() {
+            Some((_, entities)) => {
+                let mut entities: Vec<_> = entities.filter(|entity| world.is_alive(*entity)).collect();
+                entities.sort_unstable_by_key(|entity| entity.index);
+                (entities, None)
+            }
+            None => (vec![], Some(world.entity_iter())),
+        };
+        driven.into_iter()
+            .chain(scanned.into_iter().flatten())
+            .filter(move |entity| spawned_since.is_none_or(|tick| world.spawn_ticks[entity.index] > tick))
+            .filter(move |entity| include_disabled || world.enabled[entity.index])
+    }
+
+    fn entities<'v>(&'v self) -> EntityIterator<'w, 'v, B, impl 'w + Iterator<Item=EntityId>>
         where 'w: 'v {
-        let iter = self.world.entity_iter();
         EntityIterator {
             view: self,
-            iter,
+            iter: self.candidates(),
         }
     }
-}
 
-pub trait Bounds {
-    type Result<'a, C>
-        where Self: 'a,
-              C: 'a + Prepend;
+    /// Counts the matched entities without fetching their components. Only the entities of the
+    /// smallest store the view requires are visited, so it is O(n) in the size of that store, or
+    /// in the number of entity slots of the world when the view requires no component.
+    pub fn count(&self) -> usize {
+        self.candidates()
+            .filter(|entity| self.bounds.accepts_entity(*entity))
+            .count()
+    }
 
-    fn match_entity<'v, C>(&'v self, entity: EntityId, list: C) -> Option<Self::Result<'v, C>>
-        where C: 'v + Prepend;
-}
+    fn matches(&self, entity: EntityId) -> bool {
+        self.world.is_alive(entity)
+            && self.spawned_since.is_none_or(|tick| self.world.spawn_ticks[entity.index] > tick)
+            && (self.include_disabled || self.world.enabled[entity.index])
+    }
 
-impl<'b, T: 'static, R, Tail> Bounds for (Bound<'b, T, R>, Tail)
-    where R: BindingRequirement,
-          Tail: Bounds {
-    type Result<'a, C> = Tail::Result<'a, R::Resolved<&'a T, C>>
-        where Self: 'a,
-              C: 'a + Prepend;
+    /// Like [View::iter], but also available for views with mutable bindings, yielding `&mut T`
+    /// for components bound with [ViewBuilder::required_mut] or [ViewBuilder::optional_mut].
+    /// Counts as a mutation of every mutably bound store, see [ComponentStore::version].
+    pub fn iter_mut<'v>(&'v mut self) -> EntityIterator<'w, 'v, B, impl 'w + Iterator<Item=EntityId>>
+        where 'w: 'v {
+        // each entity is yielded once, so the mutable references never alias
+        self.entities()
+    }
 
-    fn match_entity<'v, C>(&'v self, entity: EntityId, list: C) -> Option<Self::Result<'v, C>>
-        where C: 'v + Prepend {
-        let component = self.0.store.get(entity);
-        let list = match R::resolve(component, list) {
-            Ok(list) => list,
-            Err(_) => return None,
-        };
+    /// Like [View::iter_flat], but yields mutable references like [View::iter_mut].
+    pub fn iter_flat_mut<'v>(&'v mut self) -> impl Iterator<Item=<(EntityId, B::Result<'v, ()>) as IntoTuple>::Tuple> + 'v
+        where 'w: 'v,
+              (EntityId, B::Result<'v, ()>): IntoTuple {
+        let view: &'v Self = self;
+        view.entities().map(IntoTuple::into_tuple)
+    }
 
-        self.1.match_entity(entity, list)
+    /// Like [View::get], but yields mutable references like [View::iter_mut].
+    pub fn get_mut<'v>(&'v mut self, entity: EntityId) -> Option<B::Result<'v, ()>>
+        where 'w: 'v {
+        if !self.matches(entity) {
+            return None;
+        }
+        // SAFETY: the view is borrowed exclusively for as long as the components are
+        unsafe { self.bounds.match_entity(entity, ()) }
     }
 }
 
-impl Bounds for () {
-    type Result<'a, C> = C
-        where Self: 'a,
-              C: 'a + Prepend;
+impl<'w, B: ReadOnlyBounds> View<'w, B> {
+    pub fn iter<'v>(&'v self) -> EntityIterator<'w, 'v, B, impl 'w + Iterator<Item=EntityId>>
+        where 'w: 'v {
+        self.entities()
+    }
 
-    fn match_entity<'w, C>(&self, _entity: EntityId, list: C) -> Option<C>
-        where C: 'w + Prepend {
-        Some(list)
+    /// Like [View::iter], but guarantees the entities are yielded in ascending [EntityId] order.
+    /// Entities spawned later never move the ones already matched relative to each other, and
+    /// dropping an entity only removes it from the sequence, so per-entity data such as instance
+    /// buffers can be laid out in this order frame after frame. Dropped slots are reused though,
+    /// so a newly spawned entity may appear in between existing ones.
+    pub fn iter_sorted_by_entity<'v>(&'v self) -> EntityIterator<'w, 'v, B, impl 'w + Iterator<Item=EntityId>>
+        where 'w: 'v {
+        // candidates are visited in slot order, whether driven by a store or by the world
+        self.entities()
     }
-}
 
-pub struct EntityIterator<'w, 'v, B: Bounds, I: 'w + Iterator<Item=EntityId>> {
+    /// Like [View::iter], but yields flat tuples of the entity and its components, e.g.
+    /// `(EntityId, &A, Option<&B>)` rather than `(EntityId, (&A, (Option<&B>, ())))`. Marked
+    /// components are left out, like in the nested form. Views of up to five bound components
+    /// are supported.
+    pub fn iter_flat<'v>(&'v self) -> impl Iterator<Item=<(EntityId, B::Result<'v, ()>) as IntoTuple>::Tuple> + 'v
+        where 'w: 'v,
+              (EntityId, B::Result<'v, ()>): IntoTuple {
+        self.iter().map(IntoTuple::into_tuple)
+    }
+
+    /// Matches a single entity against the view, returning its components if it is alive and
+    /// matches the view's bounds. Stale ids of dropped entities never match, even when a component
+    /// was put for them after they were dropped.
+    pub fn get<'v>(&'v self, entity: EntityId) -> Option<B::Result<'v, ()>>
+        where 'w: 'v {
+        if !self.matches(entity) {
+            return None;
+        }
+        // SAFETY: read only bounds never hand out mutable references
+        unsafe { self.bounds.match_entity(entity, ()) }
+    }
+
+    /// Collects the matched entities and their components in structure-of-arrays form: a [Vec] of
+    /// entity ids, and an hlist with a [Vec] per bound component, e.g.
+    /// `(Vec<&A>, (Vec<Option<&B>>, ()))`. The i-th element of every [Vec] belongs to the i-th
+    /// entity, which suits bulk processing of a single component type.
+    pub fn collect_soa<'v>(&'v self) -> (Vec<EntityId>, <B::Result<'v, ()> as IntoColumns>::Columns)
+        where 'w: 'v,
+              B::Result<'v, ()>: IntoColumns {
+        let mut entities = Vec::new();
+        let mut columns = Default::default();
+        for (entity, components) in self.iter() {
+            entities.push(entity);
+            components.push_into(&mut columns);
+        }
+        (entities, columns)
+    }
+}
+
+/// Id of an entity matched by a [JoinedView], namespaced by the world it belongs to, since ids of
+/// separate worlds may collide.
+#[derive(Eq, PartialEq, Copy, Clone, Debug)]
+pub enum JoinedEntity {
+    Primary(EntityId),
+    Secondary(EntityId),
+}
+
+/// Two [View]s with the same bindings over separate worlds, see [ViewBuilder::build_joined].
+/// Entities of the primary world are yielded first.
+pub struct JoinedView<'w, B: Bounds> {
+    primary: View<'w, B>,
+    secondary: View<'w, B>,
+}
+
+impl<'w, B: Bounds> JoinedView<'w, B> {
+    fn entities(&'w self) -> impl Iterator<Item=(JoinedEntity, B::Result<'w, ()>)> + 'w {
+        self.primary.entities().map(|(entity, components)| (JoinedEntity::Primary(entity), components))
+            .chain(self.secondary.entities().map(|(entity, components)| (JoinedEntity::Secondary(entity), components)))
+    }
+
+    /// Like [View::iter_mut], over both worlds.
+    pub fn iter_mut<'v>(&'v mut self) -> impl Iterator<Item=(JoinedEntity, B::Result<'v, ()>)> + 'v
+        where 'w: 'v {
+        // each entity is yielded once, so the mutable references never alias
+        let view: &'v JoinedView<'v, B> = self;
+        view.entities()
+    }
+
+    /// Like [View::get_mut], looking the entity up in the world it belongs to.
+    pub fn get_mut<'v>(&'v mut self, entity: JoinedEntity) -> Option<B::Result<'v, ()>>
+        where 'w: 'v {
+        match entity {
+            JoinedEntity::Primary(entity) => self.primary.get_mut(entity),
+            JoinedEntity::Secondary(entity) => self.secondary.get_mut(entity),
+        }
+    }
+}
+
+impl<'w, B: ReadOnlyBounds> JoinedView<'w, B> {
+    /// Like [View::iter], over both worlds.
+    pub fn iter<'v>(&'v self) -> impl Iterator<Item=(JoinedEntity, B::Result<'v, ()>)> + 'v
+        where 'w: 'v {
+        let view: &'v JoinedView<'v, B> = self;
+        view.entities()
+    }
+
+    /// Like [View::get], looking the entity up in the world it belongs to.
+    pub fn get<'v>(&'v self, entity: JoinedEntity) -> Option<B::Result<'v, ()>>
+        where 'w: 'v {
+        match entity {
+            JoinedEntity::Primary(entity) => self.primary.get(entity),
+            JoinedEntity::Secondary(entity) => self.secondary.get(entity),
+        }
+    }
+}
+
+pub trait Bounds {
+    type Result<'a, C>
+        where Self: 'a,
+              C: 'a + Prepend;
+
+    /// # Safety
+    ///
+    /// Mutably bound components of an entity must not be matched again while references to
+    /// them from a previous match are alive.
+    unsafe fn match_entity<'v, C>(&'v self, entity: EntityId, list: C) -> Option<Self::Result<'v, C>>
+        where C: 'v + Prepend;
+
+    /// Whether [Bounds::match_entity] would match `entity`, without fetching any components.
+    fn accepts_entity(&self, entity: EntityId) -> bool;
+
+    /// Size and entities of the smallest bound store whose component every matched entity must
+    /// have, if any.
+    fn smallest_required(&self) -> Option<(usize, Box<dyn Iterator<Item=EntityId> + '_>)>;
+}
+
+/// [Bounds] without mutable bindings, which can be matched through a shared reference to the
+/// view.
+pub trait ReadOnlyBounds: Bounds {}
+
+impl<'b, T: 'static, R, L, Tail> Bounds for (Bound<'b, T, R, L>, Tail)
+    where R: BindingRequirement,
+          L: LockType,
+          Tail: Bounds {
+    type Result<'a, C> = Tail::Result<'a, R::Resolved<L::Ref<'a, T>, C>>
+        where Self: 'a,
+              C: 'a + Prepend;
+
+    unsafe fn match_entity<'v, C>(&'v self, entity: EntityId, list: C) -> Option<Self::Result<'v, C>>
+        where C: 'v + Prepend {
+        let unchanged = R::CHANGED_ONLY && !(*self.0.store.get()).changed_since(entity, self.0.binding.since);
+        let component = if unchanged { None } else { L::fetch(&self.0.store, entity) };
+        let list = match R::resolve(component, list) {
+            Ok(list) => list,
+            Err(_) => return None,
+        };
+
+        self.1.match_entity(entity, list)
+    }
+
+    fn accepts_entity(&self, entity: EntityId) -> bool {
+        // SAFETY: only reads the store, mutable references to components are never held while
+        // the view is borrowed shared
+        let store = unsafe { &*self.0.store.get() };
+        let present = store.has(entity) && (!R::CHANGED_ONLY || store.changed_since(entity, self.0.binding.since));
+        R::accepts(present) && self.1.accepts_entity(entity)
+    }
+
+    fn smallest_required(&self) -> Option<(usize, Box<dyn Iterator<Item=EntityId> + '_>)> {
+        // SAFETY: only reads the store, see accepts_entity
+        let store = unsafe { &*self.0.store.get() };
+        let own = (!R::accepts(false)).then(|| (store.len(), Box::new(store.entities()) as Box<dyn Iterator<Item=EntityId>>));
+        match (own, self.1.smallest_required()) {
+            (Some(own), Some(tail)) => Some(if tail.0 < own.0 { tail } else { own }),
+            (own, tail) => own.or(tail),
+        }
+    }
+}
+
+impl Bounds for () {
+    type Result<'a, C> = C
+        where Self: 'a,
+              C: 'a + Prepend;
+
+    unsafe fn match_entity<'w, C>(&self, _entity: EntityId, list: C) -> Option<C>
+        where C: 'w + Prepend {
+        Some(list)
+    }
+
+    fn accepts_entity(&self, _entity: EntityId) -> bool {
+        true
+    }
+
+    fn smallest_required(&self) -> Option<(usize, Box<dyn Iterator<Item=EntityId> + '_>)> {
+        None
+    }
+}
+
+impl<'b, T: 'static, R, Tail> ReadOnlyBounds for (Bound<'b, T, R, ReadLockType>, Tail)
+    where R: BindingRequirement,
+          Tail: ReadOnlyBounds {}
+
+impl ReadOnlyBounds for () {}
+
+pub struct EntityIterator<'w, 'v, B: Bounds, I: 'w + Iterator<Item=EntityId>> {
     view: &'v View<'w, B>,
     iter: I,
 }
@@ -355,7 +1305,9 @@ impl<'w, 'v, B, I> Iterator for EntityIterator<'w, 'v, B, I>
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(candidate) = self.iter.next() {
-            if let Some(matched) = self.view.bounds.match_entity(candidate, ()) {
+            // SAFETY: the candidates are distinct entities, and views with mutable bounds are
+            // only iterated while borrowed exclusively
+            if let Some(matched) = unsafe { self.view.bounds.match_entity(candidate, ()) } {
                 return Some((candidate, matched));
             }
         }
@@ -363,35 +1315,72 @@ impl<'w, 'v, B, I> Iterator for EntityIterator<'w, 'v, B, I>
     }
 }
 
-pub trait LockType {
+pub trait LockType: Sized {
     type LockGuard<'a>: Deref<Target=GenericComponentStore>;
+    /// Reference to a component in a store locked this way.
+    type Ref<'a, T: 'a>
+        where Self: 'a;
+    /// Whether the lock grants mutable access.
+    const EXCLUSIVE: bool;
 
     fn lock(rwlock: &RwLock<GenericComponentStore>) -> Self::LockGuard<'_>;
+
+    /// Pointer to the store behind a lock guard, which all accesses through the lock go through.
+    fn store_ptr<C: 'static>(guard: &mut Self::LockGuard<'_>) -> NonNull<ComponentStore<C>>;
+
+    /// # Safety
+    ///
+    /// For exclusive locks, no other reference into the store may be alive that could alias the
+    /// component of `entity`.
+    unsafe fn fetch<'a, T: 'static>(store: &'a UnsafeCell<ComponentStoreLock<'_, T, Self>>, entity: EntityId) -> Option<Self::Ref<'a, T>>;
 }
 
 pub struct ReadLockType;
 
 impl LockType for ReadLockType {
     type LockGuard<'a> = RwLockReadGuard<'a, GenericComponentStore>;
+    type Ref<'a, T: 'a> = &'a T;
+    const EXCLUSIVE: bool = false;
 
     fn lock(rwlock: &RwLock<GenericComponentStore>) -> Self::LockGuard<'_> {
         rwlock.read().expect("should always be RwLock")
     }
+
+    fn store_ptr<C: 'static>(guard: &mut Self::LockGuard<'_>) -> NonNull<ComponentStore<C>> {
+        NonNull::from(guard.store_for())
+    }
+
+    unsafe fn fetch<'a, T: 'static>(store: &'a UnsafeCell<ComponentStoreLock<'_, T, Self>>, entity: EntityId) -> Option<&'a T> {
+        (*store.get()).store.as_ref().get(entity)
+    }
 }
 
 pub struct WriteLockType;
 
 impl LockType for WriteLockType {
     type LockGuard<'a> = RwLockWriteGuard<'a, GenericComponentStore>;
+    type Ref<'a, T: 'a> = &'a mut T;
+    const EXCLUSIVE: bool = true;
 
     fn lock(rwlock: &RwLock<GenericComponentStore>) -> Self::LockGuard<'_> {
         rwlock.write().expect("should always be RwLock")
     }
+
+    fn store_ptr<C: 'static>(guard: &mut Self::LockGuard<'_>) -> NonNull<ComponentStore<C>> {
+        NonNull::from(guard.store_for_mut())
+    }
+
+    unsafe fn fetch<'a, T: 'static>(store: &'a UnsafeCell<ComponentStoreLock<'_, T, Self>>, entity: EntityId) -> Option<&'a mut T> {
+        // goes through the pointer taken at lock time rather than borrowing the whole store, which
+        // would alias the components fetched before
+        ComponentStore::get_mut_ptr((*store.get()).store.as_ptr(), entity)
+    }
 }
 
 pub struct ComponentStoreLock<'a, C: 'static, L: LockType> {
-    lock_guard: L::LockGuard<'a>,
-    phantom_data: PhantomData<C>,
+    /// Only held to keep the store locked, accesses go through `store`.
+    _lock_guard: L::LockGuard<'a>,
+    store: NonNull<ComponentStore<C>>,
 }
 
 pub type ComponentStoreReadLock<'a, C> = ComponentStoreLock<'a, C, ReadLockType>;
@@ -399,10 +1388,9 @@ pub type ComponentStoreWriteLock<'a, C> = ComponentStoreLock<'a, C, WriteLockTyp
 
 impl<'a, C: 'static, L: LockType> ComponentStoreLock<'a, C, L> {
     fn lock(rwlock: &'a RwLock<GenericComponentStore>) -> Self {
-        Self {
-            lock_guard: L::lock(rwlock),
-            phantom_data: PhantomData::default(),
-        }
+        let mut lock_guard = L::lock(rwlock);
+        let store = L::store_ptr(&mut lock_guard);
+        Self { _lock_guard: lock_guard, store }
     }
 }
 
@@ -410,29 +1398,51 @@ impl<'a, C: 'static, L: LockType> Deref for ComponentStoreLock<'a, C, L> {
     type Target = ComponentStore<C>;
 
     fn deref(&self) -> &Self::Target {
-        self.lock_guard.deref().store_for()
+        // SAFETY: the store stays locked for as long as the guard is held
+        unsafe { self.store.as_ref() }
     }
 }
 
 impl<'a, C: 'static> DerefMut for ComponentStoreLock<'a, C, WriteLockType> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        self.lock_guard.deref_mut().store_for_mut()
+        // SAFETY: the store stays exclusively locked for as long as the guard is held
+        unsafe { self.store.as_mut() }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use utils::hlist;
+    use std::any::TypeId;
+    use std::cell::RefCell;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use std::rc::Rc;
+
+    use serde::{Deserialize, Serialize};
+    use utils::{delist, hlist, HList};
 
-    use crate::world::{ViewBuilder, World};
+    use crate::snapshot::SnapshotError;
+    use crate::store::StorageKind;
+    use crate::world::{CloneComponent, DebuggableComponent, DefaultComponent, Entity, JoinedEntity, SerializableComponent, View, ViewBuilder, World};
 
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(Clone, PartialEq, Eq, Debug)]
     struct Label(String);
 
+    impl CloneComponent for Label {}
+
+    impl DebuggableComponent for Label {}
+
+    #[derive(Clone, PartialEq, Debug)]
+    struct Velocity(f32, f32);
+
+    impl CloneComponent for Velocity {}
+
+    impl DebuggableComponent for Velocity {}
+
     struct Player {
         health: f32,
     }
 
+    #[derive(Default)]
     struct Enemy;
 
     #[test]
@@ -469,8 +1479,140 @@ mod tests {
     }
 
     #[test]
-    fn single_component() {
+    fn entities_snapshot() {
         let mut world = World::default().with_component::<Label>();
+        let entities: Vec<_> = (0..3).map(|_| world.new_entity()).collect();
+        world.drop_entity(entities[1]);
+
+        let snapshot = world.entities_snapshot();
+        assert_eq!(snapshot, vec![entities[0], entities[2]]);
+
+        let mut visited = vec![];
+        for entity in world.entities_snapshot() {
+            if world.is_dead(entity) {
+                continue;
+            }
+            visited.push(entity);
+            // the spawned entity reuses the slot of the dropped one, and is never visited
+            let spawned = world.new_entity();
+            world.components_mut::<Label>().put(spawned, Label("spawned".to_owned()));
+            world.drop_entity(entities[2]);
+        }
+
+        assert_eq!(visited, vec![entities[0]]);
+        assert_eq!(world.entity_iter().count(), 2);
+    }
+
+    #[test]
+    fn view_get() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>();
+        let present = world.new_entity();
+        let absent = world.new_entity();
+        let despawned = world.new_entity();
+        for (entity, name) in [(present, "present"), (absent, "absent"), (despawned, "despawned")] {
+            world.components_mut::<Label>().put(entity, Label(name.to_owned()));
+        }
+        world.components_mut::<Velocity>().put(present, Velocity(1.0, 0.0));
+        world.components_mut::<Velocity>().put(despawned, Velocity(0.0, 1.0));
+        world.drop_entity(despawned);
+
+        let view = View::builder()
+            .required::<Label>()
+            .required::<Velocity>()
+            .build(&world);
+        let delist!(label, velocity) = view.get(present).unwrap();
+        assert_eq!((label, velocity), (&Label("present".to_owned()), &Velocity(1.0, 0.0)));
+        // lacks a required component
+        assert!(view.get(absent).is_none());
+        assert!(view.get(despawned).is_none());
+    }
+
+    #[test]
+    fn reused_slot() {
+        let mut world = World::default().with_component::<Label>();
+        let dropped = world.new_entity();
+        world.components_mut::<Label>().put(dropped, Label("dropped".to_owned()));
+        world.drop_entity(dropped);
+
+        // a component put through the stale id is not visible through views
+        world.components_mut::<Label>().put(dropped, Label("stale".to_owned()));
+        let view = View::builder().required::<Label>().build(&world);
+        assert!(view.get(dropped).is_none());
+        drop(view);
+
+        let reused = world.new_entity();
+        assert_eq!(reused.index, dropped.index);
+        world.components_mut::<Label>().put(reused, Label("reused".to_owned()));
+
+        let view = View::builder().required::<Label>().build(&world);
+        assert!(view.get(dropped).is_none());
+        assert_eq!(view.get(reused).map(|(label, ..)| label.0.as_str()), Some("reused"));
+        assert!(world.components::<Label>().get(dropped).is_none());
+    }
+
+    #[test]
+    fn on_remove() {
+        let mut world = World::default().with_component::<Label>().with_component::<Velocity>();
+        let removed = Rc::new(RefCell::new(vec![]));
+        world.on_remove::<Label>({
+            let removed = removed.clone();
+            move |entity, label| removed.borrow_mut().push((entity, label.0))
+        });
+
+        let a = world.new_entity();
+        let b = world.new_entity();
+        world.components_mut::<Label>().put(a, Label("a".to_owned()));
+        world.components_mut::<Label>().put(b, Label("b".to_owned()));
+        world.components_mut::<Velocity>().put(b, Velocity(1.0, 0.0));
+
+        world.components_mut::<Label>().put(a, Label("renamed".to_owned()));
+        assert!(world.components_mut::<Label>().discard(a));
+        assert!(!world.components_mut::<Label>().discard(a));
+        world.drop_entity(b);
+        assert!(!world.components::<Velocity>().has(b));
+
+        assert_eq!(*removed.borrow(), vec![
+            (a, "a".to_owned()),
+            (a, "renamed".to_owned()),
+            (b, "b".to_owned()),
+        ]);
+
+        // taken by the caller instead
+        world.components_mut::<Label>().put(a, Label("taken".to_owned()));
+        assert_eq!(world.components_mut::<Label>().remove(a), Some(Label("taken".to_owned())));
+        assert_eq!(removed.borrow().len(), 3);
+    }
+
+    #[test]
+    fn with_components() {
+        let mut world = World::default().with_components::<HList!(Label, Velocity, Enemy)>();
+        let entity = world.new_entity();
+        world.components_mut::<Label>().put(entity, Label("enemy".to_owned()));
+        world.components_mut::<Velocity>().put(entity, Velocity(1.0, 2.0));
+        world.components_mut::<Enemy>().put(entity, Enemy);
+
+        assert_eq!(world.components::<Label>().get(entity), Some(&Label("enemy".to_owned())));
+        assert_eq!(world.components::<Velocity>().get(entity), Some(&Velocity(1.0, 2.0)));
+        assert!(world.components::<Enemy>().has(entity));
+        assert_eq!(world.components::<Label>().kind(), StorageKind::Dense);
+
+        let mut world = World::default();
+        world.add_components::<HList!(Label, Enemy)>(StorageKind::Sparse);
+        assert_eq!(world.components::<Label>().kind(), StorageKind::Sparse);
+        assert_eq!(world.components::<Enemy>().kind(), StorageKind::Sparse);
+    }
+
+    #[test]
+    fn single_component() {
+        single_component_in(StorageKind::Dense);
+        single_component_in(StorageKind::Sparse);
+    }
+
+    fn single_component_in(kind: StorageKind) {
+        let mut world = World::default();
+        world.add_component::<Label>(kind);
         let entity_a = world.new_entity();
         let entity_b = world.new_entity();
         let entity_c = world.new_entity();
@@ -509,12 +1651,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sparse_iter_mut() {
+        let mut world = World::default();
+        world.add_component::<Label>(StorageKind::Sparse);
+        let entities: Vec<_> = (0..32).map(|i| world.spawn(hlist!(Label(i.to_string())))).collect();
+
+        let mut view = View::builder().required_mut::<Label>().build(&world);
+        // every mutable reference is alive while the map is searched for the next one
+        let mut labels: Vec<_> = view.iter_mut().map(|(_, delist!(label))| label).collect();
+        assert_eq!(labels.len(), entities.len());
+        for label in &mut labels {
+            label.0.push('!');
+        }
+        drop(labels);
+        drop(view);
+
+        for (i, entity) in entities.into_iter().enumerate() {
+            assert_eq!(world.components::<Label>().get(entity), Some(&Label(format!("{i}!"))));
+        }
+    }
+
     #[test]
     fn system() {
-        let mut world = World::default()
-            .with_component::<Label>()
-            .with_component::<Player>()
-            .with_component::<Enemy>();
+        system_in(StorageKind::Dense);
+        system_in(StorageKind::Sparse);
+    }
+
+    fn system_in(kind: StorageKind) {
+        let mut world = World::default();
+        world.add_component::<Label>(kind);
+        world.add_component::<Player>(kind);
+        world.add_component::<Enemy>(kind);
 
         let entity_a = world.new_entity();
         let entity_b = world.new_entity();
@@ -562,4 +1730,625 @@ mod tests {
             (entity_c, hlist!(&Label("Entity C".to_owned()))),
         ], enemies);
     }
+
+    #[test]
+    fn typed_entities() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Enemy>();
+
+        let enemy: Entity<Enemy> = world.spawn_typed();
+        let other = world.new_entity();
+
+        assert!(world.is_alive(enemy.id()));
+        assert!(world.components::<Enemy>().has(enemy.into()));
+        assert_eq!(world.get_typed::<Enemy>(enemy.id()), Some(enemy));
+        assert_eq!(world.get_typed::<Enemy>(other), None);
+
+        world.drop_entity(enemy.id());
+        assert_eq!(world.get_typed::<Enemy>(enemy.id()), None);
+    }
+
+    #[test]
+    fn clone_entity() {
+        let mut world = World::default()
+            .with_cloneable_component::<Label>()
+            .with_cloneable_component::<Velocity>()
+            .with_component::<Player>();
+
+        let template = world.new_entity();
+        world.components_mut::<Label>().put(template, Label("Meteor".to_owned()));
+        world.components_mut::<Velocity>().put(template, Velocity(1.0, 2.0));
+        world.components_mut::<Player>().put(template, Player { health: 1.0 });
+
+        let copy = world.clone_entity(template).unwrap();
+        assert_ne!(copy, template);
+        assert_eq!(world.components::<Label>().get(copy), Some(&Label("Meteor".to_owned())));
+        assert_eq!(world.components::<Velocity>().get(copy), Some(&Velocity(1.0, 2.0)));
+        // not registered as cloneable
+        assert!(!world.components::<Player>().has(copy));
+
+        world.components_mut::<Label>().put(copy, Label("Fragment".to_owned()));
+        world.components_mut::<Velocity>().put(template, Velocity(-1.0, 0.0));
+        assert_eq!(world.components::<Label>().get(template), Some(&Label("Meteor".to_owned())));
+        assert_eq!(world.components::<Velocity>().get(copy), Some(&Velocity(1.0, 2.0)));
+
+        world.drop_entity(copy);
+        assert_eq!(world.clone_entity(copy), None);
+    }
+
+    #[test]
+    fn cloneable_after_registration() {
+        let mut world = World::default().with_component::<Label>();
+        let template = world.new_entity();
+        world.components_mut::<Label>().put(template, Label("Meteor".to_owned()));
+
+        // keeps the existing components
+        world.add_cloneable_component::<Label>();
+        let copy = world.clone_entity(template).unwrap();
+        assert_eq!(world.components::<Label>().get(template), Some(&Label("Meteor".to_owned())));
+        assert_eq!(world.components::<Label>().get(copy), Some(&Label("Meteor".to_owned())));
+    }
+
+    #[test]
+    fn spawned_since() {
+        let mut world = World::default().with_component::<Label>();
+        let old = world.new_entity();
+        let dropped = world.new_entity();
+        world.drop_entity(dropped);
+
+        let previous_tick = world.current_tick();
+        assert_eq!(world.tick(), previous_tick + 1);
+        // reuses the slot of the dropped entity
+        let reused = world.new_entity();
+        let new = world.new_entity();
+        for entity in [old, reused, new] {
+            world.components_mut::<Label>().put(entity, Label(format!("{:?}", entity)));
+        }
+
+        assert_eq!(world.spawn_tick(old), Some(previous_tick));
+        assert_eq!(world.spawn_tick(new), Some(previous_tick + 1));
+
+        let view = ViewBuilder::new()
+            .spawned_since(previous_tick)
+            .required::<Label>()
+            .build(&world);
+        let spawned: Vec<_> = view.iter().map(|(entity, _)| entity).collect();
+        assert_eq!(spawned, vec![reused, new]);
+    }
+
+    #[test]
+    fn debug_entity() {
+        let mut world = World::default()
+            .with_debuggable_component::<Label>()
+            .with_debuggable_component::<Velocity>()
+            .with_component::<Player>();
+
+        let entity = world.new_entity();
+        world.components_mut::<Label>().put(entity, Label("Meteor".to_owned()));
+        world.components_mut::<Player>().put(entity, Player { health: 1.0 });
+
+        let dump = world.debug_entity(entity);
+        assert!(dump.contains(r#"Label("Meteor")"#), "{dump}");
+        // absent from the entity
+        assert!(!dump.contains("Velocity"), "{dump}");
+        // not registered as debuggable
+        assert!(!dump.contains("Player"), "{dump}");
+
+        world.components_mut::<Velocity>().put(entity, Velocity(1.0, 2.0));
+        assert!(world.debug_entity(entity).contains("Velocity(1.0, 2.0)"));
+    }
+
+    #[test]
+    fn disjoint_access() {
+        let world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>()
+            .with_component::<Player>();
+
+        world.assert_disjoint(&[TypeId::of::<Label>(), TypeId::of::<Velocity>()]);
+
+        let view = ViewBuilder::new()
+            .required::<Label>()
+            .build(&world);
+        world.assert_disjoint(&[TypeId::of::<Velocity>(), TypeId::of::<Player>()]);
+
+        let locked = catch_unwind(AssertUnwindSafe(|| world.assert_disjoint(&[TypeId::of::<Label>()])));
+        assert!(locked.is_err());
+        drop(view);
+        world.assert_disjoint(&[TypeId::of::<Label>()]);
+
+        let _velocities = world.components_mut::<Velocity>();
+        let locked = catch_unwind(AssertUnwindSafe(|| world.assert_disjoint(&[TypeId::of::<Velocity>()])));
+        assert!(locked.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "accessed mutably more than once")]
+    fn overlapping_access() {
+        let world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>();
+
+        world.assert_disjoint(&[TypeId::of::<Label>(), TypeId::of::<Velocity>(), TypeId::of::<Label>()]);
+    }
+
+    #[test]
+    fn collect_soa() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>()
+            .with_component::<Player>();
+
+        let first = world.new_entity();
+        let skipped = world.new_entity();
+        let second = world.new_entity();
+        let third = world.new_entity();
+        for (entity, name) in [(first, "first"), (second, "second"), (third, "third")] {
+            world.components_mut::<Label>().put(entity, Label(name.to_owned()));
+        }
+        world.components_mut::<Velocity>().put(skipped, Velocity(0.0, 0.0));
+        world.components_mut::<Velocity>().put(third, Velocity(3.0, 0.0));
+        world.components_mut::<Velocity>().put(first, Velocity(1.0, 0.0));
+        world.components_mut::<Player>().put(second, Player { health: 2.0 });
+
+        let view = ViewBuilder::new()
+            .required::<Label>()
+            .optional::<Velocity>()
+            .build(&world);
+        let (entities, delist!(labels, velocities)) = view.collect_soa();
+
+        assert_eq!(entities, vec![first, second, third]);
+        assert_eq!(labels, vec![&Label("first".to_owned()), &Label("second".to_owned()), &Label("third".to_owned())]);
+        assert_eq!(velocities, vec![Some(&Velocity(1.0, 0.0)), None, Some(&Velocity(3.0, 0.0))]);
+        for (entity, label) in entities.iter().zip(&labels) {
+            assert_eq!(world.components::<Label>().get(*entity), Some(*label));
+        }
+    }
+
+    #[test]
+    fn iter_flat() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>()
+            .with_component::<Player>();
+        let first = world.new_entity();
+        let second = world.new_entity();
+        world.components_mut::<Label>().put(first, Label("first".to_owned()));
+        world.components_mut::<Label>().put(second, Label("second".to_owned()));
+        world.components_mut::<Velocity>().put(first, Velocity(1.0, 0.0));
+        world.components_mut::<Player>().put(first, Player { health: 1.0 });
+
+        let view = View::builder().required::<Label>().build(&world);
+        let nested: Vec<_> = view.iter().map(|(entity, delist!(label))| (entity, label)).collect();
+        assert_eq!(view.iter_flat().collect::<Vec<_>>(), nested);
+
+        let view = View::builder().required::<Label>().optional::<Velocity>().build(&world);
+        let nested: Vec<_> = view.iter().map(|(entity, delist!(label, velocity))| (entity, label, velocity)).collect();
+        assert_eq!(view.iter_flat().collect::<Vec<_>>(), nested);
+        assert_eq!(nested.len(), 2);
+
+        let view = View::builder()
+            .required::<Label>()
+            .required::<Velocity>()
+            .required::<Player>()
+            .build(&world);
+        let flat: Vec<_> = view.iter_flat().collect();
+        let nested: Vec<_> = view.iter().map(|(entity, delist!(label, velocity, player))| (entity, label, velocity, player)).collect();
+        assert_eq!(flat.len(), 1);
+        let (entity, label, velocity, player) = flat[0];
+        assert_eq!(entity, first);
+        assert_eq!((label, velocity, player.health), (nested[0].1, nested[0].2, nested[0].3.health));
+    }
+
+    #[test]
+    fn excluded_components() {
+        let mut world = World::default()
+            .with_component::<Velocity>()
+            .with_sparse_component::<Player>();
+        let player = world.new_entity();
+        let other = world.new_entity();
+        world.components_mut::<Velocity>().put(player, Velocity(1.0, 0.0));
+        world.components_mut::<Velocity>().put(other, Velocity(0.0, 1.0));
+        world.components_mut::<Player>().put(player, Player { health: 1.0 });
+
+        let view = View::builder()
+            .required::<Velocity>()
+            .without::<Player>()
+            .build(&world);
+        let matched: Vec<_> = view.iter().map(|(entity, delist!(velocity))| (entity, velocity.clone())).collect();
+        assert_eq!(matched, vec![(other, Velocity(0.0, 1.0))]);
+        assert!(view.get(player).is_none());
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+
+    impl DefaultComponent for Score {}
+
+    #[test]
+    fn default_components() {
+        let mut world = World::default();
+        let entity = world.new_entity();
+        world.put(entity, Score(3));
+        world.components_mut_or_default::<Score>().get_mut(entity).unwrap().0 += 1;
+        assert_eq!(world.components::<Score>().get(entity), Some(&Score(4)));
+        assert_eq!(View::builder().required::<Score>().build(&world).count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown component type")]
+    fn strict_registration() {
+        let mut world = World::default().strict();
+        let entity = world.new_entity();
+        world.put(entity, Score(3));
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Position(f32, f32);
+
+    impl SerializableComponent for Position {}
+
+    #[test]
+    fn snapshot_round_trip() {
+        let mut world = World::default()
+            .with_serializable_component::<Position>()
+            .with_component::<Label>();
+        let dropped = world.spawn(hlist!(Position(0.0, 0.0)));
+        let kept = world.spawn(hlist!(Position(1.0, 2.0), Label("kept".to_owned())));
+        world.drop_entity(dropped);
+        let reused = world.spawn(hlist!(Position(3.0, 4.0)));
+        world.set_enabled(reused, false);
+
+        let saved = serde_json::to_string(&world.snapshot().unwrap()).unwrap();
+
+        world.drop_entity(reused);
+        world.components_mut::<Position>().put(kept, Position(-1.0, -1.0));
+        let spawned = world.spawn(hlist!(Position(5.0, 6.0)));
+        world.restore(serde_json::from_str(&saved).unwrap()).unwrap();
+
+        assert!(world.is_dead(dropped));
+        assert!(world.is_alive(kept));
+        assert!(world.is_alive(reused));
+        assert!(!world.is_enabled(reused));
+        assert!(world.is_dead(spawned));
+        assert_eq!(world.components::<Position>().get(kept), Some(&Position(1.0, 2.0)));
+        assert_eq!(world.components::<Position>().get(reused), Some(&Position(3.0, 4.0)));
+        // components that aren't serializable are not part of the snapshot
+        assert_eq!(world.components::<Label>().get(kept), None);
+        assert_eq!(world.snapshot().unwrap(), serde_json::from_str(&saved).unwrap());
+    }
+
+    #[test]
+    fn snapshot_unknown_component() {
+        let mut source = World::default().with_serializable_component::<Position>();
+        source.spawn(hlist!(Position(1.0, 2.0)));
+        let snapshot = source.snapshot().unwrap();
+
+        let mut target = World::default().with_component::<Position>();
+        let entity = target.spawn(hlist!(Position(3.0, 4.0)));
+        let error = target.restore(snapshot).unwrap_err();
+        assert!(matches!(error, SnapshotError::UnknownComponent(name) if name.ends_with("Position")));
+        assert_eq!(target.components::<Position>().get(entity), Some(&Position(3.0, 4.0)));
+    }
+
+    #[test]
+    fn snapshot_inconsistent() {
+        let mut world = World::default().with_serializable_component::<Position>();
+        let entity = world.spawn(hlist!(Position(1.0, 2.0)));
+        let snapshot = world.snapshot().unwrap();
+
+        let mut truncated = snapshot.clone();
+        truncated.spawn_ticks.clear();
+        assert!(matches!(world.restore(truncated), Err(SnapshotError::Inconsistent(_))));
+
+        // the component is left behind by a dropped entity
+        let mut dead = snapshot;
+        dead.entities[entity.index].1 = false;
+        assert!(matches!(world.restore(dead), Err(SnapshotError::Inconsistent(_))));
+
+        assert!(world.is_alive(entity));
+        assert_eq!(world.components::<Position>().get(entity), Some(&Position(1.0, 2.0)));
+    }
+
+    #[test]
+    fn reuse_slots() {
+        let mut world = World::default();
+        let mut entities: Vec<_> = (0..4).map(|_| world.new_entity()).collect();
+        for _ in 0..3 {
+            let dropped = [entities[1], entities[3], entities[2]];
+            world.drop_entities(dropped);
+            // the last dropped slot is reused first
+            let reused: Vec<_> = (0..3).map(|_| world.new_entity()).collect();
+            assert_eq!(reused.iter().map(|entity| entity.index).collect::<Vec<_>>(), vec![2, 3, 1]);
+            assert!(dropped.iter().all(|entity| world.is_dead(*entity)));
+            assert!(reused.iter().all(|entity| world.is_alive(*entity)));
+            entities = vec![entities[0], reused[2], reused[0], reused[1]];
+        }
+        // no new slots were needed
+        assert_eq!(world.new_entity().index, 4);
+    }
+
+    #[test]
+    fn driven_by_smallest_store() {
+        let mut world = World::default()
+            .with_component::<Velocity>()
+            .with_sparse_component::<Enemy>();
+        let entities: Vec<_> = (0..20).map(|i| world.spawn(hlist!(Velocity(i as f32, 0.0)))).collect();
+        for entity in [entities[15], entities[3], entities[9]] {
+            world.components_mut::<Enemy>().put(entity, Enemy);
+        }
+        world.drop_entity(entities[9]);
+        // a component put for a dropped entity never matches
+        world.components_mut::<Enemy>().put(entities[9], Enemy);
+        let spawned = world.new_entity();
+        world.components_mut::<Enemy>().put(spawned, Enemy);
+
+        let view = View::builder().required::<Velocity>().marked::<Enemy>().build(&world);
+        let matched: Vec<_> = view.iter().map(|(entity, _)| entity).collect();
+        assert_eq!(matched, vec![entities[3], entities[15]]);
+        assert_eq!(view.count(), 2);
+        drop(view);
+
+        let view = View::builder().optional::<Velocity>().marked::<Enemy>().build(&world);
+        let matched: Vec<_> = view.iter().map(|(entity, _)| entity).collect();
+        assert_eq!(matched, vec![entities[3], spawned, entities[15]]);
+    }
+
+    #[test]
+    fn sorted_by_entity() {
+        let mut world = World::default().with_component::<Velocity>().with_sparse_component::<Enemy>();
+        let entities: Vec<_> = (0..6).map(|i| world.spawn(hlist!(Velocity(i as f32, 0.0)))).collect();
+        // the sparse store holds its entities out of slot order
+        for entity in [entities[4], entities[1], entities[3]] {
+            world.components_mut::<Enemy>().put(entity, Enemy);
+        }
+        let sorted = |world: &World| -> Vec<_> {
+            View::builder().required::<Velocity>().marked::<Enemy>().build(world)
+                .iter_sorted_by_entity()
+                .map(|(entity, _)| entity)
+                .collect()
+        };
+        assert_eq!(sorted(&world), vec![entities[1], entities[3], entities[4]]);
+
+        let added: Vec<_> = (0..2).map(|_| world.spawn(hlist!(Velocity(0.0, 0.0), Enemy))).collect();
+        world.drop_entity(entities[2]);
+        world.drop_entity(entities[5]);
+        assert_eq!(sorted(&world), vec![entities[1], entities[3], entities[4], added[0], added[1]]);
+
+        world.drop_entity(entities[3]);
+        assert_eq!(sorted(&world), vec![entities[1], entities[4], added[0], added[1]]);
+    }
+
+    #[test]
+    fn component_changes() {
+        let mut world = World::default().with_component::<Velocity>().with_component::<Label>();
+        let entities: Vec<_> = (0..3).map(|i| world.spawn(hlist!(Velocity(i as f32, 0.0)))).collect();
+        // everything put so far counts as changed
+        assert_eq!(world.changes::<Velocity>().collect::<Vec<_>>(), entities);
+        assert_eq!(world.changes::<Velocity>().count(), 0);
+
+        world.components_mut::<Velocity>().get_mut(entities[2]).unwrap().0 += 1.0;
+        world.components_mut::<Velocity>().put(entities[0], Velocity(5.0, 0.0));
+        world.components_mut::<Label>().put(entities[1], Label("unrelated".to_owned()));
+        assert_eq!(world.changes::<Velocity>().collect::<Vec<_>>(), vec![entities[0], entities[2]]);
+        assert_eq!(world.changes::<Velocity>().count(), 0);
+        assert_eq!(world.changes::<Label>().collect::<Vec<_>>(), vec![entities[1]]);
+    }
+
+    #[test]
+    fn count_entities() {
+        let mut world = World::default().with_component::<Label>().with_component::<Velocity>();
+        assert!(world.is_empty());
+        let moving = world.spawn(hlist!(Label("moving".to_owned()), Velocity(1.0, 0.0)));
+        world.spawn(hlist!(Label("still".to_owned())));
+        let dropped = world.spawn(hlist!(Velocity(0.0, 1.0)));
+        let version = world.components::<Velocity>().version();
+        world.components_mut::<Velocity>().put(moving, Velocity(2.0, 0.0));
+        world.drop_entity(dropped);
+        assert_eq!(world.len(), 2);
+        assert!(!world.is_empty());
+
+        assert_eq!(View::builder().required::<Label>().build(&world).count(), 2);
+        assert_eq!(View::builder().required::<Label>().without::<Velocity>().build(&world).count(), 1);
+        assert_eq!(View::builder().changed::<Velocity>(version).build(&world).count(), 1);
+        assert_eq!(View::builder().optional_mut::<Velocity>().build(&world).count(), 2);
+        let view = View::builder().optional::<Label>().marked::<Velocity>().build(&world);
+        assert_eq!(view.count(), view.iter().count());
+        assert_eq!(view.count(), 1);
+    }
+
+    #[test]
+    fn disabled_entities() {
+        let mut world = World::default().with_component::<Velocity>();
+        let enabled = world.new_entity();
+        let disabled = world.new_entity();
+        world.components_mut::<Velocity>().put(enabled, Velocity(1.0, 0.0));
+        world.components_mut::<Velocity>().put(disabled, Velocity(0.0, 1.0));
+        world.set_enabled(disabled, false);
+        assert!(world.is_alive(disabled));
+        assert!(!world.is_enabled(disabled));
+
+        let view = View::builder().required::<Velocity>().build(&world);
+        assert_eq!(view.iter().map(|(entity, _)| entity).collect::<Vec<_>>(), vec![enabled]);
+        assert!(view.get(disabled).is_none());
+        drop(view);
+
+        let view = View::builder().required::<Velocity>().include_disabled().build(&world);
+        assert_eq!(view.iter().map(|(entity, _)| entity).collect::<Vec<_>>(), vec![enabled, disabled]);
+        assert_eq!(view.get(disabled), Some(hlist!(&Velocity(0.0, 1.0))));
+        drop(view);
+
+        world.set_enabled(disabled, true);
+        assert_eq!(View::builder().required::<Velocity>().build(&world).iter().count(), 2);
+
+        // a reused slot starts out enabled again
+        world.set_enabled(disabled, false);
+        world.drop_entity(disabled);
+        let spawned = world.new_entity();
+        assert!(world.is_enabled(spawned));
+    }
+
+    #[test]
+    fn spawn_bundle() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>();
+        let entity = world.spawn(hlist!(Label("spawned".to_owned()), Velocity(1.0, 2.0)));
+        assert_eq!(world.components::<Label>().get(entity), Some(&Label("spawned".to_owned())));
+        assert_eq!(world.components::<Velocity>().get(entity), Some(&Velocity(1.0, 2.0)));
+
+        let unregistered = catch_unwind(AssertUnwindSafe(|| world.spawn(hlist!(Enemy))));
+        let message = unregistered.unwrap_err();
+        assert!(message.downcast_ref::<String>().unwrap().contains("unknown component type"));
+    }
+
+    #[test]
+    fn drop_entities() {
+        let mut world = World::default().with_component::<Label>();
+        let [a, b, c] = [(); 3].map(|_| world.spawn(hlist!(Label("entity".to_owned()))));
+        let dropped = Rc::new(RefCell::new(vec![]));
+        let hook = dropped.clone();
+        world.on_remove::<Label>(move |entity, _| hook.borrow_mut().push(entity));
+
+        world.drop_entities([a, b, a]);
+        assert!(world.is_dead(a) && world.is_dead(b));
+        assert!(world.is_alive(c));
+        assert_eq!(*dropped.borrow(), vec![a, b]);
+    }
+
+    #[test]
+    fn joined_worlds() {
+        let mut game = World::default().with_component::<Label>().with_component::<Velocity>();
+        let mut ui = World::default().with_component::<Label>();
+        let player = game.spawn(hlist!(Label("player".to_owned()), Velocity(1.0, 0.0)));
+        let button = ui.spawn(hlist!(Label("button".to_owned())));
+        ui.spawn(hlist!());
+        // both are the first entity of their world
+        assert_eq!(player, button);
+
+        let view = View::builder().required::<Label>().build_joined(&game, &ui);
+        let labels: Vec<_> = view.iter()
+            .map(|(entity, delist!(label))| (entity, label.0.as_str()))
+            .collect();
+        assert_eq!(labels, vec![
+            (JoinedEntity::Primary(player), "player"),
+            (JoinedEntity::Secondary(button), "button"),
+        ]);
+        assert_eq!(view.get(JoinedEntity::Secondary(button)).map(|delist!(label)| label.0.as_str()), Some("button"));
+        drop(view);
+
+        let mut view = View::builder().required_mut::<Label>().build_joined(&game, &ui);
+        for (_, delist!(label)) in view.iter_mut() {
+            label.0.push('!');
+        }
+        drop(view);
+        assert_eq!(game.components::<Label>().get(player), Some(&Label("player!".to_owned())));
+        assert_eq!(ui.components::<Label>().get(button), Some(&Label("button!".to_owned())));
+    }
+
+    #[test]
+    #[should_panic(expected = "two separate worlds")]
+    fn joined_same_world() {
+        let world = World::default().with_component::<Label>();
+        let _view = View::builder().required_mut::<Label>().build_joined(&world, &world);
+    }
+
+    #[test]
+    fn changed_components() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>();
+        let moving = world.spawn(hlist!(Label("moving".to_owned()), Velocity(1.0, 0.0)));
+        let still = world.spawn(hlist!(Label("still".to_owned()), Velocity(0.0, 0.0)));
+        let version = world.components::<Velocity>().version();
+
+        let changed = |world: &World| View::builder()
+            .required::<Label>()
+            .changed::<Velocity>(version)
+            .build(world)
+            .iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        assert!(changed(&world).is_empty());
+
+        world.components_mut::<Velocity>().get_mut(moving).unwrap().0 += 1.0;
+        assert_eq!(changed(&world), vec![moving]);
+        // changes to other component types don't count
+        world.components_mut::<Label>().put(still, Label("renamed".to_owned()));
+        assert_eq!(changed(&world), vec![moving]);
+    }
+
+    #[test]
+    fn resources() {
+        struct Bounds(f32);
+
+        let mut world = World::default().with_component::<Velocity>();
+        world.insert_resource(Bounds(2.0));
+        world.spawn(hlist!(Velocity(1.5, 3.0)));
+
+        let view = View::builder().required::<Velocity>().build(&world);
+        let inside = view.iter_flat()
+            .filter(|(_, velocity)| velocity.0.abs() < world.resource::<Bounds>().0)
+            .count();
+        assert_eq!(inside, 1);
+        drop(view);
+
+        world.resource_mut::<Bounds>().0 = 1.0;
+        world.insert_resource(Label("replaced".to_owned()));
+        world.insert_resource(Label("label".to_owned()));
+        assert_eq!(world.resource::<Bounds>().0, 1.0);
+        assert_eq!(world.resource::<Label>(), &Label("label".to_owned()));
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown resource type")]
+    fn missing_resource() {
+        World::default().resource::<Label>();
+    }
+
+    #[test]
+    fn mutable_view() {
+        let mut world = World::default()
+            .with_component::<Label>()
+            .with_component::<Velocity>();
+        let moving = world.new_entity();
+        let still = world.new_entity();
+        world.components_mut::<Label>().put(moving, Label("moving".to_owned()));
+        world.components_mut::<Label>().put(still, Label("still".to_owned()));
+        world.components_mut::<Velocity>().put(moving, Velocity(1.0, 2.0));
+        let version = world.components::<Velocity>().version();
+
+        let mut view = View::builder()
+            .required_mut::<Label>()
+            .optional_mut::<Velocity>()
+            .build(&world);
+        for (_, label, velocity) in view.iter_flat_mut() {
+            label.0.push('!');
+            if let Some(velocity) = velocity {
+                velocity.0 *= 2.0;
+            }
+        }
+        let delist!(label, velocity) = view.get_mut(moving).unwrap();
+        assert_eq!(label, &mut Label("moving!".to_owned()));
+        velocity.unwrap().1 = 0.0;
+        drop(view);
+
+        assert_eq!(world.components::<Velocity>().get(moving), Some(&Velocity(2.0, 0.0)));
+        assert!(world.components::<Velocity>().version() > version);
+
+        let mut view = View::builder().required_mut::<Velocity>().build(&world);
+        assert_eq!(view.iter_mut().count(), 1);
+        assert!(view.get_mut(still).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "bound mutably more than once")]
+    fn mutable_aliasing() {
+        let world = World::default().with_component::<Label>();
+        View::builder()
+            .required::<Label>()
+            .optional_mut::<Label>()
+            .build(&world);
+    }
 }
@@ -27,7 +27,8 @@ impl<A: AssetSource> Deref for AssetSourceResource<A> {
 
 #[cfg(not(target_family = "wasm"))]
 pub mod desktop {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use assets::source::archive::ZipAssetSource;
     use assets::source::desktop_fs::DirectoryAssetSource;
     use utils::{HList, hlist};
     use utils::hlist::{Concat, IntoShape};
@@ -52,6 +53,28 @@ pub mod desktop {
             })
         }
     }
+
+    pub trait ZipAssetSourceExt<R, I, P: AsRef<Path>> {
+        type Output;
+
+        /// Serves assets from the zip archive at `path`, panicking if it can't be opened.
+        fn setup_zip_asset_source(self, path: P) -> Self::Output;
+    }
+
+    impl<R, I, P> ZipAssetSourceExt<R, I, P> for ProcessBuilder<R>
+        where P: AsRef<Path>,
+              R: 'static + IntoShape<HList!(), I>,
+              R::Remainder: Concat {
+        type Output = ProcessBuilder<<R::Remainder as Concat>::Concatenated<HList!(AssetSourceResource<ZipAssetSource>)>>;
+
+        fn setup_zip_asset_source(self, path: P) -> Self::Output {
+            self.setup(move |_| {
+                let source = ZipAssetSource::open(&path)
+                    .unwrap_or_else(|err| panic!("failed to open asset archive {:?}: {}", path.as_ref(), err));
+                hlist!(AssetSourceResource::new(source))
+            })
+        }
+    }
 }
 
 #[cfg(target_family = "wasm")]
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+/// Most fixed steps taken per frame, so a long stall, e.g. while the window was being dragged,
+/// doesn't make the following frames spend all their time catching up.
+const MAX_FIXED_STEPS: u32 = 8;
+
+/// Resource measuring the time between frames, advanced by the surface before each
+/// [Process::tick](crate::process::Process::tick). Handlers read it instead of keeping their own
+/// timestamps.
+///
+/// Variable frame times make simulations diverge between runs, so physics can instead run in
+/// steps of a fixed duration with [FrameClock::set_fixed_timestep], taking the steps accumulated
+/// each frame from [FrameClock::fixed_step].
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    delta: Duration,
+    elapsed: Duration,
+    frames: u64,
+    fixed_timestep: Option<Duration>,
+    accumulated: Duration,
+}
+
+impl FrameClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new frame `delta` after the previous one.
+    pub fn advance(&mut self, delta: Duration) {
+        self.delta = delta;
+        self.elapsed += delta;
+        self.frames += 1;
+        if let Some(timestep) = self.fixed_timestep {
+            self.accumulated = (self.accumulated + delta).min(timestep * MAX_FIXED_STEPS);
+        }
+    }
+
+    /// Time between the previous frame and the current one.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    /// Sum of the deltas of all frames so far.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Accumulates frame times to be taken in steps of `timestep` with [FrameClock::fixed_step],
+    /// or stops accumulating for `None`.
+    pub fn set_fixed_timestep(&mut self, timestep: Option<Duration>) {
+        assert!(timestep != Some(Duration::ZERO), "fixed timestep must not be zero");
+        self.fixed_timestep = timestep;
+        self.accumulated = Duration::ZERO;
+    }
+
+    pub fn fixed_timestep(&self) -> Option<Duration> {
+        self.fixed_timestep
+    }
+
+    /// Takes a step of the fixed timestep if enough time accumulated, call until it returns `None`
+    /// each frame, e.g. `while let Some(dt) = clock.fixed_step() { ... }`.
+    pub fn fixed_step(&mut self) -> Option<Duration> {
+        let timestep = self.fixed_timestep?;
+        if self.accumulated < timestep {
+            return None;
+        }
+        self.accumulated -= timestep;
+        Some(timestep)
+    }
+
+    /// How far the time left after the fixed steps of this frame is into the next step, from 0 to
+    /// 1, to interpolate what is drawn between the last two steps.
+    pub fn fixed_step_fraction(&self) -> f32 {
+        match self.fixed_timestep {
+            Some(timestep) => self.accumulated.as_secs_f32() / timestep.as_secs_f32(),
+            None => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::frame_clock::FrameClock;
+
+    #[test]
+    fn fixed_steps() {
+        let mut clock = FrameClock::new();
+        clock.set_fixed_timestep(Some(Duration::from_millis(10)));
+        let mut steps_per_frame = vec![];
+        for delta in [25, 4, 11, 1000] {
+            clock.advance(Duration::from_millis(delta));
+            let mut steps = 0;
+            while let Some(step) = clock.fixed_step() {
+                assert_eq!(step, Duration::from_millis(10));
+                steps += 1;
+            }
+            steps_per_frame.push(steps);
+        }
+        // the remaining 5ms carry over to the next frames, the stall is capped
+        assert_eq!(steps_per_frame, vec![2, 0, 2, 8]);
+        assert_eq!(clock.delta(), Duration::from_millis(1000));
+        assert_eq!(clock.elapsed(), Duration::from_millis(1040));
+        assert_eq!(clock.frames(), 4);
+        assert_eq!(clock.fixed_step_fraction(), 0.0);
+
+        clock.advance(Duration::from_millis(5));
+        assert_eq!(clock.fixed_step(), None);
+        assert!((clock.fixed_step_fraction() - 0.5).abs() < 1e-6);
+
+        clock.set_fixed_timestep(None);
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(clock.fixed_step(), None);
+    }
+}
@@ -1,8 +1,12 @@
 pub mod asset_resource;
+pub mod frame_clock;
+pub mod obj;
 pub mod platform;
 pub mod process;
+pub mod replay;
 pub mod resources;
 pub mod surface;
+pub mod task_pool;
 pub mod wgpu_render;
 pub mod winit_surface;
 
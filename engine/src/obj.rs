@@ -0,0 +1,318 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use assets::{AssetPipeline, LoadAssetError};
+use assets::path::AssetPath;
+use assets::source::AssetSource;
+use render::geometry::{Geometry, GeometryFormat, Indices};
+use render::material::{AttributeDefinition, AttributeSemantics, AttributeType};
+
+#[derive(Debug, Error)]
+pub enum ParseObjError {
+    #[error("line {line}: invalid {element}")]
+    Invalid { line: usize, element: &'static str },
+    #[error("line {line}: index {index} is out of range")]
+    IndexOutOfRange { line: usize, index: i64 },
+    #[error("{}", .0)]
+    Io(#[from] std::io::Error),
+}
+
+/// Loads Wavefront `.obj` files as [Geometry], see [parse_obj].
+pub struct ObjAssetPipeline;
+
+#[async_trait(? Send)]
+impl AssetPipeline for ObjAssetPipeline {
+    async fn load_asset(&self, path: AssetPath, source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
+        let data = source.open_asset_file(&path).await?.read_fully().await;
+        let geometry = parse_obj(data.as_slice()).map_err(LoadAssetError::other)?;
+        Ok(Box::new(geometry))
+    }
+}
+
+/// Corner of a face: indices of its position, texture coordinate and normal.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Decodes a Wavefront `.obj` file line by line into indexed triangles. Vertices consist of a
+/// [Position](AttributeSemantics::Position) and a [Normal](AttributeSemantics::Normal), followed
+/// by a [TexCoord](AttributeSemantics::TexCoord) if the file has texture coordinates. The `v`
+/// axis of texture coordinates is flipped to put `(0, 0)` at the top left.
+///
+/// Polygon faces are triangulated as fans. Faces without normals get the normal of their plane,
+/// so they are shaded flat. Indices are 16 bit unless there are more vertices than they can
+/// address. Objects, groups and materials are ignored.
+pub fn parse_obj(reader: impl BufRead) -> Result<Geometry, ParseObjError> {
+    let mut positions = vec![];
+    let mut tex_coords = vec![];
+    let mut normals = vec![];
+    let mut faces: Vec<Vec<Corner>> = vec![];
+
+    for (line, text) in reader.lines().enumerate() {
+        let text = text?;
+        let line = line + 1;
+        let mut tokens = text.split_whitespace();
+        let Some(keyword) = tokens.next() else { continue };
+        match keyword {
+            "v" => positions.push(floats::<3>(tokens, line, "position")?),
+            "vt" => {
+                let [u, v] = floats::<2>(tokens, line, "texture coordinate")?;
+                tex_coords.push([u, 1.0 - v]);
+            }
+            "vn" => normals.push(normalize(floats::<3>(tokens, line, "normal")?)),
+            "f" => {
+                let face = tokens
+                    .map(|corner| parse_corner(corner, line, [positions.len(), tex_coords.len(), normals.len()]))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if face.len() < 3 {
+                    return Err(ParseObjError::Invalid { line, element: "face" });
+                }
+                faces.push(face);
+            }
+            _ => {}
+        }
+    }
+
+    let textured = faces.iter().flatten().any(|(_, tex_coord, _)| tex_coord.is_some());
+    let mut vertices: HashMap<(usize, Option<usize>, [u32; 3]), u32> = HashMap::new();
+    let mut data = vec![];
+    let mut indices: Vec<u32> = vec![];
+    for face in &faces {
+        let face_normal = plane_normal(face.iter().map(|(position, ..)| positions[*position]));
+        let mut face_indices = Vec::with_capacity(face.len());
+        for (position, tex_coord, normal) in face {
+            let normal = normal.map_or(face_normal, |normal| normals[normal]);
+            let key = (*position, *tex_coord, normal.map(f32::to_bits));
+            let index = match vertices.get(&key) {
+                Some(index) => *index,
+                None => {
+                    let index = vertices.len() as u32;
+                    let mut vertex = positions[*position].to_vec();
+                    vertex.extend_from_slice(&normal);
+                    if textured {
+                        vertex.extend_from_slice(&tex_coord.map_or([0.0; 2], |tex_coord| tex_coords[tex_coord]));
+                    }
+                    data.extend(vertex.iter().flat_map(|value| value.to_ne_bytes()));
+                    vertices.insert(key, index);
+                    index
+                }
+            };
+            face_indices.push(index);
+        }
+        for i in 1..face_indices.len() - 1 {
+            indices.extend_from_slice(&[face_indices[0], face_indices[i], face_indices[i + 1]]);
+        }
+    }
+
+    let mut format = vec![
+        AttributeDefinition {
+            name: None,
+            semantics: AttributeSemantics::Position { transform: Default::default() },
+            typ: AttributeType::Float32(3),
+        },
+        AttributeDefinition {
+            name: None,
+            semantics: AttributeSemantics::Normal,
+            typ: AttributeType::Float32(3),
+        },
+    ];
+    if textured {
+        format.push(AttributeDefinition {
+            name: None,
+            semantics: AttributeSemantics::TexCoord,
+            typ: AttributeType::Float32(2),
+        });
+    }
+
+    // the largest 16 bit index is left out, as it restarts strips
+    let indices = if vertices.len() <= u16::MAX as usize {
+        Indices::Uint16(indices.into_iter().map(|index| index as u16).collect())
+    } else {
+        Indices::Uint32(indices)
+    };
+    Ok(Geometry::new(data, GeometryFormat::from(format), indices))
+}
+
+fn floats<'a, const N: usize>(tokens: impl Iterator<Item=&'a str>, line: usize, element: &'static str) -> Result<[f32; N], ParseObjError> {
+    let invalid = || ParseObjError::Invalid { line, element };
+    // positions may have an optional w, and texture coordinates an optional depth
+    let values = tokens.take(N)
+        .map(|token| token.parse().map_err(|_| invalid()))
+        .collect::<Result<Vec<f32>, _>>()?;
+    values.try_into().map_err(|_| invalid())
+}
+
+/// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner, where negative indices count back from
+/// the most recent element. `counts` are the numbers of positions, texture coordinates and
+/// normals so far.
+fn parse_corner(corner: &str, line: usize, counts: [usize; 3]) -> Result<Corner, ParseObjError> {
+    let mut parts = corner.split('/');
+    let mut index = |count: usize, required: bool| -> Result<Option<usize>, ParseObjError> {
+        let part = parts.next().filter(|part| !part.is_empty());
+        let Some(part) = part else {
+            return if required { Err(ParseObjError::Invalid { line, element: "face" }) } else { Ok(None) };
+        };
+        let index: i64 = part.parse().map_err(|_| ParseObjError::Invalid { line, element: "face" })?;
+        let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+        if (0..count as i64).contains(&resolved) {
+            Ok(Some(resolved as usize))
+        } else {
+            Err(ParseObjError::IndexOutOfRange { line, index })
+        }
+    };
+
+    let position = index(counts[0], true)?.unwrap();
+    let tex_coord = index(counts[1], false)?;
+    let normal = index(counts[2], false)?;
+    Ok((position, tex_coord, normal))
+}
+
+fn normalize([x, y, z]: [f32; 3]) -> [f32; 3] {
+    let length = (x * x + y * y + z * z).sqrt();
+    if length > 0.0 { [x / length, y / length, z / length] } else { [0.0; 3] }
+}
+
+/// Normal of a counterclockwise polygon using Newell's method, which also handles polygons that
+/// aren't quite planar.
+fn plane_normal(points: impl Iterator<Item=[f32; 3]> + Clone) -> [f32; 3] {
+    let next = points.clone().cycle().skip(1);
+    let normal = points.zip(next).fold([0.0; 3], |[x, y, z], (a, b)| [
+        x + (a[1] - b[1]) * (a[2] + b[2]),
+        y + (a[2] - b[2]) * (a[0] + b[0]),
+        z + (a[0] - b[0]) * (a[1] + b[1]),
+    ]);
+    normalize(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use async_trait::async_trait;
+
+    use assets::{AssetPipeline, LoadAssetError};
+    use assets::path::AssetPath;
+    use assets::source::{AssetReader, AssetSource};
+    use render::geometry::{Geometry, Indices};
+    use render::material::AttributeSemantics;
+
+    use crate::obj::{ObjAssetPipeline, parse_obj, ParseObjError};
+
+    const CUBE: &str = "
+# unit cube without normals
+o cube
+v -0.5 -0.5 0.5
+v 0.5 -0.5 0.5
+v 0.5 0.5 0.5
+v -0.5 0.5 0.5
+v -0.5 -0.5 -0.5
+v 0.5 -0.5 -0.5
+v 0.5 0.5 -0.5
+v -0.5 0.5 -0.5
+f 1 2 3 4
+f 6 5 8 7
+f 5 1 4 8
+f 2 6 7 3
+f 4 3 7 8
+f 5 6 2 1
+";
+
+    struct CubeSource;
+
+    struct CubeReader;
+
+    #[async_trait(? Send)]
+    impl AssetReader for CubeReader {
+        async fn read_fully(&mut self) -> Vec<u8> {
+            CUBE.as_bytes().to_vec()
+        }
+    }
+
+    #[async_trait(? Send)]
+    impl AssetSource for CubeSource {
+        async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+            match path.path_string() {
+                "/cube.obj" => Ok(Box::new(CubeReader)),
+                _ => Err(LoadAssetError::NotFound(path.clone())),
+            }
+        }
+    }
+
+    fn vertices(geometry: &Geometry) -> Vec<Vec<f32>> {
+        let floats: Vec<f32> = geometry.data().chunks_exact(4)
+            .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect();
+        floats.chunks_exact(geometry.format().vertex_size() / 4).map(<[f32]>::to_vec).collect()
+    }
+
+    #[test]
+    fn cube() {
+        let geometry = futures::executor::block_on(
+            ObjAssetPipeline.load_asset(AssetPath::new("/cube.obj").unwrap(), &CubeSource),
+        ).unwrap();
+        let geometry = geometry.downcast::<Geometry>().unwrap();
+
+        // corners are split between the faces, as each face has its own normal
+        assert_eq!(geometry.vertex_count(), 24);
+        assert_eq!(geometry.indices().len(), 36);
+        let semantics: Vec<_> = geometry.format().attributes().iter().map(|attribute| attribute.semantics.default_name()).collect();
+        assert_eq!(semantics, vec!["position", "normal"]);
+
+        for vertex in vertices(&geometry) {
+            let (position, normal) = vertex.split_at(3);
+            // pointing outwards along the axis the vertex is furthest out on
+            let axis = (0..3).find(|axis| normal[*axis] != 0.0).unwrap();
+            assert_eq!(normal.iter().filter(|n| **n != 0.0).count(), 1, "{normal:?}");
+            assert_eq!(normal[axis], position[axis] * 2.0, "{vertex:?}");
+        }
+    }
+
+    #[test]
+    fn normals_and_tex_coords() {
+        let obj = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 0 1
+vn 0 0 2
+f 1/1/1 2/2/1 3/3/1
+f -3//-1 -2//-1 -1//-1
+";
+        let geometry = parse_obj(Cursor::new(obj)).unwrap();
+        let semantics: Vec<_> = geometry.format().attributes().iter().map(|attribute| &attribute.semantics).collect();
+        assert!(matches!(semantics[..], [AttributeSemantics::Position { .. }, AttributeSemantics::Normal, AttributeSemantics::TexCoord]));
+        assert_eq!(geometry.indices(), &Indices::Uint16(vec![0, 1, 2, 3, 4, 5]));
+        let vertices = vertices(&geometry);
+        assert_eq!(vertices[1], vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0]);
+        // missing texture coordinates default to zero
+        assert_eq!(vertices[4], vec![1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn many_vertices() {
+        // one triangle per three vertices, each vertex used once
+        let triangles = u16::MAX as usize / 3 + 1;
+        let mut obj = String::new();
+        for i in 0..triangles * 3 {
+            obj += &format!("v {i} {} 0\n", i % 3);
+        }
+        for i in 0..triangles {
+            obj += &format!("f {} {} {}\n", i * 3 + 1, i * 3 + 2, i * 3 + 3);
+        }
+        let geometry = parse_obj(Cursor::new(obj)).unwrap();
+        assert_eq!(geometry.vertex_count(), triangles * 3);
+        let Indices::Uint32(indices) = geometry.indices() else { panic!("expected 32 bit indices") };
+        assert_eq!(indices.last(), Some(&(triangles as u32 * 3 - 1)));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(parse_obj(Cursor::new("v 0 0\n")), Err(ParseObjError::Invalid { line: 1, .. })));
+        assert!(matches!(parse_obj(Cursor::new("v 0 0 0\nf 1 2 3\n")), Err(ParseObjError::IndexOutOfRange { line: 2, index: 2 })));
+        assert!(matches!(parse_obj(Cursor::new("v 0 0 0\nf 1 1\n")), Err(ParseObjError::Invalid { line: 2, .. })));
+    }
+}
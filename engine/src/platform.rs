@@ -3,8 +3,10 @@ use async_trait::async_trait;
 use utils::{HList, hlist};
 use utils::hlist::{Concat, IntoShape};
 use crate::asset_resource::AssetSourceResource;
+use crate::frame_clock::FrameClock;
 use crate::process::ProcessBuilder;
 use crate::surface::SurfaceResource;
+use crate::task_pool::TaskPool;
 use crate::wgpu_render::{setup_wgpu_render_resource, WGPURenderResource};
 use crate::winit_surface::{setup_winit_resource, WinitSurface};
 
@@ -107,6 +109,8 @@ impl PlatformWithDefaultSetup for DefaultPlatform {
     type SetupInput = ();
     type SetupOutput = HList!(
         SurfaceResource<WinitSurface>,
+        FrameClock,
+        TaskPool,
         WGPURenderResource,
         AssetSourceResource<DefaultPlatformAssetSource>,
     );
@@ -139,7 +143,7 @@ impl PlatformWithDefaultSetup for DefaultPlatform {
         let wgpu_resource = setup_wgpu_render_resource(&winit_resource).await;
         let asset_source_resource = AssetSourceResource::new(new_default_platform_asset_source());
 
-        hlist!(winit_resource, wgpu_resource, asset_source_resource)
+        hlist!(winit_resource, FrameClock::new(), TaskPool::new(), wgpu_resource, asset_source_resource)
     }
 }
 
@@ -1,19 +1,58 @@
-use std::any::Any;
+use std::any::{Any, type_name, TypeId};
 use std::future::IntoFuture;
 use std::ops::{Deref, DerefMut};
+use std::time::Duration;
 use events::{EventSystem, Event, UnhandledEvent};
+use thiserror::Error;
 use utils::hlist::{Concat, IntoShape};
 use crate::resources::Resources;
+use crate::surface::SurfaceEvent;
 
 pub struct ProcessInfo;
 
+/// Emitted by [Process::tick] to advance the application logic by `dt`, the time passed since the
+/// previous tick.
+pub struct Update {
+    pub dt: Duration,
+}
+
+impl Event for Update {
+    type Output = ();
+}
+
+/// An hlist of resource types, e.g. the dependencies declared with [ProcessBuilder::requires].
+pub trait ResourceTypes {
+    /// Appends the type id and name of every resource type of the list.
+    fn collect(types: &mut Vec<(TypeId, &'static str)>);
+}
+
+impl ResourceTypes for () {
+    fn collect(_types: &mut Vec<(TypeId, &'static str)>) {}
+}
+
+impl<H: 'static, T: ResourceTypes> ResourceTypes for (H, T) {
+    fn collect(types: &mut Vec<(TypeId, &'static str)>) {
+        types.push((TypeId::of::<H>(), type_name::<H>()));
+        T::collect(types);
+    }
+}
+
+/// A dependency declared with [ProcessBuilder::requires] that no earlier setup step produced.
+#[derive(Debug, Error, PartialEq)]
+#[error("setup step `{step}` requires resource `{resource}`, which no earlier setup step produced")]
+pub struct MissingDependency {
+    pub step: &'static str,
+    pub resource: &'static str,
+}
+
 pub struct ProcessBuilder<R> {
     resources: R,
+    missing: Vec<MissingDependency>,
 }
 
 impl ProcessBuilder<()> {
     pub fn new() -> Self {
-        ProcessBuilder { resources: () }
+        ProcessBuilder { resources: (), missing: vec![] }
     }
 }
 
@@ -26,7 +65,7 @@ impl<R: 'static> ProcessBuilder<R> {
         let (input, remainder) = self.resources.into_shape();
         let output = setup(input);
         let resources = remainder.concat(output);
-        ProcessBuilder { resources }
+        ProcessBuilder { resources, missing: self.missing }
     }
 
     pub async fn setup_async<F, Input, InputI, Output, Fut>(self, setup: F) -> ProcessBuilder<<R::Remainder as Concat>::Concatenated<Output>>
@@ -38,11 +77,39 @@ impl<R: 'static> ProcessBuilder<R> {
         let (input, remainder) = self.resources.into_shape();
         let output = setup(input).await;
         let resources = remainder.concat(output);
-        ProcessBuilder { resources }
+        ProcessBuilder { resources, missing: self.missing }
+    }
+
+    /// Declares that the setup step named `step` depends on every resource type of the hlist
+    /// `Req`. Steps taking their inputs by type already fail to compile when a resource is missing,
+    /// but with an error that hardly points at the cause; declaring the dependencies up front
+    /// makes [ProcessBuilder::try_build] report each missing one by name instead.
+    pub fn requires<Req: ResourceTypes>(mut self, step: &'static str) -> Self
+        where R: ResourceTypes {
+        let mut produced = vec![];
+        R::collect(&mut produced);
+        let mut required = vec![];
+        Req::collect(&mut required);
+        for (typ, resource) in required {
+            if produced.iter().all(|(produced, _)| *produced != typ) {
+                self.missing.push(MissingDependency { step, resource });
+            }
+        }
+        self
     }
 
+    /// Creates the process, or returns the first dependency declared with
+    /// [ProcessBuilder::requires] that wasn't produced before its step.
+    pub fn try_build(mut self) -> Result<Process<R>, MissingDependency> {
+        match self.missing.drain(..).next() {
+            Some(missing) => Err(missing),
+            None => Ok(Process::new(self.resources)),
+        }
+    }
+
+    /// Like [ProcessBuilder::try_build], but panics naming a missing dependency.
     pub fn build(self) -> Process<R> {
-        Process::new(self.resources)
+        self.try_build().unwrap_or_else(|missing| panic!("{}", missing))
     }
 }
 
@@ -91,6 +158,15 @@ impl<R: 'static> Process<R> {
         self.event_system.handle_event(message, &mut self.resources)
     }
 
+    /// Advances the process by one step: dispatches [Update] with `dt`, followed by
+    /// [SurfaceEvent::Draw]. Surfaces call this once per frame, but it can also be called directly
+    /// to drive the process without a surface, e.g. in benchmarks or simulations. Events without
+    /// handlers are ignored, so a process without rendering doesn't need to handle drawing.
+    pub fn tick(&mut self, dt: Duration) {
+        let _unhandled = self.handle_event(Update { dt });
+        let _unhandled = self.handle_event(SurfaceEvent::Draw);
+    }
+
     pub fn handle_generic_message(&mut self, message: Box<dyn Any>) -> Result<Box<dyn Any>, UnhandledEvent> {
         self.event_system.handle_generic_event(message, &mut self.resources)
     }
@@ -98,8 +174,14 @@ impl<R: 'static> Process<R> {
 
 #[cfg(test)]
 mod tests {
+    use std::any::type_name;
+    use std::time::Duration;
+
+    use events::Context;
     use utils::{hlist, HList, delist};
-    use crate::process::ProcessBuilder;
+    use crate::process::{MissingDependency, ProcessBuilder, Update};
+    use crate::resources::Resources;
+    use crate::surface::SurfaceEvent;
 
     struct ResourceA(u32);
 
@@ -119,4 +201,87 @@ mod tests {
         assert_eq!(res_a.0, 30u32);
         assert_eq!(res_b.0, 0.7f32);
     }
+
+    #[test]
+    fn dependencies() {
+        let missing = ProcessBuilder::new()
+            .setup(|_| hlist!(ResourceA(1)))
+            .requires::<HList!(ResourceA, ResourceB)>("physics")
+            .setup(|_: HList!()| hlist!(ResourceB(0.5)))
+            .try_build()
+            .err()
+            .unwrap();
+        assert_eq!(missing, MissingDependency { step: "physics", resource: type_name::<ResourceB>() });
+        assert!(missing.to_string().contains("ResourceB"), "{missing}");
+
+        let process = ProcessBuilder::new()
+            .setup(|_| hlist!(ResourceA(1), ResourceB(0.5)))
+            .requires::<HList!(ResourceA, ResourceB)>("physics")
+            .setup(|_: HList!()| hlist!(ResourceC("done")))
+            .try_build();
+        assert!(process.is_ok());
+    }
+
+    #[derive(Default)]
+    struct Simulation {
+        elapsed: Duration,
+        updates: u32,
+        draws: u32,
+    }
+
+    #[test]
+    fn tick() {
+        let mut process = ProcessBuilder::new()
+            .setup(|_| hlist!(Simulation::default()))
+            .build();
+        process.event_system().handlers_for().append(|update: Update, mut context: Context<Update, Resources<HList!(Simulation)>>| {
+            let simulation: &mut Simulation = context.get();
+            simulation.elapsed += update.dt;
+            simulation.updates += 1;
+        });
+        process.event_system().handlers_for().append(|event: SurfaceEvent, mut context: Context<SurfaceEvent, Resources<HList!(Simulation)>>| {
+            if let SurfaceEvent::Draw = event {
+                let simulation: &mut Simulation = context.get();
+                // drawing sees the state of the update before it
+                assert_eq!(simulation.updates, simulation.draws + 1);
+                simulation.draws += 1;
+            }
+        });
+
+        for _ in 0..3 {
+            process.tick(Duration::from_millis(20));
+        }
+
+        let simulation: &mut Simulation = process.get();
+        assert_eq!(simulation.updates, 3);
+        assert_eq!(simulation.draws, 3);
+        assert_eq!(simulation.elapsed, Duration::from_millis(60));
+    }
+
+    /// Added by a plugin after the process was built.
+    struct PluginState {
+        updates: u32,
+    }
+
+    #[test]
+    fn dynamic_resources() {
+        let mut process = ProcessBuilder::new()
+            .setup(|_| hlist!(ResourceA(1)))
+            .build();
+        process.event_system().handlers_for().append(|_: Update, mut context: Context<Update, Resources<HList!(ResourceA)>>| {
+            if let Some(plugin) = context.get_dynamic::<PluginState>() {
+                plugin.updates += 1;
+            }
+        });
+
+        process.tick(Duration::from_millis(20));
+        assert!(process.insert_dynamic(PluginState { updates: 0 }).is_none());
+        process.tick(Duration::from_millis(20));
+        process.tick(Duration::from_millis(20));
+
+        assert_eq!(process.get_dynamic::<PluginState>().map(|plugin| plugin.updates), Some(2));
+        assert!(process.get_dynamic::<ResourceA>().is_none());
+        assert_eq!(process.remove_dynamic::<PluginState>().map(|plugin| plugin.updates), Some(2));
+        assert!(process.get_dynamic::<PluginState>().is_none());
+    }
 }
@@ -0,0 +1,264 @@
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use events::Event;
+use thiserror::Error;
+
+use crate::process::Process;
+
+/// Emitted when an input action of type `A`, e.g. a key bound to shooting, is pressed or released.
+/// Applications emit it from their raw input events with [Process::handle_event], so it can be
+/// recorded with an [InputRecorder] and replayed with an [InputPlayer].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InputAction<A> {
+    pub action: A,
+    pub pressed: bool,
+}
+
+impl<A: 'static> Event for InputAction<A> {
+    type Output = ();
+}
+
+/// [InputAction] that happened before the `tick`th update of a recording.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RecordedInput<A> {
+    pub tick: u64,
+    pub input: InputAction<A>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("malformed input event on line {0}")]
+    Malformed(usize),
+    #[error("failed to read recording: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Input actions of a session, in the order they happened.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording<A> {
+    pub inputs: Vec<RecordedInput<A>>,
+}
+
+impl<A: Display> Recording<A> {
+    /// Writes one `tick action pressed` line per input, e.g. to a file of an asset directory.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for RecordedInput { tick, input } in &self.inputs {
+            writeln!(writer, "{} {} {}", tick, input.action, input.pressed)?;
+        }
+        Ok(())
+    }
+}
+
+impl<A: FromStr> Recording<A> {
+    /// Reads a recording written with [Recording::write_to].
+    pub fn read_from(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut inputs = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let malformed = || ReplayError::Malformed(index + 1);
+            let mut fields = line.split_whitespace();
+            let mut field = || fields.next().ok_or_else(malformed);
+            let tick = field()?.parse().map_err(|_| malformed())?;
+            let action = field()?.parse().map_err(|_| malformed())?;
+            let pressed = field()?.parse().map_err(|_| malformed())?;
+            inputs.push(RecordedInput { tick, input: InputAction { action, pressed } });
+        }
+        Ok(Recording { inputs })
+    }
+}
+
+/// Resource logging the [InputAction]s of a session along with the update they precede. Call
+/// [InputRecorder::record] from the handler of the actions and [InputRecorder::advance] from the
+/// update handler. Replaying the recording with the same initial state, including the seeds of
+/// any random number generators, reproduces the session.
+pub struct InputRecorder<A> {
+    tick: u64,
+    recording: Recording<A>,
+}
+
+impl<A> Default for InputRecorder<A> {
+    fn default() -> Self {
+        InputRecorder { tick: 0, recording: Recording { inputs: vec![] } }
+    }
+}
+
+impl<A: Clone> InputRecorder<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, input: &InputAction<A>) {
+        self.recording.inputs.push(RecordedInput { tick: self.tick, input: input.clone() });
+    }
+
+    /// Counts an update, inputs recorded afterwards precede the next one.
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    pub fn recording(&self) -> &Recording<A> {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording<A> {
+        self.recording
+    }
+}
+
+/// Feeds the inputs of a [Recording] back into a [Process].
+pub struct InputPlayer<A> {
+    tick: u64,
+    inputs: std::vec::IntoIter<RecordedInput<A>>,
+    next: Option<RecordedInput<A>>,
+}
+
+impl<A: 'static> InputPlayer<A> {
+    pub fn new(recording: Recording<A>) -> Self {
+        let mut inputs = recording.inputs.into_iter();
+        let next = inputs.next();
+        InputPlayer { tick: 0, inputs, next }
+    }
+
+    /// Emits the inputs recorded before the current update as [InputAction] events. Call before
+    /// each [Process::tick].
+    pub fn play<R: 'static>(&mut self, process: &mut Process<R>) {
+        while let Some(recorded) = self.next.take_if(|recorded| recorded.tick <= self.tick) {
+            let _unhandled = process.handle_event(recorded.input);
+            self.next = self.inputs.next();
+        }
+        self.tick += 1;
+    }
+
+    /// Whether every recorded input was played.
+    pub fn is_finished(&self) -> bool {
+        self.next.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::{Display, Formatter};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    use ecs::world::{EntityId, World};
+    use events::Context;
+    use utils::{hlist, HList};
+
+    use crate::process::{Process, ProcessBuilder, Update};
+    use crate::replay::{InputAction, InputPlayer, InputRecorder, Recording};
+    use crate::resources::Resources;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Action {
+        Spawn,
+    }
+
+    impl Display for Action {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "spawn")
+        }
+    }
+
+    impl FromStr for Action {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "spawn" => Ok(Action::Spawn),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Position(f32);
+
+    struct Simulation {
+        world: World,
+        rng: StdRng,
+        spawning: bool,
+    }
+
+    type Resource = HList!(Simulation, InputRecorder<Action>);
+
+    /// Spawns an entity at a random position on every update while spawning is held, and moves
+    /// all entities a random distance.
+    fn simulation(seed: u64) -> Process<Resource> {
+        let mut process = ProcessBuilder::new()
+            .setup(|_| hlist!(Simulation {
+                world: World::default().with_component::<Position>(),
+                rng: StdRng::seed_from_u64(seed),
+                spawning: false,
+            }, InputRecorder::<Action>::new()))
+            .build();
+        process.event_system().handlers_for().append(|input: InputAction<Action>, mut context: Context<InputAction<Action>, Resources<Resource>>| {
+            let recorder: &mut InputRecorder<Action> = context.get();
+            recorder.record(&input);
+            let simulation: &mut Simulation = context.get();
+            simulation.spawning = input.pressed;
+        });
+        process.event_system().handlers_for().append(|_: Update, mut context: Context<Update, Resources<Resource>>| {
+            let simulation: &mut Simulation = context.get();
+            let step = simulation.rng.gen::<f32>();
+            let entities: Vec<_> = simulation.world.components::<Position>().iter().map(|(entity, _)| entity).collect();
+            for entity in entities {
+                simulation.world.components_mut::<Position>().get_mut(entity).unwrap().0 += step;
+            }
+            if simulation.spawning {
+                let position = Position(simulation.rng.gen());
+                let entity = simulation.world.new_entity();
+                simulation.world.components_mut::<Position>().put(entity, position);
+            }
+            let recorder: &mut InputRecorder<Action> = context.get();
+            recorder.advance();
+        });
+        process
+    }
+
+    fn positions(process: &mut Process<Resource>) -> Vec<(EntityId, f32)> {
+        let simulation: &mut Simulation = process.get();
+        simulation.world.components::<Position>().iter()
+            .map(|(entity, position)| (entity, position.0))
+            .collect()
+    }
+
+    #[test]
+    fn replay() {
+        let mut recorded = simulation(7);
+        for tick in 0..12 {
+            match tick {
+                2 | 8 => { let _ = recorded.handle_event(InputAction { action: Action::Spawn, pressed: true }); }
+                5 | 9 => { let _ = recorded.handle_event(InputAction { action: Action::Spawn, pressed: false }); }
+                _ => {}
+            }
+            recorded.tick(Duration::from_millis(16));
+        }
+        let recorder: &mut InputRecorder<Action> = recorded.get();
+        let mut written = vec![];
+        recorder.recording().write_to(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written.clone()).unwrap().lines().next(), Some("2 spawn true"));
+        let recording = Recording::<Action>::read_from(written.as_slice()).unwrap();
+        assert_eq!(&recording, recorder.recording());
+
+        let mut replayed = simulation(7);
+        let mut player = InputPlayer::new(recording);
+        for _ in 0..12 {
+            player.play(&mut replayed);
+            replayed.tick(Duration::from_millis(16));
+        }
+        assert!(player.is_finished());
+        let expected = positions(&mut recorded);
+        assert_eq!(expected.len(), 4);
+        assert_eq!(positions(&mut replayed), expected);
+        let replay_recorder: &mut InputRecorder<Action> = replayed.get();
+        assert_eq!(replay_recorder.recording().inputs.len(), 4);
+
+        assert!(Recording::<Action>::read_from("2 jump true".as_bytes()).is_err());
+    }
+}
@@ -1,12 +1,35 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
 use utils::hlist::{ToMut, Has, IntoShape};
 
 pub struct Resources<R> {
     resource_list: R,
+    /// Resources added after the list was built, e.g. by plugins loaded at runtime.
+    dynamic: HashMap<TypeId, Box<dyn Any>>,
 }
 
 impl<R> Resources<R> {
     pub fn new(resource_list: R) -> Self {
-        Resources { resource_list }
+        Resources { resource_list, dynamic: HashMap::new() }
+    }
+
+    /// Adds a resource whose type isn't part of the resource list, returning the previous
+    /// dynamic resource of the same type. Unlike listed resources, dynamic ones are looked up at
+    /// runtime with [Resources::get_dynamic].
+    pub fn insert_dynamic<T: 'static>(&mut self, resource: T) -> Option<T> {
+        self.dynamic.insert(TypeId::of::<T>(), Box::new(resource))
+            .map(|previous| *previous.downcast().expect("dynamic resources are keyed by type"))
+    }
+
+    pub fn get_dynamic<T: 'static>(&mut self) -> Option<&mut T> {
+        self.dynamic.get_mut(&TypeId::of::<T>())
+            .map(|resource| resource.downcast_mut().expect("dynamic resources are keyed by type"))
+    }
+
+    pub fn remove_dynamic<T: 'static>(&mut self) -> Option<T> {
+        self.dynamic.remove(&TypeId::of::<T>())
+            .map(|resource| *resource.downcast().expect("dynamic resources are keyed by type"))
     }
 
     pub fn get<T, I>(&mut self) -> &mut T
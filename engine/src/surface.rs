@@ -1,17 +1,29 @@
 use std::error::Error;
 use std::ops::{Deref, DerefMut};
-use events::Event;
-use utils::HList;
+use assets::path::AssetPath;
+use assets::source::AssetWatcher;
+use events::{Event, EventQueue};
+use utils::{delist, HList};
+use crate::frame_clock::FrameClock;
+use crate::task_pool::TaskPool;
 use crate::process::Process;
 use crate::resources::{HasResources, Resources};
 
 pub struct SurfaceResource<S> {
     surface: S,
+    /// Events queued by handlers, see [SurfaceResource::queue_event].
+    queued: EventQueue<SurfaceEvent>,
 }
 
 impl<S> SurfaceResource<S> {
     pub fn new(surface: S) -> Self {
-        SurfaceResource { surface }
+        SurfaceResource { surface, queued: EventQueue::new() }
+    }
+
+    /// Queues a follow-up event, dispatched by [dispatch_surface_events] after the events queued
+    /// before it. Events queued outside of its dispatch wait for the next one.
+    pub fn queue_event(&mut self, event: SurfaceEvent) {
+        self.queued.push(event);
     }
 }
 
@@ -36,6 +48,7 @@ pub mod input {
     pub use winit::event::ButtonId;
     pub use winit::event::ElementState;
     pub use winit::event::KeyboardInput;
+    pub use winit::event::MouseButton;
     pub use winit::event::ScanCode;
     pub use winit::event::VirtualKeyCode;
 }
@@ -48,10 +61,64 @@ pub enum SurfaceEvent {
     Draw,
     CloseRequested,
     DeviceEvent(input::DeviceEvent),
+    /// The cursor moved to `x`, `y` in physical pixels, from the top left corner of the surface.
+    /// On the web the position is relative to the canvas.
+    CursorMoved {
+        x: f64,
+        y: f64,
+    },
+    MouseButton {
+        button: input::MouseButton,
+        state: input::ElementState,
+    },
+    MouseWheel {
+        delta: input::MouseScrollDelta,
+    },
+    /// A watched asset was modified and should be reloaded, see [RunnableSurface::set_asset_watcher].
+    AssetChanged(AssetPath),
 }
 
 impl Event for SurfaceEvent {
     type Output = ();
+
+    /// Only the latest of consecutive resizes matters, as each one reconfigures the surface.
+    /// Likewise an asset only needs to be reloaded once for consecutive changes, and only the last
+    /// of consecutive cursor positions is reported.
+    fn coalesces_with(&self, previous: &Self) -> bool {
+        match (self, previous) {
+            (SurfaceEvent::Resize { .. }, SurfaceEvent::Resize { .. }) => true,
+            (SurfaceEvent::CursorMoved { .. }, SurfaceEvent::CursorMoved { .. }) => true,
+            (SurfaceEvent::AssetChanged(path), SurfaceEvent::AssetChanged(previous)) => path == previous,
+            _ => false,
+        }
+    }
+}
+
+/// Polls the watcher and queues a [SurfaceEvent::AssetChanged] for every changed asset.
+pub fn queue_asset_changes(watcher: &mut dyn AssetWatcher, queue: &mut EventQueue<SurfaceEvent>) {
+    for path in watcher.poll_changes() {
+        queue.push(SurfaceEvent::AssetChanged(path));
+    }
+}
+
+/// Dispatches the events of `queue` to the process with [EventQueue::dispatch_all], along with
+/// the follow-up events its handlers [queue](SurfaceResource::queue_event) in response. Returns
+/// the number of events left for the next call.
+pub fn dispatch_surface_events<R: 'static, S: 'static, IS>(process: &mut Process<R>, queue: &mut EventQueue<SurfaceEvent>) -> usize
+    where Resources<R>: HasResources<HList!(SurfaceResource<S>, FrameClock, TaskPool), IS> {
+    fn take_queued<R: 'static, S: 'static, IS>(process: &mut Process<R>, queue: &mut EventQueue<SurfaceEvent>)
+        where Resources<R>: HasResources<HList!(SurfaceResource<S>, FrameClock, TaskPool), IS> {
+        let delist!(surface, _, _) = process.res();
+        for event in surface.queued.drain() {
+            queue.push(event);
+        }
+    }
+
+    take_queued(process, queue);
+    queue.dispatch_all(|event, queue| {
+        let _unhandled = process.handle_event(event);
+        take_queued(process, queue);
+    })
 }
 
 pub enum Exit {
@@ -73,15 +140,20 @@ impl Default for Exit {
 }
 
 /// A surface that is able to be executed and produce [SurfaceEvents](SurfaceEvent) with the
-/// resources available in the process.
+/// resources available in the process. The surface advances the [FrameClock] before each frame
+/// and polls the [TaskPool] once per iteration of its event loop.
 pub trait RunnableSurface {
     type Output;
 
     fn run<R: 'static, IS>(process: Process<R>) -> Self::Output
         where Self: Sized,
-              Resources<R>: HasResources<HList!(SurfaceResource<Self>), IS>;
+              Resources<R>: HasResources<HList!(SurfaceResource<Self>, FrameClock, TaskPool), IS>;
 
     fn set_exit(&mut self, exit: Exit);
+
+    /// Polls the watcher once per iteration of the event loop, emitting
+    /// [SurfaceEvent::AssetChanged] for the assets it reports.
+    fn set_asset_watcher(&mut self, watcher: Box<dyn AssetWatcher>);
 }
 
 pub trait RunExt<R, S: RunnableSurface, IS> {
@@ -90,8 +162,120 @@ pub trait RunExt<R, S: RunnableSurface, IS> {
 
 impl<R: 'static, S, IS> RunExt<R, S, IS> for Process<R>
     where S: RunnableSurface,
-          Resources<R>: HasResources<HList!(SurfaceResource<S>), IS> {
+          Resources<R>: HasResources<HList!(SurfaceResource<S>, FrameClock, TaskPool), IS> {
     fn run(self) -> S::Output {
         S::run(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use assets::path::AssetPath;
+    use assets::source::AssetWatcher;
+    use events::{Context, EventQueue};
+    use utils::{delist, hlist, HList};
+
+    use crate::frame_clock::FrameClock;
+    use crate::process::ProcessBuilder;
+    use crate::resources::Resources;
+    use crate::surface::{dispatch_surface_events, queue_asset_changes, SurfaceEvent, SurfaceResource};
+    use crate::surface::input::{ElementState, MouseButton};
+    use crate::task_pool::TaskPool;
+
+    /// Reports each batch of changes on a separate poll.
+    struct MockWatcher(Vec<Vec<&'static str>>);
+
+    impl AssetWatcher for MockWatcher {
+        fn poll_changes(&mut self) -> Vec<AssetPath> {
+            match self.0.is_empty() {
+                true => vec![],
+                false => self.0.remove(0).into_iter().map(|path| AssetPath::new(path).unwrap()).collect(),
+            }
+        }
+    }
+
+    fn changed_paths(queue: &mut EventQueue<SurfaceEvent>) -> Vec<String> {
+        queue.drain()
+            .map(|event| match event {
+                SurfaceEvent::AssetChanged(path) => path.path_string().to_owned(),
+                _ => panic!("unexpected event"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn asset_changed_events() {
+        let mut watcher = MockWatcher(vec![
+            vec!["/shaders/main.wgsl"],
+            vec![],
+            vec!["/a.png", "/b.png"],
+        ]);
+        let mut queue = EventQueue::new();
+
+        queue_asset_changes(&mut watcher, &mut queue);
+        assert_eq!(changed_paths(&mut queue), vec!["/shaders/main.wgsl"]);
+
+        queue_asset_changes(&mut watcher, &mut queue);
+        assert!(queue.is_empty());
+
+        queue_asset_changes(&mut watcher, &mut queue);
+        assert_eq!(changed_paths(&mut queue), vec!["/a.png", "/b.png"]);
+    }
+
+    #[test]
+    fn repeated_changes_coalesce() {
+        let mut watcher = MockWatcher(vec![vec!["/a.png"], vec!["/a.png"], vec!["/b.png"]]);
+        let mut queue = EventQueue::new();
+        for _ in 0..3 {
+            queue_asset_changes(&mut watcher, &mut queue);
+        }
+        assert_eq!(changed_paths(&mut queue), vec!["/a.png", "/b.png"]);
+    }
+
+    #[test]
+    fn cursor_moves_coalesce() {
+        let mut queue = EventQueue::new();
+        queue.push(SurfaceEvent::CursorMoved { x: 1.0, y: 2.0 });
+        queue.push(SurfaceEvent::CursorMoved { x: 3.0, y: 4.0 });
+        queue.push(SurfaceEvent::MouseButton { button: MouseButton::Left, state: ElementState::Pressed });
+        queue.push(SurfaceEvent::CursorMoved { x: 5.0, y: 6.0 });
+        let events: Vec<_> = queue.drain()
+            .map(|event| match event {
+                SurfaceEvent::CursorMoved { x, y } => format!("move {x} {y}"),
+                SurfaceEvent::MouseButton { button, state } => format!("{button:?} {state:?}"),
+                _ => panic!("unexpected event"),
+            })
+            .collect();
+        assert_eq!(events, vec!["move 3 4", "Left Pressed", "move 5 6"]);
+    }
+
+    type TestResources = HList!(SurfaceResource<()>, FrameClock, TaskPool, Vec<&'static str>);
+
+    #[test]
+    fn handlers_queue_follow_ups() {
+        let mut process = ProcessBuilder::new()
+            .setup(|_| hlist!(SurfaceResource::new(()), FrameClock::new(), TaskPool::new(), Vec::<&'static str>::new()))
+            .build();
+        process.event_system().handlers_for().append(|event: SurfaceEvent, mut context: Context<SurfaceEvent, Resources<TestResources>>| {
+            let delist!(surface, dispatched) = context.get_some::<HList!(SurfaceResource<()>, Vec<&'static str>), _>();
+            match event {
+                SurfaceEvent::CloseRequested => {
+                    dispatched.push("close");
+                    surface.queue_event(SurfaceEvent::Draw);
+                }
+                SurfaceEvent::Resize { .. } => dispatched.push("resize"),
+                SurfaceEvent::Draw => dispatched.push("draw"),
+                _ => panic!("unexpected event"),
+            }
+        });
+
+        let mut queue = EventQueue::new();
+        queue.push(SurfaceEvent::CloseRequested);
+        queue.push(SurfaceEvent::Resize { width: 1, height: 1 });
+        assert_eq!(dispatch_surface_events(&mut process, &mut queue), 0);
+
+        // the follow-up is dispatched after the events queued before it
+        let dispatched: &mut Vec<&'static str> = process.get();
+        assert_eq!(*dispatched, vec!["close", "resize", "draw"]);
+    }
+}
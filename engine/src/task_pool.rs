@@ -0,0 +1,187 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+
+/// Result slot of a task spawned on a [TaskPool].
+pub struct TaskHandle<T> {
+    result: Rc<RefCell<Option<T>>>,
+}
+
+impl<T> TaskHandle<T> {
+    /// Whether the task has completed and its result has not been taken yet.
+    pub fn is_finished(&self) -> bool {
+        self.result.borrow().is_some()
+    }
+
+    /// Takes the result of the task, if it has completed.
+    pub fn take(&self) -> Option<T> {
+        self.result.borrow_mut().take()
+    }
+}
+
+/// Resource for running background tasks, such as streaming assets, alongside the event loop.
+///
+/// On the web, tasks are handed to the browser's event loop. On desktop, tasks only make progress
+/// when [TaskPool::poll] is called, which the surface does once per iteration of its event loop.
+#[derive(Default)]
+pub struct TaskPool {
+    #[cfg(not(target_family = "wasm"))]
+    tasks: Vec<desktop::Task>,
+}
+
+impl TaskPool {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Spawns `future` as a task, returning a handle its result can be taken from once it
+    /// completes.
+    pub fn spawn<F>(&mut self, future: F) -> TaskHandle<F::Output>
+        where F: 'static + Future {
+        let result = Rc::new(RefCell::new(None));
+        let slot = result.clone();
+        let task = async move {
+            let output = future.await;
+            *slot.borrow_mut() = Some(output);
+        };
+
+        #[cfg(not(target_family = "wasm"))]
+        self.tasks.push(desktop::Task::new(task));
+        #[cfg(target_family = "wasm")]
+        wasm_bindgen_futures::spawn_local(task);
+
+        TaskHandle { result }
+    }
+
+    /// Polls every task that has been woken since it was last polled, dropping completed tasks.
+    /// Does nothing on the web, where the browser drives tasks.
+    pub fn poll(&mut self) {
+        #[cfg(not(target_family = "wasm"))]
+        self.tasks.retain_mut(|task| !task.poll());
+    }
+
+    /// Number of tasks that have not completed yet. Always zero on the web.
+    pub fn pending(&self) -> usize {
+        #[cfg(not(target_family = "wasm"))]
+        return self.tasks.len();
+        #[cfg(target_family = "wasm")]
+        return 0;
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod desktop {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::task::{Context, Wake, Waker};
+
+    /// Marks its task for polling when woken.
+    struct TaskWaker {
+        woken: AtomicBool,
+    }
+
+    impl Wake for TaskWaker {
+        fn wake(self: Arc<Self>) {
+            self.woken.store(true, Ordering::Release);
+        }
+    }
+
+    pub(super) struct Task {
+        future: Pin<Box<dyn Future<Output=()>>>,
+        waker: Arc<TaskWaker>,
+    }
+
+    impl Task {
+        pub(super) fn new<F: 'static + Future<Output=()>>(future: F) -> Self {
+            Task {
+                future: Box::pin(future),
+                // polled for the first time on the next poll
+                waker: Arc::new(TaskWaker { woken: AtomicBool::new(true) }),
+            }
+        }
+
+        /// Polls the task if it has been woken, returning whether it has completed.
+        pub(super) fn poll(&mut self) -> bool {
+            if !self.waker.woken.swap(false, Ordering::Acquire) {
+                return false;
+            }
+
+            let waker = Waker::from(self.waker.clone());
+            let mut context = Context::from_waker(&waker);
+            self.future.as_mut().poll(&mut context).is_ready()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use crate::task_pool::TaskPool;
+
+    /// Returns pending a number of times before completing, waking itself each time.
+    struct Yield(u32);
+
+    impl Future for Yield {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 == 0 {
+                return Poll::Ready(());
+            }
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn result_after_polls() {
+        let mut pool = TaskPool::new();
+        let handle = pool.spawn(async {
+            Yield(2).await;
+            42
+        });
+        assert!(!handle.is_finished());
+
+        pool.poll();
+        pool.poll();
+        assert_eq!(handle.take(), None);
+        assert_eq!(pool.pending(), 1);
+
+        pool.poll();
+        assert!(handle.is_finished());
+        assert_eq!(handle.take(), Some(42));
+        assert_eq!(pool.pending(), 0);
+        // result can only be taken once
+        assert_eq!(handle.take(), None);
+    }
+
+    /// Never wakes itself after returning pending.
+    struct Stalled;
+
+    impl Future for Stalled {
+        type Output = ();
+
+        fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<()> {
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn unwoken_tasks_not_polled() {
+        let mut pool = TaskPool::new();
+        let handle = pool.spawn(async {
+            Stalled.await;
+        });
+        for _ in 0..3 {
+            pool.poll();
+        }
+        assert!(!handle.is_finished());
+        assert_eq!(pool.pending(), 1);
+    }
+}
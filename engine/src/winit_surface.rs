@@ -1,8 +1,11 @@
 use std::mem::swap;
 
+use assets::source::AssetWatcher;
+use events::EventQueue;
+use instant::Instant;
 use log::debug;
 use never_say_never::Never;
-use winit::dpi::PhysicalSize;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{DeviceEvent, Event, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::window::{Window, WindowBuilder};
@@ -10,9 +13,11 @@ use winit::window::{Window, WindowBuilder};
 use utils::{hlist, HList, delist};
 use utils::hlist::{Concat, IntoShape};
 
+use crate::frame_clock::FrameClock;
 use crate::process::{Process, ProcessBuilder};
 use crate::resources::{HasResources, Resources};
-use crate::surface::{Exit, RunnableSurface, SurfaceEvent, SurfaceResource};
+use crate::surface::{dispatch_surface_events, Exit, queue_asset_changes, RunnableSurface, SurfaceEvent, SurfaceResource};
+use crate::task_pool::TaskPool;
 use crate::wgpu_render::WGPUCompatible;
 
 enum EventLoopState {
@@ -42,6 +47,7 @@ pub struct WinitSurface {
     event_loop: EventLoopState,
     window: Window,
     exit: Option<Exit>,
+    asset_watcher: Option<Box<dyn AssetWatcher>>,
 }
 
 impl WGPUCompatible for WinitSurface {
@@ -64,6 +70,7 @@ pub fn setup_winit_resource() -> SurfaceResource<WinitSurface> {
         event_loop: event_loop.into(),
         window,
         exit: None,
+        asset_watcher: None,
     })
 }
 
@@ -82,11 +89,11 @@ impl<R, I> WinitSetupExt<R, I> for ProcessBuilder<R>
         R: 'static + IntoShape<(), I>,
         R::Remainder: Concat,
 {
-    type Output = ProcessBuilder<<R::Remainder as Concat>::Concatenated<HList!(SurfaceResource<WinitSurface>)>>;
+    type Output = ProcessBuilder<<R::Remainder as Concat>::Concatenated<HList!(SurfaceResource<WinitSurface>, FrameClock, TaskPool)>>;
 
     fn setup_winit(self) -> Self::Output
     {
-        self.setup(|_: HList!()| hlist!(setup_winit_resource()))
+        self.setup(|_: HList!()| hlist!(setup_winit_resource(), FrameClock::new(), TaskPool::new()))
     }
 }
 
@@ -94,9 +101,9 @@ impl RunnableSurface for WinitSurface {
     type Output = Never;
 
     fn run<R: 'static, IS>(mut process: Process<R>) -> Self::Output
-        where Resources<R>: HasResources<HList!(SurfaceResource<WinitSurface>), IS> {
+        where Resources<R>: HasResources<HList!(SurfaceResource<WinitSurface>, FrameClock, TaskPool), IS> {
         //let surface: &mut SurfaceResource<_> = process.get_mut();
-        let delist!(surface) = process.res();
+        let delist!(surface, _, _) = process.res();
         let event_loop = surface
             .event_loop
             .detach()
@@ -105,37 +112,66 @@ impl RunnableSurface for WinitSurface {
 
         debug!(target: "krill::surface::winit", "Starting event loop.");
 
+        // input and window events are collected and dispatched once per iteration of the loop, so
+        // e.g. a burst of resizes only reconfigures the surface once
+        let mut queue = EventQueue::new();
+        let mut previous_tick = Instant::now();
+
         event_loop.run(move |event, _, control_flow| {
             match event {
+                Event::MainEventsCleared => {
+                    let delist!(surface, _, tasks) = process.res();
+                    // tasks completed here can be taken by the handlers of this iteration
+                    tasks.poll();
+                    if let Some(watcher) = &mut surface.asset_watcher {
+                        queue_asset_changes(watcher.as_mut(), &mut queue);
+                    }
+                    dispatch_surface_events(&mut process, &mut queue);
+                }
                 Event::RedrawRequested(window_id) if window_id == window => {
-                    let _unhandled = process.handle_event(SurfaceEvent::Draw);
+                    let now = Instant::now();
+                    let delist!(_, clock, _) = process.res();
+                    clock.advance(now - previous_tick);
+                    process.tick(now - previous_tick);
+                    previous_tick = now;
                 }
                 Event::RedrawEventsCleared => {
-                    let delist!(surface) = process.res();
+                    let delist!(surface, _, _) = process.res();
                     surface.window.request_redraw();
                 }
                 Event::WindowEvent { event, window_id } if window_id == window => {
                     match event {
                         WindowEvent::Resized(PhysicalSize { width, height }) => {
-                            let _unhandled = process.handle_event(SurfaceEvent::Resize { width, height });
+                            queue.push(SurfaceEvent::Resize { width, height });
                         }
                         WindowEvent::CloseRequested => {
-                            let _unhandled = process.handle_event(SurfaceEvent::CloseRequested);
+                            queue.push(SurfaceEvent::CloseRequested);
                         }
                         WindowEvent::KeyboardInput { input, .. } => {
-                            let _unhandled = process.handle_event(SurfaceEvent::DeviceEvent(DeviceEvent::Key(input)));
+                            queue.push(SurfaceEvent::DeviceEvent(DeviceEvent::Key(input)));
+                        }
+                        // winit reports physical pixels relative to the window, or the canvas on
+                        // the web
+                        WindowEvent::CursorMoved { position: PhysicalPosition { x, y }, .. } => {
+                            queue.push(SurfaceEvent::CursorMoved { x, y });
+                        }
+                        WindowEvent::MouseInput { button, state, .. } => {
+                            queue.push(SurfaceEvent::MouseButton { button, state });
+                        }
+                        WindowEvent::MouseWheel { delta, .. } => {
+                            queue.push(SurfaceEvent::MouseWheel { delta });
                         }
                         _ => {}
                     }
                 }
                 Event::DeviceEvent { event, .. } => {
-                    let _unhandled = process.handle_event(SurfaceEvent::DeviceEvent(event));
+                    queue.push(SurfaceEvent::DeviceEvent(event));
                 }
                 _ => {},
             };
 
             //let surface: &mut SurfaceResource<_> = process.resources_mut().get_mut();
-            let delist!(surface) = process.res();
+            let delist!(surface, _, _) = process.res();
             match surface.exit.take() {
                 Some(Exit::Exit) => control_flow.set_exit(),
                 Some(Exit::Status(code)) => control_flow.set_exit_with_code(code),
@@ -148,4 +184,8 @@ impl RunnableSurface for WinitSurface {
     fn set_exit(&mut self, exit: Exit) {
         self.exit = Some(exit)
     }
+
+    fn set_asset_watcher(&mut self, watcher: Box<dyn AssetWatcher>) {
+        self.asset_watcher = Some(watcher);
+    }
 }
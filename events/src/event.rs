@@ -3,4 +3,11 @@
 /// the chain, or return an output immedietly. 
 pub trait Event {
     type Output;
+
+    /// Returns whether this event supersedes `previous` when queued directly after it in an
+    /// [EventQueue](crate::EventQueue), so only this event gets dispatched. Events never
+    /// coalesce unless an event type opts in by overriding this.
+    fn coalesces_with(&self, _previous: &Self) -> bool {
+        false
+    }
 }
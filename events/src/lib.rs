@@ -1,7 +1,9 @@
 mod event;
 mod handlers;
+mod queue;
 mod system;
 
 pub use event::Event;
 pub use handlers::{Context, EventHandlers, UnhandledEvent};
+pub use queue::{EventQueue, MAX_DISPATCH_DEPTH};
 pub use system::EventSystem;
@@ -0,0 +1,162 @@
+use std::mem::take;
+use std::vec::Drain;
+
+use crate::Event;
+
+/// Maximum number of rounds dispatched by a single [EventQueue::dispatch_all], bounding how long
+/// events emitting follow-up events can keep each other going.
+pub const MAX_DISPATCH_DEPTH: usize = 16;
+
+/// Holds events until they are dispatched, collapsing events that
+/// [coalesce](Event::coalesces_with) with the event queued right before them.
+pub struct EventQueue<E: Event> {
+    events: Vec<E>,
+}
+
+impl<E: Event> EventQueue<E> {
+    pub fn new() -> Self {
+        EventQueue { events: vec![] }
+    }
+
+    /// Queues an event, replacing the most recently queued event if the new one supersedes it.
+    pub fn push(&mut self, event: E) {
+        match self.events.last_mut() {
+            Some(previous) if event.coalesces_with(previous) => *previous = event,
+            _ => self.events.push(event),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Removes all queued events, in the order they were queued.
+    pub fn drain(&mut self) -> Drain<'_, E> {
+        self.events.drain(..)
+    }
+
+    /// Passes every queued event to `dispatch` in the order they were queued, along with the queue
+    /// itself so handling an event can queue follow-up events.
+    ///
+    /// Dispatching happens breadth-first: follow-up events are only dispatched after all events
+    /// that were queued before them, in a new round. Follow-ups of those form yet another round,
+    /// up to [MAX_DISPATCH_DEPTH] rounds. Events queued during the last round are kept for the
+    /// next call, and their number is returned.
+    pub fn dispatch_all(&mut self, mut dispatch: impl FnMut(E, &mut EventQueue<E>)) -> usize {
+        for _ in 0..MAX_DISPATCH_DEPTH {
+            if self.is_empty() {
+                break;
+            }
+
+            for event in take(&mut self.events) {
+                dispatch(event, self);
+            }
+        }
+        self.len()
+    }
+}
+
+impl<E: Event> Default for EventQueue<E> {
+    fn default() -> Self {
+        EventQueue::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Event, EventQueue, EventSystem};
+    use crate::queue::MAX_DISPATCH_DEPTH;
+
+    #[derive(Debug, PartialEq)]
+    enum WindowEvent {
+        Resize(u32, u32),
+        Draw,
+    }
+
+    impl Event for WindowEvent {
+        type Output = ();
+
+        fn coalesces_with(&self, previous: &Self) -> bool {
+            matches!((self, previous), (WindowEvent::Resize(..), WindowEvent::Resize(..)))
+        }
+    }
+
+    #[test]
+    fn coalesces_resizes() {
+        let mut queue = EventQueue::new();
+        queue.push(WindowEvent::Resize(100, 100));
+        queue.push(WindowEvent::Resize(200, 150));
+        queue.push(WindowEvent::Resize(300, 200));
+        queue.push(WindowEvent::Draw);
+        queue.push(WindowEvent::Draw);
+        assert_eq!(queue.len(), 3);
+
+        let mut system: EventSystem<Vec<WindowEvent>> = EventSystem::new();
+        system.handlers_for().append(|event: WindowEvent, mut context| context.push(event));
+
+        let mut dispatched = vec![];
+        for event in queue.drain() {
+            system.handle_event(event, &mut dispatched).unwrap();
+        }
+
+        assert!(queue.is_empty());
+        assert_eq!(dispatched, vec![WindowEvent::Resize(300, 200), WindowEvent::Draw, WindowEvent::Draw]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum ChainEvent {
+        Start(u32),
+        FollowUp(u32),
+    }
+
+    impl Event for ChainEvent {
+        type Output = ();
+    }
+
+    #[test]
+    fn follow_ups_after_current_round() {
+        let mut queue = EventQueue::new();
+        queue.push(ChainEvent::Start(1));
+        queue.push(ChainEvent::Start(2));
+
+        let mut dispatched = vec![];
+        let remaining = queue.dispatch_all(|event, queue| {
+            if let ChainEvent::Start(n) = event {
+                queue.push(ChainEvent::FollowUp(n));
+            }
+            dispatched.push(event);
+        });
+
+        assert_eq!(remaining, 0);
+        assert!(queue.is_empty());
+        assert_eq!(dispatched, vec![
+            ChainEvent::Start(1),
+            ChainEvent::Start(2),
+            ChainEvent::FollowUp(1),
+            ChainEvent::FollowUp(2),
+        ]);
+    }
+
+    #[test]
+    fn runaway_emitter_capped() {
+        let mut queue = EventQueue::new();
+        queue.push(ChainEvent::FollowUp(0));
+
+        let mut dispatched = 0;
+        let remaining = queue.dispatch_all(|event, queue| {
+            dispatched += 1;
+            if let ChainEvent::FollowUp(n) = event {
+                queue.push(ChainEvent::FollowUp(n + 1));
+            }
+        });
+
+        assert_eq!(dispatched, MAX_DISPATCH_DEPTH);
+        assert_eq!(remaining, 1);
+        // continues where the previous call stopped
+        queue.dispatch_all(|event, _| assert_eq!(event, ChainEvent::FollowUp(MAX_DISPATCH_DEPTH as u32)));
+    }
+}
@@ -12,9 +12,20 @@ struct VertexOutput {
     @location(0) color: vec4<f32>
 }
 
+struct TimeUniform {
+    seconds: f32,
+    // uniform buffers are padded to 16 bytes
+    _padding0: f32,
+    _padding1: f32,
+    _padding2: f32,
+}
+
 @group(0) @binding(0)
 var<uniform> camera: CameraUniform;
 
+@group(1) @binding(0)
+var<uniform> time: TimeUniform;
+
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
@@ -25,5 +36,7 @@ fn vs_main(input: VertexInput) -> VertexOutput {
 
 @fragment
 fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
-    return color;
+    // slow, subtle pulse of the brightness
+    let pulse = 0.95 + 0.05 * sin(time.seconds * 2.0);
+    return vec4(color.rgb * pulse, color.a);
 }
@@ -0,0 +1,197 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+
+use nalgebra::Vector3;
+
+use engine::ecs::world::{EntityId, View, World};
+use engine::events::{Context, Event, EventSystem};
+
+use crate::game::Body;
+
+#[derive(Clone, Debug)]
+pub struct Collider {
+    pub size: f32,
+}
+
+fn collides(a: &Collider, a_pos: &Vector3<f32>, b: &Collider, b_pos: &Vector3<f32>) -> bool {
+    let distance = (a_pos - b_pos).magnitude();
+    distance < (a.size + b.size)
+}
+
+/// Emitted once per unordered pair of overlapping colliders, see [emit_collisions].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub a: EntityId,
+    pub b: EntityId,
+}
+
+impl Event for CollisionEvent {
+    type Output = ();
+}
+
+impl CollisionEvent {
+    /// Returns the colliding entities ordered as (marked with `A`, marked with `B`), or [None] if
+    /// the collision isn't between entities with those markers.
+    pub fn between<A: 'static, B: 'static>(&self, world: &World) -> Option<(EntityId, EntityId)> {
+        let a = world.components::<A>();
+        let b = world.components::<B>();
+        if a.has(self.a) && b.has(self.b) {
+            Some((self.a, self.b))
+        } else if a.has(self.b) && b.has(self.a) {
+            Some((self.b, self.a))
+        } else {
+            None
+        }
+    }
+}
+
+/// Wraps `handler` so it only receives collisions between an entity marked with `A` and one marked
+/// with `B`, in that order. Any other collision is delegated to the next handler.
+pub fn between<A: 'static, B: 'static, S: Borrow<World>>(mut handler: impl FnMut(EntityId, EntityId, &mut S)) -> impl FnMut(CollisionEvent, Context<'_, '_, CollisionEvent, S>) {
+    move |event, mut context| {
+        match event.between::<A, B>((*context).borrow()) {
+            Some((a, b)) => handler(a, b, &mut context),
+            None => {
+                context.delegate(event);
+            }
+        }
+    }
+}
+
+type Cell = (i32, i32);
+
+type GridEntry<'a> = (EntityId, &'a Vector3<f32>, &'a Collider);
+
+/// Uniform grid bucketing colliders by the cells their bounding squares overlap, so only colliders
+/// sharing a cell are tested against each other.
+struct SpatialGrid<'a> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<GridEntry<'a>>>,
+}
+
+impl<'a> SpatialGrid<'a> {
+    fn new(cell_size: f32) -> Self {
+        SpatialGrid { cell_size, cells: HashMap::new() }
+    }
+
+    /// Inclusive range of cells covered by the bounding square of a collider.
+    fn cell_range(&self, position: &Vector3<f32>, collider: &Collider) -> (Cell, Cell) {
+        let cell = |value: f32| (value / self.cell_size).floor() as i32;
+        (
+            (cell(position.x - collider.size), cell(position.y - collider.size)),
+            (cell(position.x + collider.size), cell(position.y + collider.size)),
+        )
+    }
+
+    fn insert(&mut self, entity: EntityId, position: &'a Vector3<f32>, collider: &'a Collider) {
+        let ((min_x, min_y), (max_x, max_y)) = self.cell_range(position, collider);
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                self.cells.entry((x, y)).or_default().push((entity, position, collider));
+            }
+        }
+    }
+
+    fn collisions(&self) -> Vec<CollisionEvent> {
+        let mut events = vec![];
+        for (cell, colliders) in &self.cells {
+            for (index, (a, a_pos, a_collider)) in colliders.iter().enumerate() {
+                for (b, b_pos, b_collider) in &colliders[index + 1..] {
+                    // pairs sharing several cells are only reported from the first shared cell
+                    let (a_min, _) = self.cell_range(a_pos, a_collider);
+                    let (b_min, _) = self.cell_range(b_pos, b_collider);
+                    let first_shared = (a_min.0.max(b_min.0), a_min.1.max(b_min.1));
+                    if first_shared == *cell && collides(a_collider, a_pos, b_collider, b_pos) {
+                        events.push(CollisionEvent { a: *a, b: *b });
+                    }
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Finds all pairs of overlapping entities with a [Body] and [Collider], using a grid of
+/// `cell_size` wide cells.
+pub fn detect_collisions(world: &World, cell_size: f32) -> Vec<CollisionEvent> {
+    let colliders = View::builder()
+        .required::<Body>()
+        .required::<Collider>()
+        .build(world);
+
+    let mut grid = SpatialGrid::new(cell_size);
+    for (entity, body, collider) in colliders.iter_flat() {
+        grid.insert(entity, body.transform.position(), collider);
+    }
+    grid.collisions()
+}
+
+/// Dispatches a [CollisionEvent] for every pair of overlapping colliders in the world borrowed
+/// from `state`.
+pub fn emit_collisions<S: 'static + Borrow<World>>(events: &mut EventSystem<S>, state: &mut S, cell_size: f32) {
+    for event in detect_collisions((*state).borrow(), cell_size) {
+        // unhandled collisions are simply ignored
+        let _ = events.handle_event(event, state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Borrow;
+
+    use nalgebra::vector;
+
+    use engine::ecs::world::World;
+    use engine::events::{Context, EventSystem};
+
+    use crate::collision::{Collider, CollisionEvent, emit_collisions};
+    use crate::game::Body;
+
+    struct State {
+        world: World,
+        events: Vec<CollisionEvent>,
+    }
+
+    impl Borrow<World> for State {
+        fn borrow(&self) -> &World {
+            &self.world
+        }
+    }
+
+    #[test]
+    fn one_event_per_pair() {
+        let mut world = World::default()
+            .with_component::<Body>()
+            .with_component::<Collider>();
+        let mut spawn = |x: f32, y: f32, size: f32| {
+            let entity = world.new_entity();
+            let mut body = Body::default();
+            body.transform.position = vector!(x, y, 0.0);
+            world.components_mut::<Body>().put(entity, body);
+            world.components_mut::<Collider>().put(entity, Collider { size });
+            entity
+        };
+        // a and b share several cells, b and c only touch a single one
+        let a = spawn(0.9, 0.9, 1.5);
+        let b = spawn(1.1, 1.2, 1.5);
+        let c = spawn(3.5, 1.2, 1.0);
+        spawn(10.0, 10.0, 0.5);
+
+        let mut system = EventSystem::new();
+        system.handlers_for().append(|event, mut context: Context<CollisionEvent, State>| context.events.push(event));
+        let mut state = State { world, events: vec![] };
+        emit_collisions(&mut system, &mut state, 1.0);
+
+        let mut pairs: Vec<_> = state.events.iter()
+            .map(|event| [event.a, event.b])
+            .collect();
+        assert_eq!(pairs.len(), 2, "{pairs:?}");
+        for pair in &mut pairs {
+            if pair[0] == b {
+                pair.swap(0, 1);
+            }
+        }
+        assert!(pairs.contains(&[a, b]), "{pairs:?}");
+        assert!(pairs.contains(&[c, b]), "{pairs:?}");
+    }
+}
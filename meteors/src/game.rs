@@ -1,4 +1,5 @@
-use std::mem::swap;
+use std::borrow::Borrow;
+use std::mem::{swap, take};
 use std::time::Duration;
 
 use bytemuck::bytes_of;
@@ -12,15 +13,41 @@ use rand::random;
 use engine::asset_resource::AssetSourceResource;
 use engine::assets::source::AssetSource;
 use engine::ecs::world::{EntityId, View, World};
-use engine::events::Context;
+use engine::events::{Context, EventSystem};
+use engine::frame_clock::FrameClock;
+use engine::process::Update;
 use engine::render::{Batch, RenderApi};
 use engine::surface::{Exit, RunnableSurface, SurfaceEvent, SurfaceResource};
 use engine::surface::input::{DeviceEvent, ElementState, VirtualKeyCode};
-use engine::utils::{HList, hlist};
+use engine::utils::{delist, HList, hlist};
 use engine::wgpu_render::WGPURenderResource;
 
-use crate::graphics::{BACKGROUND_COLOR, FOREGROUND_COLOR, GameModel, Graphics, Shape};
+use crate::collision::{between, Collider, emit_collisions};
+use crate::graphics::{BACKGROUND_COLOR, DIM_FOREGROUND_COLOR, FOREGROUND_COLOR, GameModel, GameShader, Graphics, Shape};
+use crate::text::{Alignment, FontWeight};
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Shoot,
+}
+
+/// Keys as seen by a single update step.
+#[derive(Debug, Default, PartialEq)]
+struct SampledInput {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    /// Number of times shoot was pressed since the previous update.
+    shots: u32,
+}
+
+/// Buffers key events between updates, so presses and releases happening within a single frame
+/// are not lost.
 #[derive(Debug, Default)]
 struct InputState {
     up: bool,
@@ -28,7 +55,41 @@ struct InputState {
     left: bool,
     right: bool,
     shoot: bool,
-    has_shot: bool,
+    events: Vec<(Key, bool)>,
+}
+
+impl InputState {
+    fn record(&mut self, key: Key, pressed: bool) {
+        self.events.push((key, pressed));
+    }
+
+    /// Applies the events buffered since the previous update, returning the keys held now along
+    /// with the number of shots fired in between.
+    fn sample(&mut self) -> SampledInput {
+        let mut shots = 0;
+        for (key, pressed) in self.events.drain(..) {
+            let held = match key {
+                Key::Up => &mut self.up,
+                Key::Down => &mut self.down,
+                Key::Left => &mut self.left,
+                Key::Right => &mut self.right,
+                Key::Shoot => &mut self.shoot,
+            };
+            // repeated presses while holding the key don't count
+            if key == Key::Shoot && pressed && !*held {
+                shots += 1;
+            }
+            *held = pressed;
+        }
+
+        SampledInput {
+            up: self.up,
+            down: self.down,
+            left: self.left,
+            right: self.right,
+            shots,
+        }
+    }
 }
 
 type Vec2 = Vector2<f32>;
@@ -36,7 +97,7 @@ type Vec3 = Vector3<f32>;
 
 #[derive(Clone, Debug, Default)]
 pub struct Body {
-    transform: Transform,
+    pub(crate) transform: Transform,
     velocity: Vec3,
     angular_velocity: f32,
     transient: bool,
@@ -44,7 +105,7 @@ pub struct Body {
 
 #[derive(Clone, Debug)]
 pub struct Transform {
-    position: Vec3,
+    pub(crate) position: Vec3,
     rotation: f32,
     size: f32,
 }
@@ -92,35 +153,14 @@ enum Type {
     Meteor,
 }
 
-#[derive(Clone, Debug)]
-pub struct Collider {
-    size: f32,
-}
-
-fn collides(a: &Collider, a_pos: &Vec3, b: &Collider, b_pos: &Vec3) -> bool {
-    let distance = (a_pos - b_pos).magnitude();
-    distance < (a.size + b.size)
-}
-
 fn default_world() -> World {
     World::default()
-        .with_component::<Player>()
-        .with_component::<Meteor>()
-        .with_component::<Bullet>()
-        .with_component::<Body>()
-        .with_component::<Shape>()
-        .with_component::<Collider>()
+        .with_sparse_component::<Player>()
+        .with_components::<HList!(Meteor, Bullet, Body, Shape, Collider)>()
 }
 
 fn add_player(world: &mut World) -> EntityId {
-    let player = world.new_entity();
-
-    world.components_mut::<Player>().put(player, Player);
-    world.components_mut::<Body>().put(player, Default::default());
-    world.components_mut::<Shape>().put(player, Shape::Ship);
-    world.components_mut::<Collider>().put(player, Collider { size: 0.025 });
-
-    player
+    world.spawn(hlist!(Player, Body::default(), Shape::Ship, Collider { size: 0.025 }))
 }
 
 pub enum GameState {
@@ -187,7 +227,6 @@ pub struct GameOverState {
 
 pub struct GlobalState {
     input_state: InputState,
-    previous_update: Instant,
     bounds: Vec2,
 }
 
@@ -223,7 +262,6 @@ impl Default for GlobalState {
     fn default() -> Self {
         GlobalState {
             input_state: Default::default(),
-            previous_update: Instant::now(),
             bounds: vector!(Self::VIEWPORT_SCALE, Self::VIEWPORT_SCALE),
         }
     }
@@ -243,10 +281,73 @@ impl GlobalState {
     }
 }
 
+/// Width of the cells colliders are bucketed into, roughly the size of the largest meteor.
+const COLLISION_CELL_SIZE: f32 = 2.0;
+
+/// State handed to collision handlers while the collisions of a single update are dispatched.
+struct CollisionContext {
+    world: World,
+    create: Vec<(Type, Components)>,
+    remove: Vec<EntityId>,
+    score: u32,
+    hit_start_meteor: bool,
+}
+
+impl CollisionContext {
+    fn new(world: World) -> Self {
+        CollisionContext {
+            world,
+            create: vec![],
+            remove: vec![],
+            score: 0,
+            hit_start_meteor: false,
+        }
+    }
+}
+
+impl Borrow<World> for CollisionContext {
+    fn borrow(&self) -> &World {
+        &self.world
+    }
+}
+
+fn main_menu_collisions() -> EventSystem<CollisionContext> {
+    let mut events = EventSystem::new();
+    events.handlers_for().append(between::<Bullet, Meteor, _>(|bullet, meteor, context: &mut CollisionContext| {
+        context.hit_start_meteor = true;
+        let bullet_rotation = context.world.components::<Body>().get(bullet).unwrap().transform.rotation;
+        let velocity = Rotation3::from_euler_angles(0.0, 0.0, bullet_rotation) * vector!(0.0, 1.8, 0.0);
+        let meteors = View::builder().required::<Body>().required::<Collider>().build(&context.world);
+        let delist!(body, collider) = meteors.get(meteor).unwrap();
+        split_meteor(body, collider, velocity, &mut context.create);
+        context.remove.push(meteor);
+        context.remove.push(bullet);
+    }));
+    events
+}
+
+fn in_game_collisions() -> EventSystem<CollisionContext> {
+    let mut events = EventSystem::new();
+    events.handlers_for().append(between::<Player, Meteor, _>(|player, _, context: &mut CollisionContext| {
+        context.remove.push(player);
+    }));
+    events.handlers_for().append(between::<Bullet, Meteor, _>(|bullet, meteor, context: &mut CollisionContext| {
+        let meteors = View::builder().required::<Body>().required::<Collider>().build(&context.world);
+        let delist!(body, collider) = meteors.get(meteor).unwrap();
+        context.score += calculate_score(body.transform.size);
+        context.remove.push(bullet);
+        context.remove.push(meteor);
+        split_meteor(body, collider, Vec3::zeros(), &mut context.create);
+    }));
+    events
+}
+
 pub struct GameResource {
     pub graphics: Graphics,
     pub state: GameState,
     pub global: GlobalState,
+    main_menu_collisions: EventSystem<CollisionContext>,
+    in_game_collisions: EventSystem<CollisionContext>,
 }
 
 impl GameResource {
@@ -255,6 +356,8 @@ impl GameResource {
             graphics: Graphics::new(render),
             state: Default::default(),
             global: Default::default(),
+            main_menu_collisions: main_menu_collisions(),
+            in_game_collisions: in_game_collisions(),
         }
     }
 }
@@ -272,12 +375,110 @@ pub async fn setup_game_resources<A: AssetSource>(resources: HList!(WGPURenderRe
 const MAX_METEOR_SIZE: f32 = 2.0;
 const SIZE_BIAS: f32 = 1.8;
 
+/// Advances the game by one [Update], sampling the input buffered since the previous update, so
+/// key presses are counted the same regardless of the frame rate.
+pub fn on_update<R, I>(update: Update, mut context: Context<Update, R>)
+    where R: HasResources<HList!(GameResource), I>, {
+    let (game, _) = context.res();
+
+    game.state = match game.state.take() {
+        GameState::Empty => GameState::new(),
+        GameState::MainMenu(mut state) => {
+            let mut create = vec![];
+            let mut remove = vec![];
+            common_update_world(GameContext {
+                global: &mut game.global,
+                delta: update.dt,
+                world: &mut state.world,
+                create: &mut create,
+                remove: &mut remove,
+            });
+            let mut collisions = CollisionContext::new(take(&mut state.world));
+            emit_collisions(&mut game.main_menu_collisions, &mut collisions, COLLISION_CELL_SIZE);
+            let hit_start_meteor = collisions.hit_start_meteor;
+            state.world = collisions.world;
+            remove.extend(collisions.remove);
+            create.extend(collisions.create);
+
+            state.world.drop_entities(remove);
+            create_entities(create, &mut state.world);
+
+            if hit_start_meteor {
+                GameState::InGame(IngameState::from(state.world))
+            } else {
+                GameState::MainMenu(state)
+            }
+        }
+        GameState::InGame(mut state) => {
+            // update game state
+            let mut create = vec![];
+            let mut remove = vec![];
+            common_update_world(GameContext {
+                global: &mut game.global,
+                delta: update.dt,
+                world: &mut state.world,
+                create: &mut create,
+                remove: &mut remove,
+            });
+
+            if state.previous_meteor.elapsed() >= state.meteor_timer {
+                spawn_meteor(&state.world, &game.global, &mut create);
+                state.previous_meteor = Instant::now();
+                // spawn next meteor 10% sooner to increase difficulty
+                state.meteor_timer = Duration::from_secs_f32(state.meteor_timer.as_secs_f32() * 0.90);
+            }
+
+            // handle collisions
+            let mut collisions = CollisionContext::new(take(&mut state.world));
+            emit_collisions(&mut game.in_game_collisions, &mut collisions, COLLISION_CELL_SIZE);
+            state.world = collisions.world;
+            state.score += collisions.score;
+            remove.extend(collisions.remove);
+            create.extend(collisions.create);
+
+            state.world.drop_entities(remove);
+            create_entities(create, &mut state.world);
+
+            // transition to game over state if all players are dead
+            let player_count = View::builder().marked::<Player>().build(&state.world).count();
+            if player_count == 0 {
+                debug!(target: "meteors", "Game over, score: {}", state.score);
+                GameState::GameOver(GameOverState {
+                    score: state.score,
+                    world: state.world,
+                    dead_time: Instant::now(),
+                    fade_out: Duration::from_secs(3),
+                })
+            } else {
+                GameState::InGame(state)
+            }
+        }
+        GameState::GameOver(mut state) => {
+            common_update_world(GameContext {
+                global: &mut game.global,
+                delta: update.dt,
+                world: &mut state.world,
+                remove: &mut vec![],
+                create: &mut vec![],
+            });
+
+            if state.dead_time.elapsed() > state.fade_out {
+                debug!(target:"meteors", "Restarting game...");
+                GameState::InGame(Default::default())
+            } else {
+                GameState::GameOver(state)
+            }
+        }
+    };
+}
+
 pub fn on_surface_event<R, S, I>(event: SurfaceEvent, mut context: Context<SurfaceEvent, R>) -> ()
     where S: RunnableSurface,
-          R: HasResources<HList!(GameResource, WGPURenderResource, SurfaceResource<S>), I>, {
+          R: HasResources<HList!(GameResource, WGPURenderResource, SurfaceResource<S>, FrameClock), I>, {
     let (game, resources) = context.res();
     let (render, resources) = resources;
-    let (surface, _) = resources;
+    let (surface, resources) = resources;
+    let (clock, _) = resources;
 
     match event {
         SurfaceEvent::Resize { width, height } => {
@@ -285,31 +486,13 @@ pub fn on_surface_event<R, S, I>(event: SurfaceEvent, mut context: Context<Surfa
             game.global.calculate_bounds(width, height);
         }
         SurfaceEvent::Draw => {
+            let mut batch = Batch::new(&game.graphics.material, vec![&game.graphics.camera_uniform, &game.graphics.time_uniform]);
             let mut models = vec![];
 
-            game.state = match game.state.take() {
-                GameState::Empty => GameState::new(),
-                GameState::MainMenu(mut state) => {
-                    let mut create = vec![];
-                    let mut remove = vec![];
-                    common_update_world(GameContext {
-                        global: &mut game.global,
-                        world: &mut state.world,
-                        create: &mut create,
-                        remove: &mut remove,
-                    });
-                    let mut hit_start_meteor = false;
-                    check_collisions_between::<Bullet, Meteor, _>(&state.world, |((bullet, bullet_body, _), (meteor, meteor_body, meteor_collider))| {
-                        hit_start_meteor = true;
-                        let velocity = vector!(0.0, 1.8, 0.0);
-                        split_meteor(meteor_body, meteor_collider, Rotation3::from_euler_angles(0.0, 0.0, bullet_body.transform.rotation) * velocity, &mut create);
-                        remove.push(meteor);
-                        remove.push(bullet);
-                    });
-                    remove_entities(remove, &mut state.world);
-                    create_entities(create, &mut state.world);
-
-                    draw_world(&state.world, &mut game.graphics, &mut models);
+            match &game.state {
+                GameState::Empty => {}
+                GameState::MainMenu(state) => {
+                    draw_world(&state.world, &game.graphics, 1.0, &mut batch);
                     draw_logo(&game.graphics, &mut models);
 
                     game.graphics.draw_arrow_keys(
@@ -317,108 +500,28 @@ pub fn on_surface_event<R, S, I>(event: SurfaceEvent, mut context: Context<Surfa
                         FOREGROUND_COLOR,
                         &mut models,
                     );
-                    game.graphics.draw_text(
-                        "MOVE",
-                        Matrix4::new_scaling(0.3).append_translation(&vector!(-5.55, -2.8, 0.0)),
-                        FOREGROUND_COLOR,
-                        &mut models,
-                    );
+                    draw_label(&game.graphics, "MOVE", vector!(-4.5, -2.8), &mut models);
 
                     game.graphics.draw_spacebar(
                         Matrix4::new_scaling(0.3).append_translation(&vector!(0.0, -2.0, 0.0)),
                         FOREGROUND_COLOR,
                         &mut models,
                     );
-                    game.graphics.draw_text(
-                        "SHOOT",
-                        Matrix4::new_scaling(0.3).append_translation(&vector!(-1.4, -2.8, 0.0)),
-                        FOREGROUND_COLOR,
-                        &mut models,
-                    );
+                    draw_label(&game.graphics, "SHOOT", vector!(0.0, -2.8), &mut models);
 
-                    game.graphics.draw_text(
-                        "DESTROY",
-                        Matrix4::new_scaling(0.3).append_translation(&vector!(2.6, -2.8, 0.0)),
-                        FOREGROUND_COLOR,
-                        &mut models,
-                    );
-
-                    if hit_start_meteor {
-                        GameState::InGame(IngameState::from(state.world))
-                    } else {
-                        GameState::MainMenu(state)
-                    }
+                    draw_label(&game.graphics, "DESTROY", vector!(4.5, -2.8), &mut models);
                 }
-                GameState::InGame(mut state) => {
-                    // update game state
-                    let mut create = vec![];
-                    let mut remove = vec![];
-                    common_update_world(GameContext {
-                        global: &mut game.global,
-                        world: &mut state.world,
-                        create: &mut create,
-                        remove: &mut remove,
-                    });
-
-                    if state.previous_meteor.elapsed() >= state.meteor_timer {
-                        spawn_meteor(&state.world, &game.global, &mut create);
-                        state.previous_meteor = Instant::now();
-                        // spawn next meteor 10% sooner to increase difficulty
-                        state.meteor_timer = Duration::from_secs_f32(state.meteor_timer.as_secs_f32() * 0.90);
-                    }
-
-                    // handle collisions
-                    check_collisions_between::<Player, Meteor, _>(&state.world, |((player, ..), ..)| {
-                        remove.push(player);
-                    });
-                    check_collisions_between::<Bullet, Meteor, _>(&state.world, |((bullet, ..), (meteor, body, collider))| {
-                        state.score += calculate_score(body.transform.size);
-                        remove.push(bullet);
-                        remove.push(meteor);
-                        split_meteor(body, collider, Vec3::zeros(), &mut create);
-                    });
-
-                    remove_entities(remove, &mut state.world);
-                    create_entities(create, &mut state.world);
-
-                    draw_world(&state.world, &mut game.graphics, &mut models);
+                GameState::InGame(state) => {
+                    draw_world(&state.world, &game.graphics, 1.0, &mut batch);
                     draw_score(state.score, &game.global, &game.graphics, &mut models);
-
-                    // transition to game over state if all players are dead
-                    let player_count = View::builder().marked::<Player>().build(&state.world).iter().count();
-                    if player_count == 0 {
-                        debug!(target: "meteors", "Game over, score: {}", state.score);
-                        GameState::GameOver(GameOverState {
-                            score: state.score,
-                            world: state.world,
-                            dead_time: Instant::now(),
-                            fade_out: Duration::from_secs(3),
-                        })
-                    } else {
-                        GameState::InGame(state)
-                    }
                 }
-                GameState::GameOver(mut state) => {
-                    common_update_world(GameContext {
-                        global: &mut game.global,
-                        world: &mut state.world,
-                        remove: &mut vec![],
-                        create: &mut vec![],
-                    });
-
-                    draw_world(&state.world, &mut game.graphics, &mut models);
+                GameState::GameOver(state) => {
+                    // fade the remaining meteors out until the game restarts
+                    let opacity = 1.0 - state.dead_time.elapsed().as_secs_f32() / state.fade_out.as_secs_f32();
+                    draw_world(&state.world, &game.graphics, opacity.max(0.0), &mut batch);
                     draw_score(state.score, &game.global, &game.graphics, &mut models);
-
-                    if state.dead_time.elapsed() > state.fade_out {
-                        debug!(target:"meteors", "Restarting game...");
-                        GameState::InGame(Default::default())
-                    } else {
-                        GameState::GameOver(state)
-                    }
                 }
-            };
-
-            game.global.previous_update = Instant::now();
+            }
 
             // setup camera uniform buffer
             let camera_scale = vector!(1.0 / game.global.bounds.x, 1.0 / game.global.bounds.y);
@@ -427,14 +530,18 @@ pub fn on_surface_event<R, S, I>(event: SurfaceEvent, mut context: Context<Surfa
             render.get_buffer(game.graphics.camera_uniform_buffer)
                 .unwrap()
                 .upload(0, bytes_of(&view_matrix));
+            let time = [clock.elapsed().as_secs_f32(), 0.0, 0.0, 0.0];
+            render.get_buffer(game.graphics.time_uniform_buffer)
+                .unwrap()
+                .upload(0, bytes_of(&time));
 
             // draw game
             let frame = render.request_frame();
 
             let mut drawer = render.new_drawer(&frame);
 
-            let mut batch = Batch::new(&game.graphics.material, vec![&game.graphics.camera_uniform]);
             batch.clear(BACKGROUND_COLOR);
+            batch.lod_view(view_matrix);
             batch.models(models);
 
             drawer.submit_batch(batch);
@@ -444,20 +551,15 @@ pub fn on_surface_event<R, S, I>(event: SurfaceEvent, mut context: Context<Surfa
         }
         SurfaceEvent::CloseRequested => surface.set_exit(Exit::Exit),
         SurfaceEvent::DeviceEvent(DeviceEvent::Key(key)) => {
-            let state = key.state == ElementState::Pressed;
-            match key.virtual_keycode {
-                Some(VirtualKeyCode::Up) => game.global.input_state.up = state,
-                Some(VirtualKeyCode::Down) => game.global.input_state.down = state,
-                Some(VirtualKeyCode::Left) => game.global.input_state.left = state,
-                Some(VirtualKeyCode::Right) => game.global.input_state.right = state,
-                Some(VirtualKeyCode::Space) => {
-                    game.global.input_state.shoot = state;
-                    if !state {
-                        game.global.input_state.has_shot = false;
-                    }
-                }
-                _ => (),
-            }
+            let key_code = match key.virtual_keycode {
+                Some(VirtualKeyCode::Up) => Key::Up,
+                Some(VirtualKeyCode::Down) => Key::Down,
+                Some(VirtualKeyCode::Left) => Key::Left,
+                Some(VirtualKeyCode::Right) => Key::Right,
+                Some(VirtualKeyCode::Space) => Key::Shoot,
+                _ => return,
+            };
+            game.global.input_state.record(key_code, key.state == ElementState::Pressed);
         }
         _ => {}
     }
@@ -472,25 +574,20 @@ struct Components {
 
 struct GameContext<'a> {
     global: &'a mut GlobalState,
+    /// Time since the previous update.
+    delta: Duration,
     world: &'a mut World,
     create: &'a mut Vec<(Type, Components)>,
     remove: &'a mut Vec<EntityId>,
 }
 
-fn remove_entities(entities: Vec<EntityId>, world: &mut World) {
-    for entity in entities {
-        world.drop_entity(entity);
-    }
-}
-
 fn create_entities(entities: Vec<(Type, Components)>, world: &mut World) {
     for (typ, Components { body, shape, collider }) in entities {
-        let entity = world.new_entity();
-        match typ {
-            Type::Player => world.components_mut::<Player>().put(entity, Player),
-            Type::Bullet => world.components_mut::<Bullet>().put(entity, Bullet),
-            Type::Meteor => world.components_mut::<Meteor>().put(entity, Meteor),
-        }
+        let entity = match typ {
+            Type::Player => world.spawn(hlist!(Player)),
+            Type::Bullet => world.spawn(hlist!(Bullet)),
+            Type::Meteor => world.spawn(hlist!(Meteor)),
+        };
 
         if let Some(body) = body {
             world.components_mut::<Body>().put(entity, body);
@@ -512,97 +609,88 @@ fn calculate_score(size: f32) -> u32 {
     score
 }
 
-/// Common operations that need to occur every frame regardless of game state
+/// Common operations that need to occur every update regardless of game state
 fn common_update_world(mut context: GameContext) {
-    let elapsed_since_previous_frame = context.global.previous_update.elapsed().as_secs_f32();
-
-    let mut bodies = context.world.components_mut::<Body>();
+    let elapsed_since_previous_frame = context.delta.as_secs_f32();
+    let input = context.global.input_state.sample();
 
     // update player
     const MAX_SPEED: f32 = 12.0;
     const THRUST_AMOUNT: f32 = 7.0;
     let thrust_direction = vector!(
         0.0,
-        if context.global.input_state.up { THRUST_AMOUNT } else { 0.0 }
-            + if context.global.input_state.down { -THRUST_AMOUNT } else { 0.0 },
+        if input.up { THRUST_AMOUNT } else { 0.0 }
+            + if input.down { -THRUST_AMOUNT } else { 0.0 },
         0.0
     );
 
     const ROTATION_SPEED: f32 = 2.1;
-    let player_rotation = (if context.global.input_state.left { 1.0 } else { 0.0 } +
-        if context.global.input_state.right { -1.0 } else { 0.0 }) * ROTATION_SPEED;
+    let player_rotation = (if input.left { 1.0 } else { 0.0 } +
+        if input.right { -1.0 } else { 0.0 }) * ROTATION_SPEED;
 
     const BULLET_SPEED: f32 = 20.0;
-    let shoot = if context.global.input_state.shoot && !context.global.input_state.has_shot {
-        context.global.input_state.has_shot = true;
-        true
-    } else { false };
 
-    for (player, ..) in View::builder()
+    let mut players = View::builder()
         .marked::<Player>()
-        .build(context.world)
-        .iter() {
-        if let Some(mut body) = bodies.get(player).cloned() {
-            body.transform.rotation += player_rotation * elapsed_since_previous_frame;
-
-            let thrust_angle = Rotation3::from_axis_angle(&Vec3::z_axis(), body.transform.rotation);
-            let thrust = thrust_angle * thrust_direction;
-            body.velocity += thrust * elapsed_since_previous_frame;
-            if body.velocity.magnitude() > MAX_SPEED {
-                body.velocity = body.velocity.normalize() * MAX_SPEED;
-            }
-
-            if shoot {
-                let angle = Rotation3::from_axis_angle(&Vec3::z_axis(), body.transform.rotation);
-                let angle = angle * Vec3::y_axis();
-                context.create.push((
-                    Type::Bullet,
-                    Components {
-                        body: Some(Body {
-                            transform: Transform {
-                                position: body.transform.position + angle.scale(0.2),
-                                rotation: body.transform.rotation,
-                                size: 1.0,
-                            },
-                            velocity: angle.scale(BULLET_SPEED),
-                            transient: true,
-
-                            ..Default::default()
-                        }),
-                        shape: Some(Shape::Bullet),
-                        collider: Some(Collider { size: 0.1 }),
-                    },
-                ));
-            }
+        .required_mut::<Body>()
+        .build(context.world);
+    for (_, body) in players.iter_flat_mut() {
+        body.transform.rotation += player_rotation * elapsed_since_previous_frame;
+
+        let thrust_angle = Rotation3::from_axis_angle(&Vec3::z_axis(), body.transform.rotation);
+        let thrust = thrust_angle * thrust_direction;
+        body.velocity += thrust * elapsed_since_previous_frame;
+        if body.velocity.magnitude() > MAX_SPEED {
+            body.velocity = body.velocity.normalize() * MAX_SPEED;
+        }
 
-            bodies.put(player, body);
+        for _ in 0..input.shots {
+            let angle = Rotation3::from_axis_angle(&Vec3::z_axis(), body.transform.rotation);
+            let angle = angle * Vec3::y_axis();
+            context.create.push((
+                Type::Bullet,
+                Components {
+                    body: Some(Body {
+                        transform: Transform {
+                            position: body.transform.position + angle.scale(0.2),
+                            rotation: body.transform.rotation,
+                            size: 1.0,
+                        },
+                        velocity: angle.scale(BULLET_SPEED),
+                        transient: true,
+
+                        ..Default::default()
+                    }),
+                    shape: Some(Shape::Bullet),
+                    collider: Some(Collider { size: 0.1 }),
+                },
+            ));
         }
     }
+    drop(players);
 
     // update physics
-    for entity in context.world.entity_iter() {
-        if let Some(body) = bodies.get(entity) {
-            let mut body = body.clone();
-            body.transform.rotation += body.angular_velocity * elapsed_since_previous_frame;
-            body.transform.position += body.velocity * elapsed_since_previous_frame;
-
-            if body.transient {
-                if body.transform.position.x.abs() > context.global.bounds.x || body.transform.position.y.abs() > context.global.bounds.y {
-                    context.remove.push(entity);
-                }
-            } else {
-                // wraps position to screen bounds
-                body.transform.position.x = (body.transform.position.x + context.global.bounds.x) % (context.global.bounds.x * 2.0) - context.global.bounds.x;
-                body.transform.position.y = (body.transform.position.y + context.global.bounds.y) % (context.global.bounds.y * 2.0) - context.global.bounds.y;
-                if body.transform.position.x < -context.global.bounds.x {
-                    body.transform.position.x += context.global.bounds.x * 2.0;
-                }
-                if body.transform.position.y < -context.global.bounds.y {
-                    body.transform.position.y += context.global.bounds.y * 2.0;
-                }
+    let mut bodies = View::builder()
+        .required_mut::<Body>()
+        .build(context.world);
+    for (entity, body) in bodies.iter_flat_mut() {
+        body.transform.rotation += body.angular_velocity * elapsed_since_previous_frame;
+        body.transform.position += body.velocity * elapsed_since_previous_frame;
+
+        if body.transient {
+            if body.transform.position.x.abs() > context.global.bounds.x || body.transform.position.y.abs() > context.global.bounds.y {
+                context.remove.push(entity);
+            }
+        } else {
+            // wraps position to screen bounds
+            body.transform.position.x = (body.transform.position.x + context.global.bounds.x) % (context.global.bounds.x * 2.0) - context.global.bounds.x;
+            body.transform.position.y = (body.transform.position.y + context.global.bounds.y) % (context.global.bounds.y * 2.0) - context.global.bounds.y;
+            if body.transform.position.x < -context.global.bounds.x {
+                body.transform.position.x += context.global.bounds.x * 2.0;
+            }
+            if body.transform.position.y < -context.global.bounds.y {
+                body.transform.position.y += context.global.bounds.y * 2.0;
             }
-
-            bodies.put(entity, body);
         }
     }
 }
@@ -686,49 +774,41 @@ fn split_meteor(body: &Body, collider: &Collider, velocity: Vec3, create: &mut V
     }
 }
 
-fn check_collisions_between<A: 'static, B: 'static, F>(world: &World, f: F)
-    where F: FnMut(((EntityId, &Body, &Collider), (EntityId, &Body, &Collider))) {
-    let a = View::builder()
-        .marked::<A>()
-        .required::<Body>()
-        .required::<Collider>()
-        .build(world);
-    let b = View::builder()
-        .marked::<B>()
-        .required::<Body>()
-        .required::<Collider>()
-        .build(world);
-
-    a.iter().flat_map(|(a, (body_a, (collider_a, ..)))|
-        b.iter().filter_map(move |(b, (body_b, (collider_b, ..)))| {
-            collides(collider_a, body_a.transform.position(), collider_b, body_b.transform.position())
-                .then(|| ((a, body_a, collider_a), (b, body_b, collider_b)))
-        })
-    ).for_each(f);
-}
-
-fn draw_world(world: &World, graphics: &Graphics, models: &mut Vec<GameModel>) {
+fn draw_world(world: &World, graphics: &Graphics, opacity: f32, batch: &mut Batch<GameShader>) {
     // collect shapes from the ecs (player, meteors and bullets)
     let shapes = View::builder()
         .required::<Shape>()
         .required::<Body>()
         .build(world);
-    for (_, (shape, (body, ..))) in shapes.iter() {
-        graphics.draw_shape(shape, &body.transform, models);
-    }
+    batch.models_from_view(&shapes, |_, (shape, (body, ..))| {
+        let mut model = graphics.shape_model(shape, &body.transform);
+        model.input.color.a *= opacity;
+        model
+    });
 }
 
 fn draw_score(score: u32, global: &GlobalState, graphics: &Graphics, models: &mut Vec<GameModel>) {
     const SAFE_AREA: Vec2 = Vec2::new(0.5, 0.5);
     const FONT_SIZE: f32 = 0.5;
 
-    let score = format!("{}", score);
+    let score = score.to_string();
+    // right-aligned to the top right corner, growing towards the left as the score increases
     let text_translation = Matrix4::new_translation(&Vec3::new(
-        -global.bounds.x + SAFE_AREA.x,
+        global.bounds.x - SAFE_AREA.x,
         global.bounds.y - SAFE_AREA.y,
         0.0,
     )) * Matrix4::new_scaling(FONT_SIZE);
-    graphics.draw_text(&score, text_translation, FOREGROUND_COLOR, models);
+    let spans = [("SCORE ", DIM_FOREGROUND_COLOR), (score.as_str(), FOREGROUND_COLOR)];
+    graphics.draw_text_spans(&spans, text_translation, Alignment::Right, FontWeight::Regular, models);
+}
+
+/// Draws a line of instruction text horizontally centered on `position`.
+fn draw_label(graphics: &Graphics, text: &str, position: Vec2, models: &mut Vec<GameModel>) {
+    const FONT_SIZE: f32 = 0.3;
+
+    let transform = Matrix4::new_scaling(FONT_SIZE)
+        .append_translation(&vector!(position.x, position.y, 0.0));
+    graphics.draw_text(text, transform, Alignment::Center, FOREGROUND_COLOR, FontWeight::Light, models);
 }
 
 fn draw_logo(graphics: &Graphics, models: &mut Vec<GameModel>) {
@@ -738,10 +818,57 @@ fn draw_logo(graphics: &Graphics, models: &mut Vec<GameModel>) {
         0.0, 0.0, 1.0, 0.0;
         0.0, 0.1, 0.0, 1.0];
     const LOGO_SIZE: f32 = 0.8;
-    // approximately centered text
     let transform = skew
-        .prepend_translation(&vector!(-6.2, 0.0, 0.0)) // center text for skew effect
         .append_nonuniform_scaling(&vector!(1.0 * LOGO_SIZE, 1.2 * LOGO_SIZE, 1.0))
         .append_translation(&vector!(0.0, 5.0, 0.0));
-    graphics.draw_text("METEORS", transform, FOREGROUND_COLOR, models);
+    graphics.draw_text("METEORS", transform, Alignment::Center, FOREGROUND_COLOR, FontWeight::Bold, models);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::game::{common_update_world, GameContext, GlobalState, IngameState, InputState, Key, SampledInput, Type};
+
+    #[test]
+    fn presses_within_one_frame() {
+        let mut input = InputState::default();
+        input.record(Key::Shoot, true);
+        input.record(Key::Shoot, false);
+        input.record(Key::Up, true);
+        input.record(Key::Shoot, true);
+        input.record(Key::Shoot, false);
+        assert_eq!(input.sample(), SampledInput { up: true, shots: 2, ..Default::default() });
+
+        // held keys carry over, shots don't
+        assert_eq!(input.sample(), SampledInput { up: true, ..Default::default() });
+
+        // key repeat while holding doesn't shoot again
+        input.record(Key::Shoot, true);
+        input.record(Key::Shoot, true);
+        input.record(Key::Up, false);
+        assert_eq!(input.sample(), SampledInput { shots: 1, ..Default::default() });
+        input.record(Key::Shoot, true);
+        assert_eq!(input.sample().shots, 0);
+    }
+
+    #[test]
+    fn update_shoots_every_press() {
+        let mut global = GlobalState::default();
+        let mut state = IngameState::default();
+        // both presses happen before the next update step
+        for pressed in [true, false, true, false] {
+            global.input_state.record(Key::Shoot, pressed);
+        }
+
+        let mut create = vec![];
+        common_update_world(GameContext {
+            global: &mut global,
+            delta: Duration::from_millis(16),
+            world: &mut state.world,
+            create: &mut create,
+            remove: &mut vec![],
+        });
+        assert_eq!(create.iter().filter(|(typ, _)| matches!(typ, Type::Bullet)).count(), 2);
+    }
 }
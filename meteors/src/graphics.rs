@@ -10,25 +10,41 @@ use rand::{Rng, SeedableRng};
 use rand::distributions::Standard;
 use rand::rngs::StdRng;
 
-use engine::render::{BufferUsages, Color, Handle, Model, RenderApi, VecBuf};
+use engine::render::{Color, Handle, Model, RenderApi, UniformBuffer};
 use engine::render::geometry::{Geometry, GeometryFormat};
+use engine::render::lod::Lod;
 use engine::render::material::{AttributeDefinition, AttributeSemantics, AttributeType, Material, UniformDefinition, UniformEntryDefinition, UniformEntryTypeDefinition, UniformVisibility};
-use engine::render::shader::{Shader, ShaderDefinition, ShaderStage, VertexFormat, VertexMapper};
+use engine::render::shader::{BlendMode, Shader, ShaderDefinition, ShaderStage, TargetFormat, VertexFormat, VertexMapper};
 use engine::render::uniform::{UniformInstance, UniformInstanceEntry};
 
 use crate::game::Transform;
-use crate::text::Text;
+use crate::text::{Alignment, FontWeight, Text};
 
 pub struct GameShader;
 
+/// How the color of a model combines with the vertex colors of its geometry.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum ColorMode {
+    /// Tints the vertex colors with the model color.
+    #[default]
+    Multiply,
+    /// Uses the model color for every vertex, ignoring the vertex colors.
+    Replace,
+}
+
 pub struct ModelProperties {
     pub transform: Matrix4<f32>,
     pub color: Color,
+    pub color_mode: ColorMode,
 }
 
 impl ModelProperties {
     pub fn new(transform: Matrix4<f32>, color: Color) -> Self {
-        Self { transform, color }
+        Self { transform, color, color_mode: ColorMode::default() }
+    }
+
+    pub fn with_color_mode(self, color_mode: ColorMode) -> Self {
+        Self { color_mode, ..self }
     }
 }
 
@@ -40,7 +56,14 @@ impl Shader for GameShader {
 
     fn process_vertex(&self, input: &Self::Input, vertex: &mut Vertex) {
         vertex.position = input.transform.transform_point(&vertex.position);
-        vertex.color *= input.color;
+        match input.color_mode {
+            ColorMode::Multiply => vertex.color *= input.color,
+            ColorMode::Replace => vertex.color = input.color,
+        }
+    }
+
+    fn model_transform(&self, input: &Self::Input) -> Option<Matrix4<f32>> {
+        Some(input.transform)
     }
 
     fn shader_definition(&self) -> ShaderDefinition {
@@ -48,11 +71,18 @@ impl Shader for GameShader {
             shader_modules: vec![include_str!("assets/game.wgsl").to_owned()],
             vertex_shader: ShaderStage { module: 0, entrypoint: "vs_main".to_owned() },
             fragment_shader: ShaderStage { module: 0, entrypoint: "fs_main".to_owned() },
+            fragment_targets: vec![TargetFormat::Surface],
             attribute_locations: HashMap::from([
                 ("position".to_owned(), 0),
                 ("color".to_owned(), 1),
             ]),
-            uniforms: vec!["camera".to_owned()],
+            uniforms: vec!["camera".to_owned(), "time".to_owned()],
+            multisample: Default::default(),
+            blend: BlendMode::AlphaBlend,
+            depth: None,
+            topology: Default::default(),
+            primitive: Default::default(),
+            instance_attributes: vec![],
         }
     }
 }
@@ -108,9 +138,14 @@ impl Vertex {
 pub struct Graphics {
     pub material: Material<GameShader>,
     pub camera_uniform: UniformInstance,
-    pub camera_uniform_buffer: Handle<VecBuf>,
+    pub camera_uniform_buffer: Handle<UniformBuffer>,
+    /// Seconds since the game started, for animating fragments.
+    pub time_uniform: UniformInstance,
+    pub time_uniform_buffer: Handle<UniformBuffer>,
     pub ship_geometry: Handle<Geometry>,
     pub meteor_geometry: Handle<Geometry>,
+    /// Used in place of `meteor_geometry` for meteors covering a large part of the screen.
+    pub detailed_meteor_geometry: Handle<Geometry>,
     pub bullet_geometry: Handle<Geometry>,
     pub arrow_geometries: [Handle<Geometry>; 4],
     pub spacebar_geometry: Handle<Geometry>,
@@ -125,9 +160,20 @@ impl Graphics {
                 typ: UniformEntryTypeDefinition::Buffer,
             }],
         });
-        let camera_uniform_buffer = render.new_buffer(size_of::<Matrix4<f32>>(), BufferUsages::UNIFORM | BufferUsages::COPY_DST);
+        let camera_uniform_buffer = render.new_uniform_buffer(size_of::<Matrix4<f32>>());
         let camera_uniform = render.instantiate_uniform("camera", vec![Some(UniformInstanceEntry::Buffer(camera_uniform_buffer.into()))]);
 
+        render.register_uniform("time", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::Buffer,
+            }],
+        });
+        let time_uniform_buffer = render.new_uniform_buffer(size_of::<[f32; 4]>());
+        let time_uniform = render.instantiate_uniform("time", vec![Some(UniformInstanceEntry::Buffer(time_uniform_buffer.into()))]);
+
+        // smooths the edges of the vector shapes, if the device supports it
+        render.set_sample_count(4);
         let material = render.new_material(GameShader);
 
         let format = GeometryFormat::from(vec![
@@ -148,12 +194,14 @@ impl Graphics {
             format.clone(),
             SHIP_INDICES.to_vec(),
         );
-        let meteor_vertices = generate_meteor_geometry();
-        let meteor_geometry = render.new_geometry(
-            cast_slice(&meteor_vertices).to_vec(),
-            format.clone(),
-            generate_triangle_strip_indices(meteor_vertices.len()),
-        );
+        let [meteor_geometry, detailed_meteor_geometry] = [10, 24].map(|vertex_count| {
+            let meteor_vertices = generate_meteor_geometry(vertex_count);
+            render.new_geometry(
+                cast_slice(&meteor_vertices).to_vec(),
+                format.clone(),
+                generate_triangle_strip_indices(meteor_vertices.len()),
+            )
+        });
         let bullet_geometry = render.new_geometry(
             cast_slice(&BULLET_VERTICES).to_vec(),
             format.clone(),
@@ -183,8 +231,11 @@ impl Graphics {
             material,
             camera_uniform,
             camera_uniform_buffer,
+            time_uniform,
+            time_uniform_buffer,
             ship_geometry,
             meteor_geometry,
+            detailed_meteor_geometry,
             bullet_geometry,
             arrow_geometries,
             spacebar_geometry,
@@ -192,38 +243,34 @@ impl Graphics {
         }
     }
 
-    pub fn draw_shape(&self, shape: &Shape, transform: &Transform, models: &mut Vec<GameModel>) {
+    pub fn shape_model(&self, shape: &Shape, transform: &Transform) -> GameModel {
         let properties = ModelProperties::new(transform.to_matrix(), FOREGROUND_COLOR);
         match shape {
-            Shape::Ship => models.push(Model::new(self.ship_geometry, properties)),
-            Shape::Meteor => models.push(Model::new(self.meteor_geometry, properties)),
-            Shape::Bullet => models.push(Model::new(self.bullet_geometry, properties)),
-        };
+            Shape::Ship => Model::new(self.ship_geometry, properties),
+            Shape::Meteor => Model::new(self.meteor_geometry, properties).with_lods(vec![
+                Lod { min_screen_size: 0.15, geometry: self.detailed_meteor_geometry },
+            ]),
+            Shape::Bullet => Model::new(self.bullet_geometry, properties),
+        }
     }
 
-    pub fn draw_text(&self, text: &str, transform: Matrix4<f32>, color: Color, models: &mut Vec<GameModel>) {
-        let text = text
-            .chars()
-            .filter(|c| c.is_ascii())
-            .flat_map(|c| c.to_uppercase());
-        const LETTER_SPACING: f32 = 0.3;
-
-        let mut offset = 0.0;
-        for char in text {
-            if let Some(character) = self.text.character(char) {
-                let char_translation = Matrix4::new_translation(&vector!(
-                    offset - character.bounds.0,
-                    -1.0,
-                    0.0
-                ));
-
-                offset += character.size() + LETTER_SPACING;
-                models.push(Model::new(
-                    character.data,
-                    ModelProperties::new(transform * char_translation, color),
-                ));
-            }
-        }
+    /// Draws `text` with the top of its first line at the origin of `transform`, placing its lines
+    /// horizontally according to `alignment`.
+    pub fn draw_text(&self, text: &str, transform: Matrix4<f32>, alignment: Alignment, color: Color, weight: FontWeight, models: &mut Vec<GameModel>) {
+        self.draw_text_spans(&[(text, color)], transform, alignment, weight, models);
+    }
+
+    /// Draws runs of text in different colors, laid out like a single text with
+    /// [Graphics::draw_text].
+    pub fn draw_text_spans(&self, spans: &[(&str, Color)], transform: Matrix4<f32>, alignment: Alignment, weight: FontWeight, models: &mut Vec<GameModel>) {
+        self.text.layout_spans(spans, weight, alignment, |character, translation, color| {
+            let char_translation = Matrix4::new_translation(&translation.push(0.0));
+
+            models.push(Model::new(
+                character.data,
+                ModelProperties::new(transform * char_translation, color).with_color_mode(ColorMode::Replace),
+            ));
+        });
     }
 
     pub fn draw_arrow_keys(&self, transform: Matrix4<f32>, color: Color, models: &mut Vec<GameModel>) {
@@ -273,6 +320,8 @@ pub enum Shape {
 }
 
 pub const FOREGROUND_COLOR: Color = Color::new(0.980392157, 0.921568627, 0.843137255, 1.0);
+/// Foreground color for labels set off from the values they describe.
+pub const DIM_FOREGROUND_COLOR: Color = Color::new(FOREGROUND_COLOR.r, FOREGROUND_COLOR.g, FOREGROUND_COLOR.b, 0.5);
 pub const BACKGROUND_COLOR: Color = Color::new(0.0, 0.011764706, 0.08627451, 1.0);
 
 const SHIP_VERTICES: [Vertex; 4] = [
@@ -382,9 +431,9 @@ const SPACEBAR_VERTICES: [Vertex; 42] = [
     Vertex::new(point!(-2.8, 0.8, 0.0), Color::WHITE),
 ];
 
-fn generate_meteor_geometry() -> Vec<Vertex> {
+fn generate_meteor_geometry(vertex_count: usize) -> Vec<Vertex> {
     let radius = 0.5;
-    let mut vertices: [Vertex; 10] = Default::default();
+    let mut vertices = vec![Vertex::default(); vertex_count];
 
     let mut indices = vec![0];
     for i in 1..=vertices.len() / 2 {
@@ -394,7 +443,6 @@ fn generate_meteor_geometry() -> Vec<Vertex> {
 
     let mut rng = StdRng::seed_from_u64(0).sample_iter::<f32, _>(Standard);
 
-    let vertex_count = vertices.len();
     for (i, vertex) in vertices.iter_mut().enumerate() {
         let progress = (indices[i] as f32 / vertex_count as f32) * f32::pi() * 2.0;
 
@@ -411,5 +459,36 @@ fn generate_meteor_geometry() -> Vec<Vertex> {
         };
     }
 
-    vertices.to_vec()
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix4, point};
+
+    use engine::render::Color;
+    use engine::render::shader::Shader;
+
+    use crate::graphics::{ColorMode, GameShader, ModelProperties, Vertex};
+
+    fn shade(vertex_color: Color, color_mode: ColorMode) -> [f32; 4] {
+        let mut vertex = Vertex::new(point![0.0, 0.0, 0.0], vertex_color);
+        let input = ModelProperties::new(Matrix4::identity(), Color::new(0.5, 1.0, 0.25, 1.0))
+            .with_color_mode(color_mode);
+        GameShader.process_vertex(&input, &mut vertex);
+        let Color { r, g, b, a } = vertex.color;
+        [r, g, b, a]
+    }
+
+    #[test]
+    fn color_modes() {
+        let white = Color::WHITE;
+        let red = Color::new(1.0, 0.0, 0.0, 0.5);
+
+        assert_eq!(shade(white, ColorMode::Multiply), [0.5, 1.0, 0.25, 1.0]);
+        assert_eq!(shade(red, ColorMode::Multiply), [0.5, 0.0, 0.0, 0.5]);
+
+        assert_eq!(shade(white, ColorMode::Replace), [0.5, 1.0, 0.25, 1.0]);
+        assert_eq!(shade(red, ColorMode::Replace), [0.5, 1.0, 0.25, 1.0]);
+    }
 }
@@ -2,6 +2,7 @@ use engine::platform::{detect_platform, Platform, SetupPlatformDefaultsExt};
 use engine::process::ProcessBuilder;
 use engine::surface::RunExt;
 
+mod collision;
 mod game;
 mod graphics;
 mod text;
@@ -29,6 +30,7 @@ fn main() {
             .setup_async(game::setup_game_resources).await
             .build();
 
+        process.event_system().handlers_for().append(game::on_update);
         process.event_system().handlers_for().append(game::on_surface_event);
 
         process.run();
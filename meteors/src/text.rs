@@ -1,105 +1,289 @@
-use std::iter::once;
-
 use bytemuck::cast_slice;
 use nalgebra::{point, vector, Vector2};
 
 use engine::render::{Color, Handle, RenderApi};
 use engine::render::geometry::{Geometry, GeometryFormat};
+use engine::render::tessellate::tessellate_polyline;
 
 use crate::graphics::{generate_triangle_strip_indices, Vertex};
 use crate::text::gen::LineBuilder;
 
+/// Thickness of the strokes of a glyph.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum FontWeight {
+    Light,
+    #[default]
+    Regular,
+    Bold,
+}
+
+impl FontWeight {
+    const ALL: [FontWeight; 3] = [FontWeight::Light, FontWeight::Regular, FontWeight::Bold];
+
+    /// Stroke width relative to [FontWeight::Regular].
+    fn stroke_scale(&self) -> f32 {
+        match self {
+            FontWeight::Light => 0.6,
+            FontWeight::Regular => 1.0,
+            FontWeight::Bold => 1.4,
+        }
+    }
+}
+
+/// Horizontal placement of each line of text relative to the origin it is laid out at.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Alignment {
+    /// Lines start at the origin.
+    #[default]
+    Left,
+    /// Lines are centered on the origin.
+    Center,
+    /// Lines end at the origin.
+    Right,
+}
+
+impl Alignment {
+    /// Fraction of the width of a line it is shifted left by.
+    fn shift(&self) -> f32 {
+        match self {
+            Alignment::Left => 0.0,
+            Alignment::Center => 0.5,
+            Alignment::Right => 1.0,
+        }
+    }
+}
+
 pub struct Text {
-    characters: [Option<Character<Handle<Geometry>>>; 59],
+    /// Characters of each [FontWeight], in [FontWeight::ALL] order.
+    weights: [[Option<Character<Handle<Geometry>>>; CHARACTER_COUNT]; 3],
 }
 
 impl Text {
     pub fn new(render: &mut RenderApi, vertex_format: &GeometryFormat) -> Self {
         Text {
-            characters: [
-                // start at ASCII char 32 (space)
-                Some(character_space()),
-                Some(character_exclamation()),
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                Some(character_0()),
-                Some(character_1()),
-                Some(character_2()),
-                Some(character_3()),
-                Some(character_4()),
-                Some(character_5()),
-                Some(character_6()),
-                Some(character_7()),
-                Some(character_8()),
-                Some(character_9()),
-                Some(character_colon()),
-                None,
-                None,
-                None,
-                None,
-                None,
-                None,
-                Some(character_a()),
-                Some(character_b()),
-                Some(character_c()),
-                Some(character_d()),
-                Some(character_e()),
-                Some(character_f()),
-                Some(character_g()),
-                Some(character_h()),
-                Some(character_i()),
-                Some(character_j()),
-                Some(character_k()),
-                Some(character_l()),
-                Some(character_m()),
-                Some(character_n()),
-                Some(character_o()),
-                Some(character_p()),
-                Some(character_q()),
-                Some(character_r()),
-                Some(character_s()),
-                Some(character_t()),
-                Some(character_u()),
-                Some(character_v()),
-                Some(character_w()),
-                Some(character_x()),
-                Some(character_y()),
-                Some(character_z()),
-            ].map(|character|
-                character.map(|char| char.map(|(topology, vertices)| {
-                    let vertices: Vec<_> = vertices.into_iter().map(|v| {
-                        Vertex { position: point![v.x, v.y, 0.0], color: Color::WHITE }
-                    }).collect();
-                    let indices = match topology {
-                        Topology::Triangles => (0..vertices.len() as u16).collect(),
-                        Topology::TriangleStrip => generate_triangle_strip_indices(vertices.len()),
-                    };
-                    render.new_geometry(
-                        cast_slice(&vertices).to_vec(),
-                        vertex_format.clone(),
-                        indices,
-                    )
-                }))
-            )
+            weights: FontWeight::ALL.map(|weight| {
+                standard_characters().map(|character|
+                    character.map(|char| char.map(|lines| {
+                        let (vertices, indices) = stroke(&lines, weight);
+                        let vertices: Vec<_> = vertices.into_iter().map(|v| {
+                            Vertex { position: point![v.x, v.y, 0.0], color: Color::WHITE }
+                        }).collect();
+                        render.new_geometry(
+                            cast_slice(&vertices).to_vec(),
+                            vertex_format.clone(),
+                            indices,
+                        )
+                    }))
+                )
+            })
+        }
+    }
+
+    pub fn character(&self, character: char, weight: FontWeight) -> Option<&Character<Handle<Geometry>>> {
+        let characters = &self.weights[FontWeight::ALL.iter().position(|w| *w == weight).unwrap()];
+        character_at(characters, character)
+    }
+
+    /// Lays out consecutive runs of text like a single string, calling `place` with each
+    /// character, the translation of its glyph and the tag of its run, e.g. its color, see
+    /// [layout_spans]. Returns the width of the widest line, which is the same for every weight.
+    pub fn layout_spans<'a, S: Copy>(&'a self, spans: &[(&str, S)], weight: FontWeight, alignment: Alignment, place: impl FnMut(&'a Character<Handle<Geometry>>, Vector2<f32>, S)) -> f32 {
+        layout_spans(spans, alignment, |char| self.character(char, weight), place)
+    }
+}
+
+/// Horizontal space between two characters.
+const LETTER_SPACING: f32 = 0.3;
+/// Height of every character above its baseline, which spans from `-1.0` to `1.0` vertically.
+pub const LINE_HEIGHT: f32 = 2.0;
+/// Vertical space between two lines, leaving room for descenders.
+pub const LINE_SPACING: f32 = 0.8;
+/// Vertical position of the baseline in glyph space, which the bottom of most glyphs rests on.
+const BASELINE: f32 = -1.0;
+/// Vertical position of the centerline at the top of lowercase letters without ascender.
+const X_HEIGHT: f32 = 0.2;
+/// Height of lowercase letters without ascender above the baseline, including their stroke.
+const X_HEIGHT_ASCENT: f32 = X_HEIGHT + 0.1 - BASELINE;
+/// Vertical position of the centerline at the bottom of descenders, e.g. of a g.
+const DESCENDER: f32 = -1.4;
+/// Depth of descenders below the baseline, including their stroke.
+const DESCENT: f32 = BASELINE - DESCENDER + 0.1;
+/// Number of glyphs, covering ASCII characters from space to `z`.
+const CHARACTER_COUNT: usize = 91;
+
+fn character_at<T>(characters: &[Option<Character<T>>], character: char) -> Option<&Character<T>> {
+    let char_code = (character as usize).checked_sub(32)?;
+    characters.get(char_code)?.as_ref()
+}
+
+/// Lays out the text of consecutive spans as a single string with the top of its first line at the
+/// origin, calling `place` with each character, the translation of its glyph and the tag of its
+/// span. Glyphs are placed next to each other, with their baselines on the common baseline of
+/// their line, below the top by the tallest ascent. Each `\n` starts a new line, [LINE_HEIGHT] and
+/// [LINE_SPACING] below the previous one, and every line is placed horizontally according to
+/// `alignment`. Returns the width of the widest line.
+fn layout_spans<'a, T: 'a, S: Copy>(spans: &[(&str, S)], alignment: Alignment, character: impl Fn(char) -> Option<&'a Character<T>>, mut place: impl FnMut(&'a Character<T>, Vector2<f32>, S)) -> f32 {
+    let mut lines = vec![vec![]];
+    for (text, tag) in spans {
+        for char in text.chars() {
+            if char == '\n' {
+                lines.push(vec![]);
+            } else if let Some(character) = character(char) {
+                lines.last_mut().unwrap().push((character, *tag));
+            }
+        }
+    }
+
+    let mut widest = 0.0;
+    for (index, characters) in lines.into_iter().enumerate() {
+        let top = -(index as f32) * (LINE_HEIGHT + LINE_SPACING);
+        let baseline = top - characters.iter()
+            .map(|(character, _)| character.ascent)
+            .fold(0.0, f32::max);
+        let width = line_width(&characters);
+        let start = -width * alignment.shift();
+
+        let mut offset = start;
+        for (character, tag) in characters {
+            place(character, vector!(offset - character.bounds.0, baseline - character.baseline), tag);
+            offset += character.size() + LETTER_SPACING;
         }
+        widest = f32::max(widest, width);
     }
+    widest
+}
+
+fn line_width<T, S>(characters: &[(&Character<T>, S)]) -> f32 {
+    let spacing = characters.len().saturating_sub(1) as f32 * LETTER_SPACING;
+    characters.iter().map(|(character, _)| character.size()).sum::<f32>() + spacing
+}
 
-    pub fn character(&self, character: char) -> Option<&Character<Handle<Geometry>>> {
-        let char_code = (character as usize) - 32;
-        self.characters.get(char_code)?.as_ref()
+/// Width of the strokes of a [FontWeight::Regular] glyph, horizontally and vertically. Vertical
+/// strokes are three times as thick as horizontal ones.
+const STROKE: Vector2<f32> = Vector2::new(0.6, 0.2);
+
+/// Strokes the centerlines of a glyph, returning its vertices and triangle list indices.
+///
+/// Lines are tessellated in a space scaled down by the stroke size, so a uniform stroke there
+/// becomes thicker on vertical than on horizontal lines.
+fn stroke(lines: &[Vec<Vector2<f32>>], weight: FontWeight) -> (Vec<Vector2<f32>>, Vec<u16>) {
+    let stroke = STROKE * weight.stroke_scale();
+
+    let mut vertices = vec![];
+    let mut indices = vec![];
+    for line in lines {
+        let scaled: Vec<_> = line.iter().map(|point| point.component_div(&stroke)).collect();
+        let strip = tessellate_polyline(&scaled, 1.0);
+        append_strip(&mut vertices, &mut indices, strip.into_iter().map(|point| point.component_mul(&stroke)).collect());
     }
+    (vertices, indices)
+}
+
+/// Appends a triangle strip alternating between the two rails of a stroke, with triangle list
+/// indices. Rails of different lengths would leave a triangle spanning a single rail, distorting
+/// the glyph.
+fn append_strip(vertices: &mut Vec<Vector2<f32>>, indices: &mut Vec<u16>, strip: Vec<Vector2<f32>>) {
+    debug_assert!(strip.len().is_multiple_of(2), "rails of a glyph strip must have the same number of points");
+    let offset = vertices.len() as u16;
+    indices.extend(generate_triangle_strip_indices(strip.len()).into_iter().map(|index| index + offset));
+    vertices.extend(strip);
+}
+
+fn standard_characters() -> [Option<StandardCharacter>; CHARACTER_COUNT] {
+    [
+        // start at ASCII char 32 (space)
+        Some(character_space()),
+        Some(character_exclamation()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(character_0()),
+        Some(character_1()),
+        Some(character_2()),
+        Some(character_3()),
+        Some(character_4()),
+        Some(character_5()),
+        Some(character_6()),
+        Some(character_7()),
+        Some(character_8()),
+        Some(character_9()),
+        Some(character_colon()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(character_a()),
+        Some(character_b()),
+        Some(character_c()),
+        Some(character_d()),
+        Some(character_e()),
+        Some(character_f()),
+        Some(character_g()),
+        Some(character_h()),
+        Some(character_i()),
+        Some(character_j()),
+        Some(character_k()),
+        Some(character_l()),
+        Some(character_m()),
+        Some(character_n()),
+        Some(character_o()),
+        Some(character_p()),
+        Some(character_q()),
+        Some(character_r()),
+        Some(character_s()),
+        Some(character_t()),
+        Some(character_u()),
+        Some(character_v()),
+        Some(character_w()),
+        Some(character_x()),
+        Some(character_y()),
+        Some(character_z()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        Some(character_lower_a()),
+        Some(character_lower_b()),
+        Some(character_lower_c()),
+        Some(character_lower_d()),
+        Some(character_lower_e()),
+        Some(character_lower_f()),
+        Some(character_lower_g()),
+        Some(character_lower_h()),
+        Some(character_lower_i()),
+        Some(character_lower_j()),
+        Some(character_lower_k()),
+        Some(character_lower_l()),
+        Some(character_lower_m()),
+        Some(character_lower_n()),
+        Some(character_lower_o()),
+        Some(character_lower_p()),
+        Some(character_lower_q()),
+        Some(character_lower_r()),
+        Some(character_lower_s()),
+        Some(character_lower_t()),
+        Some(character_lower_u()),
+        Some(character_lower_v()),
+        Some(character_lower_w()),
+        Some(character_lower_x()),
+        Some(character_lower_y()),
+        Some(character_lower_z()),
+    ]
 }
 
 mod gen {
@@ -137,16 +321,6 @@ mod gen {
                 iter: self.iter.chain(points),
             }
         }
-
-        pub fn line(self, from: Vector2<f32>, to: Vector2<f32>) -> LineBuilder<impl Iterator<Item=Vector2<f32>>> {
-            let l = to - from;
-            LineBuilder {
-                iter: self.iter.chain((0..SUBDIVISIONS).map(move |i| {
-                    let f = i as f32 / (SUBDIVISIONS - 1) as f32;
-                    from + l * f
-                }))
-            }
-        }
     }
 
     impl<I> IntoIterator for LineBuilder<I>
@@ -203,12 +377,28 @@ mod gen {
 
 pub struct Character<T> {
     pub data: T,
+    /// Horizontal extent of the glyph.
     pub bounds: (f32, f32),
+    /// Vertical position of the baseline in glyph space.
+    pub baseline: f32,
+    /// Height of the glyph above its baseline.
+    pub ascent: f32,
+    /// Depth of the glyph below its baseline, e.g. of the tail of a Q.
+    pub descent: f32,
 }
 
 impl<T> Character<T> {
+    /// Creates a glyph resting on the standard [BASELINE], without a descent.
     pub fn new(data: T, bounds: (f32, f32)) -> Self {
-        Character { data, bounds }
+        Character { data, bounds, baseline: BASELINE, ascent: LINE_HEIGHT, descent: 0.0 }
+    }
+
+    pub fn with_descent(self, descent: f32) -> Self {
+        Character { descent, ..self }
+    }
+
+    pub fn with_ascent(self, ascent: f32) -> Self {
+        Character { ascent, ..self }
     }
 
     pub fn map<R, F>(self, f: F) -> Character<R>
@@ -216,6 +406,9 @@ impl<T> Character<T> {
         Character {
             data: f(self.data),
             bounds: self.bounds,
+            baseline: self.baseline,
+            ascent: self.ascent,
+            descent: self.descent,
         }
     }
 
@@ -224,556 +417,308 @@ impl<T> Character<T> {
     }
 }
 
-fn intertwine<T>(line1: impl IntoIterator<Item=T>, line2: impl IntoIterator<Item=T>) -> impl Iterator<Item=T> {
-    line1.into_iter().zip(line2).flat_map(|(a, b)| once(a).chain(once(b)))
+/// Radius of the centerline around rounded corners.
+const RADIUS: f32 = 0.3;
+
+/// Centerlines of a glyph, each stroked separately.
+type StandardCharacter = Character<Vec<Vec<Vector2<f32>>>>;
+
+pub fn character_space() -> StandardCharacter {
+    Character::new(vec![], (0.0, 0.5))
 }
 
-const INNER_RADIUS: f32 = 0.2;
-const OUTER_RADIUS: f32 = 0.4;
+pub fn character_exclamation() -> StandardCharacter {
+    Character::new(
+        vec![
+            vec![vector!(0.3, 1.0), vector!(0.3, -0.2)],
+            vec![vector!(0.3, -0.4), vector!(0.3, -1.0)],
+        ],
+        (0.0, 0.6),
+    )
+}
 
-pub enum Topology {
-    Triangles,
-    TriangleStrip,
+/// Closed outline with rounded corners, spanning from `bottom` to `top`.
+fn rounded_loop(bottom: f32, top: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(0.0, top)])
+        .rounded(vector!(0.5 - RADIUS, top - RADIUS), RADIUS, 0.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, bottom + RADIUS), RADIUS, 90.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, bottom + RADIUS), RADIUS, 180.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, top - RADIUS), RADIUS, 270.0, 90.0)
+        .points([vector!(0.0, top)])
+        .into_iter()
+        .collect()
 }
 
-type StandardCharacter = Character<(Topology, Vec<Vector2<f32>>)>;
+/// Rounded bowl on the right of a vertical stem at `-0.5`, spanning from `bottom` to `top`.
+fn bowl(bottom: f32, top: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(-0.5, top)])
+        .rounded(vector!(0.5 - RADIUS, top - RADIUS), RADIUS, 0.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, bottom + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(-0.5, bottom)])
+        .into_iter()
+        .collect()
+}
 
-pub fn character_space() -> StandardCharacter {
-    Character { data: (Topology::Triangles, vec![]), bounds: (0.0, 0.5) }
+fn stem() -> Vec<Vector2<f32>> {
+    vec![vector!(-0.5, 1.0), vector!(-0.5, -1.0)]
 }
 
-pub fn character_exclamation() -> StandardCharacter {
-    let data = vec![
-        vector!(0.0, 1.0),
-        vector!(0.6, 1.0),
-        vector!(0.0, -0.2),
-        vector!(0.0, -0.2),
-        vector!(0.6, 1.0),
-        vector!(0.6, -0.2),
-        vector!(0.0, -0.4),
-        vector!(0.6, -0.4),
-        vector!(0.0, -1.0),
-        vector!(0.0, -1.0),
-        vector!(0.6, -0.4),
-        vector!(0.6, -1.0),
-    ];
-
-    Character {
-        data: (Topology::Triangles, data),
-        bounds: (0.0, 0.6),
-    }
+/// Mirrors a line horizontally around the center of the glyph, e.g. to put a [bowl] on the left.
+fn mirror(line: Vec<Vector2<f32>>) -> Vec<Vector2<f32>> {
+    line.into_iter().map(|point| vector!(-point.x, point.y)).collect()
 }
 
 pub fn character_0() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
-        .points([vector!(0.4, 1.0)]);
-    let line2 = LineBuilder::new()
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
-        .points([vector!(0.2, 0.8)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![rounded_loop(-0.9, 0.9)], (-0.8, 0.8))
 }
 
 pub fn character_1() -> StandardCharacter {
     Character::new(
-        (Topology::TriangleStrip, vec![
-            vector!(0.3, -1.0),
-            vector!(0.3, 1.0),
-            vector!(-0.3, -1.0),
-            vector!(-0.3, 1.0),
-            vector!(-0.3, 0.8),
-            vector!(-0.3, 1.0),
-            vector!(-0.5, 0.8),
-            vector!(-0.5, 1.0),
-        ]),
+        vec![vec![vector!(-0.5, 0.9), vector!(0.0, 0.9), vector!(0.0, -1.0)]],
         (-0.5, 0.3),
     )
 }
 
 pub fn character_2() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -0.2 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.2 - INNER_RADIUS), INNER_RADIUS, 0.0, -90.0)
-        .points([vector!(-0.2, -0.8), vector!(0.8, -0.8)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.8, 1.0 - INNER_RADIUS)])
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -OUTER_RADIUS), OUTER_RADIUS, 0.0, -90.0)
-        .points([vector!(-0.8, -1.0), vector!(0.8, -1.0)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let line = LineBuilder::new()
+        .points([vector!(-0.8, 0.9)])
+        .rounded(vector!(0.5 - RADIUS, 0.9 - RADIUS), RADIUS, 0.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, -0.1 + RADIUS), RADIUS, 90.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.1 - RADIUS), RADIUS, 0.0, -90.0)
+        .points([vector!(-0.5, -0.9), vector!(0.8, -0.9)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8))
 }
 
 pub fn character_3() -> StandardCharacter {
     let left = -0.4;
 
-    let line1 = LineBuilder::new()
-        .points([vector!(left, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.4), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(left, 0.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.2 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(left, -1.0)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(left, 0.8)])
-        .line(vector!(0.2, 0.8), vector!(0.2, 0.2))
-        .line(vector!(0.2, 0.8), vector!(0.2, 0.2))
-        .points([vector!(left, 0.2)])
-        .line(vector!(0.2, 0.0), vector!(0.2, -0.8))
-        .line(vector!(0.2, 0.0), vector!(0.2, -0.8))
-        .points([vector!(left, -0.8)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (left, 0.8))
+    let top = LineBuilder::new()
+        .points([vector!(left, 0.9)])
+        .rounded(vector!(0.5 - RADIUS, 0.9 - RADIUS), RADIUS, 0.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, 0.1 + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(left, 0.1)]);
+    let bottom = LineBuilder::new()
+        .points([vector!(0.0, 0.1)])
+        .rounded(vector!(0.5 - RADIUS, 0.1 - RADIUS), RADIUS, 0.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(left, -0.9)]);
+
+    Character::new(vec![top.into_iter().collect(), bottom.into_iter().collect()], (left, 0.8))
 }
 
 pub fn character_4() -> StandardCharacter {
-    let thickness = 0.6;
-    let data = vec![
-        vector!(0.2, -1.0),
-        vector!(0.8, -1.0),
-        vector!(0.2, 1.0 - thickness),
-        vector!(0.8, 1.0),
-        vector!(0.2, 1.0),
-        vector!(-0.8 + thickness, -0.5),
-        vector!(-0.8, -0.5),
-        vector!(-0.8, -0.7),
-        vector!(0.8, -0.5),
-        vector!(0.8, -0.7),
-    ];
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![
+            vec![vector!(0.5, -1.0), vector!(0.5, 0.9), vector!(-0.5, -0.6), vector!(0.8, -0.6)],
+        ],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_5() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, -1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -0.7 + OUTER_RADIUS), OUTER_RADIUS, 90.0, -90.0)
-        .points([
-            vector!(-0.2, -0.7 + OUTER_RADIUS * 2.0),
-            vector!(-0.2, 0.8),
-            vector!(0.8, 0.8),
-        ]);
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.8, -0.8)])
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, -90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.5 + INNER_RADIUS), INNER_RADIUS, 90.0, -90.0)
-        .points([
-            vector!(-0.8, -0.5 + INNER_RADIUS * 2.0),
-            vector!(-0.8, 1.0),
-            vector!(0.8, 1.0),
-        ]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let line = LineBuilder::new()
+        .points([vector!(-0.8, -0.9)])
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, 180.0, -90.0)
+        .rounded(vector!(0.5 - RADIUS, 0.0 - RADIUS), RADIUS, 90.0, -90.0)
+        .points([vector!(-0.5, 0.0), vector!(-0.5, 0.9), vector!(0.8, 0.9)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8))
 }
 
 pub fn character_6() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.8, 1.0)])
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, -90.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, -180.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.2 - OUTER_RADIUS), OUTER_RADIUS, -270.0, -90.0)
-        .line(vector!(0.8 - OUTER_RADIUS, 0.2), vector!(-0.2, 0.2));
-    let line2 = LineBuilder::new()
-        .points([vector!(0.8, 0.8)])
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, -90.0, -90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, -180.0, -90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -INNER_RADIUS), INNER_RADIUS, -270.0, -90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -INNER_RADIUS), INNER_RADIUS, 0.0, -90.0);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let line = LineBuilder::new()
+        .points([vector!(0.8, 0.9)])
+        .rounded(vector!(-0.5 + RADIUS, 0.9 - RADIUS), RADIUS, 0.0, -90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.9 + RADIUS), RADIUS, -90.0, -90.0)
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, -180.0, -90.0)
+        .rounded(vector!(0.5 - RADIUS, 0.1 - RADIUS), RADIUS, -270.0, -90.0)
+        .points([vector!(-0.5, 0.1)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8))
 }
 
 pub fn character_7() -> StandardCharacter {
-    let data = vec![
-        vector!(-0.8, 1.0),
-        vector!(-0.8, 0.8),
-        vector!(0.8, 1.0),
-        vector!(0.0, 0.8),
-        vector!(0.0, -1.0),
-        vector!(-0.8, -1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![vec![vector!(-0.8, 0.9), vector!(0.5, 0.9), vector!(-0.4, -1.0)]],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_8() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.4, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(0.0, -INNER_RADIUS), INNER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(0.0, -0.8 + INNER_RADIUS), INNER_RADIUS, -90.0, -90.0)
-        .rounded(vector!(0.0, -0.8 + INNER_RADIUS), INNER_RADIUS, -180.0, -90.0)
-        .rounded(vector!(0.0, -INNER_RADIUS), INNER_RADIUS, -270.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.0, 0.8)])
-        .rounded(vector!(0.0, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.0, 0.2 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -0.2), OUTER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, -90.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, -180.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -0.2), OUTER_RADIUS, -270.0, -90.0)
-        .rounded(vector!(0.0, 0.2 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(0.0, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![rounded_loop(0.1, 0.9), rounded_loop(-0.9, 0.1)], (-0.8, 0.8))
 }
 
 pub fn character_9() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, -1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 90.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -0.2 + OUTER_RADIUS), OUTER_RADIUS, -90.0, -90.0)
-        .line(vector!(-0.8 + OUTER_RADIUS, -0.2), vector!(0.2, -0.2));
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.8, -0.8)])
-        .rounded(vector!(0.0, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, -90.0)
-        .rounded(vector!(0.0, 0.8 - INNER_RADIUS), INNER_RADIUS, 90.0, -90.0)
-        .rounded(vector!(0.0, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(0.0, INNER_RADIUS), INNER_RADIUS, -90.0, -90.0)
-        .rounded(vector!(0.0, INNER_RADIUS), INNER_RADIUS, -180.0, -90.0);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let line = LineBuilder::new()
+        .points([vector!(-0.8, -0.9)])
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, 180.0, -90.0)
+        .rounded(vector!(0.5 - RADIUS, 0.9 - RADIUS), RADIUS, 90.0, -90.0)
+        .rounded(vector!(-0.5 + RADIUS, 0.9 - RADIUS), RADIUS, 0.0, -90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.1 + RADIUS), RADIUS, -90.0, -90.0)
+        .points([vector!(0.5, -0.1)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8))
 }
 
 pub fn character_colon() -> StandardCharacter {
-    let data = vec![
-        vector!(0.0, 0.6),
-        vector!(0.4, 0.6),
-        vector!(0.0, 0.2),
-        vector!(0.4, 0.6),
-        vector!(0.0, 0.2),
-        vector!(0.4, 0.2),
-        vector!(0.0, -0.2),
-        vector!(0.4, -0.2),
-        vector!(0.0, -0.6),
-        vector!(0.4, -0.2),
-        vector!(0.0, -0.6),
-        vector!(0.4, -0.6),
-    ];
-    Character::new((Topology::Triangles, data), (0.0, 0.4))
+    // each dot is two horizontal strokes, as vertical ones would be too wide
+    Character::new(
+        [0.5, 0.3, -0.3, -0.5].into_iter()
+            .map(|y| vec![vector!(0.0, y), vector!(0.4, y)])
+            .collect(),
+        (0.0, 0.4),
+    )
 }
 
 pub fn character_a() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, -1.0)])
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .points([
-            vector!(0.8, 0.0),
-            vector!(0.8, -0.1),
-            vector!(0.8, -0.1),
-            vector!(0.8, -0.2),
-            vector!(0.8, -1.0),
-        ]);
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.2, -1.0)])
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .points([
-            vector!(0.2, 0.0),
-            vector!(-0.2, 0.0),
-            vector!(-0.2, -0.2),
-            vector!(0.2, -0.2),
-            vector!(0.2, -1.0),
-        ]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let arch = LineBuilder::new()
+        .points([vector!(-0.5, -1.0)])
+        .rounded(vector!(-0.5 + RADIUS, 0.9 - RADIUS), RADIUS, 270.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, 0.9 - RADIUS), RADIUS, 0.0, 90.0)
+        .points([vector!(0.5, -1.0)]);
+
+    Character::new(
+        vec![arch.into_iter().collect(), vec![vector!(-0.5, -0.1), vector!(0.5, -0.1)]],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_b() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.2 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.8, -1.0),
-            vector!(-0.8, 1.0),
-        ]);
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.8, 0.8)])
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.2 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.2)])
-        .rounded(vector!(0.2 - INNER_RADIUS, -INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.2, -0.8),
-            vector!(-0.2, 0.8),
-        ]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![stem(), bowl(0.1, 0.9), bowl(-0.9, 0.1)], (-0.8, 0.8))
 }
 
-pub fn character_c() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0);
-    let line2 = LineBuilder::new()
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0);
+/// Outline open to the right, reaching up to `top`.
+fn c_line(top: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, 90.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.9 + RADIUS), RADIUS, 180.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, top - RADIUS), RADIUS, 270.0, 90.0)
+        .rounded(vector!(0.5 - RADIUS, top - RADIUS), RADIUS, 0.0, 90.0)
+        .into_iter()
+        .collect()
+}
 
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+pub fn character_c() -> StandardCharacter {
+    Character::new(vec![c_line(0.9)], (-0.8, 0.8))
 }
 
 pub fn character_d() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(-0.8, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.8, -1.0),
-            vector!(-0.8, 1.0),
-        ]);
-    let line2 = LineBuilder::new()
-        .points([vector!(-0.8, 0.8)])
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.2, -0.8),
-            vector!(-0.2, 0.8),
-        ]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![stem(), bowl(-0.9, 0.9)], (-0.8, 0.8))
 }
 
 pub fn character_e() -> StandardCharacter {
     let right = 1.4;
 
-    let line1 = [
-        vector!(right, 1.0),
-        vector!(0.0, 1.0),
-        vector!(0.0, 0.2),
-        vector!(0.0, 0.1),
-        vector!(0.0, 0.1),
-        vector!(0.0, 0.0),
-        vector!(0.0, -1.0),
-        vector!(right, -1.0),
-    ];
-    let line2 = [
-        vector!(right, 0.8),
-        vector!(0.6, 0.8),
-        vector!(0.6, 0.2),
-        vector!(right, 0.2),
-        vector!(right, 0.0),
-        vector!(0.6, 0.0),
-        vector!(0.6, -0.8),
-        vector!(right, -0.8),
-    ];
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (0.0, right))
+    Character::new(
+        vec![
+            vec![vector!(right, 0.9), vector!(0.3, 0.9), vector!(0.3, -0.9), vector!(right, -0.9)],
+            vec![vector!(0.3, 0.1), vector!(right, 0.1)],
+        ],
+        (0.0, right),
+    )
 }
 
 pub fn character_f() -> StandardCharacter {
     let right = 1.4;
 
-    let line1 = [
-        vector!(right, 1.0),
-        vector!(0.0, 1.0),
-        vector!(0.0, 0.2),
-        vector!(0.0, 0.1),
-        vector!(0.0, 0.1),
-        vector!(0.0, 0.0),
-        vector!(0.0, -1.0),
-    ];
-    let line2 = [
-        vector!(right, 0.8),
-        vector!(0.6, 0.8),
-        vector!(0.6, 0.2),
-        vector!(right, 0.2),
-        vector!(right, 0.0),
-        vector!(0.6, 0.0),
-        vector!(0.6, -1.0),
-    ];
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (0.0, right))
+    Character::new(
+        vec![
+            vec![vector!(right, 0.9), vector!(0.3, 0.9), vector!(0.3, -1.0)],
+            vec![vector!(0.3, 0.1), vector!(right, 0.1)],
+        ],
+        (0.0, right),
+    )
 }
 
 pub fn character_g() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.8, 0.2)])
-        .line(vector!(0.8, -0.6), vector!(0.8, -1.0))
-        .rounded(vector!(-0.8+OUTER_RADIUS, -1.0+OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.8+OUTER_RADIUS, 1.0-OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
-        .points([vector!(0.8,1.0)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.2,0.2)])
-        .rounded(vector!(0.2-INNER_RADIUS, -0.8+INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.2+INNER_RADIUS, -0.8+INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.2+INNER_RADIUS, 0.8-INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
-        .points([vector!(0.8, 0.8)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let line = LineBuilder::new()
+        .points([vector!(0.5, 0.2), vector!(0.5, -0.9)])
+        .rounded(vector!(-0.5 + RADIUS, -0.9 + RADIUS), RADIUS, 180.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, 0.9 - RADIUS), RADIUS, 270.0, 90.0)
+        .points([vector!(0.8, 0.9)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8))
 }
 
 pub fn character_h() -> StandardCharacter {
-    let data = vec![
-        vector!(-0.8, 1.0),
-        vector!(-0.8, -1.0),
-        vector!(-0.2, 1.0),
-        vector!(-0.2, -1.0),
-        vector!(-0.2, 0.0),
-        vector!(-0.2, -0.2),
-        vector!(0.2, 0.0),
-        vector!(0.2, -0.2),
-        vector!(0.2, 1.0),
-        vector!(0.2, -1.0),
-        vector!(0.8, 1.0),
-        vector!(0.8, -1.0),
-    ];
-
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![
+            vec![vector!(-0.5, 1.0), vector!(-0.5, -1.0)],
+            vec![vector!(0.5, 1.0), vector!(0.5, -1.0)],
+            vec![vector!(-0.5, -0.1), vector!(0.5, -0.1)],
+        ],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_i() -> StandardCharacter {
-    let data = vec![
-        vector!(-0.5, 1.0),
-        vector!(0.5, 1.0),
-        vector!(-0.5, 0.8),
-        vector!(0.5, 0.8),
-        vector!(-0.3, 0.8),
-        vector!(0.3, 0.8),
-        vector!(-0.3, -0.8),
-        vector!(0.3, -0.8),
-        vector!(-0.5, -0.8),
-        vector!(0.5, -0.8),
-        vector!(-0.5, -1.0),
-        vector!(0.5, -1.0),
-    ];
-
-    Character::new((Topology::TriangleStrip, data), (-0.5, 0.5))
+    Character::new(
+        vec![
+            vec![vector!(-0.5, 0.9), vector!(0.5, 0.9)],
+            vec![vector!(0.0, 0.9), vector!(0.0, -0.9)],
+            vec![vector!(-0.5, -0.9), vector!(0.5, -0.9)],
+        ],
+        (-0.5, 0.5),
+    )
 }
 
 pub fn character_j() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.6, 1.0)])
-        .rounded(vector!(0.6-OUTER_RADIUS, -1.0+OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, -1.0)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.0,1.0)])
-        .rounded(vector!(0.0-INNER_RADIUS, -0.8+INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, -0.8)]);
+    let line = LineBuilder::new()
+        .points([vector!(0.3, 1.0)])
+        .rounded(vector!(0.3 - RADIUS, -0.9 + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(-0.2, -0.9)]);
 
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.2, 0.6))
+    Character::new(vec![line.into_iter().collect()], (-0.2, 0.6))
 }
 
 pub fn character_k() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.8, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, -0.1 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.2, -0.2),
-            vector!(-0.2, -1.0),
-            vector!(-0.8, -1.0),
-            vector!(-0.2, -0.0),
-        ])
-        .rounded(vector!(0.8-OUTER_RADIUS, -0.1-OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .points([vector!(0.8, -1.0)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.2, 1.0)])
-        .rounded(vector!(0.2 - INNER_RADIUS, INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(-0.2, 0.0),
-            vector!(-0.2, 1.0),
-            vector!(-0.8, 1.0),
-            vector!(-0.2, -0.2),
-        ])
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.2 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .points([vector!(0.2, -1.0)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let upper = LineBuilder::new()
+        .points([vector!(0.5, 1.0)])
+        .rounded(vector!(0.5 - RADIUS, -0.1 + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(-0.5, -0.1)]);
+    let lower = LineBuilder::new()
+        .points([vector!(-0.5, -0.1)])
+        .rounded(vector!(0.5 - RADIUS, -0.1 - RADIUS), RADIUS, 0.0, 90.0)
+        .points([vector!(0.5, -1.0)]);
+
+    Character::new(
+        vec![stem(), upper.into_iter().collect(), lower.into_iter().collect()],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_l() -> StandardCharacter {
     let right = 1.4;
 
-    let data = vec![
-        vector!(0.0, 1.0),
-        vector!(0.6, 1.0),
-        vector!(0.0, -1.0),
-        vector!(0.6, -0.8),
-        vector!(right, -1.0),
-        vector!(right, -0.8),
-    ];
-    Character::new((Topology::TriangleStrip, data), (0.0, right))
+    Character::new(
+        vec![vec![vector!(0.3, 1.0), vector!(0.3, -0.9), vector!(right, -0.9)]],
+        (0.0, right),
+    )
 }
 
 pub fn character_m() -> StandardCharacter {
-    let line_height = 0.8;
-    let line_offset = -0.2;
-
-    let data = vec![
-        vector!(-0.8, -1.0),
-        vector!(-0.2, -1.0),
-        vector!(-0.8, 1.0),
-        vector!(-0.2, 1.0 - line_height),
-        vector!(-0.2, 1.0),
-        vector!(0.0, line_offset),
-        vector!(0.0, line_offset + line_height),
-        vector!(0.2, 1.0 - line_height),
-        vector!(0.2, 1.0),
-        vector!(0.8, 1.0),
-        vector!(0.2, -1.0),
-        vector!(0.8, -1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![
+            vec![vector!(-0.5, -1.0), vector!(-0.5, 1.0)],
+            vec![vector!(0.5, -1.0), vector!(0.5, 1.0)],
+            vec![vector!(-0.5, 0.6), vector!(0.0, 0.2), vector!(0.5, 0.6)],
+        ],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_n() -> StandardCharacter {
-    let line_height = -0.1;
-
-    let data = vec![
-        vector!(-0.8, -1.0),
-        vector!(-0.2, -1.0),
-        vector!(-0.8, 1.0),
-        vector!(-0.2, line_height),
-        vector!(-0.2, 1.0),
-        vector!(0.2, -1.0),
-        vector!(0.2, -line_height),
-        vector!(0.8, -1.0),
-        vector!(0.2, 1.0),
-        vector!(0.8, 1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![
+            vec![vector!(-0.5, -1.0), vector!(-0.5, 1.0)],
+            vec![vector!(0.5, -1.0), vector!(0.5, 1.0)],
+            vec![vector!(-0.4, 0.9), vector!(0.4, -0.9)],
+        ],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_o() -> StandardCharacter {
@@ -782,238 +727,521 @@ pub fn character_o() -> StandardCharacter {
 }
 
 pub fn character_p() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([
-            vector!(-0.8, -1.0),
-            vector!(-0.8, 1.0),
-        ])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.0)]);
-    let line2 = LineBuilder::new()
-        .points([
-            vector!(-0.2, -1.0),
-            vector!(-0.2, 0.8),
-        ])
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.2 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.2)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![stem(), bowl(0.1, 0.9)], (-0.8, 0.8))
 }
 
 pub fn character_q() -> StandardCharacter {
-    let slant = 0.2;
-    let line_height = 0.4;
-
-    let line1 = LineBuilder::new()
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(0.3, -1.0),
-            vector!(0.3 + slant, -1.0 - line_height),
-            vector!(-0.3 + slant, -1.0 - line_height),
-            vector!(-0.3, -1.0),
-            vector!(-0.8 + OUTER_RADIUS, -1.0)
-        ]);
-    let line2 = LineBuilder::new()
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([
-            vector!(0.3 - slant * (line_height / 2.0), -0.8),
-            vector!(0.3 - slant * (line_height / 2.0), -0.8),
-            vector!(-0.3 - slant * (line_height / 2.0), -0.8),
-            vector!(-0.3 - slant * (line_height / 2.0), -0.8),
-            vector!(-0.2, -0.8),
-        ]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![rounded_loop(-0.9, 0.9), vec![vector!(0.0, -0.8), vector!(0.2, -1.4)]],
+        (-0.8, 0.8),
+    ).with_descent(0.4)
 }
 
 pub fn character_r() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([
-            vector!(-0.8, -1.0),
-            vector!(-0.8, 1.0),
-        ])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.2 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .points([vector!(0.8, -1.0)]);
-    let line2 = LineBuilder::new()
-        .points([
-            vector!(-0.2, -1.0),
-            vector!(-0.2, 0.8),
-        ])
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, 0.2 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.2, 0.2)])
-        .rounded(vector!(0.2 - INNER_RADIUS, -INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .points([vector!(0.2, -1.0)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    let leg = LineBuilder::new()
+        .points([vector!(-0.5, 0.1)])
+        .rounded(vector!(0.5 - RADIUS, 0.1 - RADIUS), RADIUS, 0.0, 90.0)
+        .points([vector!(0.5, -1.0)]);
+
+    Character::new(vec![stem(), bowl(0.1, 0.9), leg.into_iter().collect()], (-0.8, 0.8))
+}
+
+/// S curve from `top` through `middle` to the bottom, with corners of `radius` to fit its halves.
+fn s_line(top: f32, middle: f32, radius: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(0.8, top)])
+        .rounded(vector!(-0.5 + radius, top - radius), radius, 0.0, -90.0)
+        .rounded(vector!(-0.5 + radius, middle + radius), radius, 270.0, -90.0)
+        .rounded(vector!(0.5 - radius, middle - radius), radius, 0.0, 90.0)
+        .rounded(vector!(0.5 - radius, -0.9 + radius), radius, 90.0, 90.0)
+        .points([vector!(-0.8, -0.9)])
+        .into_iter()
+        .collect()
 }
 
 pub fn character_s() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.8, 1.0)])
-        .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, OUTER_RADIUS), OUTER_RADIUS, 270.0, -90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.8, -0.8)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.8, 0.8)])
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, -90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, 0.2 + INNER_RADIUS), INNER_RADIUS, 270.0, -90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, 0.2 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .points([vector!(-0.8, -1.0)]);
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(vec![s_line(0.9, 0.1, RADIUS)], (-0.8, 0.8))
 }
 
 pub fn character_t() -> StandardCharacter {
     let size = 0.8;
-    let data = vec![
-        vector!(-0.3, -1.0),
-        vector!(0.3, -1.0),
-        vector!(-0.3, 0.8),
-        vector!(0.3, 0.8),
-        vector!(-size, 0.8),
-        vector!(size, 0.8),
-        vector!(-size, 1.0),
-        vector!(size, 1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (-size, size))
+
+    Character::new(
+        vec![
+            vec![vector!(-size, 0.9), vector!(size, 0.9)],
+            vec![vector!(0.0, 0.9), vector!(0.0, -1.0)],
+        ],
+        (-size, size),
+    )
+}
+
+/// Cup open to the top, with its sides starting at `top`.
+fn u_line(top: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(0.5, top)])
+        .rounded(vector!(0.5 - RADIUS, -0.9 + RADIUS), RADIUS, 90.0, 90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.9 + RADIUS), RADIUS, 180.0, 90.0)
+        .points([vector!(-0.5, top)])
+        .into_iter()
+        .collect()
 }
 
 pub fn character_u() -> StandardCharacter {
-    let line1 = LineBuilder::new()
-        .points([vector!(0.8, 1.0)])
-        .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
-        .points([vector!(-0.8, 1.0)]);
-    let line2 = LineBuilder::new()
-        .points([vector!(0.2, 1.0)])
-        .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
-        .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
-        .points([vector!(-0.2, 1.0)]);
+    Character::new(vec![u_line(1.0)], (-0.8, 0.8))
+}
+
+fn v_character(top: f32) -> StandardCharacter {
+    let width = 1.4;
 
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    Character::new(
+        vec![vec![vector!(0.3, top), vector!(width / 2.0, -1.0), vector!(width - 0.3, top)]],
+        (0.0, width),
+    )
 }
 
 pub fn character_v() -> StandardCharacter {
-    let width = 1.4;
-    let weight = 0.5;
+    v_character(1.0)
+}
+
+fn w_character(top: f32) -> StandardCharacter {
+    let width = 2.2;
 
-    let data = vec![
-        vector!(0.0, 1.0),
-        vector!(weight, 1.0),
-        vector!((width - weight) / 2.0, -1.0),
-        vector!((width + weight) / 2.0, -1.0),
-        vector!(width - weight, 1.0),
-        vector!(width, 1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (0.0, width))
+    Character::new(
+        vec![vec![
+            vector!(0.3, top),
+            vector!(width / 3.0, -1.0),
+            vector!(width / 2.0, top),
+            vector!(width / 1.5, -1.0),
+            vector!(width - 0.3, top),
+        ]],
+        (0.0, width),
+    )
 }
 
 pub fn character_w() -> StandardCharacter {
-    let width = 2.2;
-    let weight = 0.5;
-
-    let data = vec![
-        vector!(0.0, 1.0),
-        vector!(weight, 1.0),
-        vector!(width / 3.0 - weight / 2.0, -1.0),
-        vector!(width / 3.0 + weight / 2.0, -1.0),
-        vector!((width - weight) / 2.0, 1.0),
-        vector!((width + weight) / 2.0, 1.0),
-        vector!(width / 1.5 - weight / 2.0, -1.0),
-        vector!(width / 1.5 + weight / 2.0, -1.0),
-        vector!(width - weight, 1.0),
-        vector!(width, 1.0),
-    ];
-    Character::new((Topology::TriangleStrip, data), (0.0, width))
+    w_character(1.0)
 }
 
-pub fn character_x() -> StandardCharacter {
+fn x_character(top: f32) -> StandardCharacter {
     let width = 1.6;
-    let weight = 0.6;
-
-    let data = vec![
-        vector!(0.0, 1.0),
-        vector!(weight, 1.0),
-        vector!(width - weight, -1.0),
-        vector!(weight, 1.0),
-        vector!(width - weight, -1.0),
-        vector!(width, -1.0),
-        vector!(width - weight, 1.0),
-        vector!(width, 1.0),
-        vector!(0.0, -1.0),
-        vector!(width, 1.0),
-        vector!(0.0, -1.0),
-        vector!(weight, -1.0),
-    ];
-    Character::new((Topology::Triangles, data), (0.0, width))
+
+    Character::new(
+        vec![
+            vec![vector!(0.3, top), vector!(width - 0.3, -1.0)],
+            vec![vector!(width - 0.3, top), vector!(0.3, -1.0)],
+        ],
+        (0.0, width),
+    )
+}
+
+pub fn character_x() -> StandardCharacter {
+    x_character(1.0)
 }
 
 pub fn character_y() -> StandardCharacter {
     let middle = -0.2;
 
-    let data = vec![
-        vector!(-0.8, 1.0),
-        vector!(-0.2, 1.0),
-        vector!(-0.3, middle),
-        vector!(-0.2, 1.0),
-        vector!(-0.3, middle),
-        vector!(0.3, middle),
-        vector!(0.2, 1.0),
-        vector!(0.8, 1.0),
-        vector!(-0.3, middle),
-        vector!(0.8, 1.0),
-        vector!(-0.3, middle),
-        vector!(0.3, middle),
-        vector!(-0.3, middle),
-        vector!(0.3, middle),
-        vector!(-0.3, -1.0),
-        vector!(0.3, middle),
-        vector!(-0.3, -1.0),
-        vector!(0.3, -1.0),
-    ];
-    Character::new((Topology::Triangles, data), (-0.8, 0.8))
+    Character::new(
+        vec![
+            vec![vector!(-0.5, 1.0), vector!(0.0, middle), vector!(0.0, -1.0)],
+            vec![vector!(0.5, 1.0), vector!(0.0, middle)],
+        ],
+        (-0.8, 0.8),
+    )
+}
+
+fn z_character(top: f32) -> StandardCharacter {
+    Character::new(
+        vec![vec![vector!(-0.8, top), vector!(0.45, top), vector!(-0.45, -0.9), vector!(0.8, -0.9)]],
+        (-0.8, 0.8),
+    )
 }
 
 pub fn character_z() -> StandardCharacter {
-    let width = 0.7;
-
-    let line1 = [
-        vector!(-0.8, 1.0),
-        vector!(0.8, 1.0),
-        vector!(0.8, 0.8),
-        vector!(-0.8 + width, -0.8),
-        vector!(-0.8 + width, -0.8),
-        vector!(0.8, -0.8),
-    ];
-    let line2 = [
-        vector!(-0.8, 0.8),
-        vector!(0.8 - width, 0.8),
-        vector!(0.8 - width, 0.8),
-        vector!(-0.8, -0.8),
-        vector!(-0.8, -1.0),
-        vector!(0.8, -1.0),
-    ];
-
-    let data = intertwine(line1, line2).collect();
-    Character::new((Topology::TriangleStrip, data), (-0.8, 0.8))
+    z_character(0.9)
+}
+
+/// Arch from the top of a stem at `left` over to a stem at `right`, down to the baseline.
+fn arch(left: f32, right: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(left, X_HEIGHT)])
+        .rounded(vector!(right - RADIUS, X_HEIGHT - RADIUS), RADIUS, 0.0, 90.0)
+        .points([vector!(right, -1.0)])
+        .into_iter()
+        .collect()
+}
+
+/// Tittle of an i or j above a stem at `x`, drawn like the dots of [character_colon].
+fn tittle(x: f32) -> Vec<Vec<Vector2<f32>>> {
+    [0.9, 0.7].into_iter()
+        .map(|y| vec![vector!(x - 0.2, y), vector!(x + 0.2, y)])
+        .collect()
+}
+
+/// Stem at `x` from the x-height down to a hook to the left at the descender.
+fn descending_hook(x: f32) -> Vec<Vector2<f32>> {
+    LineBuilder::new()
+        .points([vector!(x, X_HEIGHT)])
+        .rounded(vector!(x - RADIUS, DESCENDER + RADIUS), RADIUS, 90.0, 90.0)
+        .points([vector!(x - 0.6, DESCENDER)])
+        .into_iter()
+        .collect()
+}
+
+pub fn character_lower_a() -> StandardCharacter {
+    Character::new(
+        vec![rounded_loop(-0.9, X_HEIGHT), vec![vector!(0.5, X_HEIGHT), vector!(0.5, -1.0)]],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_b() -> StandardCharacter {
+    Character::new(vec![stem(), bowl(-0.9, X_HEIGHT)], (-0.8, 0.8))
+}
+
+pub fn character_lower_c() -> StandardCharacter {
+    Character::new(vec![c_line(X_HEIGHT)], (-0.8, 0.8)).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_d() -> StandardCharacter {
+    Character::new(vec![mirror(stem()), mirror(bowl(-0.9, X_HEIGHT))], (-0.8, 0.8))
+}
+
+pub fn character_lower_e() -> StandardCharacter {
+    let middle = (X_HEIGHT - 0.9) / 2.0;
+    let line = LineBuilder::new()
+        .points([vector!(-0.5, middle), vector!(0.5, middle)])
+        .rounded(vector!(0.5 - RADIUS, X_HEIGHT - RADIUS), RADIUS, 90.0, -90.0)
+        .rounded(vector!(-0.5 + RADIUS, X_HEIGHT - RADIUS), RADIUS, 0.0, -90.0)
+        .rounded(vector!(-0.5 + RADIUS, -0.9 + RADIUS), RADIUS, -90.0, -90.0)
+        .points([vector!(0.7, -0.9)]);
+
+    Character::new(vec![line.into_iter().collect()], (-0.8, 0.8)).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_f() -> StandardCharacter {
+    let line = LineBuilder::new()
+        .points([vector!(0.6, 0.9)])
+        .rounded(vector!(-0.2 + RADIUS, 0.9 - RADIUS), RADIUS, 0.0, -90.0)
+        .points([vector!(-0.2, -1.0)]);
+
+    Character::new(
+        vec![line.into_iter().collect(), vec![vector!(-0.6, X_HEIGHT), vector!(0.5, X_HEIGHT)]],
+        (-0.6, 0.6),
+    )
+}
+
+pub fn character_lower_g() -> StandardCharacter {
+    Character::new(vec![rounded_loop(-0.9, X_HEIGHT), descending_hook(0.5)], (-0.8, 0.8))
+        .with_ascent(X_HEIGHT_ASCENT)
+        .with_descent(DESCENT)
+}
+
+pub fn character_lower_h() -> StandardCharacter {
+    Character::new(vec![stem(), arch(-0.5, 0.5)], (-0.8, 0.8))
+}
+
+pub fn character_lower_i() -> StandardCharacter {
+    let mut lines = tittle(0.3);
+    lines.push(vec![vector!(0.3, X_HEIGHT), vector!(0.3, -1.0)]);
+    Character::new(lines, (0.0, 0.6))
+}
+
+pub fn character_lower_j() -> StandardCharacter {
+    let mut lines = tittle(0.3);
+    lines.push(descending_hook(0.3));
+    Character::new(lines, (-0.3, 0.6)).with_descent(DESCENT)
+}
+
+pub fn character_lower_k() -> StandardCharacter {
+    Character::new(
+        vec![stem(), vec![vector!(0.5, X_HEIGHT), vector!(-0.5, -0.4), vector!(0.5, -1.0)]],
+        (-0.8, 0.8),
+    )
+}
+
+pub fn character_lower_l() -> StandardCharacter {
+    Character::new(vec![vec![vector!(0.3, 1.0), vector!(0.3, -1.0)]], (0.0, 0.6))
+}
+
+pub fn character_lower_m() -> StandardCharacter {
+    Character::new(
+        vec![vec![vector!(-1.0, X_HEIGHT), vector!(-1.0, -1.0)], arch(-1.0, 0.0), arch(0.0, 1.0)],
+        (-1.3, 1.3),
+    ).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_n() -> StandardCharacter {
+    Character::new(
+        vec![vec![vector!(-0.5, X_HEIGHT), vector!(-0.5, -1.0)], arch(-0.5, 0.5)],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_o() -> StandardCharacter {
+    Character::new(vec![rounded_loop(-0.9, X_HEIGHT)], (-0.8, 0.8)).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_p() -> StandardCharacter {
+    Character::new(
+        vec![vec![vector!(-0.5, X_HEIGHT), vector!(-0.5, DESCENDER - 0.1)], bowl(-0.9, X_HEIGHT)],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT).with_descent(DESCENT)
+}
+
+pub fn character_lower_q() -> StandardCharacter {
+    Character::new(
+        vec![vec![vector!(0.5, X_HEIGHT), vector!(0.5, DESCENDER - 0.1)], mirror(bowl(-0.9, X_HEIGHT))],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT).with_descent(DESCENT)
+}
+
+pub fn character_lower_r() -> StandardCharacter {
+    let arm = LineBuilder::new()
+        .points([vector!(-0.5, X_HEIGHT)])
+        .rounded(vector!(0.5 - RADIUS, X_HEIGHT - RADIUS), RADIUS, 0.0, 90.0)
+        .points([vector!(0.5, -0.2)]);
+
+    Character::new(
+        vec![vec![vector!(-0.5, X_HEIGHT), vector!(-0.5, -1.0)], arm.into_iter().collect()],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_s() -> StandardCharacter {
+    // the halves are too short for the corners of the capital S
+    Character::new(vec![s_line(X_HEIGHT, -0.3, 0.2)], (-0.8, 0.8)).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_t() -> StandardCharacter {
+    let line = LineBuilder::new()
+        .points([vector!(0.0, 0.7)])
+        .rounded(vector!(RADIUS, -0.9 + RADIUS), RADIUS, 270.0, -90.0)
+        .points([vector!(0.6, -0.9)]);
+
+    Character::new(
+        vec![line.into_iter().collect(), vec![vector!(-0.5, X_HEIGHT), vector!(0.6, X_HEIGHT)]],
+        (-0.5, 0.6),
+    ).with_ascent(0.7 - BASELINE)
+}
+
+pub fn character_lower_u() -> StandardCharacter {
+    Character::new(vec![u_line(X_HEIGHT + 0.1)], (-0.8, 0.8)).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_v() -> StandardCharacter {
+    v_character(X_HEIGHT + 0.1).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_w() -> StandardCharacter {
+    w_character(X_HEIGHT + 0.1).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_x() -> StandardCharacter {
+    x_character(X_HEIGHT + 0.1).with_ascent(X_HEIGHT_ASCENT)
+}
+
+pub fn character_lower_y() -> StandardCharacter {
+    Character::new(
+        vec![
+            vec![vector!(-0.5, X_HEIGHT + 0.1), vector!(0.0, -0.9)],
+            vec![vector!(0.5, X_HEIGHT + 0.1), vector!(-0.25, DESCENDER - 0.1)],
+        ],
+        (-0.8, 0.8),
+    ).with_ascent(X_HEIGHT_ASCENT).with_descent(DESCENT)
+}
+
+pub fn character_lower_z() -> StandardCharacter {
+    z_character(X_HEIGHT).with_ascent(X_HEIGHT_ASCENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::iter::once;
+
+    use nalgebra::{vector, Vector2};
+
+    use crate::text::{Alignment, append_strip, Character, character_0, character_at, FontWeight, layout_spans, LETTER_SPACING, LINE_HEIGHT, LINE_SPACING, standard_characters, stroke};
+    use crate::text::gen::LineBuilder;
+
+    /// Lays out a single span of `text`, see [layout_spans].
+    fn layout<'a, T: 'a>(text: &str, alignment: Alignment, character: impl Fn(char) -> Option<&'a Character<T>>, mut place: impl FnMut(&'a Character<T>, Vector2<f32>)) -> f32 {
+        layout_spans(&[(text, ())], alignment, character, |character, translation, ()| place(character, translation))
+    }
+
+    #[test]
+    fn layout_width() {
+        let characters = standard_characters();
+        let mut advance = 0.0;
+        let mut offsets = vec![];
+        let width = layout("Hi 1!", Alignment::Left, |char| character_at(&characters, char), |character, translation| {
+            let offset = translation.x + character.bounds.0;
+            offsets.push(offset);
+            advance = offset + character.size();
+        });
+
+        let sizes: Vec<_> = "Hi 1!".chars()
+            .map(|char| character_at(&characters, char).unwrap().size())
+            .collect();
+        assert_eq!(offsets.len(), 5);
+        assert_eq!(offsets[1], sizes[0] + LETTER_SPACING);
+        assert_eq!(width, advance);
+        assert!((width - (sizes.iter().sum::<f32>() + 4.0 * LETTER_SPACING)).abs() < 1e-5);
+
+        assert_eq!(layout("", Alignment::Left, |char| character_at(&characters, char), |_, _| {}), 0.0);
+    }
+
+    #[test]
+    fn aligned_lines() {
+        let characters = standard_characters();
+        let place = |text, alignment| {
+            let mut lines: Vec<(f32, f32, f32)> = vec![];
+            let width = layout(text, alignment, |char| character_at(&characters, char), |character, translation| {
+                let (left, right) = (translation.x + character.bounds.0, translation.x + character.bounds.1);
+                let baseline = translation.y + character.baseline;
+                match lines.last_mut() {
+                    Some((line_baseline, _, end)) if *line_baseline == baseline => *end = right,
+                    _ => lines.push((baseline, left, right)),
+                }
+            });
+            (width, lines)
+        };
+
+        let (width, left) = place("WIDE\nI", Alignment::Left);
+        let wide = left[0].2 - left[0].1;
+        assert!((width - wide).abs() < 1e-5, "{width} != {wide}");
+        assert_eq!(left[0].1, 0.0);
+        assert_eq!(left[1].1, 0.0);
+        assert_eq!(left[0].0, -LINE_HEIGHT);
+        assert_eq!(left[1].0, -2.0 * LINE_HEIGHT - LINE_SPACING);
+
+        let (_, center) = place("WIDE\nI", Alignment::Center);
+        for (baseline, start, end) in center {
+            assert!((start + end).abs() < 1e-5, "line at {baseline} spans {start} to {end}");
+        }
+
+        let (_, right) = place("WIDE\nI", Alignment::Right);
+        assert!(right.iter().all(|(_, _, end)| end.abs() < 1e-5), "{right:?}");
+        assert!((right[0].1 + wide).abs() < 1e-5);
+    }
+
+    #[test]
+    fn spans_share_cursor() {
+        let characters = standard_characters();
+        let mut single = vec![];
+        let width = layout("SCORE 120", Alignment::Center, |char| character_at(&characters, char), |_, translation| single.push(translation));
+        let mut spans = vec![];
+        let spans_width = layout_spans(&[("SCORE ", 0), ("1", 1), ("20", 1)], Alignment::Center, |char| character_at(&characters, char), |_, translation, tag| spans.push((translation, tag)));
+
+        assert_eq!(spans_width, width);
+        assert_eq!(spans.iter().map(|(translation, _)| *translation).collect::<Vec<_>>(), single);
+        assert_eq!(spans.iter().map(|(_, tag)| *tag).collect::<Vec<_>>(), vec![0, 0, 0, 0, 0, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn common_baseline() {
+        let characters = standard_characters();
+        let mut placed = vec![];
+        layout("QO", Alignment::Left, |char| character_at(&characters, char), |character, translation| {
+            let (vertices, _) = stroke(&character.data, FontWeight::Regular);
+            let (min, _) = bounds(vertices);
+            placed.push((translation.y + character.baseline, translation.y + min.y, character.descent));
+        });
+
+        let [(q_baseline, q_bottom, q_descent), (o_baseline, o_bottom, _)] = placed[..] else {
+            panic!("expected two glyphs, got {placed:?}");
+        };
+        assert_eq!(q_baseline, o_baseline);
+        assert_eq!(q_baseline, -LINE_HEIGHT);
+        // the round bottom of the O rests on the baseline, while the tail of the Q hangs below it
+        assert!((o_bottom - o_baseline).abs() < 0.05, "{o_bottom} != {o_baseline}");
+        assert!((q_bottom - (q_baseline - q_descent)).abs() < 0.1, "{q_bottom} != {q_baseline} - {q_descent}");
+    }
+
+    #[test]
+    fn lowercase_glyphs() {
+        let characters = standard_characters();
+        for char in 'a'..='z' {
+            let character = character_at(&characters, char).unwrap_or_else(|| panic!("no glyph for {char}"));
+            let (vertices, _) = stroke(&character.data, FontWeight::Regular);
+            let (min, max) = bounds(vertices);
+            let top = character.baseline + character.ascent;
+            let bottom = character.baseline - character.descent;
+            assert!((max.y - top).abs() < 0.05, "top of {char} at {} instead of {top}", max.y);
+            assert!((min.y - bottom).abs() < 0.05, "bottom of {char} at {} instead of {bottom}", min.y);
+        }
+        let ascent = |char| character_at(&characters, char).unwrap().ascent;
+        assert!(ascent('x') < ascent('X'));
+        assert_eq!(ascent('b'), ascent('B'));
+
+        for char in ['{', '~', '\n', 'é'] {
+            assert!(character_at(&characters, char).is_none(), "glyph for {char:?}");
+        }
+    }
+
+    fn bounds(vertices: impl IntoIterator<Item=Vector2<f32>>) -> (Vector2<f32>, Vector2<f32>) {
+        vertices.into_iter().fold(
+            (Vector2::repeat(f32::MAX), Vector2::repeat(f32::MIN)),
+            |(min, max), v| (min.inf(&v), max.sup(&v)),
+        )
+    }
+
+    /// The 0 glyph as it was drawn before glyphs were stroked, from an inner and outer rail.
+    fn rails_character_0() -> Vec<Vector2<f32>> {
+        const INNER_RADIUS: f32 = 0.2;
+        const OUTER_RADIUS: f32 = 0.4;
+
+        let line1 = LineBuilder::new()
+            .rounded(vector!(0.8 - OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 0.0, 90.0)
+            .rounded(vector!(0.8 - OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 90.0, 90.0)
+            .rounded(vector!(-0.8 + OUTER_RADIUS, -1.0 + OUTER_RADIUS), OUTER_RADIUS, 180.0, 90.0)
+            .rounded(vector!(-0.8 + OUTER_RADIUS, 1.0 - OUTER_RADIUS), OUTER_RADIUS, 270.0, 90.0)
+            .points([vector!(0.4, 1.0)]);
+        let line2 = LineBuilder::new()
+            .rounded(vector!(0.2 - INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 0.0, 90.0)
+            .rounded(vector!(0.2 - INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 90.0, 90.0)
+            .rounded(vector!(-0.2 + INNER_RADIUS, -0.8 + INNER_RADIUS), INNER_RADIUS, 180.0, 90.0)
+            .rounded(vector!(-0.2 + INNER_RADIUS, 0.8 - INNER_RADIUS), INNER_RADIUS, 270.0, 90.0)
+            .points([vector!(0.2, 0.8)]);
+
+        line1.into_iter().zip(line2).flat_map(|(a, b)| once(a).chain(once(b))).collect()
+    }
+
+    #[test]
+    fn append_strip_offsets_indices() {
+        let mut vertices = vec![vector!(0.0, 0.0)];
+        let mut indices = vec![];
+        append_strip(&mut vertices, &mut indices, vec![vector!(0.0, 1.0), vector!(0.0, -1.0), vector!(1.0, 1.0), vector!(1.0, -1.0)]);
+        assert_eq!(vertices.len(), 5);
+        assert_eq!(indices, vec![1, 2, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "same number of points")]
+    fn append_strip_mismatched_rails() {
+        append_strip(&mut vec![], &mut vec![], vec![vector!(0.0, 1.0), vector!(0.0, -1.0), vector!(1.0, 1.0)]);
+    }
+
+    #[test]
+    fn regular_weight_matches_rails() {
+        let (vertices, _) = stroke(&character_0().data, FontWeight::Regular);
+        let (min, max) = bounds(vertices);
+        let (expected_min, expected_max) = bounds(rails_character_0());
+
+        assert!((min - expected_min).abs().max() < 0.05, "{min} != {expected_min}");
+        assert!((max - expected_max).abs().max() < 0.05, "{max} != {expected_max}");
+    }
+
+    #[test]
+    fn weight_changes_stroke_width() {
+        let width = |weight| {
+            let (vertices, _) = stroke(&character_0().data, weight);
+            let (min, max) = bounds(vertices);
+            max.x - min.x
+        };
+
+        assert!(width(FontWeight::Light) < width(FontWeight::Regular));
+        assert!(width(FontWeight::Regular) < width(FontWeight::Bold));
+    }
 }
@@ -1,10 +1,14 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
 use wgpu::{Adapter, Device, Queue, ShaderSource};
 
-use crate::{BufferUsages, TextureFormat};
-use crate::material::{AttributeDefinition, UniformDefinition, UniformEntryTypeDefinition, UniformVisibility};
+use crate::{BufferUsages, FilterMode, InstanceData, TextureFormat};
+use crate::material::{AttributeDefinition, ReloadError, UniformDefinition, UniformEntryTypeDefinition, UniformVisibility};
 use crate::render_api::DeviceResources;
-use crate::shader::ShaderDefinition;
-use crate::surface_context::SurfaceContext;
+use crate::shader::{DepthDefinition, MultisampleDefinition, PrimitiveDefinition, ShaderDefinition, Topology};
+use crate::texture::{DEPTH_FORMAT, Sampler, Texture};
 use crate::vecbuf::VecBuf;
 
 pub struct DeviceContext {
@@ -33,6 +37,147 @@ impl DeviceContext {
         VecBuf::new(buffer, capacity, usage)
     }
 
+    /// Submits `copy`, which fills a buffer of `size` bytes, and returns the contents of the buffer
+    /// once the copy is done. Blocks in the returned future until the GPU finished the copy.
+    pub(crate) async fn read_back(&self, size: u64, copy: impl FnOnce(&mut wgpu::CommandEncoder, &wgpu::Buffer)) -> Vec<u8> {
+        let staging = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = self.device.create_command_encoder(&Default::default());
+        copy(&mut encoder, &staging);
+        let submission = self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        slice.map_async(wgpu::MapMode::Read, |result| {
+            // the receiver is only dropped with the future
+            let _ = sender.send(result);
+        });
+        // map callbacks only run while polling on native backends
+        self.device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
+        receiver.await
+            .expect("map callback called")
+            .expect("readback buffer mapped");
+        let data = slice.get_mapped_range().to_vec();
+        staging.unmap();
+        data
+    }
+
+    /// Creates a texture to render to with `sample_count` samples per pixel. Multisampled color
+    /// is rendered to an intermediate texture and resolved into the returned texture, so it can
+    /// be sampled and copied as usual. Depth can't be resolved, so a multisampled depth texture
+    /// is only usable as a depth attachment.
+    ///
+    /// The texture is sampled as `sampled_format` if given, which must be the sRGB or linear
+    /// counterpart of `format`.
+    pub(crate) fn create_render_texture(&self, width: u32, height: u32, format: TextureFormat, sample_count: u32, sampled_format: Option<TextureFormat>) -> Texture {
+        let view_formats: Vec<_> = sampled_format.into_iter().collect();
+        let descriptor = |sample_count, usage| wgpu::TextureDescriptor {
+            label: Default::default(),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &view_formats,
+        };
+        let single_sampled_usage = wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC;
+
+        let texture = if sample_count == 1 {
+            let texture = self.device.create_texture(&descriptor(1, single_sampled_usage));
+            Texture::new(texture, format, (width, height))
+        } else if format == DEPTH_FORMAT {
+            self.create_attachment_texture(width, height, format, sample_count)
+        } else {
+            let texture = self.device.create_texture(&descriptor(1, single_sampled_usage));
+            let multisampled = self.device.create_texture(&descriptor(sample_count, wgpu::TextureUsages::RENDER_ATTACHMENT));
+            Texture::new(texture, format, (width, height)).with_multisampled(multisampled, sample_count)
+        };
+        match sampled_format {
+            Some(sampled_format) => {
+                assert_eq!(sampled_format.remove_srgb_suffix(), format.remove_srgb_suffix(), "sampled format must only differ from the texture format in its encoding");
+                texture.with_sampled_format(sampled_format)
+            }
+            None => texture,
+        }
+    }
+
+    /// Creates a texture that can only be rendered to, e.g. the multisampled color of the frame
+    /// that is resolved into the surface texture.
+    pub(crate) fn create_attachment_texture(&self, width: u32, height: u32, format: TextureFormat, sample_count: u32) -> Texture {
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Default::default(),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        Texture::new(texture, format, (width, height)).with_sample_count(sample_count)
+    }
+
+    /// Whether `format` can be rendered to with `sample_count` samples per pixel. Unless the
+    /// device was requested with adapter specific format features, only the guaranteed features
+    /// of the format are available.
+    pub(crate) fn supports_sample_count(&self, format: TextureFormat, sample_count: u32) -> bool {
+        let features = if self.device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
+            self.adapter.get_texture_format_features(format)
+        } else {
+            format.describe().guaranteed_format_features
+        };
+        features.flags.sample_count_supported(sample_count)
+    }
+
+    /// Whether textures can be viewed in a format other than the one they were created with, e.g.
+    /// sampled as sRGB while stored as linear. Unsupported by the GL backends.
+    pub(crate) fn supports_view_formats(&self) -> bool {
+        self.adapter.get_downlevel_capabilities().flags.contains(wgpu::DownlevelFlags::VIEW_FORMATS)
+    }
+
+    /// Creates a texture to sample from, filled with `data` laid out in tightly packed rows.
+    pub(crate) fn create_texture(&self, width: u32, height: u32, format: TextureFormat, data: &[u8]) -> Texture {
+        let size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Default::default(),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let block_size = format.describe().block_size as u32;
+        assert_eq!(data.len(), (width * height * block_size) as usize, "texture data doesn't match its size and format");
+        self.queue.write_texture(
+            texture.as_image_copy(),
+            data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(width * block_size),
+                rows_per_image: None,
+            },
+            size,
+        );
+        Texture::new(texture, format, (width, height))
+    }
+
+    pub(crate) fn create_sampler(&self, filter: FilterMode) -> Sampler {
+        Sampler::new(self.device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: filter,
+            min_filter: filter,
+            ..Default::default()
+        }))
+    }
+
     pub(crate) fn create_uniform_bind_group_layout(&self, name: &str, uniform: &UniformDefinition) -> wgpu::BindGroupLayout {
         let entries: Vec<_> = uniform.entries.iter()
             .enumerate()
@@ -49,7 +194,18 @@ impl DeviceContext {
                             ty: wgpu::BufferBindingType::Uniform,
                             has_dynamic_offset: false,
                             min_binding_size: None,
-                        }
+                        },
+                        UniformEntryTypeDefinition::DynamicBuffer { .. } => wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: true,
+                            min_binding_size: e.typ.dynamic_size(),
+                        },
+                        UniformEntryTypeDefinition::Texture => wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        UniformEntryTypeDefinition::Sampler => wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
                     },
                 }
             })
@@ -62,34 +218,45 @@ impl DeviceContext {
 
     pub(crate) fn create_render_pipeline(&self,
                                          resources: &DeviceResources,
-                                         surface: &SurfaceContext,
+                                         surface_format: Option<TextureFormat>,
                                          shader: ShaderDefinition,
                                          attributes: Vec<AttributeDefinition>,
+                                         sample_count: u32,
                                          /*material: MaterialDefinition,
-                                         pipeline: PipelineDefinition*/) -> wgpu::RenderPipeline {
-        let shader_modules: Vec<_> = shader.shader_modules.into_iter()
-            .map(|s| self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Default::default(),
-                source: ShaderSource::Wgsl(s.into()),
-            }))
+                                         pipeline: PipelineDefinition*/) -> Result<Pipelines, ReloadError> {
+        let (attributes, array_stride) = vertex_attributes(attributes, &shader.attribute_locations)?;
+        let (instance_attributes, instance_stride) = vertex_attributes(shader.instance_attributes, &shader.attribute_locations)?;
+        assert!(instance_attributes.is_empty() || instance_stride == size_of::<InstanceData>(), "instance attributes must match the layout of InstanceData");
+
+        // materials commonly share sources, e.g. a standard vertex shader, so each is compiled once
+        {
+            let mut cache = resources.shader_modules.borrow_mut();
+            for source in &shader.shader_modules {
+                if !cache.contains_key(source) {
+                    let module = self.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                        label: Default::default(),
+                        source: ShaderSource::Wgsl(source.as_str().into()),
+                    });
+                    cache.insert(source.clone(), Arc::new(module));
+                }
+            }
+        }
+        let cache = resources.shader_modules.borrow();
+        let shader_modules: Vec<_> = shader.shader_modules.iter()
+            .map(|source| cache[source].clone())
             .collect();
+        drop(cache);
 
-        let array_stride: usize = attributes.iter().map(|a| a.typ.size()).sum();
-        let mut offset = 0;
-        let attributes: Vec<_> = attributes.into_iter()
-            .map(|a| {
-                let shader_location = match a.name {
-                    Some(name) => shader.attribute_locations[&name],
-                    None => shader.attribute_locations[a.semantics.default_name()],
-                };
-                let attrib = wgpu::VertexAttribute {
-                    format: a.typ.into(),
-                    offset,
-                    shader_location,
-                };
-                offset += a.typ.size() as wgpu::BufferAddress;
-                attrib
-            })
+        let multisample = multisample_state(sample_count, &shader.multisample);
+        let primitive = primitive_state(shader.topology, &shader.primitive);
+
+        let blend = shader.blend.state();
+        let targets: Vec<_> = shader.fragment_targets.iter()
+            .map(|target| Some(wgpu::ColorTargetState {
+                format: target.resolve(surface_format),
+                blend,
+                write_mask: Default::default(),
+            }))
             .collect();
 
         let uniforms = shader.uniforms.into_iter()
@@ -102,36 +269,184 @@ impl DeviceContext {
             bind_group_layouts: uniforms.as_slice(),
             push_constant_ranges: &[],
         });
-        self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let mut vertex_buffers = vec![
+            // Vertex buffer
+            wgpu::VertexBufferLayout {
+                attributes: attributes.as_slice(),
+                step_mode: wgpu::VertexStepMode::Vertex,
+                array_stride: array_stride as _,
+            },
+        ];
+        if !instance_attributes.is_empty() {
+            vertex_buffers.push(wgpu::VertexBufferLayout {
+                attributes: instance_attributes.as_slice(),
+                step_mode: wgpu::VertexStepMode::Instance,
+                array_stride: instance_stride as _,
+            });
+        }
+        let vertex = wgpu::VertexState {
+            module: &shader_modules[shader.vertex_shader.module],
+            entry_point: &shader.vertex_shader.entrypoint,
+            buffers: &vertex_buffers,
+        };
+
+        let color = self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Default::default(),
-            primitive: Default::default(),
-            depth_stencil: None,
-            multisample: Default::default(),
+            primitive,
+            depth_stencil: shader.depth.as_ref().map(|depth| depth_stencil_state(depth.compare, depth.write, depth_bias_state(depth))),
+            multisample,
             fragment: Some(wgpu::FragmentState {
                 module: &shader_modules[shader.fragment_shader.module],
                 entry_point: &shader.fragment_shader.entrypoint,
-                targets: &[
-                    Some(wgpu::ColorTargetState {
-                        format: surface.format().unwrap_or(TextureFormat::Rgba8Unorm),
-                        blend: None,
-                        write_mask: Default::default(),
-                    }),
-                ],
+                targets: targets.as_slice(),
             }),
-            vertex: wgpu::VertexState {
-                module: &shader_modules[shader.vertex_shader.module],
-                entry_point: &shader.vertex_shader.entrypoint,
-                buffers: &[
-                    // Vertex buffer
-                    wgpu::VertexBufferLayout {
-                        attributes: attributes.as_slice(),
-                        step_mode: wgpu::VertexStepMode::Vertex,
-                        array_stride: array_stride as _,
-                    },
-                ],
-            },
+            vertex: vertex.clone(),
             layout: Some(&layout),
             multiview: None,
+        });
+        let depth_only = shader.depth.as_ref().map(|depth| {
+            self.device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Default::default(),
+                primitive,
+                // biased the same way as the color pipeline, so an equal test after the prepass matches
+                depth_stencil: Some(depth_stencil_state(wgpu::CompareFunction::Less, true, depth_bias_state(depth))),
+                multisample,
+                fragment: None,
+                vertex,
+                layout: Some(&layout),
+                multiview: None,
+            })
+        });
+
+        Ok(Pipelines { color, depth_only, shader_modules })
+    }
+
+    /// Like [DeviceContext::create_render_pipeline], but also returns validation errors, e.g. of a
+    /// shader failing to compile, rather than raising them on the device.
+    pub(crate) fn try_create_render_pipeline(&self,
+                                             resources: &DeviceResources,
+                                             surface_format: Option<TextureFormat>,
+                                             shader: ShaderDefinition,
+                                             attributes: Vec<AttributeDefinition>,
+                                             sample_count: u32) -> Result<Pipelines, ReloadError> {
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipelines = self.create_render_pipeline(resources, surface_format, shader, attributes, sample_count);
+        let validation = futures::executor::block_on(self.device.pop_error_scope());
+        let pipelines = pipelines?;
+        match validation {
+            Some(error) => Err(error.into()),
+            None => Ok(pipelines),
+        }
+    }
+}
+
+/// Lays `attributes` out one after another, at the shader locations of their names. Returns the
+/// attributes and the size of all of them together, or the first attribute the shader has no
+/// location for.
+fn vertex_attributes(attributes: Vec<AttributeDefinition>, locations: &HashMap<String, u32>) -> Result<(Vec<wgpu::VertexAttribute>, usize), ReloadError> {
+    let mut offset = 0;
+    let attributes = attributes.into_iter()
+        .map(|a| {
+            let name = a.name.as_deref().unwrap_or(a.semantics.default_name());
+            let shader_location = *locations.get(name)
+                .ok_or_else(|| ReloadError::UnknownAttribute(name.to_owned()))?;
+            let attrib = wgpu::VertexAttribute {
+                format: a.typ.into(),
+                offset: offset as _,
+                shader_location,
+            };
+            offset += a.typ.size();
+            Ok(attrib)
         })
+        .collect::<Result<_, ReloadError>>()?;
+    Ok((attributes, offset))
+}
+
+/// Pipelines built from a single [ShaderDefinition].
+pub(crate) struct Pipelines {
+    pub(crate) color: wgpu::RenderPipeline,
+    /// Writes only the nearest depth of the geometry, without running the fragment shader. Only
+    /// built for shaders with depth testing.
+    pub(crate) depth_only: Option<wgpu::RenderPipeline>,
+    /// Modules the pipelines were built from, keeping their entries in the
+    /// [cache](DeviceResources::evict_shader_modules) alive.
+    pub(crate) shader_modules: Vec<Arc<wgpu::ShaderModule>>,
+}
+
+fn depth_stencil_state(compare: wgpu::CompareFunction, write: bool, bias: wgpu::DepthBiasState) -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: write,
+        depth_compare: compare,
+        stencil: Default::default(),
+        bias,
+    }
+}
+
+fn depth_bias_state(depth: &DepthDefinition) -> wgpu::DepthBiasState {
+    wgpu::DepthBiasState {
+        constant: depth.depth_bias,
+        slope_scale: depth.slope_scale,
+        clamp: 0.0,
+    }
+}
+
+fn primitive_state(topology: Topology, definition: &PrimitiveDefinition) -> wgpu::PrimitiveState {
+    let topology = match topology {
+        Topology::Triangles => wgpu::PrimitiveTopology::TriangleList,
+        Topology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+        Topology::Points { size } => {
+            if size != 1.0 {
+                log::warn!(target: "krill-render", "Point size {} is not supported by the backend, drawing points one pixel wide.", size);
+            }
+            wgpu::PrimitiveTopology::PointList
+        }
+    };
+
+    wgpu::PrimitiveState {
+        topology,
+        front_face: definition.front_face,
+        cull_mode: definition.cull_mode,
+        ..Default::default()
+    }
+}
+
+pub(crate) fn multisample_state(sample_count: u32, definition: &MultisampleDefinition) -> wgpu::MultisampleState {
+    let alpha_to_coverage_enabled = definition.alpha_to_coverage && sample_count > 1;
+    if definition.alpha_to_coverage && !alpha_to_coverage_enabled {
+        log::warn!(target: "krill-render", "Alpha-to-coverage requires MSAA, ignoring for pipeline with a single sample.");
+    }
+
+    wgpu::MultisampleState {
+        count: sample_count,
+        alpha_to_coverage_enabled,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::device_context::multisample_state;
+    use crate::shader::MultisampleDefinition;
+
+    #[test]
+    fn alpha_to_coverage_propagates() {
+        let definition = MultisampleDefinition { alpha_to_coverage: true, ..Default::default() };
+
+        let state = multisample_state(4, &definition);
+        assert_eq!(state.count, 4);
+        assert!(state.alpha_to_coverage_enabled);
+
+        let state = multisample_state(4, &MultisampleDefinition::default());
+        assert!(!state.alpha_to_coverage_enabled);
+    }
+
+    #[test]
+    fn alpha_to_coverage_requires_msaa() {
+        let definition = MultisampleDefinition { alpha_to_coverage: true, ..Default::default() };
+
+        let state = multisample_state(1, &definition);
+        assert_eq!(state.count, 1);
+        assert!(!state.alpha_to_coverage_enabled);
     }
 }
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::{DeviceContext, TextureFormat};
+
+/// Full screen triangle writing black to `targets` color targets. Blending with the blend
+/// constant decides how much of the previous contents remain.
+fn fade_shader(targets: usize) -> String {
+    let outputs: String = (0..targets)
+        .map(|i| format!("    @location({i}) target{i}: vec4<f32>,\n"))
+        .collect();
+    let assignments: String = (0..targets)
+        .map(|i| format!("    output.target{i} = vec4(0.0);\n"))
+        .collect();
+    format!(r"
+struct FragmentOutput {{
+{outputs}}}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {{
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+}}
+
+@fragment
+fn fs_main() -> FragmentOutput {{
+    var output: FragmentOutput;
+{assignments}    return output;
+}}
+")
+}
+
+/// Pipelines darkening the color targets of a pass, by target formats and sample count, see
+/// [Drawer::fade_previous](crate::render_api::Drawer::fade_previous).
+#[derive(Default)]
+pub(crate) struct FadePipelines {
+    pipelines: HashMap<(Vec<TextureFormat>, u32), wgpu::RenderPipeline>,
+}
+
+impl FadePipelines {
+    pub(crate) fn get(&mut self, context: &DeviceContext, formats: &[TextureFormat], sample_count: u32) -> &wgpu::RenderPipeline {
+        self.pipelines.entry((formats.to_vec(), sample_count))
+            .or_insert_with(|| create_fade_pipeline(context, formats, sample_count))
+    }
+}
+
+fn create_fade_pipeline(context: &DeviceContext, formats: &[TextureFormat], sample_count: u32) -> wgpu::RenderPipeline {
+    let device = &context.device;
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("fade"),
+        source: wgpu::ShaderSource::Wgsl(fade_shader(formats.len()).into()),
+    });
+    // keeps `1 - constant` of the previous color, and all of the previous alpha
+    let blend = wgpu::BlendState {
+        color: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::OneMinusConstant,
+            operation: wgpu::BlendOperation::Add,
+        },
+        alpha: wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        },
+    };
+    let targets: Vec<_> = formats.iter()
+        .map(|format| Some(wgpu::ColorTargetState {
+            format: *format,
+            blend: Some(blend),
+            write_mask: wgpu::ColorWrites::ALL,
+        }))
+        .collect();
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("fade"),
+        layout: None,
+        vertex: wgpu::VertexState {
+            module: &module,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        primitive: Default::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &module,
+            entry_point: "fs_main",
+            targets: &targets,
+        }),
+        multiview: None,
+    })
+}
@@ -1,4 +1,7 @@
+use std::borrow::Cow;
+
 use crate::material::AttributeDefinition;
+use crate::shader::{STRIP_RESTART, STRIP_RESTART_U32};
 
 struct GeometryDefinition {
     vertex_data: Vec<u8>,
@@ -10,11 +13,52 @@ struct VertexFormatDefinition {
     attributes: Vec<AttributeDefinition>,
 }
 
+/// Indices of a [Geometry], 16 bit unless the geometry has more vertices than they can address.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Indices {
     Uint16(Vec<u16>),
     Uint32(Vec<u32>),
 }
 
+impl Indices {
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::Uint16(indices) => indices.len(),
+            Indices::Uint32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::Uint16(_) => wgpu::IndexFormat::Uint16,
+            Indices::Uint32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    /// The indices as 32 bit, for triangle lists.
+    pub(crate) fn widened(&self) -> Cow<'_, [u32]> {
+        match self {
+            Indices::Uint16(indices) => indices.iter().map(|index| *index as u32).collect(),
+            Indices::Uint32(indices) => Cow::Borrowed(indices),
+        }
+    }
+
+    /// The indices as 32 bit, for triangle strips, with [STRIP_RESTART] widened to
+    /// [STRIP_RESTART_U32].
+    pub(crate) fn widened_strips(&self) -> Cow<'_, [u32]> {
+        match self {
+            Indices::Uint16(indices) => indices.iter()
+                .map(|index| if *index == STRIP_RESTART { STRIP_RESTART_U32 } else { *index as u32 })
+                .collect(),
+            Indices::Uint32(indices) => Cow::Borrowed(indices),
+        }
+    }
+}
+
 impl From<Vec<u32>> for Indices {
     fn from(value: Vec<u32>) -> Self {
         Indices::Uint32(value)
@@ -32,15 +76,36 @@ pub struct Geometry {
     /// as the material expects it.
     pub(crate) data: Vec<u8>,
     pub(crate) format: GeometryFormat,
-    pub(crate) indices: Vec<u16>,
+    pub(crate) indices: Indices,
 }
 
 impl Geometry {
-    pub(crate) fn new(vertex_data: Vec<u8>, vertex_format: GeometryFormat, indices: Vec<u16>) -> Self {
+    /// Creates geometry that is not yet known to a render api, e.g. when loading it as an asset.
+    /// Add it with [RenderApi::add_geometry](crate::RenderApi::add_geometry) to draw it.
+    pub fn new(vertex_data: Vec<u8>, vertex_format: GeometryFormat, indices: impl Into<Indices>) -> Self {
         Geometry {
             data: vertex_data,
             format: vertex_format,
-            indices,
+            indices: indices.into(),
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn format(&self) -> &GeometryFormat {
+        &self.format
+    }
+
+    pub fn indices(&self) -> &Indices {
+        &self.indices
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        match self.format.vertex_size() {
+            0 => 0,
+            size => self.data.len() / size,
         }
     }
 }
@@ -1,27 +1,39 @@
 use std::ops::Deref;
 
-pub use wgpu::BufferUsages;
+pub use wgpu::{BufferUsages, CompareFunction, CompositeAlphaMode, Face, FilterMode, FrontFace, PresentMode};
 
 pub use color::Color;
 pub use device_context::DeviceContext;
 pub use maybe::*;
-pub use render_api::{Batch, Model, RenderApi};
+pub use render_api::{Batch, InstanceData, Model, RenderApi};
 pub use surface_context::SurfaceContext;
+pub use texture::{DEPTH_FORMAT, ImageAsset, ImageAssetPipeline, Sampler, Texture};
+pub use timing::{BatchStats, RenderStats};
 pub use utils::Handle;
-pub use vecbuf::VecBuf;
+pub use vecbuf::{BufferRole, IndexBuffer, UniformBuffer, VecBuf, VertexBuffer};
 pub use wgpu_context::WGPUContext;
 
 pub mod material;
 pub mod geometry;
+pub mod lod;
 mod vecbuf;
 mod color;
 mod device_context;
+mod fade;
 mod surface_context;
 mod render_api;
+pub mod tessellate;
 pub mod uniform;
 mod maybe;
+pub mod postprocess;
 mod wgpu_context;
 pub mod shader;
+pub mod sprite;
+pub mod streaming;
+mod texture;
+mod timing;
+#[cfg(test)]
+mod testing;
 
 pub type TextureFormat = wgpu::TextureFormat;
 
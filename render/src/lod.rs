@@ -0,0 +1,68 @@
+use nalgebra::{Matrix4, vector};
+
+use utils::Handle;
+
+use crate::geometry::Geometry;
+
+/// A more detailed geometry for a [Model](crate::Model), used once the model covers at least
+/// `min_screen_size` of the screen.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Lod {
+    pub min_screen_size: f32,
+    pub geometry: Handle<Geometry>,
+}
+
+/// Picks the geometry to draw for a model of the given projected size. `base` is used when the
+/// model is smaller than every level.
+pub fn select_lod(base: Handle<Geometry>, lods: &[Lod], screen_size: f32) -> Handle<Geometry> {
+    lods.iter()
+        .filter(|lod| screen_size >= lod.min_screen_size)
+        .max_by(|a, b| a.min_screen_size.total_cmp(&b.min_screen_size))
+        .map_or(base, |lod| lod.geometry)
+}
+
+/// Estimates how much of the screen a unit sized model covers after being transformed into clip
+/// space, where `1.0` spans the whole screen.
+pub fn projected_size(transform: &Matrix4<f32>) -> f32 {
+    let x = transform.transform_vector(&vector!(1.0, 0.0, 0.0)).xy().norm();
+    let y = transform.transform_vector(&vector!(0.0, 1.0, 0.0)).xy().norm();
+    // clip space spans two units in each direction
+    x.max(y) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix4, vector};
+
+    use utils::CompactList;
+
+    use crate::geometry::{Geometry, GeometryFormat};
+    use crate::lod::{Lod, projected_size, select_lod};
+
+    #[test]
+    fn selection() {
+        let mut geometries = CompactList::default();
+        let [low, medium, high] = [(); 3].map(|_| {
+            geometries.add(Geometry::new(vec![], GeometryFormat::from(vec![]), Vec::<u16>::new()))
+        });
+        let lods = [
+            Lod { min_screen_size: 0.5, geometry: high },
+            Lod { min_screen_size: 0.1, geometry: medium },
+        ];
+
+        assert_eq!(select_lod(low, &lods, 0.01), low);
+        assert_eq!(select_lod(low, &lods, 0.2), medium);
+        assert_eq!(select_lod(low, &lods, 0.8), high);
+        assert_eq!(select_lod(low, &[], 0.8), low);
+    }
+
+    #[test]
+    fn projection() {
+        let view = Matrix4::new_nonuniform_scaling(&vector!(0.1, 0.2, 1.0));
+        let small = view * Matrix4::new_scaling(0.5);
+        let large = view * Matrix4::new_scaling(5.0);
+
+        assert!((projected_size(&small) - 0.05).abs() < 1e-6);
+        assert!((projected_size(&large) - 0.5).abs() < 1e-6);
+    }
+}
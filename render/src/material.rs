@@ -1,7 +1,10 @@
 use std::cell::{RefCell, RefMut};
-use std::ops::DerefMut;
+use std::num::NonZeroU64;
+use std::ops::{DerefMut, Range};
 use std::str::FromStr;
+use std::sync::Arc;
 use bytemuck::cast_slice;
+use nalgebra::Matrix4;
 
 use serde::{Deserialize, Deserializer};
 use thiserror::Error;
@@ -9,8 +12,10 @@ use thiserror::Error;
 use utils::Handle;
 use crate::render_api::DeviceResources;
 
-use crate::{BufferUsages, DeviceContext, Model, MutableHandle, SurfaceContext, VecBuf};
-use crate::shader::{Shader, VertexFormat, VertexMapper};
+use crate::{BufferUsages, DeviceContext, InstanceData, Model, MutableHandle, RenderApi, TextureFormat, VecBuf};
+use crate::geometry::Geometry;
+use crate::lod::{projected_size, select_lod};
+use crate::shader::{Shader, ShaderDefinition, STRIP_RESTART_U32, Topology, VertexFormat, VertexMapper};
 
 #[derive(Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -61,6 +66,19 @@ pub enum InvalidVertexFormatString {
     OutOfRange,
 }
 
+/// Error rebuilding a [Material] from a changed shader, see [Material::reload].
+#[derive(Debug, Error)]
+pub enum ReloadError {
+    #[error("unknown uniform: {0}")]
+    UnknownUniform(String),
+    #[error("shader has no location for attribute: {0}")]
+    UnknownAttribute(String),
+    #[error("failed to build pipeline: {0}")]
+    Pipeline(#[from] wgpu::Error),
+    #[error("sample count {0} is not a power of two")]
+    InvalidSampleCount(u32),
+}
+
 impl FromStr for AttributeType {
     type Err = InvalidVertexFormatString;
 
@@ -115,6 +133,17 @@ pub enum AttributeSemantics {
         transform: PositionTransformation,
     },
     Color,
+    /// Distance from the vertex to the nearest edge of the shape, used for anti-aliasing, see
+    /// [EDGE_ALPHA_WGSL](crate::tessellate::EDGE_ALPHA_WGSL).
+    EdgeDistance,
+    /// Texture coordinates, with `(0, 0)` at the top left of the texture.
+    TexCoord,
+    /// Unit length surface normal.
+    Normal,
+    /// Column of the model transform of an instance, see [InstanceData](crate::InstanceData).
+    TransformColumn(u8),
+    /// Color of an instance, see [InstanceData](crate::InstanceData).
+    InstanceColor,
 }
 
 impl AttributeSemantics {
@@ -122,6 +151,14 @@ impl AttributeSemantics {
         match self {
             AttributeSemantics::Position { .. } => "position",
             AttributeSemantics::Color => "color",
+            AttributeSemantics::EdgeDistance => "edge_distance",
+            AttributeSemantics::TexCoord => "tex_coord",
+            AttributeSemantics::Normal => "normal",
+            AttributeSemantics::TransformColumn(0) => "transform_0",
+            AttributeSemantics::TransformColumn(1) => "transform_1",
+            AttributeSemantics::TransformColumn(2) => "transform_2",
+            AttributeSemantics::TransformColumn(_) => "transform_3",
+            AttributeSemantics::InstanceColor => "instance_color",
         }
     }
 }
@@ -155,6 +192,26 @@ pub struct UniformEntryDefinition {
 #[serde(rename_all = "kebab-case", tag = "type")]
 pub enum UniformEntryTypeDefinition {
     Buffer,
+    /// Buffer of which only a `size` bytes long window is bound at a time. Models choose the start
+    /// of the window with [Model::with_uniform_offsets](crate::Model::with_uniform_offsets), so
+    /// they can use different data within a single batch.
+    DynamicBuffer {
+        size: u64,
+    },
+    /// Filterable 2D texture, e.g. from [RenderApi::new_texture](crate::RenderApi::new_texture).
+    Texture,
+    /// Filtering sampler, from [RenderApi::new_sampler](crate::RenderApi::new_sampler).
+    Sampler,
+}
+
+impl UniformEntryTypeDefinition {
+    /// Size of the bound window of a [UniformEntryTypeDefinition::DynamicBuffer].
+    pub(crate) fn dynamic_size(&self) -> Option<NonZeroU64> {
+        match self {
+            UniformEntryTypeDefinition::DynamicBuffer { size } => NonZeroU64::new(*size),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Deserialize)]
@@ -181,46 +238,235 @@ pub enum UniformObjectFieldFormat {
 pub struct Material<S: Shader> {
     shader: S,
     pipeline: wgpu::RenderPipeline,
+    depth_pipeline: Option<wgpu::RenderPipeline>,
+    /// Keeps the cached modules of the pipelines from being
+    /// [evicted](DeviceResources::evict_shader_modules).
+    shader_modules: Vec<Arc<wgpu::ShaderModule>>,
     bind_groups: Vec<Handle<wgpu::BindGroupLayout>>,
+    target_formats: Vec<TextureFormat>,
+    sample_count: u32,
+    topology: Topology,
+    /// Whether the shader has instance attributes, see [Batch::instanced](crate::Batch::instanced).
+    instanced: bool,
     cache: RefCell<MaterialCache>,
 }
 
+/// Appends the strips of `indices`, offset by `offset` and separated by [STRIP_RESTART_U32], to the
+/// strip ending at `end`. Rather than relying on primitive restart, which not every backend
+/// enables, strips are joined by repeating the indices around each seam, forming degenerate
+/// triangles that cover no pixels. Returns the number of indices appended, which are pushed to
+/// `out` if given.
+///
+/// The winding of the triangles of a strip alternates, so every strip has to start at an even
+/// position to keep its winding and not be culled. `end` is repeated once more at seams after an
+/// odd number of indices, and the last index at the end of an odd number of appended indices, so
+/// strips start at even positions whether drawn from the start of this call or of an earlier one.
+fn stitch_strips(indices: &[u32], offset: u32, end: &mut Option<u32>, mut out: Option<&mut Vec<u32>>) -> usize {
+    let mut count = 0;
+    let mut push = |index: u32, count: &mut usize| {
+        *count += 1;
+        if let Some(out) = &mut out {
+            out.push(index);
+        }
+    };
+    for strip in indices.split(|index| *index == STRIP_RESTART_U32).filter(|strip| !strip.is_empty()) {
+        if let Some(end) = *end {
+            if count % 2 == 1 {
+                push(end, &mut count);
+            }
+            push(end, &mut count);
+            push(strip[0] + offset, &mut count);
+        }
+        for index in strip {
+            push(index + offset, &mut count);
+        }
+        *end = strip.last().map(|index| index + offset);
+    }
+    if let (1, Some(end)) = (count % 2, *end) {
+        push(end, &mut count);
+    }
+    count
+}
+
+/// Sample count of the pipelines built from `definition`, which is `default` unless the definition
+/// [overrides](crate::shader::MultisampleDefinition::sample_count) it.
+fn resolve_sample_count(definition: &ShaderDefinition, default: u32) -> Result<u32, ReloadError> {
+    match definition.multisample.sample_count {
+        Some(count) if !count.is_power_of_two() => Err(ReloadError::InvalidSampleCount(count)),
+        count => Ok(count.unwrap_or(default)),
+    }
+}
+
+/// Uploads `indices` to `buffer`, narrowed to 16 bit if they address at most `vertex_count`
+/// vertices within the range of 16 bit indices. The largest 16 bit index is left out, as some
+/// backends always treat it as a strip restart. Returns the format of the uploaded indices.
+fn upload_indices(buffer: &mut MutableHandle<VecBuf>, indices: &[u32], vertex_count: usize) -> wgpu::IndexFormat {
+    if vertex_count <= u16::MAX as usize {
+        let narrowed: Vec<u16> = indices.iter().map(|index| *index as u16).collect();
+        buffer.upload(0, cast_slice(&narrowed));
+        wgpu::IndexFormat::Uint16
+    } else {
+        buffer.upload(0, cast_slice(indices));
+        wgpu::IndexFormat::Uint32
+    }
+}
+
 pub struct Counter {
-    pub vertices: u16,
-    pub indices: u16,
+    pub vertices: u32,
+    pub indices: u32,
+    /// Number of vertices and indices counted up to and including each model, in model order.
+    model_ends: Vec<(u32, u32)>,
+    /// Whether the index buffer of the previous call was reused, as the models drew the same
+    /// geometry.
+    pub reused_indices: bool,
+}
+
+impl Counter {
+    /// Number of vertices or indices drawn for the counted models with `material`.
+    pub fn draw_count<S: Shader>(&self, material: &Material<S>) -> u32 {
+        if material.is_indexed() { self.indices } else { self.vertices }
+    }
+
+    /// Range of vertices or indices drawn with `material` for the given range of models.
+    pub(crate) fn draw_range<S: Shader>(&self, material: &Material<S>, models: Range<usize>) -> Range<u32> {
+        let end = |model: usize| {
+            let (vertices, indices) = model.checked_sub(1).map_or((0, 0), |model| self.model_ends[model]);
+            if material.is_indexed() { indices } else { vertices }
+        };
+        end(models.start)..end(models.end)
+    }
 }
 
 impl<S: Shader> Material<S> {
-    pub(crate) fn new(shader: S, device: &DeviceContext, resources: &DeviceResources, surface: &SurfaceContext) -> Self {
+    pub(crate) fn new(shader: S, device: &DeviceContext, resources: &DeviceResources, surface_format: Option<TextureFormat>, sample_count: u32) -> Self {
         let definition = shader.shader_definition();
         let bind_groups = definition.uniforms.iter()
             .map(|name| resources.uniforms.get(name).expect(&format!("uniform: {}", name)).layout)
             .collect();
-        let pipeline = device.create_render_pipeline(resources, surface, definition, S::Format::describe());
+        let target_formats = definition.fragment_targets.iter()
+            .map(|target| target.resolve(surface_format))
+            .collect();
+        let topology = definition.topology;
+        let instanced = !definition.instance_attributes.is_empty();
+        let sample_count = resolve_sample_count(&definition, sample_count).unwrap_or_else(|error| panic!("{}", error));
+        let pipelines = device.create_render_pipeline(resources, surface_format, definition, S::Format::describe(), sample_count)
+            .unwrap_or_else(|error| panic!("{}", error));
         Material {
-            pipeline,
+            pipeline: pipelines.color,
+            depth_pipeline: pipelines.depth_only,
+            shader_modules: pipelines.shader_modules,
             bind_groups,
+            target_formats,
+            sample_count,
+            topology,
+            instanced,
             shader,
             cache: RefCell::new(MaterialCache::new(device)),
         }
     }
 
-    pub fn cache_models(&self, device: &DeviceContext, resources: &DeviceResources, models: &[Model<S::Input>]) -> Counter {
+    /// Rebuilds the material from `shader`, e.g. after its source changed on disk. The new
+    /// pipelines are built aside and only swapped in once they compiled, so when the shader is
+    /// broken the error is logged and returned, and the material keeps drawing with its previous
+    /// shader. Picks up the [sample count](RenderApi::set_sample_count) of the render api unless
+    /// the new shader overrides it, like [Material::rebuild].
+    ///
+    /// Shader modules no material uses anymore, like the ones of the previous or the broken
+    /// shader, are dropped afterwards.
+    pub fn reload(&mut self, render: &RenderApi, shader: S) -> Result<(), ReloadError> {
+        let result = self.try_reload(render, shader);
+        render.resources.evict_shader_modules();
+        if let Err(error) = &result {
+            log::error!(target: "krill-render", "Failed to reload material, keeping the previous shader: {}", error);
+        }
+        result
+    }
+
+    /// Rebuilds the pipelines from the current shader, picking up the
+    /// [sample count](RenderApi::set_sample_count) of the render api unless the shader overrides
+    /// it. Errors like [Material::reload].
+    pub fn rebuild(&mut self, render: &RenderApi) -> Result<(), ReloadError> {
+        let definition = self.shader.shader_definition();
+        let result = self.build(render, definition, render.sample_count());
+        render.resources.evict_shader_modules();
+        if let Err(error) = &result {
+            log::error!(target: "krill-render", "Failed to rebuild material, keeping the previous pipelines: {}", error);
+        }
+        result
+    }
+
+    fn try_reload(&mut self, render: &RenderApi, shader: S) -> Result<(), ReloadError> {
+        self.build(render, shader.shader_definition(), render.sample_count())?;
+        self.shader = shader;
+        Ok(())
+    }
+
+    /// Replaces the pipelines with ones built from `definition`, keeping the current ones if that
+    /// fails.
+    fn build(&mut self, render: &RenderApi, definition: ShaderDefinition, sample_count: u32) -> Result<(), ReloadError> {
+        let bind_groups = definition.uniforms.iter()
+            .map(|name| render.resources.uniforms.get(name)
+                .map(|uniform| uniform.layout)
+                .ok_or_else(|| ReloadError::UnknownUniform(name.clone())))
+            .collect::<Result<_, _>>()?;
+        let surface_format = render.surface_format();
+        let target_formats = definition.fragment_targets.iter()
+            .map(|target| target.resolve(surface_format))
+            .collect();
+        let topology = definition.topology;
+        let instanced = !definition.instance_attributes.is_empty();
+        let sample_count = resolve_sample_count(&definition, sample_count)?;
+        let pipelines = render.device.try_create_render_pipeline(&render.resources, surface_format, definition, S::Format::describe(), sample_count)?;
+
+        self.pipeline = pipelines.color;
+        self.depth_pipeline = pipelines.depth_only;
+        self.shader_modules = pipelines.shader_modules;
+        self.bind_groups = bind_groups;
+        self.target_formats = target_formats;
+        self.topology = topology;
+        self.instanced = instanced;
+        self.sample_count = sample_count;
+        Ok(())
+    }
+
+    /// Uploads the geometry of every model, transformed by the shader. When a `view` is given,
+    /// models with [Lod](crate::lod::Lod)s use the geometry matching their size on screen.
+    ///
+    /// Vertices depend on the model inputs and are uploaded on every call, while the indices only
+    /// depend on the geometry. They are kept from the previous call when the models draw the same
+    /// geometry in the same order, which geometry handles identify as geometry never changes.
+    ///
+    /// Indices are uploaded as 32 bit once the models have more vertices than 16 bit indices can
+    /// address, regardless of the index format of their geometry.
+    pub fn cache_models(&self, device: &DeviceContext, resources: &DeviceResources, models: &[Model<S::Input>], view: Option<&Matrix4<f32>>) -> Counter {
         let mut index_counter = 0;
         let mut vertex_counter = 0;
+        let mut model_ends = Vec::with_capacity(models.len());
+        // last index of the strips uploaded so far
+        let mut strip_end = None;
 
         let mut cache = self.cache();
         let cache = cache.deref_mut();
         let mut vertex_buffer = MutableHandle::from_ref(device, &mut cache.vertex_buffer);
         let mut index_buffer = MutableHandle::from_ref(device, &mut cache.index_buffer);
 
-        for model in models {
-            let geometry = resources.geometries.get(model.geometry).unwrap();
+        let geometries: Vec<_> = models.iter()
+            .map(|model| match (view, self.shader.model_transform(&model.input)) {
+                (Some(view), Some(transform)) if !model.lods.is_empty() => {
+                    select_lod(model.geometry, &model.lods, projected_size(&(view * transform)))
+                }
+                _ => model.geometry,
+            })
+            .collect();
+        let reused_indices = cache.index_geometries == geometries;
+
+        for (model, geometry) in models.iter().zip(&geometries) {
+            let geometry = resources.geometries.get(*geometry).unwrap();
+            let index_offset = u32::try_from(vertex_counter).expect("batch has more vertices than 32 bit indices can address");
 
             let vertex_offset = cache.vertex_staging_buffer.len();
 
             cache.vertex_staging_buffer.extend_from_slice(&geometry.data);
-            cache.index_staging_buffer.extend_from_slice(&geometry.indices);
 
             // pass each vertex through the shader vertex mapper
             let vertex_count = geometry.data.len() / geometry.format.vertex_size();
@@ -230,27 +476,92 @@ impl<S: Shader> Material<S> {
                 self.shader.process_vertex(&model.input, vertex);
             }
 
-            // Update index offset
-            let indices = &mut cache.index_staging_buffer[index_counter..index_counter + geometry.indices.len()];
-            for index in indices.iter_mut() {
-                *index += vertex_counter as u16;
+            if self.topology == Topology::TriangleStrip {
+                let staging = (!reused_indices).then_some(&mut cache.index_staging_buffer);
+                index_counter += stitch_strips(&geometry.indices.widened_strips(), index_offset, &mut strip_end, staging);
+            } else {
+                if !reused_indices {
+                    // offset indices to the vertices of the model
+                    cache.index_staging_buffer.extend(geometry.indices.widened().iter().map(|index| index + index_offset));
+                }
+                index_counter += geometry.indices.len();
             }
 
             vertex_counter += vertex_count;
-            index_counter += geometry.indices.len();
+            model_ends.push((vertex_counter as _, index_counter as _));
         }
 
         vertex_buffer.upload(0, &cache.vertex_staging_buffer);
-        index_buffer.upload(0, cast_slice(&cache.index_staging_buffer));
         cache.vertex_staging_buffer.clear();
-        cache.index_staging_buffer.clear();
+        if !reused_indices {
+            cache.index_format = upload_indices(&mut index_buffer, &cache.index_staging_buffer, vertex_counter);
+            cache.index_staging_buffer.clear();
+            cache.index_geometries = geometries;
+        }
 
         Counter {
             indices: index_counter as _,
             vertices: vertex_counter as _,
+            model_ends,
+            reused_indices,
         }
     }
 
+    /// Uploads the geometry and instances of each instanced draw. The geometry is uploaded
+    /// untransformed, once per draw, and the instances follow a [default](InstanceData::default)
+    /// instance drawn by models.
+    pub(crate) fn cache_instances(&self, device: &DeviceContext, resources: &DeviceResources, instanced: &[(Handle<Geometry>, Vec<InstanceData>)]) -> Vec<InstancedDraw> {
+        if !self.instanced {
+            return vec![];
+        }
+        let mut cache = self.cache();
+        let cache = cache.deref_mut();
+        let mut instances = vec![InstanceData::default()];
+        let mut draws = Vec::with_capacity(instanced.len());
+        // indices of instanced geometry are relative to its base vertex
+        let mut largest_geometry = 0;
+        let vertex_format = S::Format::describe();
+        for (geometry, geometry_instances) in instanced {
+            let geometry = resources.geometries.get(*geometry).unwrap();
+            assert!(geometry.format.attributes() == vertex_format.as_slice(), "instanced geometry must have the vertex format of the shader");
+            let base_vertex = cache.vertex_staging_buffer.len() / geometry.format.vertex_size();
+            cache.vertex_staging_buffer.extend_from_slice(&geometry.data);
+            let elements = if self.is_indexed() {
+                let start = cache.index_staging_buffer.len();
+                if self.topology == Topology::TriangleStrip {
+                    stitch_strips(&geometry.indices.widened_strips(), 0, &mut None, Some(&mut cache.index_staging_buffer));
+                } else {
+                    cache.index_staging_buffer.extend_from_slice(&geometry.indices.widened());
+                }
+                largest_geometry = largest_geometry.max(geometry.vertex_count());
+                start as u32..cache.index_staging_buffer.len() as u32
+            } else {
+                base_vertex as u32..(base_vertex + geometry.data.len() / geometry.format.vertex_size()) as u32
+            };
+            let start = instances.len() as u32;
+            instances.extend_from_slice(geometry_instances);
+            draws.push(InstancedDraw {
+                elements,
+                base_vertex: if self.is_indexed() { base_vertex as i32 } else { 0 },
+                instances: start..instances.len() as u32,
+            });
+        }
+
+        MutableHandle::from_ref(device, &mut cache.instanced_vertex_buffer).upload(0, &cache.vertex_staging_buffer);
+        cache.vertex_staging_buffer.clear();
+        if self.is_indexed() {
+            let mut index_buffer = MutableHandle::from_ref(device, &mut cache.instanced_index_buffer);
+            cache.instanced_index_format = upload_indices(&mut index_buffer, &cache.index_staging_buffer, largest_geometry);
+            cache.index_staging_buffer.clear();
+        }
+        MutableHandle::from_ref(device, &mut cache.instance_buffer).upload(0, cast_slice(&instances));
+        draws
+    }
+
+    pub(crate) fn shader(&self) -> &S {
+        &self.shader
+    }
+
     pub(crate) fn cache(&self) -> RefMut<MaterialCache> {
         self.cache.borrow_mut()
     }
@@ -258,13 +569,56 @@ impl<S: Shader> Material<S> {
     pub fn pipeline(&self) -> &wgpu::RenderPipeline {
         &self.pipeline
     }
+
+    /// Pipeline writing only depth, available if the shader uses depth testing.
+    pub fn depth_pipeline(&self) -> Option<&wgpu::RenderPipeline> {
+        self.depth_pipeline.as_ref()
+    }
+
+    /// Whether the shader has instance attributes, so it can draw
+    /// [instanced](crate::Batch::instanced) geometry.
+    pub fn is_instanced(&self) -> bool {
+        self.instanced
+    }
+
+    /// Whether geometry is drawn using its indices, see [Topology](crate::shader::Topology).
+    pub fn is_indexed(&self) -> bool {
+        self.topology.is_indexed()
+    }
+
+    /// Formats of the color targets this material renders to, in output location order.
+    pub fn target_formats(&self) -> &[TextureFormat] {
+        &self.target_formats
+    }
+
+    /// Number of samples per pixel of the targets this material renders to.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
 }
 
 pub(crate) struct MaterialCache {
     pub(crate) vertex_buffer: VecBuf,
     pub(crate) index_buffer: VecBuf,
     pub(crate) vertex_staging_buffer: Vec<u8>,
-    pub(crate) index_staging_buffer: Vec<u16>,
+    pub(crate) index_staging_buffer: Vec<u32>,
+    /// Format the index buffer was last uploaded in, see [Material::cache_models].
+    pub(crate) index_format: wgpu::IndexFormat,
+    /// Geometry of each model the index buffer was last built for.
+    index_geometries: Vec<Handle<Geometry>>,
+    pub(crate) instanced_vertex_buffer: VecBuf,
+    pub(crate) instanced_index_buffer: VecBuf,
+    pub(crate) instanced_index_format: wgpu::IndexFormat,
+    /// Default instance for models, followed by the instances of each instanced draw.
+    pub(crate) instance_buffer: VecBuf,
+}
+
+/// Range of vertices or indices, and of instances, of an instanced draw, see
+/// [Material::cache_instances].
+pub(crate) struct InstancedDraw {
+    pub(crate) elements: Range<u32>,
+    pub(crate) base_vertex: i32,
+    pub(crate) instances: Range<u32>,
 }
 
 impl MaterialCache {
@@ -274,6 +628,12 @@ impl MaterialCache {
             index_buffer: device.create_buffer(0, BufferUsages::COPY_DST | BufferUsages::INDEX),
             vertex_staging_buffer: vec![],
             index_staging_buffer: vec![],
+            index_format: wgpu::IndexFormat::Uint16,
+            index_geometries: vec![],
+            instanced_vertex_buffer: device.create_buffer(0, BufferUsages::COPY_DST | BufferUsages::VERTEX),
+            instanced_index_buffer: device.create_buffer(0, BufferUsages::COPY_DST | BufferUsages::INDEX),
+            instanced_index_format: wgpu::IndexFormat::Uint16,
+            instance_buffer: device.create_buffer(0, BufferUsages::COPY_DST | BufferUsages::VERTEX),
         }
     }
 }
@@ -0,0 +1,334 @@
+use bytemuck::bytes_of;
+use bytemuck_derive::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+use crate::{DeviceContext, TextureFormat};
+use crate::texture::Texture;
+
+/// Samples the source texture with a triangle covering the whole target, then applies exposure,
+/// tone mapping and a vignette.
+const POSTPROCESS_SHADER: &str = r"
+struct Settings {
+    exposure: f32,
+    vignette: f32,
+    tonemap: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(0) var source: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: Settings;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    // (0, 0), (2, 0) and (0, 2), covering the unit square of texture coordinates
+    let uv = vec2(f32((index << 1u) & 2u), f32(index & 2u));
+    var output: VertexOutput;
+    output.position = vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(source, source_sampler, input.uv);
+    var rgb = color.rgb * settings.exposure;
+    if settings.tonemap == 1u {
+        rgb = rgb / (rgb + vec3(1.0));
+    }
+    // 0 in the center, 1 in the corners
+    let distance = length(input.uv - vec2(0.5)) * sqrt(2.0);
+    rgb *= 1.0 - settings.vignette * distance * distance;
+    return vec4(rgb, color.a);
+}
+";
+
+/// Curve mapping the colors of the source texture to the displayable range.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Tonemap {
+    /// Colors are only clamped.
+    #[default]
+    None,
+    /// Compresses bright colors with `c / (c + 1)`.
+    Reinhard,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PostProcessSettings {
+    pub tonemap: Tonemap,
+    /// Multiplier applied to colors before tone mapping.
+    pub exposure: f32,
+    /// How much the corners are darkened, from `0.0` for no vignette to `1.0` for black corners.
+    pub vignette: f32,
+}
+
+impl Default for PostProcessSettings {
+    /// Settings that reproduce the source texture unchanged.
+    fn default() -> Self {
+        PostProcessSettings {
+            tonemap: Tonemap::None,
+            exposure: 1.0,
+            vignette: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Pod, Zeroable)]
+#[repr(C)]
+struct SettingsUniform {
+    exposure: f32,
+    vignette: f32,
+    tonemap: u32,
+    _padding: u32,
+}
+
+impl From<PostProcessSettings> for SettingsUniform {
+    fn from(settings: PostProcessSettings) -> Self {
+        SettingsUniform {
+            exposure: settings.exposure,
+            vignette: settings.vignette,
+            tonemap: match settings.tonemap {
+                Tonemap::None => 0,
+                Tonemap::Reinhard => 1,
+            },
+            _padding: 0,
+        }
+    }
+}
+
+/// Full screen pass drawing a texture into a target with the given [PostProcessSettings], see
+/// [Drawer::submit_postprocess](crate::render_api::Drawer::submit_postprocess).
+pub struct PostProcessMaterial {
+    pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    settings: PostProcessSettings,
+    target_format: TextureFormat,
+}
+
+impl PostProcessMaterial {
+    pub(crate) fn new(device: &DeviceContext, target_format: TextureFormat, settings: PostProcessSettings) -> Self {
+        let device = &device.device;
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("postprocess"),
+            source: wgpu::ShaderSource::Wgsl(POSTPROCESS_SHADER.into()),
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("postprocess"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("postprocess"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("postprocess"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "fs_main",
+                targets: &[Some(target_format.into())],
+            }),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("postprocess"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        PostProcessMaterial {
+            pipeline,
+            bind_group_layout,
+            sampler,
+            settings,
+            target_format,
+        }
+    }
+
+    pub fn settings(&self) -> PostProcessSettings {
+        self.settings
+    }
+
+    /// Changes the settings used by following submissions of this material.
+    pub fn set_settings(&mut self, settings: PostProcessSettings) {
+        self.settings = settings;
+    }
+
+    /// Format of the targets this material can draw into.
+    pub fn target_format(&self) -> TextureFormat {
+        self.target_format
+    }
+
+    /// Records a pass drawing `source` into `target` with the current settings.
+    pub(crate) fn draw(&self, context: &DeviceContext, encoder: &mut wgpu::CommandEncoder, source: &Texture, target: &wgpu::TextureView) {
+        // a buffer per pass, so passes recorded with different settings don't overwrite each other
+        let settings = context.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("postprocess"),
+            contents: bytes_of(&SettingsUniform::from(self.settings)),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = context.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("postprocess"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(source.sampled_view()),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: settings.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("postprocess"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Batch, Color, Model, TextureFormat};
+    use crate::postprocess::PostProcessSettings;
+    use crate::shader::TargetFormat;
+    use crate::testing::{headless, pixel, quad, read_texture, TestShader, PASSTHROUGH_SHADER};
+
+    #[test]
+    fn postprocess() {
+        let mut render = headless();
+        let scene = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let identity_target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let vignette_target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        let identity = render.new_postprocess_material(PostProcessSettings::default());
+        let vignette = render.new_postprocess_material(PostProcessSettings {
+            vignette: 0.8,
+            ..Default::default()
+        });
+
+        // gray background with a white top left quarter and a red bottom right quarter
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![scene]);
+        batch.clear(Color::new(0.5, 0.5, 0.5, 1.0));
+        let white = quad(&mut render, (-1.0, 0.0), (0.0, 1.0), 0.0);
+        let red = quad(&mut render, (0.0, -1.0), (1.0, 0.0), 0.0);
+        batch.model(Model::new(white, Color::WHITE));
+        batch.model(Model::new(red, Color::new(1.0, 0.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.submit_postprocess(scene, &identity, Some(identity_target));
+        drawer.submit_postprocess(scene, &vignette, Some(vignette_target));
+        drawer.finish();
+
+        let scene = read_texture(&render, scene);
+        assert_eq!(pixel(&scene, 8, 1, 1), [255, 255, 255, 255]);
+        assert_eq!(read_texture(&render, identity_target), scene);
+
+        let vignetted = read_texture(&render, vignette_target);
+        for (x, y) in [(0, 0), (7, 0), (0, 7), (7, 7)] {
+            let corner = pixel(&vignetted, 8, x, y);
+            let original = pixel(&scene, 8, x, y);
+            assert!(corner[..3].iter().zip(&original[..3]).all(|(a, b)| a <= b), "{corner:?} > {original:?}");
+            assert!(corner[..3].iter().map(|c| *c as u32).sum::<u32>() < original[..3].iter().map(|c| *c as u32).sum::<u32>(), "corner ({x}, {y}) not darkened");
+        }
+        // barely affected in the center
+        let center = pixel(&vignetted, 8, 3, 3);
+        assert!(center[0] >= 250, "{center:?}");
+    }
+
+    #[test]
+    fn sampled_as_srgb() {
+        let mut render = headless();
+        let scene = render.new_render_texture_sampled_as(4, 4, TextureFormat::Rgba8Unorm, TextureFormat::Rgba8UnormSrgb);
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        let identity = render.new_postprocess_material(PostProcessSettings::default());
+
+        // stored as 128 without encoding, decoded from sRGB to ~0.216 when sampled
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![scene]);
+        let gray = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+        batch.model(Model::new(gray, Color::new(128.0 / 255.0, 128.0 / 255.0, 128.0 / 255.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.submit_postprocess(scene, &identity, Some(target));
+        drawer.finish();
+
+        assert_eq!(pixel(&read_texture(&render, scene), 4, 1, 1), [128, 128, 128, 255]);
+        let sampled = pixel(&read_texture(&render, target), 4, 1, 1);
+        if render.get_texture(scene).unwrap().sampled_format() == TextureFormat::Rgba8UnormSrgb {
+            assert!(sampled[..3].iter().all(|c| c.abs_diff(55) <= 1), "{sampled:?}");
+            assert_eq!(sampled[3], 255);
+        } else {
+            // without view format support, e.g. on GL, the texture is sampled as stored
+            assert_eq!(sampled, [128, 128, 128, 255]);
+        }
+    }
+}
@@ -1,30 +1,94 @@
+use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
+use std::future::Future;
 use std::iter::once;
+use std::num::NonZeroU32;
+use std::sync::Arc;
 
-use wgpu::RenderPassDescriptor;
+use ecs::world::{EntityId, ReadOnlyBounds, View};
+use bytemuck_derive::{Pod, Zeroable};
+use nalgebra::Matrix4;
+use wgpu::{BufferAddress, RenderPassDescriptor};
 
 use utils::{CompactList, Handle};
 
-use crate::{BufferUsages, Color, DeviceContext, Frame, MutableHandle, SurfaceContext, TextureFormat};
-use crate::geometry::{Geometry, GeometryFormat};
-use crate::material::{Counter, Material, UniformDefinition};
+use crate::{BufferUsages, Color, CompositeAlphaMode, DEPTH_FORMAT, DeviceContext, FilterMode, Frame, MutableHandle, PresentMode, SurfaceContext, TextureFormat};
+use crate::fade::FadePipelines;
+use crate::geometry::{Geometry, GeometryFormat, Indices};
+use crate::lod::Lod;
+use crate::material::{AttributeDefinition, AttributeSemantics, AttributeType, Counter, InstancedDraw, Material, MaterialCache, UniformDefinition};
 use crate::maybe::MaybeRef;
-use crate::shader::Shader;
-use crate::uniform::{Uniform, UniformInstance, UniformInstanceEntry};
-use crate::vecbuf::VecBuf;
+use crate::postprocess::{PostProcessMaterial, PostProcessSettings};
+use crate::shader::{Shader, TargetFormat};
+use crate::streaming::StreamingGeometry;
+use crate::texture::{Sampler, Texture};
+use crate::timing::{GpuTimer, RenderStats};
+use crate::uniform::{Uniform, UniformCache, UniformInstance, UniformInstanceEntry, UniformRing};
+use crate::vecbuf::{BufferRole, IndexBuffer, UniformBuffer, VecBuf, VertexBuffer};
 
 #[derive(Default)]
 pub struct DeviceResources {
     pub(crate) buffers: CompactList<VecBuf>,
     pub(crate) geometries: CompactList<Geometry>,
+    pub(crate) textures: CompactList<Texture>,
+    pub(crate) samplers: CompactList<Sampler>,
     pub(crate) bind_group_layouts: CompactList<wgpu::BindGroupLayout>,
+    /// Compiled shader modules by their WGSL source, shared by all materials built from it.
+    pub(crate) shader_modules: RefCell<HashMap<String, Arc<wgpu::ShaderModule>>>,
     pub(crate) uniforms: HashMap<String, Uniform>,
+    pub(crate) fade_pipelines: FadePipelines,
+    /// Bound to texture uniform entries instantiated without a value, see
+    /// [RenderApi::instantiate_uniform].
+    pub(crate) white_texture: Option<Handle<Texture>>,
+    /// Depth texture of batches drawing to the frame, see [DeviceResources::surface_depth].
+    surface_depth: Option<Handle<Texture>>,
+    /// Multisampled color of batches drawing to the frame, see [DeviceResources::surface_color].
+    surface_color: Option<Handle<Texture>>,
+}
+
+impl DeviceResources {
+    /// Returns the depth texture used by depth tested batches that draw to the frame without a
+    /// [depth output](Batch::depth_output). It is created on first use and recreated whenever
+    /// the size of the surface changes.
+    pub(crate) fn surface_depth(&mut self, device: &DeviceContext, size: (u32, u32), sample_count: u32) -> Handle<Texture> {
+        surface_attachment(&mut self.textures, &mut self.surface_depth, device, DEPTH_FORMAT, size, sample_count)
+    }
+
+    /// Returns the texture multisampled batches drawing to the frame render to, resolving it into
+    /// the frame after each batch. Like the [surface depth](DeviceResources::surface_depth), it
+    /// follows the size of the surface.
+    pub(crate) fn surface_color(&mut self, device: &DeviceContext, format: TextureFormat, size: (u32, u32), sample_count: u32) -> Handle<Texture> {
+        surface_attachment(&mut self.textures, &mut self.surface_color, device, format, size, sample_count)
+    }
+
+    /// Drops the cached shader modules no material is built from anymore, e.g. earlier versions
+    /// of a hot reloaded shader or ones that failed to compile.
+    pub(crate) fn evict_shader_modules(&self) {
+        self.shader_modules.borrow_mut().retain(|_, module| Arc::strong_count(module) > 1);
+    }
+}
+
+/// Returns the `cached` attachment if it still matches, replacing it otherwise.
+fn surface_attachment(textures: &mut CompactList<Texture>, cached: &mut Option<Handle<Texture>>, device: &DeviceContext, format: TextureFormat, size: (u32, u32), sample_count: u32) -> Handle<Texture> {
+    if let Some(handle) = *cached {
+        let matches = textures.get(handle)
+            .is_some_and(|texture| texture.size() == size && texture.format() == format && texture.sample_count() == sample_count);
+        if matches {
+            return handle;
+        }
+        textures.remove(handle);
+    }
+    let handle = textures.add(device.create_attachment_texture(size.0, size.1, format, sample_count));
+    *cached = Some(handle);
+    handle
 }
 
 pub struct RenderApi {
-    device: DeviceContext,
-    resources: DeviceResources,
-    surface: SurfaceContext,
+    pub(crate) device: DeviceContext,
+    pub(crate) resources: DeviceResources,
+    surface: Option<SurfaceContext>,
+    sample_count: u32,
+    timer: Option<GpuTimer>,
 }
 
 impl RenderApi {
@@ -32,35 +96,165 @@ impl RenderApi {
         RenderApi {
             device,
             resources: Default::default(),
-            surface,
+            surface: Some(surface),
+            sample_count: 1,
+            timer: None,
         }
     }
 
+    /// Creates a render api without a surface. It can only draw to textures, using drawers from
+    /// [RenderApi::new_offscreen_drawer].
+    pub fn new_headless(device: DeviceContext) -> Self {
+        RenderApi {
+            device,
+            resources: Default::default(),
+            surface: None,
+            sample_count: 1,
+            timer: None,
+        }
+    }
+
+    fn surface(&self) -> &SurfaceContext {
+        self.surface.as_ref().expect("headless render api has no surface")
+    }
+
     pub fn surface_format(&self) -> Option<TextureFormat> {
-        self.surface.format()
+        self.surface.as_ref().and_then(SurfaceContext::format)
     }
 
     pub fn surface_size(&self) -> Option<(u32, u32)> {
-        self.surface.size()
+        self.surface.as_ref().and_then(SurfaceContext::size)
+    }
+
+    /// Number of samples per pixel used by materials and render targets.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Sets the number of samples per pixel of materials and render textures created from now on,
+    /// returning the count that was applied. Counts the device can't render the surface format
+    /// and depth with fall back to the highest supported count below, with a warning.
+    ///
+    /// Existing materials and textures keep their sample count until rebuilt with
+    /// [Material::rebuild], and batches can only be submitted to outputs with the sample count of
+    /// their material. Multisampled batches drawing to the frame render to a multisampled copy of
+    /// it, resolved into the frame after each batch. Materials can override the count with
+    /// [MultisampleDefinition::sample_count](crate::shader::MultisampleDefinition::sample_count).
+    pub fn set_sample_count(&mut self, sample_count: u32) -> u32 {
+        assert!(sample_count.is_power_of_two(), "sample count must be a power of two");
+        let format = self.surface_format().unwrap_or(TextureFormat::Rgba8Unorm);
+        let mut supported = sample_count;
+        while supported > 1 && !(self.device.supports_sample_count(format, supported) && self.device.supports_sample_count(DEPTH_FORMAT, supported)) {
+            supported /= 2;
+        }
+        if supported != sample_count {
+            log::warn!(target: "krill-render", "{} samples per pixel are not supported by the device, using {}.", sample_count, supported);
+        }
+        self.sample_count = supported;
+        supported
+    }
+
+    /// Enables measuring the GPU time of each batch, reported by [Drawer::finish]. Requires
+    /// timestamp query support from the device, timing stays disabled with a warning otherwise.
+    ///
+    /// Reading the measurements back stalls until the GPU has finished drawing, so this is meant
+    /// for profiling only.
+    pub fn set_gpu_timing(&mut self, enabled: bool) {
+        self.timer = if enabled {
+            let timer = GpuTimer::new(&self.device);
+            if timer.is_none() {
+                log::warn!(target: "krill-render", "Timestamp queries are not supported by the device, GPU timing is unavailable.");
+            }
+            timer
+        } else {
+            None
+        };
     }
 
     pub fn configure_surface(&mut self, width: u32, height: u32) {
-        self.surface.configure(&self.device, width, height);
+        self.surface.as_mut()
+            .expect("headless render api has no surface")
+            .configure(&self.device, width, height);
+    }
+
+    /// Changes how the surface is composited, reconfiguring it if it was already configured.
+    /// Unsupported modes fall back to one the surface supports.
+    pub fn set_surface_alpha_mode(&mut self, alpha_mode: CompositeAlphaMode) {
+        let surface = self.surface.as_mut().expect("headless render api has no surface");
+        surface.set_alpha_mode(alpha_mode);
+        if let Some((width, height)) = surface.size() {
+            surface.configure(&self.device, width, height);
+        }
+    }
+
+    /// Changes when frames are presented, e.g. [PresentMode::Immediate] to measure frame times
+    /// without vertical sync, reconfiguring the surface if it was already configured. Unsupported
+    /// modes fall back to [PresentMode::Fifo].
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        let surface = self.surface.as_mut().expect("headless render api has no surface");
+        surface.set_present_mode(present_mode);
+        if let Some((width, height)) = surface.size() {
+            surface.configure(&self.device, width, height);
+        }
     }
 
     pub fn request_frame(&self) -> Frame {
-        self.surface.request_frame()
+        self.surface().request_frame()
     }
 
     pub fn present_frame(&self, frame: Frame) {
-        self.surface.present_frame(frame);
+        self.surface().present_frame(frame);
     }
 
+    /// Draws `texture` to a new frame through `material`, typically after rendering the scene to
+    /// it, and presents the frame.
+    pub fn present_with_postprocess(&mut self, texture: Handle<Texture>, material: &PostProcessMaterial) {
+        let frame = self.request_frame();
+        let mut drawer = self.new_drawer(&frame);
+        drawer.submit_postprocess(texture, material, None);
+        drawer.finish();
+        self.present_frame(frame);
+    }
+
+    /// Creates an untyped buffer with any usage. Prefer the buffers of a [BufferRole], whose
+    /// handles can't be mixed up.
     pub fn new_buffer(&mut self, capacity: usize, usage: BufferUsages) -> Handle<VecBuf> {
         let buffer = self.device.create_buffer(capacity, usage);
         self.resources.buffers.add(buffer)
     }
 
+    fn new_role_buffer<R: BufferRole>(&mut self, capacity: usize) -> Handle<R> {
+        self.new_buffer(capacity, R::USAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC).cast()
+    }
+
+    pub fn new_vertex_buffer(&mut self, capacity: usize) -> Handle<VertexBuffer> {
+        self.new_role_buffer(capacity)
+    }
+
+    pub fn new_index_buffer(&mut self, capacity: usize) -> Handle<IndexBuffer> {
+        self.new_role_buffer(capacity)
+    }
+
+    pub fn new_uniform_buffer(&mut self, capacity: usize) -> Handle<UniformBuffer> {
+        self.new_role_buffer(capacity)
+    }
+
+    /// Creates a ring of `regions` uniform values of up to `size` bytes each, typically one region
+    /// more than the number of frames in flight.
+    pub fn new_uniform_ring(&mut self, size: u32, regions: u32) -> UniformRing {
+        assert!(regions > 0, "uniform ring needs at least one region");
+        let alignment = self.device.device.limits().min_uniform_buffer_offset_alignment;
+        let stride = size.div_ceil(alignment) * alignment;
+        let buffer = self.new_uniform_buffer((stride * (regions - 1) + size) as _);
+        UniformRing::new(buffer, size, stride, regions)
+    }
+
+    /// Creates a ring of `capacity` bytes for vertices generated each frame.
+    pub fn new_streaming_geometry(&mut self, capacity: u64) -> StreamingGeometry {
+        let buffer = self.new_vertex_buffer(capacity as _);
+        StreamingGeometry::new(buffer, capacity)
+    }
+
     pub fn get_buffer<'a>(&'a mut self, handle: impl Into<MaybeRef<'a, VecBuf>>) -> Option<MutableHandle<'a, VecBuf>> {
         match handle.into() {
             MaybeRef::Handle(handle) => self.resources.buffers.get_mut(handle)
@@ -75,8 +269,30 @@ impl RenderApi {
         }
     }
 
+    /// Copies the contents of a buffer back to the CPU, up to its [length](VecBuf::len). The
+    /// buffer needs [BufferUsages::COPY_SRC], which buffers of a [BufferRole] have.
+    pub fn read_buffer<'a>(&'a self, handle: impl Into<MaybeRef<'a, VecBuf>>) -> impl Future<Output=Vec<u8>> + 'a {
+        let buffer: &VecBuf = match handle.into() {
+            MaybeRef::Handle(handle) => self.resources.buffers.get(handle).expect("buffer to read"),
+            MaybeRef::Ref(buffer) => buffer,
+        };
+        assert!(buffer.usage().contains(BufferUsages::COPY_SRC), "buffer is not readable without COPY_SRC usage");
+        // uploads are padded, so the length is a multiple of the copy alignment
+        let size = buffer.len() as BufferAddress;
+        self.device.read_back(size, move |encoder, staging| {
+            encoder.copy_buffer_to_buffer(&buffer.buffer, 0, staging, 0, size);
+        })
+    }
+
     pub fn new_material<S: Shader>(&mut self, shader: S) -> Material<S> {
-        Material::new(shader, &self.device, &self.resources, &self.surface)
+        Material::new(shader, &self.device, &self.resources, self.surface_format(), self.sample_count)
+    }
+
+    /// Creates a material for [RenderApi::present_with_postprocess], drawing into targets of the
+    /// surface format.
+    pub fn new_postprocess_material(&mut self, settings: PostProcessSettings) -> PostProcessMaterial {
+        let format = TargetFormat::Surface.resolve(self.surface_format());
+        PostProcessMaterial::new(&self.device, format, settings)
     }
 
     pub fn register_uniform(&mut self, name: &str, uniform: UniformDefinition) {
@@ -89,24 +305,29 @@ impl RenderApi {
         });
     }
 
+    /// Binds `values` to the entries of the uniform registered as `name`. Entries without a value
+    /// get an empty buffer, or a single white texel so shaders multiplying by the texture are
+    /// unaffected.
     pub fn instantiate_uniform(&mut self, name: &str, values: Vec<Option<UniformInstanceEntry>>) -> UniformInstance {
+        if self.resources.white_texture.is_none() {
+            let white = self.device.create_texture(1, 1, TextureFormat::Rgba8Unorm, &[255; 4]);
+            self.resources.white_texture = Some(self.resources.textures.add(white));
+        }
         let uniform = &self.resources.uniforms[name];
 
         UniformInstance::new(&mut self.device, &self.resources, uniform, values)
     }
 
     pub fn new_empty_geometry(&mut self) -> Handle<Geometry> {
-        self.new_geometry(vec![], GeometryFormat::empty(), vec![])
+        self.new_geometry(vec![], GeometryFormat::empty(), Vec::<u16>::new())
     }
 
-    pub fn new_geometry(&mut self, data: Vec<u8>, format: GeometryFormat, indices: Vec<u16>) -> Handle<Geometry> {
-        self.resources.geometries.add(
-            Geometry::new(
-                data,
-                format,
-                indices,
-            )
-        )
+    pub fn new_geometry(&mut self, data: Vec<u8>, format: GeometryFormat, indices: impl Into<Indices>) -> Handle<Geometry> {
+        self.add_geometry(Geometry::new(data, format, indices))
+    }
+
+    pub fn add_geometry(&mut self, geometry: Geometry) -> Handle<Geometry> {
+        self.resources.geometries.add(geometry)
     }
 
     pub fn get_geometry<'a>(&'a mut self, handle: impl Into<MaybeRef<'a, Geometry>>) -> Option<MutableHandle<Geometry>> {
@@ -123,8 +344,99 @@ impl RenderApi {
         }
     }
 
+    /// Creates a texture that can be rendered to as a [Batch] output, and sampled afterwards.
+    ///
+    /// With a [sample count](RenderApi::set_sample_count) above one, batches render to a
+    /// multisampled copy of the texture that is resolved into it after each batch, so
+    /// post-processing and reads see the smoothed result.
+    pub fn new_render_texture(&mut self, width: u32, height: u32, format: TextureFormat) -> Handle<Texture> {
+        let texture = self.device.create_render_texture(width, height, format, self.sample_count, None);
+        self.resources.textures.add(texture)
+    }
+
+    /// Like [RenderApi::new_render_texture], but post-processing and texture uniforms sample it as
+    /// `sampled_format`, the sRGB or linear counterpart of `format`. E.g. a texture rendered to as
+    /// `Rgba8Unorm` holding sRGB encoded colors is sampled as `Rgba8UnormSrgb`, so shaders read
+    /// linear values. Devices without [view format](wgpu::DownlevelFlags::VIEW_FORMATS) support,
+    /// like the GL backends, sample the texture as `format` instead, with a warning.
+    pub fn new_render_texture_sampled_as(&mut self, width: u32, height: u32, format: TextureFormat, sampled_format: TextureFormat) -> Handle<Texture> {
+        let sampled_format = if sampled_format == format {
+            None
+        } else if self.device.supports_view_formats() {
+            Some(sampled_format)
+        } else {
+            log::warn!(target: "krill-render", "Texture views in other formats are not supported by the device, sampling {:?} texture as is.", format);
+            None
+        };
+        let texture = self.device.create_render_texture(width, height, format, self.sample_count, sampled_format);
+        self.resources.textures.add(texture)
+    }
+
+    /// Creates a texture to bind to [Texture](crate::material::UniformEntryTypeDefinition::Texture) uniform
+    /// entries, filled with `data` laid out in tightly packed rows of `format` texels.
+    pub fn new_texture(&mut self, width: u32, height: u32, format: TextureFormat, data: &[u8]) -> Handle<Texture> {
+        let texture = self.device.create_texture(width, height, format, data);
+        self.resources.textures.add(texture)
+    }
+
+    pub fn new_sampler(&mut self, filter: FilterMode) -> Handle<Sampler> {
+        let sampler = self.device.create_sampler(filter);
+        self.resources.samplers.add(sampler)
+    }
+
+    pub fn get_texture(&self, handle: Handle<Texture>) -> Option<&Texture> {
+        self.resources.textures.get(handle)
+    }
+
+    /// Copies the contents of a texture back to the CPU, e.g. for screenshots, with rows tightly
+    /// packed. Multisampled render textures are read as resolved after their last batch. Depth
+    /// textures can't be read.
+    pub fn read_texture(&self, handle: Handle<Texture>) -> impl Future<Output=Vec<u8>> + '_ {
+        let texture = self.resources.textures.get(handle).expect("texture to read");
+        let (width, height) = texture.size();
+        let row_size = width * texture.format().describe().block_size as u32;
+        // texture copies write rows at multiples of 256 bytes
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let padded = self.device.read_back((padded_row_size * height) as _, move |encoder, staging| {
+            encoder.copy_texture_to_buffer(
+                texture.texture().as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: staging,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: NonZeroU32::new(padded_row_size),
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            );
+        });
+        async move {
+            padded.await
+                .chunks_exact(padded_row_size as _)
+                .flat_map(|row| &row[..row_size as usize])
+                .copied()
+                .collect()
+        }
+    }
+
     pub fn new_drawer(&mut self, frame: &Frame) -> Drawer {
-        let target = frame.surface_texture.texture.create_view(&Default::default());
+        let texture = &frame.surface_texture.texture;
+        let target = texture.create_view(&Default::default());
+        let size = texture.size();
+        let mut drawer = self.create_drawer(Some(target));
+        drawer.target_size = Some((size.width, size.height));
+        drawer
+    }
+
+    /// Creates a drawer that is not associated with a frame. Every batch submitted to it must
+    /// declare its own outputs with [Batch::output].
+    pub fn new_offscreen_drawer(&mut self) -> Drawer<'_> {
+        self.create_drawer(None)
+    }
+
+    fn create_drawer(&mut self, target: Option<wgpu::TextureView>) -> Drawer<'_> {
         let encoder = self.device.device.create_command_encoder(&Default::default());
 
         Drawer {
@@ -132,6 +444,12 @@ impl RenderApi {
             resources: &mut self.resources,
             encoder,
             target,
+            target_size: None,
+            timer: self.timer.as_ref(),
+            stats: Default::default(),
+            timed_batches: Vec::new(),
+            fade: None,
+            frame_multisampled: false,
         }
     }
 }
@@ -140,68 +458,377 @@ pub struct Drawer<'a> {
     context: &'a DeviceContext,
     resources: &'a mut DeviceResources,
     encoder: wgpu::CommandEncoder,
-    target: wgpu::TextureView,
+    target: Option<wgpu::TextureView>,
+    /// Size of the frame, if drawing to one.
+    target_size: Option<(u32, u32)>,
+    timer: Option<&'a GpuTimer>,
+    stats: RenderStats,
+    /// Indices into the batch stats of the timed batches, in query order.
+    timed_batches: Vec<usize>,
+    /// Amount to fade the outputs of the next batch by, see [Drawer::fade_previous].
+    fade: Option<f32>,
+    /// Whether the last batch drawing to the frame was multisampled, so the multisampled color
+    /// texture of the frame holds its current contents.
+    frame_multisampled: bool,
 }
 
 impl<'a> Drawer<'a> {
     pub fn submit_batch<S: Shader>(&mut self, batch: Batch<S>) {
-        let Counter { vertices, indices } = batch.material.cache_models(self.context, self.resources, &batch.models);
+        let counter = batch.material.cache_models(self.context, self.resources, &batch.models, batch.view.as_ref());
+        let instanced = batch.material.cache_instances(self.context, self.resources, &batch.instanced);
 
-        if indices == 0 {
+        let empty = counter.draw_count(batch.material) == 0 && instanced.is_empty();
+        // an empty batch still fades its outputs
+        if empty && self.fade.is_none() {
+            self.stats.batches.push(Default::default());
             return;
         }
 
         let material_cache = batch.material.cache();
-        let uniform_caches: Vec<_> = batch.uniforms.into_iter().map(|uniform| {
+        let uniform_caches: Vec<_> = batch.uniforms.iter().map(|uniform| {
             uniform.validate_bind_group(self.context, self.resources);
             uniform.cache()
         }).collect();
 
-        let load = match batch.clear {
+        let depth = match batch.material.depth_pipeline() {
+            Some(_) => self.batch_depth(&batch),
+            None => None,
+        };
+        let sample_count = batch.material.sample_count();
+        let frame_color = match self.target_size {
+            Some(size) if batch.outputs.is_empty() && sample_count > 1 => {
+                let format = batch.material.target_formats()[0];
+                Some(self.resources.surface_color(self.context, format, size, sample_count))
+            }
+            _ => None,
+        };
+        let targets: Vec<_> = if batch.outputs.is_empty() {
+            let target = self.target.as_ref().expect("batch without outputs submitted to an offscreen drawer");
+            match frame_color {
+                Some(color) => vec![(&self.resources.textures.get(color).unwrap().view, Some(target))],
+                None => vec![(target, None)],
+            }
+        } else {
+            let target_formats = batch.material.target_formats();
+            assert_eq!(batch.outputs.len(), target_formats.len(), "batch outputs must match the material's fragment targets");
+            batch.outputs.iter()
+                .zip(target_formats)
+                .map(|(output, format)| {
+                    let texture = self.resources.textures.get(*output).expect("output texture");
+                    assert_eq!(texture.format(), *format, "batch output format must match the material's fragment target");
+                    assert_eq!(texture.sample_count(), batch.material.sample_count(), "batch output sample count must match the material");
+                    texture.attachment()
+                })
+                .collect()
+        };
+
+        let fade = self.fade.take();
+        let load = match (fade, batch.clear) {
+            (None, Some(color)) => wgpu::LoadOp::Clear(color.into()),
+            _ => wgpu::LoadOp::Load,
+        };
+        if batch.outputs.is_empty() {
+            // the frame is only resolved from the multisampled texture, never copied back into it
+            assert!(
+                frame_color.is_none() || self.frame_multisampled || matches!(load, wgpu::LoadOp::Clear(_)),
+                "a multisampled batch drawing to the frame must clear it, unless the previous batch drawing to the frame was multisampled too",
+            );
+            self.frame_multisampled = frame_color.is_some();
+        }
+        let color_attachments: Vec<_> = targets.into_iter()
+            .map(|(view, resolve_target)| Some(wgpu::RenderPassColorAttachment {
+                view,
+                ops: wgpu::Operations {
+                    store: true,
+                    load,
+                },
+                resolve_target,
+            }))
+            .collect();
+        if let Some(amount) = fade {
+            let pipeline = self.resources.fade_pipelines.get(self.context, batch.material.target_formats(), batch.material.sample_count());
+            let mut render_pass = self.encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("fade"),
+                color_attachments: &color_attachments,
+                depth_stencil_attachment: None,
+            });
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_blend_constant(wgpu::Color { r: amount as f64, g: amount as f64, b: amount as f64, a: amount as f64 });
+            render_pass.draw(0..3, 0..1);
+        }
+        if empty {
+            self.stats.batches.push(Default::default());
+            return;
+        }
+
+        let depth_load = match batch.clear_depth {
             None => wgpu::LoadOp::Load,
-            Some(color) => wgpu::LoadOp::Clear(color.into()),
+            Some(depth) => wgpu::LoadOp::Clear(depth),
         };
+        let depth_view = batch.material.depth_pipeline()
+            .map(|_| depth_view(self.resources, &batch, depth));
+        let pass = self.timed_batches.len() as u32;
+        let timer = self.timer.filter(|timer| timer.can_time(pass));
+        if let Some(timer) = timer {
+            timer.begin(&mut self.encoder, pass);
+        }
         let mut render_pass = self.encoder.begin_render_pass(&RenderPassDescriptor {
             label: Default::default(),
-            color_attachments: &[Some(
-                wgpu::RenderPassColorAttachment {
-                    view: &self.target,
-                    ops: wgpu::Operations {
-                        store: true,
-                        load,
-                    },
-                    resolve_target: None,
-                },
-            )],
-            depth_stencil_attachment: None,
+            color_attachments: &color_attachments,
+            depth_stencil_attachment: depth_view.map(|view| depth_attachment(view, depth_load)),
         });
 
         render_pass.set_pipeline(batch.material.pipeline());
-        render_pass.set_vertex_buffer(0, material_cache.vertex_buffer.entire_slice());
-        render_pass.set_index_buffer(material_cache.index_buffer.entire_slice(), wgpu::IndexFormat::Uint16);
-        for (i, uniform) in uniform_caches.iter().enumerate() {
-            render_pass.set_bind_group(i as _, uniform.bind_group(), &[]);
+        if let Some(constant) = batch.blend_constant {
+            render_pass.set_blend_constant(constant.into());
         }
 
         log::trace!(
             target:"krill-render",
             "Drawing {} ({} bytes) vertices, {} ({} bytes) indices",
-            vertices, material_cache.vertex_buffer.len(),
-            indices, material_cache.index_buffer.len(),
+            counter.vertices, material_cache.vertex_buffer.len(),
+            counter.indices, material_cache.index_buffer.len(),
         );
 
-        render_pass.draw_indexed(0..indices as _, 0, 0..1);
+        draw(&mut render_pass, &batch, &uniform_caches, &material_cache, &counter, &instanced);
+        drop(render_pass);
+
+        if let Some(timer) = timer {
+            timer.end(&mut self.encoder, pass);
+            self.timed_batches.push(self.stats.batches.len());
+        }
+        self.stats.batches.push(Default::default());
     }
 
-    pub fn finish(self) {
+    /// Darkens the outputs of the next submitted batch by `amount`, from `0.0` keeping them as they
+    /// are to `1.0` turning them black, instead of clearing them. Drawing a moving model over the
+    /// faded result of the previous frame leaves a trail behind it.
+    ///
+    /// Only render textures keep their contents between frames, so the scene has to be drawn to
+    /// one and presented from there, e.g. with [RenderApi::present_with_postprocess].
+    pub fn fade_previous(&mut self, amount: f32) {
+        assert!((0.0..=1.0).contains(&amount), "fade amount must be between 0 and 1");
+        self.fade = Some(amount);
+    }
+
+    /// Depth texture tested against by `batch`, falling back to the
+    /// [surface depth](DeviceResources::surface_depth) for batches drawing to the frame.
+    fn batch_depth<S: Shader>(&mut self, batch: &Batch<S>) -> Option<Handle<Texture>> {
+        match (batch.depth, self.target_size) {
+            (None, Some(size)) if batch.outputs.is_empty() => Some(self.resources.surface_depth(self.context, size, batch.material.sample_count())),
+            (depth, _) => depth,
+        }
+    }
+
+    /// Draws the opaque batch sorted front to back, so depth testing discards as many hidden
+    /// fragments as possible, followed by the transparent batch sorted back to front, so it
+    /// blends correctly. Models are sorted by the z translation of their
+    /// [model transform](Shader::model_transform), where a smaller z is nearer.
+    pub fn submit_scene<S: Shader>(&mut self, mut opaque: Batch<S>, mut transparent: Batch<S>) {
+        let shader = opaque.material.shader();
+        sort_by_depth(&mut opaque.models, |input| model_depth(shader, input), DepthOrder::FrontToBack);
+        let shader = transparent.material.shader();
+        sort_by_depth(&mut transparent.models, |input| model_depth(shader, input), DepthOrder::BackToFront);
+
+        self.submit_batch(opaque);
+        self.submit_batch(transparent);
+    }
+
+    /// Fills the depth output of the batch with the nearest depth of its models, without writing
+    /// any color. Submitting the batch afterwards with a material comparing depth with
+    /// [CompareFunction::Equal](crate::CompareFunction::Equal) then shades each pixel only once.
+    ///
+    /// The depth output is always cleared first, to the depth set with [Batch::clear_depth] or
+    /// `1.0`.
+    pub fn depth_prepass<S: Shader>(&mut self, batch: &Batch<S>) {
+        let pipeline = batch.material.depth_pipeline()
+            .expect("depth prepass requires a material with depth testing");
+        let counter = batch.material.cache_models(self.context, self.resources, &batch.models, batch.view.as_ref());
+        let instanced = batch.material.cache_instances(self.context, self.resources, &batch.instanced);
+
+        let material_cache = batch.material.cache();
+        let uniform_caches: Vec<_> = batch.uniforms.iter().map(|uniform| {
+            uniform.validate_bind_group(self.context, self.resources);
+            uniform.cache()
+        }).collect();
+
+        let depth = self.batch_depth(batch);
+        let depth_view = depth_view(self.resources, batch, depth);
+        let mut render_pass = self.encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Default::default(),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(depth_attachment(depth_view, wgpu::LoadOp::Clear(batch.clear_depth.unwrap_or(1.0)))),
+        });
+        if counter.draw_count(batch.material) == 0 && instanced.is_empty() {
+            return;
+        }
+
+        render_pass.set_pipeline(pipeline);
+        draw(&mut render_pass, batch, &uniform_caches, &material_cache, &counter, &instanced);
+    }
+
+    /// Draws `source` through a post-processing `material` into `output`, or into the drawer's
+    /// frame if there is none. `source` can't be the output as well.
+    pub fn submit_postprocess(&mut self, source: Handle<Texture>, material: &PostProcessMaterial, output: Option<Handle<Texture>>) {
+        assert_ne!(Some(source), output, "post-processing can't sample its own output");
+        let source = self.resources.textures.get(source).expect("source texture");
+        let target = match output {
+            None => self.target.as_ref().expect("post-process without output submitted to an offscreen drawer"),
+            Some(output) => {
+                let texture = self.resources.textures.get(output).expect("output texture");
+                assert_eq!(texture.format(), material.target_format(), "post-process output format must match the material's target");
+                &texture.view
+            }
+        };
+        material.draw(self.context, &mut self.encoder, source, target);
+        self.stats.batches.push(Default::default());
+    }
+
+    /// Submits all recorded work to the GPU. With GPU timing enabled, this blocks until the GPU is
+    /// done to read the measured times back.
+    pub fn finish(mut self) -> RenderStats {
+        let passes = self.timed_batches.len() as u32;
+        let timer = self.timer.filter(|_| passes > 0);
+        if let Some(timer) = timer {
+            timer.resolve(&mut self.encoder, passes);
+        }
+
         let buffer = self.encoder.finish();
         self.context.queue.submit(once(buffer));
+
+        if let Some(timer) = timer {
+            let times = timer.read(self.context, passes);
+            for (batch, time) in self.timed_batches.iter().zip(times) {
+                self.stats.batches[*batch].gpu_time_ns = Some(time);
+            }
+        }
+        self.stats
+    }
+}
+
+/// Binds the uniforms of a batch and draws its cached models, using their indices if the material
+/// is indexed. Consecutive models with the same [uniform offsets](Model::with_uniform_offsets) are
+/// drawn together.
+fn draw<'p, S: Shader>(render_pass: &mut wgpu::RenderPass<'p>, batch: &Batch<S>, uniforms: &'p [RefMut<UniformCache>], cache: &'p MaterialCache, counter: &Counter, instanced: &[InstancedDraw]) {
+    let material = batch.material;
+    if material.is_instanced() {
+        render_pass.set_vertex_buffer(1, cache.instance_buffer.entire_slice());
+    }
+
+    let offset_counts: Vec<_> = batch.uniforms.iter().map(|uniform| uniform.dynamic_offset_count()).collect();
+    let mut offsets = vec![0; offset_counts.iter().sum()];
+    let mut bind_uniforms = |render_pass: &mut wgpu::RenderPass<'p>, model_offsets: &[u32]| {
+        assert!(model_offsets.len() <= offsets.len(), "model has more uniform offsets than the batch has dynamic uniform entries");
+        // entries without an offset from the model or batch start at the beginning of their buffer
+        offsets.fill(0);
+        assert!(batch.uniform_offsets.len() <= offsets.len(), "batch has more uniform offsets than dynamic uniform entries");
+        offsets[..batch.uniform_offsets.len()].copy_from_slice(&batch.uniform_offsets);
+        offsets[..model_offsets.len()].copy_from_slice(model_offsets);
+
+        let mut remaining = offsets.as_slice();
+        for (i, (uniform, count)) in uniforms.iter().zip(&offset_counts).enumerate() {
+            let (uniform_offsets, rest) = remaining.split_at(*count);
+            render_pass.set_bind_group(i as _, uniform.bind_group(), uniform_offsets);
+            remaining = rest;
+        }
+    };
+
+    if counter.draw_count(material) > 0 {
+        render_pass.set_vertex_buffer(0, cache.vertex_buffer.entire_slice());
+        if material.is_indexed() {
+            render_pass.set_index_buffer(cache.index_buffer.entire_slice(), cache.index_format);
+        }
+        let mut start = 0;
+        for (end, model_offsets) in offset_runs(&batch.models) {
+            bind_uniforms(render_pass, model_offsets);
+            // models read the default instance at the start of the instance buffer
+            let range = counter.draw_range(material, start..end);
+            if material.is_indexed() {
+                render_pass.draw_indexed(range, 0, 0..1);
+            } else {
+                render_pass.draw(range, 0..1);
+            }
+            start = end;
+        }
+    }
+
+    if !instanced.is_empty() {
+        bind_uniforms(render_pass, &[]);
+        render_pass.set_vertex_buffer(0, cache.instanced_vertex_buffer.entire_slice());
+        if material.is_indexed() {
+            render_pass.set_index_buffer(cache.instanced_index_buffer.entire_slice(), cache.instanced_index_format);
+        }
+        for draw in instanced {
+            if material.is_indexed() {
+                render_pass.draw_indexed(draw.elements.clone(), draw.base_vertex, draw.instances.clone());
+            } else {
+                render_pass.draw(draw.elements.clone(), draw.instances.clone());
+            }
+        }
+    }
+}
+
+/// Splits models into runs of consecutive models with the same uniform offsets, returning the end
+/// of each run along with its offsets.
+fn offset_runs<I>(models: &[Model<I>]) -> Vec<(usize, &[u32])> {
+    let mut runs: Vec<(usize, &[u32])> = vec![];
+    for (i, model) in models.iter().enumerate() {
+        match runs.last_mut() {
+            Some((end, offsets)) if *offsets == model.uniform_offsets.as_slice() => *end = i + 1,
+            _ => runs.push((i + 1, &model.uniform_offsets)),
+        }
+    }
+    runs
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DepthOrder {
+    FrontToBack,
+    BackToFront,
+}
+
+fn model_depth<S: Shader>(shader: &S, input: &S::Input) -> f32 {
+    shader.model_transform(input).map_or(0.0, |transform| transform[(2, 3)])
+}
+
+fn sort_by_depth<I>(models: &mut [Model<I>], depth: impl Fn(&I) -> f32, order: DepthOrder) {
+    models.sort_by(|a, b| {
+        let ordering = depth(&a.input).total_cmp(&depth(&b.input));
+        match order {
+            DepthOrder::FrontToBack => ordering,
+            DepthOrder::BackToFront => ordering.reverse(),
+        }
+    });
+}
+
+/// Checks that the depth output of `batch` can be used with its color outputs and material.
+fn depth_view<'r, S: Shader>(resources: &'r DeviceResources, batch: &Batch<S>, depth: Option<Handle<Texture>>) -> &'r wgpu::TextureView {
+    let handle = depth.expect("material with depth testing requires a batch depth output");
+    let texture = resources.textures.get(handle).expect("depth texture");
+    assert_eq!(texture.format(), DEPTH_FORMAT, "batch depth output must use the depth format");
+    assert_eq!(texture.sample_count(), batch.material.sample_count(), "batch depth output sample count must match the material");
+    if let Some(output) = batch.outputs.first() {
+        let output = resources.textures.get(*output).expect("output texture");
+        assert_eq!(texture.size(), output.size(), "batch depth output size must match the color outputs");
+    }
+    &texture.view
+}
+
+fn depth_attachment(view: &wgpu::TextureView, load: wgpu::LoadOp<f32>) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+    wgpu::RenderPassDepthStencilAttachment {
+        view,
+        depth_ops: Some(wgpu::Operations { load, store: true }),
+        stencil_ops: None,
     }
 }
 
 pub struct Model<I> {
     pub geometry: Handle<Geometry>,
     pub input: I,
+    /// More detailed alternatives to `geometry`, see [Batch::lod_view].
+    pub lods: Vec<Lod>,
+    /// Offsets into the dynamic uniform entries of the batch, see [Model::with_uniform_offsets].
+    pub uniform_offsets: Vec<u32>,
 }
 
 impl<I> Model<I> {
@@ -209,15 +836,74 @@ impl<I> Model<I> {
         Model {
             geometry,
             input,
+            lods: vec![],
+            uniform_offsets: vec![],
         }
     }
+
+    pub fn with_lods(self, lods: Vec<Lod>) -> Self {
+        Model { lods, ..self }
+    }
+
+    /// Draws this model with the windows of the
+    /// [dynamic uniform entries](crate::material::UniformEntryTypeDefinition::DynamicBuffer) of
+    /// its batch starting at the given byte offsets, e.g. to select a per-sprite atlas region. The
+    /// offsets are given in the order of the batch's uniforms and their entries, and must be
+    /// multiples of the device's uniform offset alignment, usually 256. Entries without an offset
+    /// start at `0`.
+    pub fn with_uniform_offsets(self, offsets: Vec<u32>) -> Self {
+        Model { uniform_offsets: offsets, ..self }
+    }
+}
+
+/// Per-instance data of an [instanced](Batch::instanced) draw, read by the shader through its
+/// [instance attributes](crate::shader::ShaderDefinition::instance_attributes).
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+#[repr(C)]
+pub struct InstanceData {
+    /// Model transform of the instance, in column major order.
+    pub transform: [[f32; 4]; 4],
+    pub color: Color,
+}
+
+impl InstanceData {
+    pub fn new(transform: Matrix4<f32>, color: Color) -> Self {
+        InstanceData { transform: transform.into(), color }
+    }
+
+    /// Instance attributes matching the layout of [InstanceData]: the four columns of the
+    /// transform, followed by the color.
+    pub fn attributes() -> Vec<AttributeDefinition> {
+        (0..4).map(AttributeSemantics::TransformColumn)
+            .chain([AttributeSemantics::InstanceColor])
+            .map(|semantics| AttributeDefinition {
+                typ: AttributeType::Float32(4),
+                semantics,
+                name: None,
+            })
+            .collect()
+    }
+}
+
+impl Default for InstanceData {
+    fn default() -> Self {
+        InstanceData::new(Matrix4::identity(), Color::WHITE)
+    }
 }
 
 pub struct Batch<'a, S: Shader> {
     material: &'a Material<S>,
     uniforms: Vec<&'a UniformInstance>,
     models: Vec<Model<S::Input>>,
+    /// Geometries drawn once for each of their instances, see [Batch::instanced].
+    instanced: Vec<(Handle<Geometry>, Vec<InstanceData>)>,
+    outputs: Vec<Handle<Texture>>,
     clear: Option<Color>,
+    blend_constant: Option<Color>,
+    view: Option<Matrix4<f32>>,
+    depth: Option<Handle<Texture>>,
+    clear_depth: Option<f32>,
+    uniform_offsets: Vec<u32>,
 }
 
 impl<'a, S: Shader> Batch<'a, S> {
@@ -226,7 +912,14 @@ impl<'a, S: Shader> Batch<'a, S> {
             material,
             uniforms,
             models: vec![],
+            instanced: vec![],
+            outputs: vec![],
             clear: None,
+            blend_constant: None,
+            view: None,
+            depth: None,
+            clear_depth: None,
+            uniform_offsets: vec![],
         }
     }
 
@@ -239,7 +932,1289 @@ impl<'a, S: Shader> Batch<'a, S> {
         self.models.extend(iter);
     }
 
+    /// Draws `geometry` once for every instance in a single draw call. Unlike models, the
+    /// geometry is uploaded as is rather than passed through the shader on the CPU, so it must
+    /// have the vertex format of the shader, which positions and colors it with the
+    /// [instance attributes](crate::shader::ShaderDefinition::instance_attributes) instead.
+    /// Instanced geometry is drawn after the models of the batch, with the uniform offsets of the
+    /// batch. Models drawn with an instanced material see a single [default](InstanceData::default)
+    /// instance.
+    pub fn instanced(&mut self, geometry: Handle<Geometry>, instances: Vec<InstanceData>) {
+        assert!(self.material.is_instanced(), "instanced draws require a material with instance attributes");
+        if !instances.is_empty() {
+            self.instanced.push((geometry, instances));
+        }
+    }
+
+    /// Creates a batch with a model for every entity matched by `view`, built by `model` from the
+    /// entity and its components.
+    pub fn from_view<'w, 'v, B, F>(material: &'a Material<S>, uniforms: Vec<&'a UniformInstance>, view: &'v View<'w, B>, model: F) -> Self
+        where 'w: 'v,
+              B: ReadOnlyBounds,
+              F: FnMut(EntityId, B::Result<'v, ()>) -> Model<S::Input> {
+        let mut batch = Batch::new(material, uniforms);
+        batch.models_from_view(view, model);
+        batch
+    }
+
+    /// Adds a model for every entity matched by `view`, see [Batch::from_view].
+    pub fn models_from_view<'w, 'v, B, F>(&mut self, view: &'v View<'w, B>, mut model: F)
+        where 'w: 'v,
+              B: ReadOnlyBounds,
+              F: FnMut(EntityId, B::Result<'v, ()>) -> Model<S::Input> {
+        self.models.extend(view.iter().map(|(entity, components)| model(entity, components)));
+    }
+
     pub fn clear(&mut self, color: Color) {
         self.clear = Some(color);
     }
+
+    /// Renders this batch into the given textures instead of the drawer's frame. One texture is
+    /// needed for each of the material's fragment targets, in output location order.
+    pub fn output(&mut self, textures: Vec<Handle<Texture>>) {
+        self.outputs = textures;
+    }
+
+    /// Sets the depth texture tested against by materials with depth testing. It must use the
+    /// [DEPTH_FORMAT] and match the size and sample count of the color outputs. Batches drawing to
+    /// the frame without one use a depth texture matching the surface, kept by the render api
+    /// across frames, so only [Batch::clear_depth] is needed there. Materials without
+    /// depth testing ignore it, so e.g. an overlay can share the outputs of a depth tested scene
+    /// and draw on top of it.
+    pub fn depth_output(&mut self, texture: Handle<Texture>) {
+        self.depth = Some(texture);
+    }
+
+    pub fn clear_depth(&mut self, depth: f32) {
+        self.clear_depth = Some(depth);
+    }
+
+    /// Sets the view transformation of the camera, enabling [Lod] selection for models whose
+    /// shader reports a [model transform](Shader::model_transform).
+    pub fn lod_view(&mut self, view: Matrix4<f32>) {
+        self.view = Some(view);
+    }
+
+    /// Sets the weight used by materials with [BlendMode::Constant](crate::shader::BlendMode::Constant)
+    /// when mixing this batch into its targets, e.g. `0.5` in every channel draws it at half
+    /// opacity.
+    pub fn with_blend_constant(&mut self, color: Color) {
+        self.blend_constant = Some(color);
+    }
+
+    /// Sets the offsets into the dynamic uniform entries of the batch for all of its models, e.g.
+    /// the region of a [UniformRing] written this frame. Offsets of a model, see
+    /// [Model::with_uniform_offsets], take precedence over these.
+    pub fn uniform_offsets(&mut self, offsets: Vec<u32>) {
+        self.uniform_offsets = offsets;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::cast_slice;
+    use nalgebra::{Matrix4, vector};
+    use ecs::world::{View, World};
+    use utils::{CompactList, delist, Handle, HList};
+
+    use crate::geometry::{Geometry, GeometryFormat};
+    use crate::material::{Material, ReloadError, UniformDefinition, UniformEntryDefinition, UniformEntryTypeDefinition, UniformVisibility};
+    use crate::postprocess::PostProcessMaterial;
+    use crate::render_api::{DepthOrder, offset_runs, sort_by_depth};
+    use crate::texture::Texture;
+    use crate::uniform::{UniformInstance, UniformInstanceEntry};
+    use crate::{Batch, BufferUsages, Color, CompareFunction, DEPTH_FORMAT, Face, FilterMode, FrontFace, InstanceData, Model, RenderApi, TextureFormat, VecBuf};
+    use crate::shader::{BlendMode, DepthDefinition, PrimitiveDefinition, Shader, ShaderDefinition, STRIP_RESTART, TargetFormat, Topology, VertexFormat};
+    use crate::testing::{headless, pixel, quad, read_texture, TestShader, TestVertex, TestVertexFormat, PASSTHROUGH_SHADER};
+
+    const COLOR_AND_ID_SHADER: &str = r"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4<f32>,
+    @location(1) id: u32,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4(position, 1.0);
+    output.color = color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> FragmentOutput {
+    var output: FragmentOutput;
+    output.color = color;
+    output.id = 7u;
+    return output;
+}
+";
+
+    #[test]
+    fn multiple_render_targets() {
+        let mut render = headless();
+        let color_target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let id_target = render.new_render_texture(8, 8, TextureFormat::R32Uint);
+        let material = render.new_material(TestShader::new(COLOR_AND_ID_SHADER).with(|definition| {
+            definition.fragment_targets = vec![
+                TargetFormat::Format(TextureFormat::Rgba8Unorm),
+                TargetFormat::Format(TextureFormat::R32Uint),
+            ];
+        }));
+        // covers the left half of the targets
+        let geometry = quad(&mut render, (-1.0, -1.0), (0.0, 1.0), 0.0);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![color_target, id_target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 0.0));
+        batch.model(Model::new(geometry, Color::new(1.0, 0.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let colors = read_texture(&render, color_target);
+        let ids = read_texture(&render, id_target);
+        let id = |x, y| u32::from_le_bytes(pixel(&ids, 8, x, y));
+
+        assert_eq!(pixel(&colors, 8, 1, 4), [255, 0, 0, 255]);
+        assert_eq!(id(1, 4), 7);
+        assert_eq!(pixel(&colors, 8, 6, 4), [0, 0, 0, 0]);
+        assert_eq!(id(6, 4), 0);
+    }
+
+    #[test]
+    fn blend_constant() {
+        let mut render = headless();
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.blend = BlendMode::Constant;
+        }));
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.with_blend_constant(Color::new(0.5, 0.5, 0.5, 0.5));
+        batch.model(Model::new(geometry, Color::WHITE));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let [r, g, b, a] = pixel(&read_texture(&render, target), 4, 2, 2);
+        for channel in [r, g, b] {
+            assert!(channel.abs_diff(128) <= 1, "expected half opacity, got {channel}");
+        }
+        assert_eq!(a, 255);
+    }
+
+    #[test]
+    fn read_back() {
+        let mut render = headless();
+        // rows of 12 bytes, padded to 256 while copying
+        let texels: Vec<u8> = (0..24).collect();
+        let texture = render.new_texture(3, 2, TextureFormat::Rgba8Unorm, &texels);
+        assert_eq!(futures::executor::block_on(render.read_texture(texture)), texels);
+
+        let vertices = [1.0f32, 2.0, 3.0];
+        let buffer = render.new_vertex_buffer(64);
+        render.get_buffer(buffer).unwrap().upload(0, cast_slice(&vertices));
+        let read = futures::executor::block_on(render.read_buffer(buffer));
+        assert_eq!(read, cast_slice::<f32, u8>(&vertices));
+    }
+
+    #[test]
+    fn alpha_blend() {
+        let mut render = headless();
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.blend = BlendMode::AlphaBlend;
+        }));
+        // overlapping in the middle column
+        let left = quad(&mut render, (-1.0, -1.0), (0.5, 1.0), 0.0);
+        let right = quad(&mut render, (-0.5, -1.0), (1.0, 1.0), 0.0);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(left, Color::new(1.0, 0.0, 0.0, 0.5)));
+        batch.model(Model::new(right, Color::new(0.0, 1.0, 0.0, 0.5)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let pixels = read_texture(&render, target);
+        let assert_near = |x, expected: [u8; 4]| {
+            let actual = pixel(&pixels, 4, x, 2);
+            assert!(actual.iter().zip(expected).all(|(a, e)| a.abs_diff(e) <= 1), "expected {expected:?} at x {x}, got {actual:?}");
+        };
+        assert_near(0, [128, 0, 0, 255]);
+        assert_near(3, [0, 128, 0, 255]);
+        // green drawn over red drawn over black
+        assert_near(2, [64, 128, 0, 255]);
+    }
+
+    /// Renders a white triangle with a diagonal edge over black into a texture with
+    /// `sample_count` samples, returning the texture and its resolved contents.
+    fn render_triangle(render: &mut RenderApi, sample_count: u32) -> (Handle<Texture>, Vec<u8>) {
+        render.set_sample_count(sample_count);
+        let target = render.new_render_texture(16, 16, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        let vertices = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 0.6]]
+            .map(|[x, y]| TestVertex { position: [x, y, 0.0], color: Color::WHITE });
+        let geometry = render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            vec![0u16, 1, 2],
+        );
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(geometry, Color::WHITE));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        (target, read_texture(render, target))
+    }
+
+    #[test]
+    fn multisampled_frame() {
+        let mut render = headless();
+        let frame = render.new_render_texture(16, 16, TextureFormat::Rgba8Unorm);
+        let mut material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        assert_eq!(render.set_sample_count(4), 4);
+        assert_eq!(material.sample_count(), 1);
+        material.rebuild(&render).unwrap();
+        assert_eq!(material.sample_count(), 4);
+
+        let vertices = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 0.6]]
+            .map(|[x, y]| TestVertex { position: [x, y, 0.0], color: Color::WHITE });
+        let geometry = render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            vec![0u16, 1, 2],
+        );
+        let mut batch = Batch::new(&material, vec![]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(geometry, Color::WHITE));
+
+        // the render texture stands in for the surface texture of a frame
+        let view = render.get_texture(frame).unwrap().texture().create_view(&Default::default());
+        let mut drawer = render.create_drawer(Some(view));
+        drawer.target_size = Some((16, 16));
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let data = read_texture(&render, frame);
+        assert!(data.chunks_exact(4).any(|pixel| pixel[0] > 0 && pixel[0] < 255), "edge pixels should be partially covered");
+        assert_eq!(pixel(&data, 16, 0, 15), [255, 255, 255, 255]);
+        assert_eq!(pixel(&data, 16, 15, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    #[should_panic(expected = "must clear it")]
+    fn multisampled_frame_load() {
+        let mut render = headless();
+        let frame = render.new_render_texture(16, 16, TextureFormat::Rgba8Unorm);
+        let single = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        assert_eq!(render.set_sample_count(4), 4);
+        let multi = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let mut background = Batch::new(&single, vec![]);
+        background.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        background.model(Model::new(geometry, Color::WHITE));
+        // would load the multisampled texture, which misses the background
+        let mut overlay = Batch::new(&multi, vec![]);
+        overlay.model(Model::new(geometry, Color::WHITE));
+
+        let view = render.get_texture(frame).unwrap().texture().create_view(&Default::default());
+        let mut drawer = render.create_drawer(Some(view));
+        drawer.target_size = Some((16, 16));
+        drawer.submit_batch(background);
+        drawer.submit_batch(overlay);
+    }
+
+    #[test]
+    fn unsupported_sample_count() {
+        let mut render = headless();
+        let applied = render.set_sample_count(16);
+        assert!(applied < 16, "{applied}");
+        assert_eq!(render.sample_count(), applied);
+    }
+
+    const INSTANCED_SHADER: &str = r"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) transform_0: vec4<f32>,
+    @location(3) transform_1: vec4<f32>,
+    @location(4) transform_2: vec4<f32>,
+    @location(5) transform_3: vec4<f32>,
+    @location(6) instance_color: vec4<f32>,
+) -> VertexOutput {
+    let transform = mat4x4(transform_0, transform_1, transform_2, transform_3);
+    var output: VertexOutput;
+    output.position = transform * vec4(position, 1.0);
+    output.color = color * instance_color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color;
+}
+";
+
+    #[test]
+    fn instanced_geometry() {
+        let mut render = headless();
+        let material = render.new_material(TestShader::new(INSTANCED_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.instance_attributes = InstanceData::attributes();
+            definition.attribute_locations.extend((2..7).map(|location| {
+                let name = ["transform_0", "transform_1", "transform_2", "transform_3", "instance_color"][location - 2];
+                (name.to_owned(), location as u32)
+            }));
+        }));
+        assert!(material.is_instanced());
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        // a quad covering the top left quarter, moved to the other quarters by the instances
+        let square = quad(&mut render, (-1.0, 0.0), (0.0, 1.0), 0.0);
+        let bottom_left = quad(&mut render, (-1.0, -1.0), (0.0, 0.0), 0.0);
+        let translation = |x: f32, y: f32| Matrix4::new_translation(&vector![x, y, 0.0]);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        // models see the default instance, keeping their geometry and color as is
+        batch.model(Model::new(bottom_left, Color::new(1.0, 1.0, 0.0, 1.0)));
+        batch.instanced(square, vec![
+            InstanceData::new(translation(0.0, 0.0), Color::new(1.0, 0.0, 0.0, 1.0)),
+            InstanceData::new(translation(1.0, 0.0), Color::new(0.0, 1.0, 0.0, 1.0)),
+            InstanceData::new(translation(1.0, -1.0), Color::new(0.0, 0.0, 1.0, 1.0)),
+        ]);
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let data = read_texture(&render, target);
+        assert_eq!(pixel(&data, 8, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&data, 8, 6, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&data, 8, 6, 6), [0, 0, 255, 255]);
+        assert_eq!(pixel(&data, 8, 1, 6), [255, 255, 0, 255]);
+    }
+
+    /// Like [PASSTHROUGH_SHADER], but ignores the vertex color and draws blue.
+    const BLUE_SHADER: &str = r"
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> @builtin(position) vec4<f32> {
+    return vec4(input.position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4(0.0, 0.0, 1.0, 1.0);
+}
+";
+
+    fn rgba_target(definition: &mut ShaderDefinition) {
+        definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+    }
+
+    #[test]
+    fn reload_material() {
+        let mut render = headless();
+        let mut material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+        let draw = |render: &mut RenderApi, material: &Material<TestShader>| {
+            let mut batch = Batch::new(material, vec![]);
+            batch.output(vec![target]);
+            batch.model(Model::new(geometry, Color::new(1.0, 0.0, 0.0, 1.0)));
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish();
+            pixel(&read_texture(render, target), 4, 1, 1)
+        };
+
+        let broken = material.reload(&render, TestShader::new("fn fs_main( {").with(rgba_target));
+        assert!(broken.is_err());
+        assert_eq!(draw(&mut render, &material), [255, 0, 0, 255]);
+        // the broken module isn't kept around
+        assert_eq!(render.resources.shader_modules.borrow().keys().collect::<Vec<_>>(), vec![PASSTHROUGH_SHADER]);
+
+        material.reload(&render, TestShader::new(BLUE_SHADER).with(rgba_target)).unwrap();
+        assert_eq!(draw(&mut render, &material), [0, 0, 255, 255]);
+        // neither is the replaced one
+        assert_eq!(render.resources.shader_modules.borrow().keys().collect::<Vec<_>>(), vec![BLUE_SHADER]);
+    }
+
+    #[test]
+    fn reload_sample_count() {
+        let mut render = headless();
+        assert_eq!(render.set_sample_count(4), 4);
+        let single_sampled = |definition: &mut ShaderDefinition| {
+            rgba_target(definition);
+            definition.multisample.sample_count = Some(1);
+        };
+        let mut material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(single_sampled));
+        assert_eq!(material.sample_count(), 1);
+
+        let invalid = material.reload(&render, TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            rgba_target(definition);
+            definition.multisample.sample_count = Some(3);
+        }));
+        assert!(matches!(invalid, Err(ReloadError::InvalidSampleCount(3))));
+        assert_eq!(material.sample_count(), 1);
+
+        // without the override, the material falls back to the render api rather than keeping 1
+        material.reload(&render, TestShader::new(PASSTHROUGH_SHADER).with(rgba_target)).unwrap();
+        assert_eq!(material.sample_count(), 4);
+    }
+
+    #[test]
+    fn reload_unknown_attribute() {
+        let mut render = headless();
+        let mut material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        let missing = material.reload(&render, TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            rgba_target(definition);
+            definition.attribute_locations.remove("color");
+        }));
+        assert!(matches!(missing, Err(ReloadError::UnknownAttribute(name)) if name == "color"));
+    }
+
+    #[test]
+    fn face_culling() {
+        let mut render = headless();
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        // the quad winds counter-clockwise, facing front by default
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+        let mut draw = |configure: fn(&mut ShaderDefinition)| {
+            let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(configure));
+            let mut batch = Batch::new(&material, vec![]);
+            batch.output(vec![target]);
+            batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+            batch.model(Model::new(geometry, Color::WHITE));
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish();
+            pixel(&read_texture(&render, target), 4, 2, 2)
+        };
+        assert_eq!(draw(|definition| {
+            rgba_target(definition);
+            definition.primitive.cull_mode = Some(Face::Back);
+        }), [255, 255, 255, 255]);
+        assert_eq!(draw(|definition| {
+            rgba_target(definition);
+            definition.primitive.cull_mode = Some(Face::Front);
+        }), [0, 0, 0, 255]);
+        assert_eq!(draw(|definition| {
+            rgba_target(definition);
+            definition.primitive = PrimitiveDefinition { front_face: FrontFace::Cw, cull_mode: Some(Face::Back) };
+        }), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn index_overflow() {
+        let mut render = headless();
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        let corner = quad(&mut render, (-1.0, -1.0), (-0.9, -0.9), 0.0);
+        let vertices = [[-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0], [1.0, 1.0]]
+            .map(|[x, y]| TestVertex { position: [x, y, 0.0], color: Color::WHITE });
+        let full = render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            vec![0u32, 1, 2, 2, 1, 3],
+        );
+
+        // the corner quads use up the 16 bit indices, so the red quad drawn last needs 32 bit ones
+        let mut models: Vec<_> = (0..16384).map(|_| Model::new(corner, Color::WHITE)).collect();
+        models.push(Model::new(full, Color::new(1.0, 0.0, 0.0, 1.0)));
+        let counter = material.cache_models(&render.device, &render.resources, &models, None);
+        assert_eq!(counter.vertices, 65540);
+        assert_eq!(material.cache().index_format, wgpu::IndexFormat::Uint32);
+        let counter = material.cache_models(&render.device, &render.resources, &models[16000..], None);
+        assert_eq!(counter.vertices, 1540);
+        assert_eq!(material.cache().index_format, wgpu::IndexFormat::Uint16);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.models(models);
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+        assert_eq!(pixel(&read_texture(&render, target), 4, 2, 2), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn reused_indices() {
+        let mut render = headless();
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(rgba_target));
+        let left = quad(&mut render, (-1.0, -1.0), (0.0, 1.0), 0.0);
+        let right = quad(&mut render, (0.0, -1.0), (1.0, 1.0), 0.0);
+        let cache = |geometries: &[Handle<Geometry>], color: Color| {
+            let models: Vec<_> = geometries.iter().map(|geometry| Model::new(*geometry, color)).collect();
+            material.cache_models(&render.device, &render.resources, &models, None)
+        };
+
+        assert!(!cache(&[left, right], Color::WHITE).reused_indices);
+        // only the model inputs changed, which the indices don't depend on
+        let counter = cache(&[left, right], Color::new(1.0, 0.0, 0.0, 1.0));
+        assert!(counter.reused_indices);
+        assert_eq!(counter.indices, 12);
+        assert!(!cache(&[right, left], Color::WHITE).reused_indices);
+        assert!(!cache(&[right], Color::WHITE).reused_indices);
+    }
+
+    #[test]
+    fn multisampled_render_texture() {
+        let mut render = headless();
+        let partial = |data: &[u8]| data.chunks_exact(4).filter(|pixel| pixel[0] > 0 && pixel[0] < 255).count();
+
+        let (_, aliased) = render_triangle(&mut render, 1);
+        assert_eq!(partial(&aliased), 0);
+
+        let (target, smooth) = render_triangle(&mut render, 4);
+        assert_eq!(render.get_texture(target).unwrap().sample_count(), 4);
+        assert!(partial(&smooth) > 0, "edge pixels should be partially covered");
+        assert_eq!(pixel(&smooth, 16, 0, 15), [255, 255, 255, 255]);
+        assert_eq!(pixel(&smooth, 16, 15, 0), [0, 0, 0, 255]);
+
+        // post-processing samples the resolved texture
+        render.set_sample_count(1);
+        let output = render.new_render_texture(16, 16, TextureFormat::Rgba8Unorm);
+        let identity = PostProcessMaterial::new(&render.device, TextureFormat::Rgba8Unorm, Default::default());
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_postprocess(target, &identity, Some(output));
+        drawer.finish();
+        assert_eq!(read_texture(&render, output), smooth);
+    }
+
+    #[test]
+    fn gpu_timing() {
+        let mut render = headless();
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let draw = |render: &mut RenderApi| {
+            let mut batch = Batch::new(&material, vec![]);
+            batch.output(vec![target]);
+            batch.model(Model::new(geometry, Color::WHITE));
+
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish()
+        };
+
+        let stats = draw(&mut render);
+        assert_eq!(stats.batches.len(), 1);
+        assert_eq!(stats.batches[0].gpu_time_ns, None);
+
+        render.set_gpu_timing(true);
+        let stats = draw(&mut render);
+        if render.device.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            assert!(stats.batches[0].gpu_time_ns.unwrap() > 0);
+        } else {
+            assert_eq!(stats.batches[0].gpu_time_ns, None);
+        }
+    }
+
+    /// Creates two strips spanning `bottom` to `top`, one over the left and one over the right
+    /// quarter of clip space, separated by a restart.
+    fn split_strips(render: &mut RenderApi, bottom: f32, top: f32) -> Handle<Geometry> {
+        let vertices = [(-1.0, -0.5), (0.5, 1.0)]
+            .into_iter()
+            .flat_map(|(left, right)| [(left, bottom), (right, bottom), (left, top), (right, top)])
+            .map(|(x, y)| TestVertex { position: [x, y, 0.0], color: Color::WHITE })
+            .collect::<Vec<_>>();
+        render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            vec![0, 1, 2, 3, STRIP_RESTART, 4, 5, 6, 7],
+        )
+    }
+
+    #[test]
+    fn strip_restart() {
+        let mut render = headless();
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.topology = Topology::TriangleStrip;
+        }));
+        let top = split_strips(&mut render, 0.0, 1.0);
+        let bottom = split_strips(&mut render, -1.0, 0.0);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(top, Color::new(0.0, 1.0, 0.0, 1.0)));
+        batch.model(Model::new(bottom, Color::new(0.0, 1.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        // neither the parts of a geometry nor consecutive models are joined across the middle
+        let data = read_texture(&render, target);
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if (2..6).contains(&x) { [0, 0, 0, 255] } else { [0, 255, 0, 255] };
+                assert_eq!(pixel(&data, 8, x, y), expected, "pixel {x}, {y}");
+            }
+        }
+    }
+
+    #[test]
+    fn strip_winding() {
+        let mut render = headless();
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.topology = Topology::TriangleStrip;
+            definition.primitive.cull_mode = Some(Face::Back);
+        }));
+        // strips of a single counter-clockwise triangle in the lower left of each quarter
+        let mut triangles = |bottom: f32, top: f32| {
+            let vertices = [(-1.0, -0.5), (0.5, 1.0)]
+                .into_iter()
+                .flat_map(|(left, right)| [(left, bottom), (right, bottom), (left, top)])
+                .map(|(x, y)| TestVertex { position: [x, y, 0.0], color: Color::WHITE })
+                .collect::<Vec<_>>();
+            render.new_geometry(
+                cast_slice(&vertices).to_vec(),
+                GeometryFormat::from(TestVertexFormat::describe()),
+                vec![0, 1, 2, STRIP_RESTART, 3, 4, 5],
+            )
+        };
+        let top = triangles(0.0, 1.0);
+        let bottom = triangles(-1.0, 0.0);
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(top, Color::new(0.0, 1.0, 0.0, 1.0)));
+        batch.model(Model::new(bottom, Color::new(0.0, 1.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        // strips following an odd number of indices keep their winding, so none are culled
+        let data = read_texture(&render, target);
+        for (x, y) in [(0, 3), (6, 3), (0, 7), (6, 7)] {
+            assert_eq!(pixel(&data, 8, x, y), [0, 255, 0, 255], "pixel {x}, {y}");
+        }
+    }
+
+    #[test]
+    fn list_indexes_last_16_bit_vertex() {
+        let mut render = headless();
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        let left = quad(&mut render, (-1.0, -1.0), (0.0, 1.0), 0.0);
+        // a quad over the right half made of the last four vertices 16 bit indices can address
+        let mut vertices = vec![TestVertex { position: [0.0; 3], color: Color::WHITE }; u16::MAX as usize - 3];
+        vertices.extend([(0.0, -1.0), (1.0, -1.0), (0.0, 1.0), (1.0, 1.0)]
+            .map(|(x, y)| TestVertex { position: [x, y, 0.0], color: Color::WHITE }));
+        let right = render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            vec![65532u16, 65533, 65534, 65534, 65533, 65535],
+        );
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(left, Color::new(0.0, 1.0, 0.0, 1.0)));
+        batch.model(Model::new(right, Color::new(0.0, 1.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        // the largest 16 bit index only restarts strips, in a list it is a vertex like any other
+        let data = read_texture(&render, target);
+        for (x, y) in [(1, 4), (6, 4)] {
+            assert_eq!(pixel(&data, 8, x, y), [0, 255, 0, 255], "pixel {x}, {y}");
+        }
+    }
+
+    #[test]
+    fn points() {
+        let mut render = headless();
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.topology = Topology::Points { size: 1.0 };
+        }));
+
+        let positions = [(1, 1), (6, 2), (3, 5)];
+        // pixel centers in clip space, no indices needed
+        let vertices = positions.map(|(x, y)| TestVertex {
+            position: [(x as f32 + 0.5) / 4.0 - 1.0, 1.0 - (y as f32 + 0.5) / 4.0, 0.0],
+            color: Color::WHITE,
+        });
+        let geometry = render.new_geometry(
+            cast_slice(&vertices).to_vec(),
+            GeometryFormat::from(TestVertexFormat::describe()),
+            Vec::<u16>::new(),
+        );
+
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(geometry, Color::new(0.0, 1.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let data = read_texture(&render, target);
+        let lit: Vec<_> = (0..8).flat_map(|y| (0..8).map(move |x| (x, y)))
+            .filter(|(x, y)| pixel(&data, 8, *x, *y) == [0, 255, 0, 255])
+            .collect();
+        let mut expected = positions.to_vec();
+        expected.sort_by_key(|(x, y)| (*y, *x));
+        assert_eq!(lit, expected);
+    }
+
+    #[test]
+    fn batch_from_view() {
+        let mut render = headless();
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER));
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let mut world = World::default().with_component::<Color>().with_component::<f32>();
+        for (i, color) in [Color::WHITE, Color::new(1.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0)].into_iter().enumerate() {
+            let entity = world.new_entity();
+            world.components_mut::<Color>().put(entity, color);
+            if i != 1 {
+                world.components_mut::<f32>().put(entity, 0.5);
+            }
+        }
+        let view = View::builder()
+            .required::<Color>()
+            .optional::<f32>()
+            .build(&world);
+        let model = |_, delist!(color, alpha): HList!(&Color, Option<&f32>)| {
+            Model::new(geometry, Color { a: alpha.copied().unwrap_or(1.0), ..*color })
+        };
+
+        let mut manual = Batch::new(&material, vec![]);
+        manual.models(view.iter().map(|(entity, components)| model(entity, components)).collect::<Vec<_>>());
+        let batch = Batch::from_view(&material, vec![], &view, model);
+
+        let inputs = |batch: &Batch<TestShader>| batch.models.iter()
+            .map(|model| (model.geometry, [model.input.r, model.input.g, model.input.b, model.input.a]))
+            .collect::<Vec<_>>();
+        assert_eq!(inputs(&batch).len(), 3);
+        assert_eq!(inputs(&batch), inputs(&manual));
+    }
+
+    const TINT_SHADER: &str = r"
+@group(0) @binding(0) var<uniform> tint: vec4<f32>;
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4(position, 1.0);
+    output.color = color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color * tint;
+}
+";
+
+    #[test]
+    fn fragment_uniform() {
+        let mut render = headless();
+        render.register_uniform("tint", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::Buffer,
+            }],
+        });
+        let buffer = render.new_uniform_buffer(16);
+        render.get_buffer(buffer).unwrap().upload(0, cast_slice(&[0.0f32, 1.0, 0.0, 1.0]));
+        let mut tint = render.instantiate_uniform("tint", vec![Some(UniformInstanceEntry::Buffer(buffer.into()))]);
+        let material = render.new_material(TestShader::new(TINT_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.uniforms = vec!["tint".to_owned()];
+        }));
+        let target = render.new_render_texture(2, 2, TextureFormat::Rgba8Unorm);
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let draw = |render: &mut RenderApi, tint: &UniformInstance| {
+            let mut batch = Batch::new(&material, vec![tint]);
+            batch.output(vec![target]);
+            batch.model(Model::new(geometry, Color::WHITE));
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish();
+            pixel(&read_texture(render, target), 2, 1, 1)
+        };
+        assert_eq!(draw(&mut render, &tint), [0, 255, 0, 255]);
+
+        // a fresh buffer has the same version as the one it replaces
+        let red = render.new_uniform_buffer(16);
+        render.get_buffer(red).unwrap().upload(0, cast_slice(&[1.0f32, 0.0, 0.0, 1.0]));
+        tint.set_entry(0, UniformInstanceEntry::Buffer(red.into()));
+        assert_eq!(draw(&mut render, &tint), [255, 0, 0, 255]);
+    }
+
+    const TEXTURE_SHADER: &str = r"
+@group(0) @binding(0) var image: texture_2d<f32>;
+@group(0) @binding(1) var image_sampler: sampler;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) color: vec4<f32>) -> @builtin(position) vec4<f32> {
+    return vec4(position, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureSample(image, image_sampler, position.xy / 2.0);
+}
+";
+
+    #[test]
+    fn texture_uniform() {
+        let mut render = headless();
+        render.register_uniform("image", UniformDefinition {
+            entries: vec![
+                UniformEntryDefinition {
+                    visibility: UniformVisibility::Fragment,
+                    typ: UniformEntryTypeDefinition::Texture,
+                },
+                UniformEntryDefinition {
+                    visibility: UniformVisibility::Fragment,
+                    typ: UniformEntryTypeDefinition::Sampler,
+                },
+            ],
+        });
+        let checker = render.new_texture(2, 2, TextureFormat::Rgba8Unorm, &[
+            255, 0, 0, 255, 0, 255, 0, 255,
+            0, 0, 255, 255, 255, 255, 255, 255,
+        ]);
+        let sampler = render.new_sampler(FilterMode::Nearest);
+        let mut image = render.instantiate_uniform("image", vec![
+            Some(UniformInstanceEntry::Texture(checker)),
+            Some(UniformInstanceEntry::Sampler(sampler.into())),
+        ]);
+        let material = render.new_material(TestShader::new(TEXTURE_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.uniforms = vec!["image".to_owned()];
+        }));
+        let target = render.new_render_texture(2, 2, TextureFormat::Rgba8Unorm);
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let draw = |render: &mut RenderApi, image: &UniformInstance| {
+            let mut batch = Batch::new(&material, vec![image]);
+            batch.output(vec![target]);
+            batch.model(Model::new(geometry, Color::WHITE));
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish();
+            read_texture(render, target)
+        };
+        let data = draw(&mut render, &image);
+        assert_eq!(pixel(&data, 2, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&data, 2, 1, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel(&data, 2, 0, 1), [0, 0, 255, 255]);
+
+        // rebinding the texture is picked up by the cached bind group
+        let black = render.new_texture(1, 1, TextureFormat::Rgba8Unorm, &[0, 0, 0, 255]);
+        image.set_entry(0, UniformInstanceEntry::Texture(black));
+        assert_eq!(pixel(&draw(&mut render, &image), 2, 1, 0), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn shared_shader_modules() {
+        let mut render = headless();
+        render.register_uniform("tint", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::Buffer,
+            }],
+        });
+        let first = render.new_material(TestShader::new(PASSTHROUGH_SHADER));
+        let second = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+        assert_eq!(render.resources.shader_modules.borrow().len(), 1);
+
+        let tinted = render.new_material(TestShader::new(TINT_SHADER).with(|definition| {
+            definition.uniforms = vec!["tint".to_owned()];
+        }));
+        assert_eq!(render.resources.shader_modules.borrow().len(), 2);
+        drop((first, second, tinted));
+    }
+
+    #[test]
+    fn typed_buffers() {
+        let mut render = headless();
+        let vertices = render.new_vertex_buffer(16);
+        let indices = render.new_index_buffer(16);
+        let uniforms = render.new_uniform_buffer(16);
+
+        let usage = |render: &mut RenderApi, buffer: Handle<VecBuf>| render.get_buffer(buffer).unwrap().usage();
+        assert_eq!(usage(&mut render, vertices.cast()), BufferUsages::VERTEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC);
+        assert_eq!(usage(&mut render, indices.cast()), BufferUsages::INDEX | BufferUsages::COPY_DST | BufferUsages::COPY_SRC);
+        assert_eq!(usage(&mut render, uniforms.cast()), BufferUsages::UNIFORM | BufferUsages::COPY_DST | BufferUsages::COPY_SRC);
+
+        render.get_buffer(uniforms).unwrap().upload(0, cast_slice(&[1.0f32, 0.0, 0.0, 1.0]));
+        assert_eq!(render.get_buffer(uniforms).unwrap().len(), 16);
+
+        render.register_uniform("tint", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::Buffer,
+            }],
+        });
+        render.instantiate_uniform("tint", vec![Some(UniformInstanceEntry::Buffer(uniforms.into()))]);
+    }
+
+    #[test]
+    fn uniform_offsets() {
+        let mut render = headless();
+        render.register_uniform("tint", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::DynamicBuffer { size: 16 },
+            }],
+        });
+        // red at offset 0, green at the next aligned offset
+        let alignment = render.device.device.limits().min_uniform_buffer_offset_alignment as usize;
+        let mut tints = vec![0; alignment + 16];
+        tints[..16].copy_from_slice(cast_slice(&[1.0f32, 0.0, 0.0, 1.0]));
+        tints[alignment..].copy_from_slice(cast_slice(&[0.0f32, 1.0, 0.0, 1.0]));
+        let buffer = render.new_buffer(tints.len(), BufferUsages::UNIFORM | BufferUsages::COPY_DST);
+        render.get_buffer(buffer).unwrap().upload(0, &tints);
+        let tint = render.instantiate_uniform("tint", vec![Some(UniformInstanceEntry::Buffer(buffer.into()))]);
+
+        let target = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(TINT_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.uniforms = vec!["tint".to_owned()];
+        }));
+        let top_left = quad(&mut render, (-1.0, 0.0), (0.0, 1.0), 0.0);
+        let bottom_left = quad(&mut render, (-1.0, -1.0), (0.0, 0.0), 0.0);
+        let right = quad(&mut render, (0.0, -1.0), (1.0, 1.0), 0.0);
+
+        let mut batch = Batch::new(&material, vec![&tint]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        batch.model(Model::new(top_left, Color::WHITE));
+        batch.model(Model::new(bottom_left, Color::WHITE).with_uniform_offsets(vec![0]));
+        batch.model(Model::new(right, Color::WHITE).with_uniform_offsets(vec![alignment as u32]));
+
+        // the first two models share the default offset, and are drawn together
+        let runs: Vec<_> = offset_runs(&batch.models).into_iter()
+            .map(|(end, offsets)| (end, offsets.to_vec()))
+            .collect();
+        assert_eq!(runs, vec![(1, vec![]), (2, vec![0]), (3, vec![alignment as u32])]);
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let data = read_texture(&render, target);
+        assert_eq!(pixel(&data, 8, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&data, 8, 1, 6), [255, 0, 0, 255]);
+        assert_eq!(pixel(&data, 8, 6, 1), [0, 255, 0, 255]);
+        assert_eq!(pixel(&data, 8, 6, 6), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn uniform_ring() {
+        let mut render = headless();
+        render.register_uniform("tint", UniformDefinition {
+            entries: vec![UniformEntryDefinition {
+                visibility: UniformVisibility::Fragment,
+                typ: UniformEntryTypeDefinition::DynamicBuffer { size: 16 },
+            }],
+        });
+        let mut ring = render.new_uniform_ring(16, 3);
+        let tint = render.instantiate_uniform("tint", vec![Some(UniformInstanceEntry::Buffer(ring.buffer().into()))]);
+        let target = render.new_render_texture(4, 4, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(TINT_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.uniforms = vec!["tint".to_owned()];
+        }));
+        let geometry = quad(&mut render, (-1.0, -1.0), (1.0, 1.0), 0.0);
+
+        let tints = [[1.0f32, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 1.0], [0.0, 0.0, 1.0, 1.0], [1.0, 1.0, 1.0, 1.0]];
+        let mut offsets = vec![];
+        for tint_value in tints {
+            // one frame per tint
+            let offset = ring.write(&mut render, cast_slice(&tint_value));
+            offsets.push(offset);
+
+            let mut batch = Batch::new(&material, vec![&tint]);
+            batch.output(vec![target]);
+            batch.uniform_offsets(vec![offset]);
+            batch.model(Model::new(geometry, Color::WHITE));
+            let mut drawer = render.new_offscreen_drawer();
+            drawer.submit_batch(batch);
+            drawer.finish();
+
+            let expected = tint_value.map(|channel| (channel * 255.0) as u8);
+            assert_eq!(pixel(&read_texture(&render, target), 4, 2, 2), expected);
+        }
+
+        // distinct regions until the ring wraps around
+        let alignment = render.device.device.limits().min_uniform_buffer_offset_alignment;
+        let stride = 16u32.div_ceil(alignment) * alignment;
+        assert_eq!(offsets, vec![0, stride, 2 * stride, 0]);
+    }
+
+    #[test]
+    fn fade_previous() {
+        let mut render = headless();
+        let target = render.new_render_texture(8, 1, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+        }));
+
+        // a white quad moving right by a quarter of the target each frame
+        for frame in 0..3 {
+            let left = -1.0 + frame as f32 * 0.5;
+            let geometry = quad(&mut render, (left, -1.0), (left + 0.5, 1.0), 0.0);
+            let mut batch = Batch::new(&material, vec![]);
+            batch.output(vec![target]);
+            batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+            batch.model(Model::new(geometry, Color::WHITE));
+
+            let mut drawer = render.new_offscreen_drawer();
+            if frame > 0 {
+                drawer.fade_previous(0.5);
+            }
+            drawer.submit_batch(batch);
+            drawer.finish();
+        }
+
+        let pixels = read_texture(&render, target);
+        let brightness: Vec<_> = (0..8).map(|x| pixel(&pixels, 8, x, 0)[0]).collect();
+        // faded twice, faded once, drawn this frame, never drawn
+        for (x, expected) in [(0, 64), (2, 128), (4, 255), (6, 0)] {
+            assert!(brightness[x].abs_diff(expected) <= 1, "{brightness:?}");
+        }
+    }
+
+    /// Draws a near green quad over the left half and a far red quad over everything, near first.
+    fn overlapping_quads<S: Shader<Input=Color>>(render: &mut RenderApi, batch: &mut Batch<S>) {
+        let near = quad(render, (-1.0, -1.0), (0.0, 1.0), 0.2);
+        let far = quad(render, (-1.0, -1.0), (1.0, 1.0), 0.5);
+        batch.model(Model::new(near, Color::new(0.0, 1.0, 0.0, 1.0)));
+        batch.model(Model::new(far, Color::new(1.0, 0.0, 0.0, 1.0)));
+    }
+
+    #[test]
+    fn depth_prepass() {
+        let mut render = headless();
+        let depth_less = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.depth = Some(DepthDefinition::default());
+        }));
+        let depth_equal = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.depth = Some(DepthDefinition { compare: CompareFunction::Equal, write: false, ..Default::default() });
+        }));
+        let targets = [(); 2].map(|_| (
+            render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm),
+            render.new_render_texture(8, 8, DEPTH_FORMAT),
+        ));
+
+        // regular depth tested pass
+        let mut reference = Batch::new(&depth_less, vec![]);
+        reference.output(vec![targets[0].0]);
+        reference.depth_output(targets[0].1);
+        reference.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        reference.clear_depth(1.0);
+        overlapping_quads(&mut render, &mut reference);
+
+        // prepass followed by a color pass only shading the nearest fragments
+        let mut prepassed = Batch::new(&depth_equal, vec![]);
+        prepassed.output(vec![targets[1].0]);
+        prepassed.depth_output(targets[1].1);
+        prepassed.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        overlapping_quads(&mut render, &mut prepassed);
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(reference);
+        drawer.depth_prepass(&prepassed);
+        drawer.submit_batch(prepassed);
+        drawer.finish();
+
+        let reference = read_texture(&render, targets[0].0);
+        let prepassed = read_texture(&render, targets[1].0);
+        assert_eq!(pixel(&reference, 8, 1, 4), [0, 255, 0, 255]);
+        assert_eq!(pixel(&reference, 8, 6, 4), [255, 0, 0, 255]);
+        assert_eq!(reference, prepassed);
+    }
+
+    #[test]
+    fn surface_depth() {
+        let mut render = headless();
+        let RenderApi { device, resources, .. } = &mut render;
+        let depth = resources.surface_depth(device, (8, 8), 1);
+        assert_eq!(resources.surface_depth(device, (8, 8), 1), depth);
+
+        // a resized surface gets a new depth texture, replacing the old one
+        let resized = resources.surface_depth(device, (16, 8), 1);
+        assert!(resources.textures.get(depth).is_none());
+        let texture = resources.textures.get(resized).unwrap();
+        assert_eq!(texture.size(), (16, 8));
+        assert_eq!(texture.format(), DEPTH_FORMAT);
+    }
+
+    #[test]
+    fn overlay_without_depth() {
+        let mut render = headless();
+        let world = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.depth = Some(DepthDefinition::default());
+        }));
+        let color = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let depth = render.new_render_texture(8, 8, DEPTH_FORMAT);
+        // other targets being multisampled doesn't affect the single sampled overlay
+        render.set_sample_count(4);
+        let overlay = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.multisample.sample_count = Some(1);
+        }));
+        assert_eq!(overlay.sample_count(), 1);
+
+        let mut world_batch = Batch::new(&world, vec![]);
+        world_batch.output(vec![color]);
+        world_batch.depth_output(depth);
+        world_batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        world_batch.clear_depth(1.0);
+        overlapping_quads(&mut render, &mut world_batch);
+
+        // behind the world, but drawn over it as the overlay doesn't test depth
+        let mut overlay_batch = Batch::new(&overlay, vec![]);
+        overlay_batch.output(vec![color]);
+        overlay_batch.depth_output(depth);
+        let geometry = quad(&mut render, (0.5, -1.0), (1.0, 1.0), 0.9);
+        overlay_batch.model(Model::new(geometry, Color::new(0.0, 0.0, 1.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(world_batch);
+        drawer.submit_batch(overlay_batch);
+        drawer.finish();
+
+        let result = read_texture(&render, color);
+        assert_eq!(pixel(&result, 8, 1, 4), [0, 255, 0, 255]);
+        assert_eq!(pixel(&result, 8, 5, 4), [255, 0, 0, 255]);
+        assert_eq!(pixel(&result, 8, 7, 4), [0, 0, 255, 255]);
+    }
+
+    /// Draws a red fill and then a coplanar green outline, returning the resulting colors.
+    fn coplanar_outline(render: &mut RenderApi, outline: &Material<TestShader>) -> Vec<u8> {
+        let fill = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.depth = Some(DepthDefinition::default());
+        }));
+        let color = render.new_render_texture(8, 8, TextureFormat::Rgba8Unorm);
+        let depth = render.new_render_texture(8, 8, DEPTH_FORMAT);
+
+        let mut fill_batch = Batch::new(&fill, vec![]);
+        fill_batch.output(vec![color]);
+        fill_batch.depth_output(depth);
+        fill_batch.clear(Color::new(0.0, 0.0, 0.0, 1.0));
+        fill_batch.clear_depth(1.0);
+        let geometry = quad(render, (-1.0, -1.0), (1.0, 1.0), 0.5);
+        fill_batch.model(Model::new(geometry, Color::new(1.0, 0.0, 0.0, 1.0)));
+
+        let mut outline_batch = Batch::new(outline, vec![]);
+        outline_batch.output(vec![color]);
+        outline_batch.depth_output(depth);
+        let geometry = quad(render, (-1.0, -1.0), (1.0, 1.0), 0.5);
+        outline_batch.model(Model::new(geometry, Color::new(0.0, 1.0, 0.0, 1.0)));
+
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(fill_batch);
+        drawer.submit_batch(outline_batch);
+        drawer.finish();
+        read_texture(render, color)
+    }
+
+    #[test]
+    fn depth_bias() {
+        let mut render = headless();
+        let unbiased = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            definition.depth = Some(DepthDefinition::default());
+        }));
+        let biased = render.new_material(TestShader::new(PASSTHROUGH_SHADER).with(|definition| {
+            definition.fragment_targets = vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)];
+            // the GL backend swaps the constant and slope factors, setting both keeps this flat
+            // quad biased on every backend
+            definition.depth = Some(DepthDefinition { depth_bias: -4, slope_scale: -4.0, ..Default::default() });
+        }));
+
+        // a coplanar outline fails the less test everywhere, unless pulled towards the camera
+        let result = coplanar_outline(&mut render, &unbiased);
+        assert!(result.chunks(4).all(|pixel| pixel == [255, 0, 0, 255]));
+        let result = coplanar_outline(&mut render, &biased);
+        assert!(result.chunks(4).all(|pixel| pixel == [0, 255, 0, 255]));
+    }
+
+    #[test]
+    fn depth_sorting() {
+        let geometry = CompactList::default()
+            .add(Geometry::new(vec![], GeometryFormat::from(vec![]), Vec::<u16>::new()));
+        let depths = [0.5, -0.2, 0.9, 0.1];
+        let sorted = |order| {
+            let mut models: Vec<_> = depths.iter().map(|depth| Model::new(geometry, *depth)).collect();
+            sort_by_depth(&mut models, |depth| *depth, order);
+            models.iter().map(|model| model.input).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted(DepthOrder::FrontToBack), vec![-0.2, 0.1, 0.5, 0.9]);
+        assert_eq!(sorted(DepthOrder::BackToFront), vec![0.9, 0.5, 0.1, -0.2]);
+    }
 }
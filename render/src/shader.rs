@@ -1,14 +1,169 @@
 use std::collections::HashMap;
 
+use nalgebra::Matrix4;
+
 use crate::geometry::GeometryFormat;
 use crate::material::AttributeDefinition;
+use crate::{CompareFunction, Face, FrontFace, TextureFormat};
 
 pub struct ShaderDefinition {
     pub shader_modules: Vec<String>,
     pub vertex_shader: ShaderStage,
     pub fragment_shader: ShaderStage,
+    /// Formats of the color targets written by the fragment shader, in output location order.
+    pub fragment_targets: Vec<TargetFormat>,
     pub attribute_locations: HashMap<String, u32>,
     pub uniforms: Vec<String>,
+    pub multisample: MultisampleDefinition,
+    /// How the fragment shader output is combined with the existing contents of each target.
+    pub blend: BlendMode,
+    /// Enables depth testing against the depth output of the batch, if set.
+    pub depth: Option<DepthDefinition>,
+    /// How vertices are assembled into primitives.
+    pub topology: Topology,
+    /// Which primitives are drawn depending on their winding.
+    pub primitive: PrimitiveDefinition,
+    /// Attributes read once per instance for [instanced](crate::Batch::instanced) draws, laid out
+    /// like [InstanceData](crate::InstanceData), e.g. [InstanceData::attributes](crate::InstanceData::attributes).
+    /// Their locations are looked up in `attribute_locations` like vertex attributes. Empty for
+    /// materials that aren't drawn instanced.
+    pub instance_attributes: Vec<AttributeDefinition>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Topology {
+    /// Every three indices of the geometry form a triangle.
+    #[default]
+    Triangles,
+    /// Every index of the geometry forms a triangle with the two before it. A [STRIP_RESTART]
+    /// index, or [STRIP_RESTART_U32] for 32 bit indices, ends the strip and starts a new one, so a geometry can hold disconnected strips,
+    /// e.g. the separate strokes of a glyph. The strips of different models are never connected.
+    /// The winding of a strip following a restart is unspecified.
+    TriangleStrip,
+    /// Every vertex is drawn as a single point, ignoring the indices of the geometry.
+    Points {
+        /// Diameter of each point in pixels. WebGPU always draws points one pixel wide, so any
+        /// other size is ignored with a warning on the current backends.
+        size: f32,
+    },
+}
+
+impl Topology {
+    /// Whether geometry is drawn using its indices.
+    pub fn is_indexed(&self) -> bool {
+        matches!(self, Topology::Triangles | Topology::TriangleStrip)
+    }
+}
+
+/// Face culling options for the pipeline built from a [ShaderDefinition].
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PrimitiveDefinition {
+    /// Winding of the vertices of front facing triangles in clip space.
+    pub front_face: FrontFace,
+    /// Faces of triangles that are not drawn, both are drawn if [None].
+    pub cull_mode: Option<Face>,
+}
+
+/// Index ending the current strip of a [Topology::TriangleStrip] geometry.
+pub const STRIP_RESTART: u16 = u16::MAX;
+
+/// [STRIP_RESTART] of geometry with [32 bit](crate::geometry::Indices::Uint32) indices.
+pub const STRIP_RESTART_U32: u32 = u32::MAX;
+
+/// Depth testing options for the pipeline built from a [ShaderDefinition].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DepthDefinition {
+    /// Test a fragment must pass against the stored depth to be drawn.
+    pub compare: CompareFunction,
+    /// Whether drawn fragments replace the stored depth.
+    pub write: bool,
+    /// Constant offset added to the depth of every fragment, in units of the smallest resolvable
+    /// depth difference. Negative values pull geometry towards the camera, letting an outline win
+    /// the depth test against a coplanar fill.
+    pub depth_bias: i32,
+    /// Offset added to the depth of every fragment, scaled by the depth slope of its primitive.
+    pub slope_scale: f32,
+}
+
+impl Default for DepthDefinition {
+    fn default() -> Self {
+        DepthDefinition {
+            compare: CompareFunction::Less,
+            write: true,
+            depth_bias: 0,
+            slope_scale: 0.0,
+        }
+    }
+}
+
+/// Multisampling options for the pipeline built from a [ShaderDefinition].
+#[derive(Default)]
+pub struct MultisampleDefinition {
+    /// Derives the sample coverage mask from the alpha output of the fragment shader, giving
+    /// cut-out transparency smooth edges without depth sorting. Only takes effect while MSAA is
+    /// enabled.
+    pub alpha_to_coverage: bool,
+    /// Number of samples per pixel of the targets the material draws to, overriding the
+    /// [sample count](crate::RenderApi::set_sample_count) of the render api. Lets e.g. an overlay
+    /// draw to single sampled targets while the world is multisampled. Must be a power of two.
+    pub sample_count: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum BlendMode {
+    /// Output replaces the contents of the target.
+    #[default]
+    Opaque,
+    /// Output is mixed with the target using the blend constant of the batch as the weight, so the
+    /// opacity of a whole batch can be changed without touching its vertices. See
+    /// [Batch::with_blend_constant](crate::Batch::with_blend_constant).
+    Constant,
+    /// Output is mixed with the target using its own alpha as the weight, for translucent colors.
+    AlphaBlend,
+    /// Output weighted by its alpha is added to the target, brightening it, e.g. for glows.
+    Additive,
+}
+
+impl BlendMode {
+    pub(crate) fn state(&self) -> Option<wgpu::BlendState> {
+        match self {
+            BlendMode::Opaque => None,
+            BlendMode::Constant => {
+                let component = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Constant,
+                    dst_factor: wgpu::BlendFactor::OneMinusConstant,
+                    operation: wgpu::BlendOperation::Add,
+                };
+                Some(wgpu::BlendState { color: component, alpha: component })
+            }
+            BlendMode::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            BlendMode::Additive => {
+                let color = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                };
+                Some(wgpu::BlendState { color, alpha: wgpu::BlendComponent::OVER })
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TargetFormat {
+    /// Uses the format of the surface, or [TextureFormat::Rgba8Unorm] if there is no configured
+    /// surface.
+    Surface,
+    Format(TextureFormat),
+}
+
+impl TargetFormat {
+    pub fn resolve(&self, surface_format: Option<TextureFormat>) -> TextureFormat {
+        match self {
+            TargetFormat::Surface => surface_format.unwrap_or(TextureFormat::Rgba8Unorm),
+            TargetFormat::Format(format) => *format,
+        }
+    }
 }
 
 pub struct ShaderStage {
@@ -24,6 +179,12 @@ pub trait Shader {
     fn process_vertex<'a>(&self, input: &Self::Input, vertex: <Self::Format as VertexFormat>::Vertex<'a>);
 
     fn shader_definition(&self) -> ShaderDefinition;
+
+    /// Returns the transformation applied to the geometry of a model with the given input, used
+    /// to measure its size on screen when selecting a [Lod](crate::lod::Lod).
+    fn model_transform(&self, _input: &Self::Input) -> Option<Matrix4<f32>> {
+        None
+    }
 }
 
 pub trait VertexFormat {
@@ -0,0 +1,366 @@
+use std::iter::Map;
+use std::mem::size_of;
+use std::slice::ChunksExactMut;
+
+use bytemuck::{cast_slice, from_bytes_mut};
+use bytemuck_derive::{Pod, Zeroable};
+use nalgebra::{Matrix4, point, Point3, vector, Vector2};
+
+use utils::Handle;
+
+use crate::{Color, RenderApi};
+use crate::geometry::{Geometry, GeometryFormat};
+use crate::material::{AttributeDefinition, AttributeSemantics, AttributeType};
+use crate::shader::{BlendMode, Shader, ShaderDefinition, VertexFormat, VertexMapper};
+
+/// Maps the texture coordinates of a sprite onto a region of its texture, optionally mirrored.
+/// Lets a single quad show any sprite of an atlas.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct UvTransform {
+    /// Top left corner of the region in texture coordinates.
+    pub offset: Vector2<f32>,
+    /// Size of the region in texture coordinates.
+    pub scale: Vector2<f32>,
+    pub flip_x: bool,
+    pub flip_y: bool,
+}
+
+impl Default for UvTransform {
+    /// The whole texture, not mirrored.
+    fn default() -> Self {
+        UvTransform {
+            offset: vector!(0.0, 0.0),
+            scale: vector!(1.0, 1.0),
+            flip_x: false,
+            flip_y: false,
+        }
+    }
+}
+
+impl UvTransform {
+    /// Selects the region of the texture starting at `offset` with the given `size`.
+    pub fn region(offset: Vector2<f32>, size: Vector2<f32>) -> Self {
+        UvTransform { offset, scale: size, ..Default::default() }
+    }
+
+    /// Selects cell `(column, row)` of an atlas evenly divided into `columns` by `rows` cells.
+    pub fn atlas_cell(columns: u32, rows: u32, column: u32, row: u32) -> Self {
+        let size = vector!(1.0 / columns as f32, 1.0 / rows as f32);
+        Self::region(vector!(column as f32 * size.x, row as f32 * size.y), size)
+    }
+
+    /// Mirrors the region horizontally.
+    pub fn flipped_x(self) -> Self {
+        UvTransform { flip_x: !self.flip_x, ..self }
+    }
+
+    /// Mirrors the region vertically.
+    pub fn flipped_y(self) -> Self {
+        UvTransform { flip_y: !self.flip_y, ..self }
+    }
+
+    /// Maps texture coordinates of the sprite geometry, between `(0, 0)` and `(1, 1)`, into the
+    /// region. Flipping mirrors within the region, so it stays selected.
+    pub fn apply(&self, uv: Vector2<f32>) -> Vector2<f32> {
+        let x = if self.flip_x { 1.0 - uv.x } else { uv.x };
+        let y = if self.flip_y { 1.0 - uv.y } else { uv.y };
+        self.offset + vector!(x, y).component_mul(&self.scale)
+    }
+}
+
+pub struct SpriteProperties {
+    pub transform: Matrix4<f32>,
+    pub color: Color,
+    pub uv: UvTransform,
+}
+
+impl SpriteProperties {
+    pub fn new(transform: Matrix4<f32>, color: Color, uv: UvTransform) -> Self {
+        SpriteProperties { transform, color, uv }
+    }
+}
+
+/// WGSL prepended to the fragment shader module of a [SpriteShader], declaring
+/// `fn alpha_discarded(color: vec4<f32>) -> bool`. It tells whether the alpha of `color` is below
+/// the alpha cutoff, and is always false without one. The `discard` itself has to be in the
+/// fragment shader, as the GL backend rejects it in functions shared with the vertex stage.
+fn alpha_test_wgsl(alpha_cutoff: Option<f32>) -> String {
+    let condition = match alpha_cutoff {
+        None => "false".to_owned(),
+        Some(cutoff) => format!("color.a < {cutoff:?}"),
+    };
+    format!("
+fn alpha_discarded(color: vec4<f32>) -> bool {{
+    return {condition};
+}}
+")
+}
+
+/// Shader for textured quads. Vertex processing transforms positions, tints colors and maps
+/// texture coordinates with the [UvTransform] of each model, while the WGSL and its texture
+/// bindings come from the given [ShaderDefinition]. Its vertex shader receives the `position`,
+/// `color` and `tex_coord` attributes of [SpriteVertex]. Its fragment shader should discard
+/// fragments for which `alpha_discarded` returns true, see [SpriteShader::with_alpha_cutoff]:
+///
+/// ```wgsl
+/// if alpha_discarded(color) {
+///     discard;
+/// }
+/// ```
+pub struct SpriteShader {
+    definition: fn() -> ShaderDefinition,
+    alpha_cutoff: Option<f32>,
+}
+
+impl SpriteShader {
+    pub fn new(definition: fn() -> ShaderDefinition) -> Self {
+        SpriteShader { definition, alpha_cutoff: None }
+    }
+
+    /// Discards fragments with an alpha below `cutoff` and draws the rest with blending disabled.
+    /// Gives crisp edges to sprites with hard transparent edges, like pixel art.
+    pub fn with_alpha_cutoff(self, cutoff: f32) -> Self {
+        // formatted into the shader source, where NaN or infinity wouldn't compile
+        assert!(cutoff.is_finite(), "alpha cutoff must be finite, got {cutoff}");
+        SpriteShader { alpha_cutoff: Some(cutoff), ..self }
+    }
+}
+
+impl Shader for SpriteShader {
+    type Input = SpriteProperties;
+    type Format = SpriteVertexFormat;
+
+    fn process_vertex(&self, input: &SpriteProperties, vertex: &mut SpriteVertex) {
+        vertex.position = input.transform.transform_point(&vertex.position);
+        vertex.color *= input.color;
+        vertex.tex_coord = input.uv.apply(vertex.tex_coord);
+    }
+
+    fn shader_definition(&self) -> ShaderDefinition {
+        let mut definition = (self.definition)();
+        let module = &mut definition.shader_modules[definition.fragment_shader.module];
+        *module = alpha_test_wgsl(self.alpha_cutoff) + module;
+        if self.alpha_cutoff.is_some() {
+            definition.blend = BlendMode::Opaque;
+        }
+        definition
+    }
+
+    fn model_transform(&self, input: &SpriteProperties) -> Option<Matrix4<f32>> {
+        Some(input.transform)
+    }
+}
+
+#[derive(Default, Copy, Clone, Pod, Zeroable)]
+#[repr(C)]
+pub struct SpriteVertex {
+    pub position: Point3<f32>,
+    pub color: Color,
+    pub tex_coord: Vector2<f32>,
+}
+
+pub struct SpriteVertexFormat;
+
+impl VertexFormat for SpriteVertexFormat {
+    type Vertex<'a> = &'a mut SpriteVertex;
+    type Mapper = Self;
+
+    fn mapper_for_format(_format: &GeometryFormat) -> Option<Self> {
+        Some(Self)
+    }
+
+    fn describe() -> Vec<AttributeDefinition> {
+        vec![
+            AttributeDefinition {
+                name: None,
+                semantics: AttributeSemantics::Position { transform: Default::default() },
+                typ: AttributeType::Float32(3),
+            },
+            AttributeDefinition {
+                name: None,
+                semantics: AttributeSemantics::Color,
+                typ: AttributeType::Float32(4),
+            },
+            AttributeDefinition {
+                name: None,
+                semantics: AttributeSemantics::TexCoord,
+                typ: AttributeType::Float32(2),
+            },
+        ]
+    }
+}
+
+impl VertexMapper for SpriteVertexFormat {
+    type Vertex<'a> = &'a mut SpriteVertex;
+    type Iterator<'a> = Map<ChunksExactMut<'a, u8>, fn(&'a mut [u8]) -> &'a mut SpriteVertex>;
+
+    fn vertices<'a>(&self, data: &'a mut [u8], _format: &GeometryFormat) -> Self::Iterator<'a> {
+        data.chunks_exact_mut(size_of::<SpriteVertex>()).map(from_bytes_mut)
+    }
+}
+
+/// Vertices of a white unit quad centered on the origin, with texture coordinates covering the
+/// whole texture.
+pub fn sprite_quad_vertices() -> [SpriteVertex; 4] {
+    [
+        (point![-0.5, 0.5, 0.0], vector![0.0, 0.0]),
+        (point![0.5, 0.5, 0.0], vector![1.0, 0.0]),
+        (point![-0.5, -0.5, 0.0], vector![0.0, 1.0]),
+        (point![0.5, -0.5, 0.0], vector![1.0, 1.0]),
+    ].map(|(position, tex_coord)| SpriteVertex { position, color: Color::WHITE, tex_coord })
+}
+
+/// Creates the quad of [sprite_quad_vertices], shared by all sprites drawn with a [SpriteShader].
+pub fn new_sprite_quad(render: &mut RenderApi) -> Handle<Geometry> {
+    render.new_geometry(
+        cast_slice(&sprite_quad_vertices()).to_vec(),
+        GeometryFormat::from(SpriteVertexFormat::describe()),
+        vec![0u16, 1, 2, 2, 1, 3],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use nalgebra::{Matrix4, vector};
+
+    use crate::{Batch, Color, Model, TextureFormat};
+    use crate::shader::{BlendMode, Shader, ShaderDefinition, ShaderStage, TargetFormat};
+    use crate::sprite::{new_sprite_quad, sprite_quad_vertices, SpriteProperties, SpriteShader, UvTransform};
+    use crate::testing::{headless, pixel, read_texture};
+
+    /// Vertices already in clip space, colored by their vertex color.
+    const COLOR_SPRITE_SHADER: &str = r"
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) tex_coord: vec2<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4(input.position, 1.0);
+    output.color = input.color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    if alpha_discarded(color) {
+        discard;
+    }
+    return color;
+}
+";
+
+    fn color_sprite_definition() -> ShaderDefinition {
+        ShaderDefinition {
+            shader_modules: vec![COLOR_SPRITE_SHADER.to_owned()],
+            vertex_shader: ShaderStage { module: 0, entrypoint: "vs_main".to_owned() },
+            fragment_shader: ShaderStage { module: 0, entrypoint: "fs_main".to_owned() },
+            fragment_targets: vec![TargetFormat::Format(TextureFormat::Rgba8Unorm)],
+            attribute_locations: HashMap::from([
+                ("position".to_owned(), 0),
+                ("color".to_owned(), 1),
+                ("tex_coord".to_owned(), 2),
+            ]),
+            uniforms: vec![],
+            multisample: Default::default(),
+            blend: BlendMode::Constant,
+            depth: None,
+            topology: Default::default(),
+            primitive: Default::default(),
+            instance_attributes: vec![],
+        }
+    }
+
+    fn definition() -> ShaderDefinition {
+        ShaderDefinition {
+            shader_modules: vec![],
+            vertex_shader: ShaderStage { module: 0, entrypoint: "vs_main".to_owned() },
+            fragment_shader: ShaderStage { module: 0, entrypoint: "fs_main".to_owned() },
+            fragment_targets: vec![TargetFormat::Surface],
+            attribute_locations: HashMap::new(),
+            uniforms: vec![],
+            multisample: Default::default(),
+            blend: Default::default(),
+            depth: None,
+            topology: Default::default(),
+            primitive: Default::default(),
+            instance_attributes: vec![],
+        }
+    }
+
+    fn processed_uvs(uv: UvTransform) -> Vec<[f32; 2]> {
+        let shader = SpriteShader::new(definition);
+        let input = SpriteProperties::new(Matrix4::identity(), Color::WHITE, uv);
+        sprite_quad_vertices().into_iter()
+            .map(|mut vertex| {
+                shader.process_vertex(&input, &mut vertex);
+                [vertex.tex_coord.x, vertex.tex_coord.y]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn flipped_uvs() {
+        let original = processed_uvs(UvTransform::default());
+        assert_eq!(original, vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]]);
+
+        let flipped = processed_uvs(UvTransform::default().flipped_x());
+        assert_eq!(flipped, vec![[1.0, 0.0], [0.0, 0.0], [1.0, 1.0], [0.0, 1.0]]);
+
+        let flipped = processed_uvs(UvTransform::default().flipped_y());
+        assert_eq!(flipped, vec![[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]]);
+    }
+
+    #[test]
+    fn atlas_cell() {
+        let cell = UvTransform::atlas_cell(4, 2, 1, 1);
+        assert_eq!(cell, UvTransform::region(vector!(0.25, 0.5), vector!(0.25, 0.5)));
+        assert_eq!(processed_uvs(cell), vec![[0.25, 0.5], [0.5, 0.5], [0.25, 1.0], [0.5, 1.0]]);
+        // mirrored within the cell
+        assert_eq!(processed_uvs(cell.flipped_x()), vec![[0.5, 0.5], [0.25, 0.5], [0.5, 1.0], [0.25, 1.0]]);
+    }
+
+    #[test]
+    fn alpha_cutoff() {
+        let shader = SpriteShader::new(color_sprite_definition).with_alpha_cutoff(0.5);
+        assert_eq!(shader.shader_definition().blend, BlendMode::Opaque);
+
+        let mut render = headless();
+        let target = render.new_render_texture(4, 2, TextureFormat::Rgba8Unorm);
+        let material = render.new_material(shader);
+        let quad = new_sprite_quad(&mut render);
+
+        // translucent sprite on the left half, mostly opaque sprite on the right half
+        let half = |x: f32| Matrix4::new_translation(&vector!(x, 0.0, 0.0)) * Matrix4::new_nonuniform_scaling(&vector!(1.0, 2.0, 1.0));
+        let mut batch = Batch::new(&material, vec![]);
+        batch.output(vec![target]);
+        batch.clear(Color::new(0.0, 0.0, 1.0, 1.0));
+        batch.model(Model::new(quad, SpriteProperties::new(half(-0.5), Color::new(1.0, 0.0, 0.0, 0.3), UvTransform::default())));
+        batch.model(Model::new(quad, SpriteProperties::new(half(0.5), Color::new(1.0, 0.0, 0.0, 0.7), UvTransform::default())));
+        let mut drawer = render.new_offscreen_drawer();
+        drawer.submit_batch(batch);
+        drawer.finish();
+
+        let pixels = read_texture(&render, target);
+        // discarded, leaving the target untouched
+        assert_eq!(pixel(&pixels, 4, 0, 0), [0, 0, 255, 255]);
+        // drawn without blending with the target
+        assert_eq!(pixel(&pixels, 4, 3, 1)[..3], [255, 0, 0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "alpha cutoff must be finite")]
+    fn non_finite_alpha_cutoff() {
+        SpriteShader::new(color_sprite_definition).with_alpha_cutoff(f32::NAN);
+    }
+}
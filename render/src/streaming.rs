@@ -0,0 +1,175 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bytemuck::{cast_slice, Pod};
+use wgpu::BufferAddress;
+
+use utils::Handle;
+
+use crate::RenderApi;
+use crate::vecbuf::VertexBuffer;
+
+/// Sub-allocates the vertices generated each frame, e.g. debug lines or particle ribbons, from a
+/// single vertex buffer used as a ring. The regions written for a frame are only reused once the
+/// GPU has finished the work submitted before [StreamingGeometry::finish_frame], so nothing is
+/// reallocated per frame, and writing only waits on the GPU when the ring is full.
+///
+/// Draw the returned ranges by slicing [StreamingGeometry::buffer].
+pub struct StreamingGeometry {
+    buffer: Handle<VertexBuffer>,
+    ring: RingAllocator,
+    /// Set once the GPU finished the work of each in flight frame, oldest first.
+    fences: VecDeque<Arc<AtomicBool>>,
+}
+
+impl StreamingGeometry {
+    pub(crate) fn new(buffer: Handle<VertexBuffer>, capacity: u64) -> Self {
+        StreamingGeometry {
+            buffer,
+            ring: RingAllocator::new(capacity),
+            fences: VecDeque::new(),
+        }
+    }
+
+    pub fn buffer(&self) -> Handle<VertexBuffer> {
+        self.buffer
+    }
+
+    /// Writes `vertices` to a free region of the ring, returning its byte range in the buffer.
+    /// Blocks until the GPU is done with previous frames if there is no free region large enough.
+    ///
+    /// Panics if the allocations of a single frame exceed the capacity of the ring.
+    pub fn alloc_vertices<V: Pod>(&mut self, render: &mut RenderApi, vertices: &[V]) -> Range<BufferAddress> {
+        let data: &[u8] = cast_slice(vertices);
+        let size = (data.len() as BufferAddress).next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT);
+
+        render.device.device.poll(wgpu::Maintain::Poll);
+        while self.fences.front().is_some_and(|fence| fence.load(Ordering::Acquire)) {
+            self.release_oldest();
+        }
+        let offset = loop {
+            if let Some(offset) = self.ring.alloc(size) {
+                break offset;
+            }
+            assert!(!self.fences.is_empty(), "allocations of a frame exceed the {} bytes of the streaming geometry", self.ring.capacity);
+            // waits for all submitted work, so every frame in flight is done afterwards
+            render.device.device.poll(wgpu::Maintain::Wait);
+            self.release_oldest();
+        };
+
+        render.get_buffer(self.buffer).unwrap().upload(offset as _, data);
+        offset..offset + data.len() as BufferAddress
+    }
+
+    /// Ends the allocations of the current frame. Call once the work drawing them was submitted,
+    /// e.g. after [Drawer::finish](crate::render_api::Drawer::finish), so their regions are only
+    /// reused after the GPU finished reading them.
+    pub fn finish_frame(&mut self, render: &RenderApi) {
+        let fence = Arc::new(AtomicBool::new(false));
+        let signal = fence.clone();
+        render.device.queue.on_submitted_work_done(move || signal.store(true, Ordering::Release));
+        self.fences.push_back(fence);
+        self.ring.finish_frame();
+    }
+
+    fn release_oldest(&mut self) {
+        self.fences.pop_front();
+        self.ring.release_oldest();
+    }
+}
+
+/// Bookkeeping of the regions of a [StreamingGeometry], without the buffer and fences.
+/// Allocations are contiguous, so one that doesn't fit at the end of the ring skips the rest of it
+/// and wraps around to the start.
+struct RingAllocator {
+    capacity: u64,
+    /// Offset of the next allocation.
+    head: u64,
+    /// Bytes ending at `head` that are allocated to the current frame or frames in flight,
+    /// including space skipped when wrapping.
+    used: u64,
+    frame_size: u64,
+    /// Sizes of the frames in flight, oldest first.
+    frames: VecDeque<u64>,
+}
+
+impl RingAllocator {
+    fn new(capacity: u64) -> Self {
+        RingAllocator { capacity, head: 0, used: 0, frame_size: 0, frames: VecDeque::new() }
+    }
+
+    /// Returns the offset of a region of `size` bytes, unless it would overlap the regions of the
+    /// current frame or frames in flight.
+    fn alloc(&mut self, size: u64) -> Option<u64> {
+        if self.used == 0 {
+            // nothing to preserve, start over for the largest contiguous space
+            self.head = 0;
+        }
+        let skipped = if self.head + size <= self.capacity { 0 } else { self.capacity - self.head };
+        if self.used + skipped + size > self.capacity {
+            return None;
+        }
+        let offset = if skipped == 0 { self.head } else { 0 };
+        self.head = offset + size;
+        self.used += skipped + size;
+        self.frame_size += skipped + size;
+        Some(offset)
+    }
+
+    fn finish_frame(&mut self) {
+        self.frames.push_back(self.frame_size);
+        self.frame_size = 0;
+    }
+
+    /// Frees the regions of the oldest frame in flight.
+    fn release_oldest(&mut self) {
+        let size = self.frames.pop_front().expect("frame in flight");
+        self.used -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::streaming::RingAllocator;
+    use crate::testing::headless;
+
+    #[test]
+    fn ring_allocation() {
+        let mut ring = RingAllocator::new(64);
+        assert_eq!(ring.alloc(20), Some(0));
+        assert_eq!(ring.alloc(24), Some(20));
+        ring.finish_frame();
+        assert_eq!(ring.alloc(16), Some(44));
+        ring.finish_frame();
+
+        // would wrap into the first frame, which is still in flight
+        assert_eq!(ring.alloc(12), None);
+        ring.release_oldest();
+        // skips the last 4 bytes and wraps around to the start
+        assert_eq!(ring.alloc(12), Some(0));
+        // fits before the end of the ring, but overlaps the second frame
+        assert_eq!(ring.alloc(40), None);
+        assert_eq!(ring.alloc(32), Some(12));
+        ring.finish_frame();
+
+        ring.release_oldest();
+        ring.release_oldest();
+        assert_eq!(ring.alloc(64), Some(0));
+    }
+
+    #[test]
+    fn streaming_frames() {
+        let mut render = headless();
+        let mut streaming = render.new_streaming_geometry(64);
+        for frame in 0..4 {
+            let first = streaming.alloc_vertices(&mut render, &[frame as f32; 5]);
+            let second = streaming.alloc_vertices(&mut render, &[[frame as f32; 2]; 3]);
+            assert_eq!((first.end - first.start, second.end - second.start), (20, 24));
+            assert!(first.end <= second.start || second.end <= first.start, "{first:?} overlaps {second:?}");
+            assert!(first.end <= 64 && second.end <= 64);
+            streaming.finish_frame(&render);
+        }
+    }
+}
@@ -1,8 +1,35 @@
+use wgpu::{CompositeAlphaMode, PresentMode};
+
 use crate::{DeviceContext, Frame, TextureFormat};
 
 pub struct SurfaceContext {
     pub(crate) surface: wgpu::Surface,
     pub(crate) surface_config: Option<wgpu::SurfaceConfiguration>,
+    /// Requested compositing mode, applied on the next [SurfaceContext::configure].
+    pub(crate) alpha_mode: CompositeAlphaMode,
+    /// Requested presentation mode, applied on the next [SurfaceContext::configure].
+    pub(crate) present_mode: PresentMode,
+}
+
+/// Returns `requested` if the surface supports it, otherwise falls back to the first supported
+/// mode.
+fn select_alpha_mode(requested: CompositeAlphaMode, supported: &[CompositeAlphaMode]) -> CompositeAlphaMode {
+    if supported.contains(&requested) || supported.is_empty() {
+        return requested;
+    }
+    let fallback = supported[0];
+    log::warn!(target: "krill-render", "Surface alpha mode {:?} is not supported, falling back to {:?}.", requested, fallback);
+    fallback
+}
+
+/// Returns `requested` if the surface supports it, otherwise falls back to [PresentMode::Fifo],
+/// which every surface supports, e.g. for [PresentMode::Immediate] on the web.
+fn select_present_mode(requested: PresentMode, supported: &[PresentMode]) -> PresentMode {
+    if supported.contains(&requested) || supported.is_empty() {
+        return requested;
+    }
+    log::warn!(target: "krill-render", "Surface present mode {:?} is not supported, falling back to {:?}.", requested, PresentMode::Fifo);
+    PresentMode::Fifo
 }
 
 impl SurfaceContext {
@@ -25,6 +52,8 @@ impl SurfaceContext {
             TextureFormat::Bgra8UnormSrgb if capabilities.formats.contains(&TextureFormat::Bgra8Unorm) => TextureFormat::Bgra8Unorm,
             _ => surface_config.format,
         };
+        surface_config.alpha_mode = select_alpha_mode(self.alpha_mode, &capabilities.alpha_modes);
+        surface_config.present_mode = select_present_mode(self.present_mode, &capabilities.present_modes);
 
         log::info!("Configuring surface with config: {:?}", surface_config);
 
@@ -32,6 +61,28 @@ impl SurfaceContext {
         self.surface_config = Some(surface_config);
     }
 
+    /// Sets how the surface is composited with the windows behind it, e.g.
+    /// [CompositeAlphaMode::PreMultiplied] for transparent windows. Takes effect on the next
+    /// [SurfaceContext::configure].
+    pub fn set_alpha_mode(&mut self, alpha_mode: CompositeAlphaMode) {
+        self.alpha_mode = alpha_mode;
+    }
+
+    pub fn alpha_mode(&self) -> CompositeAlphaMode {
+        self.surface_config.as_ref().map_or(self.alpha_mode, |config| config.alpha_mode)
+    }
+
+    /// Sets when frames are presented, [PresentMode::Fifo] waits for vertical sync while
+    /// [PresentMode::Mailbox] and [PresentMode::Immediate] don't cap the frame rate. Takes effect
+    /// on the next [SurfaceContext::configure].
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) {
+        self.present_mode = present_mode;
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.surface_config.as_ref().map_or(self.present_mode, |config| config.present_mode)
+    }
+
     pub fn present_frame(&self, frame: Frame) {
         frame.surface_texture.present();
     }
@@ -44,3 +95,26 @@ impl SurfaceContext {
         self.surface_config.as_ref().map(|config| (config.width, config.height))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use wgpu::{CompositeAlphaMode, PresentMode};
+
+    use crate::surface_context::{select_alpha_mode, select_present_mode};
+
+    #[test]
+    fn unsupported_alpha_mode() {
+        let supported = [CompositeAlphaMode::Opaque, CompositeAlphaMode::PreMultiplied];
+        assert_eq!(select_alpha_mode(CompositeAlphaMode::PreMultiplied, &supported), CompositeAlphaMode::PreMultiplied);
+        assert_eq!(select_alpha_mode(CompositeAlphaMode::PostMultiplied, &supported), CompositeAlphaMode::Opaque);
+        assert_eq!(select_alpha_mode(CompositeAlphaMode::Opaque, &[CompositeAlphaMode::Inherit]), CompositeAlphaMode::Inherit);
+    }
+
+    #[test]
+    fn unsupported_present_mode() {
+        let supported = [PresentMode::Fifo, PresentMode::Mailbox];
+        assert_eq!(select_present_mode(PresentMode::Mailbox, &supported), PresentMode::Mailbox);
+        assert_eq!(select_present_mode(PresentMode::Immediate, &supported), PresentMode::Fifo);
+        assert_eq!(select_present_mode(PresentMode::Immediate, &[PresentMode::Fifo]), PresentMode::Fifo);
+    }
+}
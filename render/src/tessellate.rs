@@ -0,0 +1,221 @@
+use std::f32::consts::TAU;
+
+use nalgebra::{vector, Vector2};
+
+/// Joins where the miter would be longer than this many times the stroke width are beveled
+/// instead, so sharp turns don't produce long spikes.
+const MITER_LIMIT: f32 = 4.0;
+
+/// Points closer together than this are treated as the same point.
+const EPSILON: f32 = 1e-5;
+
+/// Tessellates a polyline stroked with the given `width` into a triangle strip, alternating
+/// between the left and the right side of the line. Joins are mitered, and both ends are cut off
+/// square at the first and last point.
+///
+/// A closed outline can be drawn by starting and ending it at the same point in the middle of a
+/// straight segment.
+pub fn tessellate_polyline(points: &[Vector2<f32>], width: f32) -> Vec<Vector2<f32>> {
+    let mut points = points.to_vec();
+    points.dedup_by(|a, b| (*a - *b).norm() < EPSILON);
+    if points.len() < 2 {
+        return vec![];
+    }
+
+    let half_width = width / 2.0;
+    let normal = |from: Vector2<f32>, to: Vector2<f32>| {
+        let direction = (to - from).normalize();
+        vector!(-direction.y, direction.x)
+    };
+
+    let mut strip = Vec::with_capacity(points.len() * 2);
+    let mut push = |point: Vector2<f32>, offset: Vector2<f32>| {
+        strip.push(point + offset);
+        strip.push(point - offset);
+    };
+    for (i, point) in points.iter().copied().enumerate() {
+        let incoming = (i > 0).then(|| normal(points[i - 1], point));
+        let outgoing = points.get(i + 1).map(|next| normal(point, *next));
+        match (incoming, outgoing) {
+            (Some(incoming), Some(outgoing)) => {
+                let miter = incoming + outgoing;
+                // the miter is 1 / cos(half the turn) times as long as the stroke is wide
+                let scale = if miter.norm() > EPSILON { 1.0 / miter.normalize().dot(&incoming) } else { f32::INFINITY };
+                if scale <= MITER_LIMIT {
+                    push(point, miter.normalize() * half_width * scale);
+                } else {
+                    push(point, incoming * half_width);
+                    push(point, outgoing * half_width);
+                }
+            }
+            (Some(normal), None) | (None, Some(normal)) => push(point, normal * half_width),
+            (None, None) => unreachable!("polyline has at least two points"),
+        }
+    }
+    strip
+}
+
+/// WGSL function turning the interpolated distance to the edge of a [Shape] into the coverage of
+/// the fragment, fading out over about one pixel for anti-aliased edges:
+///
+/// ```wgsl
+/// fn edge_alpha(edge_distance: f32) -> f32
+/// ```
+pub const EDGE_ALPHA_WGSL: &str = r"
+fn edge_alpha(edge_distance: f32) -> f32 {
+    return clamp(edge_distance / fwidth(edge_distance) + 0.5, 0.0, 1.0);
+}
+";
+
+/// Triangulated flat shape.
+#[derive(Debug, Default)]
+pub struct Shape {
+    pub vertices: Vec<Vector2<f32>>,
+    /// Distance of each vertex to the nearest edge of the shape. Interpolated over the triangles,
+    /// it gives an [edge_alpha](EDGE_ALPHA_WGSL) for anti-aliasing.
+    pub edge_distances: Vec<f32>,
+    /// Triangle list indices into the vertices.
+    pub indices: Vec<u16>,
+}
+
+/// Points evenly spaced around a circle, counterclockwise from the positive x axis.
+fn circle_points(center: Vector2<f32>, radius: f32, segments: u16) -> impl Iterator<Item=Vector2<f32>> {
+    (0..segments).map(move |i| {
+        let (sin, cos) = (i as f32 / segments as f32 * TAU).sin_cos();
+        center + vector!(cos, sin) * radius
+    })
+}
+
+/// Triangulates a filled circle as a fan of `segments` triangles around its center.
+pub fn filled_circle(center: Vector2<f32>, radius: f32, segments: u16) -> Shape {
+    assert!(segments >= 3, "a circle needs at least three segments");
+
+    let vertices: Vec<_> = std::iter::once(center)
+        .chain(circle_points(center, radius, segments))
+        .collect();
+    let mut edge_distances = vec![0.0; vertices.len()];
+    edge_distances[0] = radius;
+    let indices = (0..segments)
+        .flat_map(|i| [0, i + 1, (i + 1) % segments + 1])
+        .collect();
+
+    Shape { vertices, edge_distances, indices }
+}
+
+/// Triangulates a ring between the `inner` and `outer` radius. Each of the `segments` consists of
+/// four triangles, as a third circle of vertices halfway between both edges lets the distance to
+/// the nearest edge be interpolated.
+pub fn ring(center: Vector2<f32>, inner: f32, outer: f32, segments: u16) -> Shape {
+    assert!(segments >= 3, "a ring needs at least three segments");
+    assert!(inner <= outer, "inner radius of a ring must not exceed the outer radius");
+
+    let middle = (inner + outer) / 2.0;
+    let vertices: Vec<_> = [inner, middle, outer].into_iter()
+        .flat_map(|radius| circle_points(center, radius, segments))
+        .collect();
+    let edge_distances = [0.0, middle - inner, 0.0].into_iter()
+        .flat_map(|distance| std::iter::repeat_n(distance, segments as _))
+        .collect();
+    let indices = (0..2)
+        .flat_map(|row| (0..segments).flat_map(move |i| {
+            let next = (i + 1) % segments;
+            let [a, b] = [i, next].map(|i| row * segments + i);
+            let [c, d] = [i, next].map(|i| (row + 1) * segments + i);
+            [a, b, c, c, b, d]
+        }))
+        .collect();
+
+    Shape { vertices, edge_distances, indices }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{vector, Vector2};
+
+    use crate::tessellate::{EDGE_ALPHA_WGSL, filled_circle, ring, tessellate_polyline};
+    use crate::testing::headless;
+
+    fn assert_close(actual: &[Vector2<f32>], expected: &[Vector2<f32>]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (actual, expected) in actual.iter().zip(expected) {
+            assert!((actual - expected).norm() < 1e-5, "expected {expected:?}, got {actual:?}");
+        }
+    }
+
+    #[test]
+    fn straight_line() {
+        let strip = tessellate_polyline(&[vector!(0.0, 0.0), vector!(1.0, 0.0), vector!(1.0, 0.0)], 0.2);
+        assert_close(&strip, &[
+            vector!(0.0, 0.1),
+            vector!(0.0, -0.1),
+            vector!(1.0, 0.1),
+            vector!(1.0, -0.1),
+        ]);
+
+        assert!(tessellate_polyline(&[vector!(0.0, 0.0)], 0.2).is_empty());
+    }
+
+    #[test]
+    fn miter_join() {
+        let strip = tessellate_polyline(&[vector!(0.0, 0.0), vector!(1.0, 0.0), vector!(1.0, 1.0)], 0.2);
+        assert_close(&strip[2..4], &[vector!(0.9, 0.1), vector!(1.1, -0.1)]);
+    }
+
+    #[test]
+    fn bevel_join() {
+        // turns back on itself, which would need an infinitely long miter
+        let strip = tessellate_polyline(&[vector!(0.0, 0.0), vector!(1.0, 0.0), vector!(0.0, 0.0)], 0.2);
+        assert_eq!(strip.len(), 8);
+        assert!(strip.iter().all(|point| point.x <= 1.0 + 1e-5));
+    }
+
+    #[test]
+    fn circle_vertex_counts() {
+        for segments in [3, 8, 32] {
+            let circle = filled_circle(vector!(1.0, 2.0), 0.5, segments);
+            assert_eq!(circle.vertices.len(), segments as usize + 1);
+            assert_eq!(circle.edge_distances.len(), circle.vertices.len());
+            assert_eq!(circle.indices.len(), segments as usize * 3);
+            assert!(circle.indices.iter().all(|index| (*index as usize) < circle.vertices.len()));
+
+            assert_eq!(circle.vertices[0], vector!(1.0, 2.0));
+            assert_eq!(circle.edge_distances[0], 0.5);
+            for vertex in &circle.vertices[1..] {
+                assert!(((vertex - vector!(1.0, 2.0)).norm() - 0.5).abs() < 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn ring_radii() {
+        let center = vector!(-1.0, 0.5);
+        let segments = 12;
+        let shape = ring(center, 0.8, 1.0, segments);
+        assert_eq!(shape.vertices.len(), segments as usize * 3);
+        assert_eq!(shape.indices.len(), segments as usize * 12);
+        assert!(shape.indices.iter().all(|index| (*index as usize) < shape.vertices.len()));
+
+        let radii: Vec<_> = shape.vertices.iter().map(|vertex| (vertex - center).norm()).collect();
+        let (min, max) = radii.iter().fold((f32::MAX, f32::MIN), |(min, max), r| (min.min(*r), max.max(*r)));
+        assert!((min - 0.8).abs() < 1e-5);
+        assert!((max - 1.0).abs() < 1e-5);
+        for (radius, distance) in radii.iter().zip(&shape.edge_distances) {
+            assert!((distance - (radius - 0.8).min(1.0 - radius)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn edge_alpha_compiles() {
+        let render = headless();
+        let source = format!("{EDGE_ALPHA_WGSL}
+@fragment
+fn fs_main(@location(0) edge_distance: f32) -> @location(0) vec4<f32> {{
+    return vec4(1.0, 1.0, 1.0, edge_alpha(edge_distance));
+}}
+");
+        render.device.device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+    }
+}
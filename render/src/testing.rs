@@ -0,0 +1,166 @@
+//! Helpers for rendering tests, using a headless device and reading results back from textures.
+
+use std::collections::HashMap;
+use std::iter::Map;
+use std::mem::size_of;
+use std::slice::ChunksExactMut;
+
+use bytemuck::{cast_slice, from_bytes_mut};
+use bytemuck_derive::{Pod, Zeroable};
+
+use utils::Handle;
+
+use crate::{Color, RenderApi, Texture, WGPUContext};
+use crate::geometry::{Geometry, GeometryFormat};
+use crate::material::{AttributeDefinition, AttributeSemantics, AttributeType};
+use crate::shader::{Shader, ShaderDefinition, ShaderStage, TargetFormat, VertexFormat, VertexMapper};
+
+/// Passes vertex position and color straight through to the first color target.
+pub(crate) const PASSTHROUGH_SHADER: &str = r"
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = vec4(input.position, 1.0);
+    output.color = input.color;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color;
+}
+";
+
+pub(crate) fn headless() -> RenderApi {
+    futures::executor::block_on(async {
+        let context = WGPUContext::new().await.unwrap();
+        let device = context.request_headless_device().await.unwrap();
+        RenderApi::new_headless(device)
+    })
+}
+
+#[derive(Copy, Clone, Default, Pod, Zeroable)]
+#[repr(C)]
+pub(crate) struct TestVertex {
+    pub(crate) position: [f32; 3],
+    pub(crate) color: Color,
+}
+
+/// Shader with vertices in clip space, tinted by the model's color.
+pub(crate) struct TestShader {
+    source: &'static str,
+    configure: fn(&mut ShaderDefinition),
+}
+
+impl TestShader {
+    pub(crate) fn new(source: &'static str) -> Self {
+        TestShader { source, configure: |_| {} }
+    }
+
+    pub(crate) fn with(self, configure: fn(&mut ShaderDefinition)) -> Self {
+        TestShader { configure, ..self }
+    }
+}
+
+impl Shader for TestShader {
+    type Input = Color;
+    type Format = TestVertexFormat;
+
+    fn process_vertex(&self, input: &Color, vertex: &mut TestVertex) {
+        vertex.color *= *input;
+    }
+
+    fn shader_definition(&self) -> ShaderDefinition {
+        let mut definition = ShaderDefinition {
+            shader_modules: vec![self.source.to_owned()],
+            vertex_shader: ShaderStage { module: 0, entrypoint: "vs_main".to_owned() },
+            fragment_shader: ShaderStage { module: 0, entrypoint: "fs_main".to_owned() },
+            fragment_targets: vec![TargetFormat::Surface],
+            attribute_locations: HashMap::from([
+                ("position".to_owned(), 0),
+                ("color".to_owned(), 1),
+            ]),
+            uniforms: vec![],
+            multisample: Default::default(),
+            blend: Default::default(),
+            depth: None,
+            topology: Default::default(),
+            primitive: Default::default(),
+            instance_attributes: vec![],
+        };
+        (self.configure)(&mut definition);
+        definition
+    }
+}
+
+pub(crate) struct TestVertexFormat;
+
+impl VertexFormat for TestVertexFormat {
+    type Vertex<'a> = &'a mut TestVertex;
+    type Mapper = Self;
+
+    fn mapper_for_format(_format: &GeometryFormat) -> Option<Self> {
+        Some(Self)
+    }
+
+    fn describe() -> Vec<AttributeDefinition> {
+        vec![
+            AttributeDefinition {
+                name: None,
+                semantics: AttributeSemantics::Position { transform: Default::default() },
+                typ: AttributeType::Float32(3),
+            },
+            AttributeDefinition {
+                name: None,
+                semantics: AttributeSemantics::Color,
+                typ: AttributeType::Float32(4),
+            },
+        ]
+    }
+}
+
+impl VertexMapper for TestVertexFormat {
+    type Vertex<'a> = &'a mut TestVertex;
+    type Iterator<'a> = Map<ChunksExactMut<'a, u8>, fn(&'a mut [u8]) -> &'a mut TestVertex>;
+
+    fn vertices<'a>(&self, data: &'a mut [u8], _format: &GeometryFormat) -> Self::Iterator<'a> {
+        data.chunks_exact_mut(size_of::<TestVertex>()).map(from_bytes_mut)
+    }
+}
+
+/// Creates a white, axis-aligned quad in clip space spanning `min` to `max` at depth `z`.
+pub(crate) fn quad(render: &mut RenderApi, min: (f32, f32), max: (f32, f32), z: f32) -> Handle<Geometry> {
+    let vertices = [
+        [min.0, min.1],
+        [max.0, min.1],
+        [min.0, max.1],
+        [max.0, max.1],
+    ].map(|[x, y]| TestVertex { position: [x, y, z], color: Color::WHITE });
+
+    render.new_geometry(
+        cast_slice(&vertices).to_vec(),
+        GeometryFormat::from(TestVertexFormat::describe()),
+        vec![0u16, 1, 2, 2, 1, 3],
+    )
+}
+
+/// Reads a texture back with [RenderApi::read_texture], blocking until it is done.
+pub(crate) fn read_texture(render: &RenderApi, handle: Handle<Texture>) -> Vec<u8> {
+    futures::executor::block_on(render.read_texture(handle))
+}
+
+/// Returns the RGBA8 pixel at `(x, y)` of tightly packed texture data.
+pub(crate) fn pixel(data: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let offset = ((y * width + x) * 4) as usize;
+    data[offset..offset + 4].try_into().unwrap()
+}
@@ -0,0 +1,130 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::TextureFormat;
+
+pub use serial::{ImageAsset, ImageAssetPipeline};
+
+mod serial;
+
+/// Format of the depth textures used for depth testing.
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+
+/// Identifies the underlying wgpu object of a [Texture], [Sampler] or
+/// [VecBuf](crate::VecBuf), so uniforms notice when they are bound to a different one.
+pub(crate) fn next_id() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+/// A GPU texture together with a default view covering all of it.
+pub struct Texture {
+    id: u64,
+    texture: wgpu::Texture,
+    pub(crate) view: wgpu::TextureView,
+    /// View reinterpreting the texture in another format for sampling, see
+    /// [Texture::sampled_format].
+    sampled: Option<(wgpu::TextureView, TextureFormat)>,
+    format: TextureFormat,
+    size: (u32, u32),
+    sample_count: u32,
+    /// Multisampled texture rendered to in place of this one, then resolved into it.
+    multisampled: Option<(wgpu::Texture, wgpu::TextureView)>,
+}
+
+impl Texture {
+    pub(crate) fn new(texture: wgpu::Texture, format: TextureFormat, size: (u32, u32)) -> Self {
+        let view = texture.create_view(&Default::default());
+        Texture {
+            id: next_id(),
+            texture,
+            view,
+            sampled: None,
+            format,
+            size,
+            sample_count: 1,
+            multisampled: None,
+        }
+    }
+
+    /// Marks the texture itself as multisampled.
+    pub(crate) fn with_sample_count(self, sample_count: u32) -> Self {
+        Texture { sample_count, ..self }
+    }
+
+    /// Renders to `multisampled` instead, resolving it into this texture at the end of each pass.
+    pub(crate) fn with_multisampled(self, multisampled: wgpu::Texture, sample_count: u32) -> Self {
+        let view = multisampled.create_view(&Default::default());
+        Texture {
+            sample_count,
+            multisampled: Some((multisampled, view)),
+            ..self
+        }
+    }
+
+    /// Samples the texture as `format`, e.g. decoding sRGB colors stored in a linear texture.
+    pub(crate) fn with_sampled_format(self, format: TextureFormat) -> Self {
+        let view = self.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(format),
+            ..Default::default()
+        });
+        Texture { sampled: Some((view, format)), ..self }
+    }
+
+    /// View read by shaders sampling the texture.
+    pub(crate) fn sampled_view(&self) -> &wgpu::TextureView {
+        self.sampled.as_ref().map_or(&self.view, |(view, _)| view)
+    }
+
+    /// View to render to, and the view to resolve it into if rendering is multisampled.
+    pub(crate) fn attachment(&self) -> (&wgpu::TextureView, Option<&wgpu::TextureView>) {
+        match &self.multisampled {
+            None => (&self.view, None),
+            Some((_, view)) => (view, Some(&self.view)),
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Format the texture is interpreted as when sampled, which differs from its
+    /// [format](Texture::format) in the sRGB encoding if the texture was created with
+    /// [RenderApi::new_render_texture_sampled_as](crate::RenderApi::new_render_texture_sampled_as).
+    pub fn sampled_format(&self) -> TextureFormat {
+        self.sampled.as_ref().map_or(self.format, |(_, format)| *format)
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Number of samples per pixel rendered to this texture. `texture` always holds the resolved
+    /// result of multisampled color rendering.
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+}
+
+/// Sampler for [Texture] uniform entries, see [RenderApi::new_sampler](crate::RenderApi::new_sampler).
+pub struct Sampler {
+    id: u64,
+    pub(crate) sampler: wgpu::Sampler,
+}
+
+impl Sampler {
+    pub(crate) fn new(sampler: wgpu::Sampler) -> Self {
+        Sampler { id: next_id(), sampler }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
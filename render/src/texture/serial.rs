@@ -0,0 +1,88 @@
+use std::any::Any;
+
+use async_trait::async_trait;
+
+use assets::{AssetPipeline, LoadAssetError};
+use assets::path::AssetPath;
+use assets::source::AssetSource;
+
+/// Decoded image with 8-bit RGBA texels in rows from top to bottom, ready for
+/// [RenderApi::new_texture](crate::RenderApi::new_texture) with an `Rgba8` format.
+pub struct ImageAsset {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Loads PNG and JPEG files as [ImageAsset]s, detecting the format from the file contents.
+pub struct ImageAssetPipeline;
+
+#[async_trait(? Send)]
+impl AssetPipeline for ImageAssetPipeline {
+    async fn load_asset(&self, path: AssetPath, source: &dyn AssetSource) -> Result<Box<dyn Any>, LoadAssetError> {
+        let data = source.open_asset_file(&path).await?.read_fully().await;
+        let image = image::load_from_memory(&data).map_err(LoadAssetError::other)?.into_rgba8();
+        Ok(Box::new(ImageAsset {
+            width: image.width(),
+            height: image.height(),
+            data: image.into_raw(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use async_trait::async_trait;
+
+    use assets::{AssetPipeline, LoadAssetError};
+    use assets::path::AssetPath;
+    use assets::source::{AssetReader, AssetSource};
+
+    use crate::texture::serial::{ImageAsset, ImageAssetPipeline};
+
+    struct BytesReader(Vec<u8>);
+
+    #[async_trait(? Send)]
+    impl AssetReader for BytesReader {
+        async fn read_fully(&mut self) -> Vec<u8> {
+            self.0.clone()
+        }
+    }
+
+    /// Serves a 2x1 PNG with a red and a half transparent blue pixel, and a file that isn't an
+    /// image.
+    struct ImageSource;
+
+    #[async_trait(? Send)]
+    impl AssetSource for ImageSource {
+        async fn open_asset_file(&self, path: &AssetPath) -> Result<Box<dyn AssetReader>, LoadAssetError> {
+            match path.path_string() {
+                "/image.png" => {
+                    let image = image::RgbaImage::from_raw(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+                    let mut png = Cursor::new(vec![]);
+                    image.write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+                    Ok(Box::new(BytesReader(png.into_inner())))
+                }
+                "/image.txt" => Ok(Box::new(BytesReader(b"not an image".to_vec()))),
+                _ => Err(LoadAssetError::NotFound(path.clone())),
+            }
+        }
+    }
+
+    fn load(path: &str) -> Result<ImageAsset, LoadAssetError> {
+        futures::executor::block_on(ImageAssetPipeline.load_asset(AssetPath::new(path).unwrap(), &ImageSource))
+            .map(|image| *image.downcast::<ImageAsset>().unwrap())
+    }
+
+    #[test]
+    fn decode_png() {
+        let image = load("/image.png").unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.data, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+
+        assert!(matches!(load("/image.txt"), Err(LoadAssetError::Other(_))));
+        assert!(matches!(load("/missing.png"), Err(LoadAssetError::NotFound(_))));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::DeviceContext;
+
+/// Maximum number of batches timed per [Drawer](crate::render_api::Drawer). Batches submitted
+/// beyond this are not timed.
+const MAX_TIMED_BATCHES: u32 = 64;
+
+/// Statistics collected while drawing, returned by
+/// [Drawer::finish](crate::render_api::Drawer::finish).
+#[derive(Default, Debug)]
+pub struct RenderStats {
+    /// One entry per submitted batch, in submission order.
+    pub batches: Vec<BatchStats>,
+}
+
+#[derive(Default, Debug)]
+pub struct BatchStats {
+    /// Time the GPU spent executing the render pass of the batch, if GPU timing is enabled and
+    /// supported.
+    pub gpu_time_ns: Option<u64>,
+}
+
+/// Records timestamps at the start and end of render passes.
+pub(crate) struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+}
+
+impl GpuTimer {
+    /// Returns [None] if the device does not support timestamp queries.
+    pub(crate) fn new(device: &DeviceContext) -> Option<Self> {
+        if !device.device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+
+        let count = MAX_TIMED_BATCHES * 2;
+        let size = (count as usize * std::mem::size_of::<u64>()) as wgpu::BufferAddress;
+        Some(GpuTimer {
+            query_set: device.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Default::default(),
+                ty: wgpu::QueryType::Timestamp,
+                count,
+            }),
+            resolve_buffer: device.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Default::default(),
+                size,
+                usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            }),
+            read_buffer: device.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Default::default(),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            period: device.queue.get_timestamp_period(),
+        })
+    }
+
+    /// Whether the pass with the given index can be timed.
+    pub(crate) fn can_time(&self, pass: u32) -> bool {
+        pass < MAX_TIMED_BATCHES
+    }
+
+    pub(crate) fn begin(&self, encoder: &mut wgpu::CommandEncoder, pass: u32) {
+        encoder.write_timestamp(&self.query_set, pass * 2);
+    }
+
+    pub(crate) fn end(&self, encoder: &mut wgpu::CommandEncoder, pass: u32) {
+        encoder.write_timestamp(&self.query_set, pass * 2 + 1);
+    }
+
+    pub(crate) fn resolve(&self, encoder: &mut wgpu::CommandEncoder, passes: u32) {
+        encoder.resolve_query_set(&self.query_set, 0..passes * 2, &self.resolve_buffer, 0);
+        let size = (passes as usize * 2 * std::mem::size_of::<u64>()) as wgpu::BufferAddress;
+        encoder.copy_buffer_to_buffer(&self.resolve_buffer, 0, &self.read_buffer, 0, size);
+    }
+
+    /// Reads back the durations of the resolved passes in nanoseconds. Blocks until the GPU has
+    /// finished all submitted work.
+    pub(crate) fn read(&self, device: &DeviceContext, passes: u32) -> Vec<u64> {
+        let size = (passes as usize * 2 * std::mem::size_of::<u64>()) as wgpu::BufferAddress;
+        let slice = self.read_buffer.slice(..size);
+        slice.map_async(wgpu::MapMode::Read, |result| result.expect("map timestamp buffer"));
+        device.device.poll(wgpu::Maintain::Wait);
+
+        let durations = {
+            let data = slice.get_mapped_range();
+            let timestamps: &[u64] = bytemuck::cast_slice(&data);
+            timestamps.chunks_exact(2)
+                .map(|pair| (pair[1].saturating_sub(pair[0]) as f64 * self.period as f64) as u64)
+                .collect()
+        };
+        self.read_buffer.unmap();
+        durations
+    }
+}
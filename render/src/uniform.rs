@@ -1,11 +1,50 @@
 use std::cell::{RefCell, RefMut};
+use std::num::NonZeroU64;
 
-use utils::Handle;
+use utils::{CompactList, Handle};
 
-use crate::{BufferUsages, DeviceContext, VecBuf};
+use crate::{BufferUsages, DeviceContext, FilterMode, RenderApi, UniformBuffer, VecBuf};
 use crate::material::{UniformEntryDefinition, UniformEntryTypeDefinition};
 use crate::maybe::MaybeOwned;
 use crate::render_api::DeviceResources;
+use crate::texture::{Sampler, Texture};
+
+/// Cycles through the regions of a single uniform buffer, so the values written for a frame never
+/// overwrite a region the GPU may still be reading for a previous frame. Bind
+/// [UniformRing::buffer] to a [DynamicBuffer](UniformEntryTypeDefinition::DynamicBuffer) entry of
+/// the region size, and draw with the offset of each write, see
+/// [Batch::uniform_offsets](crate::Batch::uniform_offsets).
+pub struct UniformRing {
+    buffer: Handle<UniformBuffer>,
+    /// Distance between regions, the region size rounded up to the offset alignment.
+    stride: u32,
+    size: u32,
+    regions: u32,
+    next: u32,
+}
+
+impl UniformRing {
+    pub(crate) fn new(buffer: Handle<UniformBuffer>, size: u32, stride: u32, regions: u32) -> Self {
+        UniformRing { buffer, stride, size, regions, next: 0 }
+    }
+
+    pub fn buffer(&self) -> Handle<UniformBuffer> {
+        self.buffer
+    }
+
+    pub fn regions(&self) -> u32 {
+        self.regions
+    }
+
+    /// Writes `data` to the next region, returning the offset of the region in the buffer.
+    pub fn write(&mut self, render: &mut RenderApi, data: &[u8]) -> u32 {
+        assert!(data.len() <= self.size as usize, "data doesn't fit in a region of the uniform ring");
+        let offset = self.next * self.stride;
+        self.next = (self.next + 1) % self.regions;
+        render.get_buffer(self.buffer).unwrap().upload(offset as _, data);
+        offset
+    }
+}
 
 pub struct Uniform {
     pub(crate) layout: Handle<wgpu::BindGroupLayout>,
@@ -15,6 +54,8 @@ pub struct Uniform {
 pub struct UniformInstance {
     layout: Handle<wgpu::BindGroupLayout>,
     entries: Vec<UniformInstanceEntry>,
+    /// Size of the window bound for each entry with a dynamic offset, [None] for other entries.
+    dynamic_sizes: Vec<Option<NonZeroU64>>,
     cache: RefCell<UniformCache>,
 }
 
@@ -29,8 +70,13 @@ impl UniformCache {
     }
 }
 
+/// State of an entry when its bind group was created. Buffers, textures and samplers are compared
+/// by the identity of their wgpu object, which buffers replace when they grow.
+#[derive(PartialEq)]
 enum EntrySignature {
-    Buffer(u32),
+    Buffer(u64),
+    Texture(u64),
+    Sampler(u64),
 }
 
 impl UniformInstance {
@@ -42,36 +88,58 @@ impl UniformInstance {
                     UniformEntryTypeDefinition::Buffer => UniformInstanceEntry::Buffer(
                         MaybeOwned::from(device.create_buffer(0, BufferUsages::UNIFORM | BufferUsages::COPY_DST))
                     ),
+                    UniformEntryTypeDefinition::DynamicBuffer { size } => UniformInstanceEntry::Buffer(
+                        MaybeOwned::from(device.create_buffer(size as _, BufferUsages::UNIFORM | BufferUsages::COPY_DST))
+                    ),
+                    UniformEntryTypeDefinition::Texture => UniformInstanceEntry::Texture(resources.white_texture
+                        .expect("white texture for texture uniform entries without a value")),
+                    UniformEntryTypeDefinition::Sampler => UniformInstanceEntry::Sampler(
+                        MaybeOwned::from(device.create_sampler(FilterMode::Linear))
+                    ),
                 }
             })
             .collect();
+        let dynamic_sizes: Vec<_> = uniform.entries.iter()
+            .map(|def| def.typ.dynamic_size())
+            .collect();
 
-        let cache = Self::cache_entries(device, resources, &entries, uniform.layout);
+        let cache = Self::cache_entries(device, resources, &entries, &dynamic_sizes, uniform.layout);
 
         UniformInstance {
             layout: uniform.layout,
             entries,
+            dynamic_sizes,
             cache: RefCell::new(cache),
         }
     }
 
+    /// Number of offsets needed to bind this uniform, one per entry with a dynamic offset.
+    pub(crate) fn dynamic_offset_count(&self) -> usize {
+        self.dynamic_sizes.iter().flatten().count()
+    }
+
     pub(crate) fn cache(&self) -> RefMut<'_, UniformCache> {
         self.cache.borrow_mut()
     }
 
-    fn cache_entries(device: &DeviceContext, resources: &DeviceResources, entries: &[UniformInstanceEntry], layout: Handle<wgpu::BindGroupLayout>) -> UniformCache {
+    fn cache_entries(device: &DeviceContext, resources: &DeviceResources, entries: &[UniformInstanceEntry], dynamic_sizes: &[Option<NonZeroU64>], layout: Handle<wgpu::BindGroupLayout>) -> UniformCache {
         let (entry_bindings, signature): (Vec<_>, Vec<_>) = entries.iter()
+            .zip(dynamic_sizes)
             .enumerate()
-            .map(|(i, entry)| {
-                let (resource, signature) = match entry {
-                    UniformInstanceEntry::Buffer(MaybeOwned::Handle(buffer)) => {
-                        let buffer = resources.buffers.get(*buffer).unwrap();
-                        (buffer.buffer.as_entire_binding(), EntrySignature::Buffer(buffer.version()))
-                    }
-                    UniformInstanceEntry::Buffer(MaybeOwned::Owned(buffer)) => {
-                        (buffer.buffer.as_entire_binding(), EntrySignature::Buffer(buffer.version()))
-                    }
+            .map(|(i, (entry, dynamic_size))| {
+                let resource = match entry.resolve(resources) {
+                    ResolvedEntry::Buffer(buffer) => match dynamic_size {
+                        None => buffer.buffer.as_entire_binding(),
+                        Some(size) => wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &buffer.buffer,
+                            offset: 0,
+                            size: Some(*size),
+                        }),
+                    },
+                    ResolvedEntry::Texture(texture) => wgpu::BindingResource::TextureView(texture.sampled_view()),
+                    ResolvedEntry::Sampler(sampler) => wgpu::BindingResource::Sampler(&sampler.sampler),
                 };
+                let signature = entry.signature(resources);
                 (wgpu::BindGroupEntry {
                     binding: i as _,
                     resource,
@@ -91,37 +159,74 @@ impl UniformInstance {
     }
 
     fn test_signature(&self, resources: &DeviceResources) -> bool {
-        self.cache.borrow().signature.iter().zip(self.entries.iter()).all(|(signature, entry)| match signature {
-            EntrySignature::Buffer(version) => match entry {
-                UniformInstanceEntry::Buffer(MaybeOwned::Owned(buffer)) => buffer.version() == *version,
-                UniformInstanceEntry::Buffer(MaybeOwned::Handle(buffer)) => {
-                    let buffer = resources.buffers.get(*buffer).unwrap();
-                    buffer.version() == *version
-                }
-            }
-        })
+        self.cache.borrow().signature.iter().zip(self.entries.iter())
+            .all(|(signature, entry)| entry.signature(resources) == *signature)
     }
 
     pub(crate) fn validate_bind_group(&self, device: &DeviceContext, resources: &DeviceResources) {
         if !self.test_signature(resources) {
-            self.cache.replace(Self::cache_entries(device, resources, &self.entries, self.layout));
+            self.cache.replace(Self::cache_entries(device, resources, &self.entries, &self.dynamic_sizes, self.layout));
         }
     }
 
     pub fn entries(&self) -> &[UniformInstanceEntry] {
         self.entries.as_slice()
     }
+
+    /// Binds `entry` in place of the entry at `index`, e.g. to draw with another texture. The bind
+    /// group is recreated the next time the uniform is drawn with.
+    pub fn set_entry(&mut self, index: usize, entry: UniformInstanceEntry) {
+        assert_eq!(
+            std::mem::discriminant(&self.entries[index]),
+            std::mem::discriminant(&entry),
+            "uniform entry {} is of a different type", index,
+        );
+        self.entries[index] = entry;
+    }
 }
 
 
 pub enum UniformInstanceEntry {
     Buffer(MaybeOwned<VecBuf>),
+    Texture(Handle<Texture>),
+    Sampler(MaybeOwned<Sampler>),
+}
+
+enum ResolvedEntry<'a> {
+    Buffer(&'a VecBuf),
+    Texture(&'a Texture),
+    Sampler(&'a Sampler),
+}
+
+fn resolve<'a, T>(value: &'a MaybeOwned<T>, list: &'a CompactList<T>, kind: &str) -> &'a T {
+    match value {
+        MaybeOwned::Handle(handle) => list.get(*handle).unwrap_or_else(|| panic!("unknown uniform {}", kind)),
+        MaybeOwned::Owned(value) => value,
+    }
 }
 
 impl UniformInstanceEntry {
     fn matches_definition(&self, entry: &UniformEntryDefinition) -> bool {
         match self {
-            UniformInstanceEntry::Buffer(_) => matches!(entry.typ, UniformEntryTypeDefinition::Buffer),
+            UniformInstanceEntry::Buffer(_) => matches!(entry.typ, UniformEntryTypeDefinition::Buffer | UniformEntryTypeDefinition::DynamicBuffer { .. }),
+            UniformInstanceEntry::Texture(_) => matches!(entry.typ, UniformEntryTypeDefinition::Texture),
+            UniformInstanceEntry::Sampler(_) => matches!(entry.typ, UniformEntryTypeDefinition::Sampler),
+        }
+    }
+
+    fn resolve<'a>(&'a self, resources: &'a DeviceResources) -> ResolvedEntry<'a> {
+        match self {
+            UniformInstanceEntry::Buffer(buffer) => ResolvedEntry::Buffer(resolve(buffer, &resources.buffers, "buffer")),
+            UniformInstanceEntry::Texture(texture) => ResolvedEntry::Texture(resources.textures.get(*texture).expect("unknown uniform texture")),
+            UniformInstanceEntry::Sampler(sampler) => ResolvedEntry::Sampler(resolve(sampler, &resources.samplers, "sampler")),
+        }
+    }
+
+    fn signature(&self, resources: &DeviceResources) -> EntrySignature {
+        match self.resolve(resources) {
+            ResolvedEntry::Buffer(buffer) => EntrySignature::Buffer(buffer.id()),
+            ResolvedEntry::Texture(texture) => EntrySignature::Texture(texture.id()),
+            ResolvedEntry::Sampler(sampler) => EntrySignature::Sampler(sampler.id()),
         }
     }
 }
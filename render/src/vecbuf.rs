@@ -1,11 +1,64 @@
 use std::borrow::Cow;
 use wgpu::BufferAddress;
 
-use crate::{BufferUsages, MutableHandle};
+use utils::Handle;
+
+use crate::{BufferUsages, MaybeOwned, MaybeRef, MutableHandle};
+use crate::texture::next_id;
+
+/// What a buffer is used for. Handles to buffers created for a role, such as
+/// `Handle<UniformBuffer>` from [RenderApi::new_uniform_buffer](crate::RenderApi::new_uniform_buffer),
+/// are only accepted where a buffer of that role is expected:
+///
+/// ```compile_fail
+/// # use render::RenderApi;
+/// # use render::uniform::UniformInstanceEntry;
+/// # fn entry(render: &mut RenderApi) -> UniformInstanceEntry {
+/// let indices = render.new_index_buffer(64);
+/// UniformInstanceEntry::Buffer(indices.into())
+/// # }
+/// ```
+///
+/// Buffers created with [RenderApi::new_buffer](crate::RenderApi::new_buffer) are untyped, and
+/// [Handle::cast] converts between handle types for anything the roles don't cover.
+pub trait BufferRole {
+    const USAGE: BufferUsages;
+}
+
+pub enum VertexBuffer {}
+
+impl BufferRole for VertexBuffer {
+    const USAGE: BufferUsages = BufferUsages::VERTEX;
+}
+
+pub enum IndexBuffer {}
+
+impl BufferRole for IndexBuffer {
+    const USAGE: BufferUsages = BufferUsages::INDEX;
+}
+
+pub enum UniformBuffer {}
+
+impl BufferRole for UniformBuffer {
+    const USAGE: BufferUsages = BufferUsages::UNIFORM;
+}
+
+impl<'a, R: BufferRole> From<Handle<R>> for MaybeRef<'a, VecBuf> {
+    fn from(value: Handle<R>) -> Self {
+        MaybeRef::Handle(value.cast())
+    }
+}
+
+impl From<Handle<UniformBuffer>> for MaybeOwned<VecBuf> {
+    fn from(value: Handle<UniformBuffer>) -> Self {
+        MaybeOwned::Handle(value.cast())
+    }
+}
 
 /// Resizable wrapper for [wgpu::Buffer].
 pub struct VecBuf {
     pub(crate) buffer: wgpu::Buffer,
+    id: u64,
     version: u32,
     size: usize,
     capacity: usize,
@@ -16,6 +69,7 @@ impl VecBuf {
     pub(crate) fn new(buffer: wgpu::Buffer, capacity: usize, usage: BufferUsages) -> Self {
         VecBuf {
             buffer,
+            id: next_id(),
             version: 0,
             size: 0,
             capacity,
@@ -27,6 +81,11 @@ impl VecBuf {
         self.version
     }
 
+    /// Identity of the current wgpu buffer, see [next_id].
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
     pub fn capacity(&self) -> usize {
         self.capacity
     }
@@ -64,6 +123,7 @@ impl<'a> MutableHandle<'a, VecBuf> {
                 usage: self.resource.usage,
                 mapped_at_creation: mapped,
             });
+            self.resource.id = next_id();
             self.resource.version += 1;
             self.resource.capacity = size as _;
 
@@ -1,3 +1,5 @@
+use wgpu::{CompositeAlphaMode, PresentMode};
+
 use crate::{DeviceContext, SurfaceContext};
 
 pub struct WGPUContext {
@@ -26,13 +28,25 @@ impl WGPUContext {
     }
 
     pub async fn request_device(&self, surface: &SurfaceContext) -> Result<DeviceContext, wgpu::RequestDeviceError> {
+        self.request_device_compatible_with(Some(&surface.surface)).await
+    }
+
+    /// Requests a device that is not tied to any surface, for rendering exclusively to textures.
+    pub async fn request_headless_device(&self) -> Result<DeviceContext, wgpu::RequestDeviceError> {
+        self.request_device_compatible_with(None).await
+    }
+
+    async fn request_device_compatible_with(&self, surface: Option<&wgpu::Surface>) -> Result<DeviceContext, wgpu::RequestDeviceError> {
         let adapter = self.instance.request_adapter(&wgpu::RequestAdapterOptions {
-            compatible_surface: Some(&surface.surface),
+            compatible_surface: surface,
             ..Default::default()
         }).await.expect("viable adapter");
         log::info!("Got adapter: {:?}", adapter.get_info());
+        // optional features, only enabled where available
+        let features = adapter.features() & wgpu::Features::TIMESTAMP_QUERY;
         let (device, queue) = adapter.request_device(
             &wgpu::DeviceDescriptor {
+                features,
                 limits: wgpu::Limits::downlevel_webgl2_defaults(),
 
                 ..Default::default()
@@ -50,6 +64,8 @@ impl WGPUContext {
         SurfaceContext {
             surface,
             surface_config: None,
+            alpha_mode: CompositeAlphaMode::Opaque,
+            present_mode: PresentMode::Fifo,
         }
     }
 }
@@ -1,3 +1,6 @@
+use std::any::type_name;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
 pub struct CompactList<T> {
@@ -60,7 +63,7 @@ impl<T> Entry<T> {
     }
 }
 
-#[derive(PartialOrd, PartialEq, Hash)]
+#[derive(PartialOrd)]
 pub struct Handle<T> {
     index: usize,
     generation: u32,
@@ -79,6 +82,40 @@ impl<T> Clone for Handle<T> {
 
 impl<T> Copy for Handle<T> {}
 
+impl<T> Handle<T> {
+    /// Reinterprets the handle as a handle of another type, e.g. to convert between a typed handle
+    /// and a handle into the list actually holding the value. Getting a value with a handle cast
+    /// to the wrong type simply finds nothing, or an unrelated value of the same type.
+    pub fn cast<U>(self) -> Handle<U> {
+        Handle {
+            index: self.index,
+            generation: self.generation,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> Debug for Handle<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle<{}>({}v{})", type_name::<T>(), self.index, self.generation)
+    }
+}
+
 impl<T> CompactList<T> {
     pub fn new() -> Self {
         Self::default()
@@ -137,3 +174,29 @@ impl<T> CompactList<T> {
         self.get_entry_mut(handle).and_then(Entry::take)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::CompactList;
+
+    #[test]
+    fn stale_handles() {
+        let mut list = CompactList::new();
+        let a = list.add("a");
+        let b = list.add("b");
+        assert_eq!(list.get(a), Some(&"a"));
+        assert_eq!(list.get(b), Some(&"b"));
+
+        list.remove(a);
+        assert_eq!(list.get(a), None);
+
+        // the freed slot is reused, but the old handle doesn't see the new value
+        let c = list.add("c");
+        assert_ne!(a, c);
+        assert_eq!(list.get(a), None);
+        assert_eq!(list.get(c), Some(&"c"));
+        assert_eq!(list.take(a), None);
+        assert_eq!(list.take(c), Some("c"));
+        assert_eq!(list.get(b), Some(&"b"));
+    }
+}
@@ -1,12 +1,18 @@
 #![allow(non_snake_case)]
 
+mod columns;
+mod fold;
 mod has;
 mod map;
 mod shape;
+mod tuple;
 
+pub use columns::*;
+pub use fold::*;
 pub use has::*;
 pub use map::*;
 pub use shape::*;
+pub use tuple::*;
 
 pub trait Prepend {
     fn prepend<T>(self, value: T) -> (T, Self);
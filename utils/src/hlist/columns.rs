@@ -0,0 +1,48 @@
+/// Splits a sequence of hlists into one [Vec] per element, turning rows into columns.
+pub trait IntoColumns {
+    /// Hlist with a [Vec] for each element type, e.g. `(Vec<A>, (Vec<B>, ()))` for `(A, (B, ()))`.
+    type Columns: Default;
+
+    /// Appends each element to its column.
+    fn push_into(self, columns: &mut Self::Columns);
+}
+
+impl IntoColumns for () {
+    type Columns = ();
+
+    fn push_into(self, _columns: &mut ()) {}
+}
+
+impl<Head, Tail> IntoColumns for (Head, Tail)
+    where Tail: IntoColumns {
+    type Columns = (Vec<Head>, Tail::Columns);
+
+    fn push_into(self, columns: &mut Self::Columns) {
+        columns.0.push(self.0);
+        self.1.push_into(&mut columns.1);
+    }
+}
+
+/// Collects the elements of every row into their columns.
+pub fn into_columns<L, I>(rows: I) -> L::Columns
+    where L: IntoColumns,
+          I: IntoIterator<Item=L> {
+    let mut columns = L::Columns::default();
+    for row in rows {
+        row.push_into(&mut columns);
+    }
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hlist;
+    use crate::hlist::into_columns;
+
+    #[test]
+    fn columns() {
+        let rows = vec![hlist!(1u32, "a"), hlist!(2u32, "b"), hlist!(3u32, "c")];
+        let columns = into_columns(rows);
+        assert_eq!(columns, hlist!(vec![1u32, 2, 3], vec!["a", "b", "c"]));
+    }
+}
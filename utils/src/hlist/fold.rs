@@ -0,0 +1,82 @@
+/// Visitor applied to each element of an hlist by [ForEachHList], threading an accumulator from
+/// one element to the next.
+///
+/// Implementing this for every `T` satisfying some trait bound, e.g. `impl<T: Debug>`, makes the
+/// visitor applicable to any hlist whose elements all implement that trait.
+pub trait FoldVisitor<T, Acc> {
+    fn visit(&mut self, acc: Acc, element: &T) -> Acc;
+}
+
+pub trait ForEachHList<V, Acc> {
+    /// Visits every element in order, starting from `init` and returning the final accumulator.
+    fn fold(&self, visitor: &mut V, init: Acc) -> Acc;
+}
+
+impl<V, Acc> ForEachHList<V, Acc> for () {
+    fn fold(&self, _visitor: &mut V, init: Acc) -> Acc {
+        init
+    }
+}
+
+impl<Head, Tail, V, Acc> ForEachHList<V, Acc> for (Head, Tail)
+    where V: FoldVisitor<Head, Acc>,
+          Tail: ForEachHList<V, Acc> {
+    fn fold(&self, visitor: &mut V, init: Acc) -> Acc {
+        let acc = visitor.visit(init, &self.0);
+        self.1.fold(visitor, acc)
+    }
+}
+
+/// Visits every element of `list` without an accumulator.
+pub fn for_each<L, V>(list: &L, visitor: &mut V)
+    where L: ForEachHList<V, ()> {
+    list.fold(visitor, ())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Debug;
+
+    use crate::hlist;
+    use crate::hlist::{for_each, FoldVisitor, ForEachHList};
+
+    struct Count;
+
+    impl<T> FoldVisitor<T, usize> for Count {
+        fn visit(&mut self, acc: usize, _element: &T) -> usize {
+            acc + 1
+        }
+    }
+
+    struct DebugConcat;
+
+    impl<T: Debug> FoldVisitor<T, String> for DebugConcat {
+        fn visit(&mut self, acc: String, element: &T) -> String {
+            format!("{acc}{element:?};")
+        }
+    }
+
+    struct Collect(Vec<String>);
+
+    impl<T: Debug> FoldVisitor<T, ()> for Collect {
+        fn visit(&mut self, _acc: (), element: &T) {
+            self.0.push(format!("{element:?}"));
+        }
+    }
+
+    #[test]
+    fn fold() {
+        let list = hlist!(5u32, "text", 2.5f32, false);
+        assert_eq!(4, list.fold(&mut Count, 0));
+        assert_eq!("5;\"text\";2.5;false;", list.fold(&mut DebugConcat, String::new()));
+        assert_eq!(0, ().fold(&mut Count, 0));
+    }
+
+    #[test]
+    fn visit_each() {
+        let list = hlist!(Some(1u8), "text".to_owned());
+        let mut collect = Collect(vec![]);
+        for_each(&list, &mut collect);
+        assert_eq!(vec!["Some(1)".to_owned(), "\"text\"".to_owned()], collect.0);
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{delist, HList};
+
+/// Converts a short hlist into a flat tuple, e.g. `(A, (B, (C, ())))` into `(A, B, C)`.
+/// Implemented for hlists of one to six elements.
+pub trait IntoTuple {
+    type Tuple;
+
+    fn into_tuple(self) -> Self::Tuple;
+}
+
+macro_rules! impl_into_tuple {
+    ($($element:ident),+) => {
+        impl<$($element),+> IntoTuple for HList!($($element),+) {
+            type Tuple = ($($element,)+);
+
+            fn into_tuple(self) -> Self::Tuple {
+                let delist!($($element),+) = self;
+                ($($element,)+)
+            }
+        }
+    };
+}
+
+impl_into_tuple!(A);
+impl_into_tuple!(A, B);
+impl_into_tuple!(A, B, C);
+impl_into_tuple!(A, B, C, D);
+impl_into_tuple!(A, B, C, D, E);
+impl_into_tuple!(A, B, C, D, E, F);
+
+#[cfg(test)]
+mod tests {
+    use crate::hlist;
+    use crate::hlist::IntoTuple;
+
+    #[test]
+    fn into_tuple() {
+        assert_eq!(hlist!(1u32).into_tuple(), (1u32,));
+        assert_eq!(hlist!(1u32, "b", 3.0f32).into_tuple(), (1u32, "b", 3.0f32));
+    }
+}